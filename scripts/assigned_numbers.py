@@ -0,0 +1,118 @@
+#!/usr/bin/env python3
+"""Regenerates src/assigned_numbers/sig.rs from the assigned numbers of the Bluetooth SIG.
+
+    python3 scripts/assigned_numbers.py                       # downloads the YAML files
+    python3 scripts/assigned_numbers.py public/assigned_numbers  # or reads a clone of
+                                                              # https://bitbucket.org/bluetooth-SIG/public
+
+Requires PyYAML.
+"""
+
+import sys
+import urllib.request
+from pathlib import Path
+
+import yaml
+
+URL = "https://bitbucket.org/bluetooth-SIG/public/raw/main/assigned_numbers"
+SOURCE = "https://bitbucket.org/bluetooth-SIG/public/src/main/assigned_numbers/"
+OUTPUT = Path(__file__).resolve().parent.parent / "src" / "assigned_numbers" / "sig.rs"
+
+# The SDP service classes, the GATT services and the 16 bit UUIDs of the SIG members share the
+# services table
+SERVICE_FILES = [
+    "uuids/service_class.yaml",
+    "uuids/service_uuids.yaml",
+    "uuids/member_uuids.yaml",
+]
+CHARACTERISTIC_FILE = "uuids/characteristic_uuids.yaml"
+COMPANY_FILE = "company_identifiers/company_identifiers.yaml"
+APPEARANCE_FILE = "core/appearance_values.yaml"
+
+
+def load(root, name):
+    if root is None:
+        request = urllib.request.Request(f"{URL}/{name}", headers={"User-Agent": "bluetui"})
+        with urllib.request.urlopen(request) as response:
+            text = response.read().decode("utf-8")
+    else:
+        text = (root / name).read_text(encoding="utf-8")
+    return yaml.safe_load(text)
+
+
+def literal(name):
+    name = " ".join(str(name).split())
+    return '"' + name.replace("\\", "\\\\").replace('"', '\\"') + '"'
+
+
+def table(name, kind, entries, width, comment=None):
+    lines = [f"// {comment}"] if comment else []
+    lines.append(f"pub(super) const {name}: &[{kind}] = &[")
+    for *keys, value in entries:
+        keys = ", ".join(f"{key:#0{width}x}" for key in keys)
+        lines.append(f"    ({keys}, {literal(value)}),")
+    lines.append("];")
+    return "\n".join(lines)
+
+
+def main():
+    root = Path(sys.argv[1]) if len(sys.argv) > 1 else None
+
+    # The first name of a UUID wins, the service classes are named before the GATT services
+    services = {}
+    for file in SERVICE_FILES:
+        for entry in load(root, file)["uuids"]:
+            services.setdefault(entry["uuid"], entry["name"])
+
+    characteristics = {
+        entry["uuid"]: entry["name"] for entry in load(root, CHARACTERISTIC_FILE)["uuids"]
+    }
+
+    companies = {
+        entry["value"]: entry["name"]
+        for entry in load(root, COMPANY_FILE)["company_identifiers"]
+    }
+
+    categories = {}
+    subcategories = {}
+    for entry in load(root, APPEARANCE_FILE)["appearance_values"]:
+        categories[entry["category"]] = entry["name"]
+        for subcategory in entry.get("subcategory") or []:
+            subcategories[(entry["category"], subcategory["value"])] = subcategory["name"]
+
+    tables = [
+        table("SERVICES", "(u16, &str)", sorted(services.items()), 6),
+        table("CHARACTERISTICS", "(u16, &str)", sorted(characteristics.items()), 6),
+        table("COMPANIES", "(u16, &str)", sorted(companies.items()), 6),
+        table(
+            "APPEARANCE_CATEGORIES",
+            "(u16, &str)",
+            sorted(categories.items()),
+            4,
+            "Categories of the GAP appearance, the 10 upper bits of the value",
+        ),
+        table(
+            "APPEARANCE_SUBCATEGORIES",
+            "(u16, u16, &str)",
+            [(*key, name) for key, name in sorted(subcategories.items())],
+            4,
+            "Subcategories, the 6 lower bits, by category",
+        ),
+    ]
+
+    header = (
+        "// Generated by `python3 scripts/assigned_numbers.py` from\n"
+        f"// {SOURCE}, do not edit.\n"
+        "//\n"
+        "// Every table is sorted by its keys, they are searched by dichotomy.\n"
+    )
+    OUTPUT.write_text(header + "\n" + "\n\n".join(tables) + "\n", encoding="utf-8")
+    print(
+        f"{len(services)} services, {len(characteristics)} characteristics, "
+        f"{len(companies)} companies and {len(categories)} appearance categories written to "
+        f"{OUTPUT}"
+    )
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,154 @@
+#!/usr/bin/env python3
+"""Regenerates src/oui.tsv, the vendors shown for the public addresses.
+
+The full IEEE MA-L registry has more than 38000 assignments, most of them to makers of network
+equipment that never shows up in a Bluetooth scan. Only the assignments below are kept: the
+makers of Bluetooth chips (CSR, Broadcom, Realtek, Espressif, Texas Instruments...) and of the
+devices that are commonly paired (phones, headsets, game controllers, boards). The names are the
+organization names shortened by hand. Add a prefix here and run the script to extend the table.
+
+The selection is checked against the registry, so that a reassigned prefix is noticed:
+
+    python3 scripts/oui.py                  # downloads the registry
+    python3 scripts/oui.py path/to/oui.csv  # or reads a copy of it
+    python3 scripts/oui.py --no-check       # writes the table without the registry
+"""
+
+import csv
+import io
+import sys
+import urllib.request
+from pathlib import Path
+
+URL = "https://standards-oui.ieee.org/oui/oui.csv"
+OUTPUT = Path(__file__).resolve().parent.parent / "src" / "oui.tsv"
+
+SELECTION = {
+    "00025B": "Cambridge Silicon Radio",
+    "0002EE": "Nokia",
+    "00037F": "Atheros Communications",
+    "000393": "Apple",
+    "00054F": "Garmin",
+    "000761": "Logitech",
+    "0009BF": "Nintendo",
+    "000A95": "Apple",
+    "001018": "Broadcom",
+    "00124B": "Texas Instruments",
+    "0012FB": "Samsung",
+    "0013A9": "Sony",
+    "001632": "Samsung",
+    "001694": "Sennheiser Communications",
+    "0017AB": "Nintendo",
+    "001882": "Huawei",
+    "00191D": "Nintendo",
+    "00197F": "Plantronics",
+    "001A7D": "cyber-blue",
+    "001B63": "Apple",
+    "001B66": "Sennheiser electronic",
+    "001C62": "LG Electronics",
+    "001D25": "Samsung",
+    "001DBA": "Sony",
+    "001E75": "LG Electronics",
+    "001EC2": "Apple",
+    "001F20": "Logitech",
+    "001F32": "Nintendo",
+    "001FA7": "Sony Interactive Entertainment",
+    "00241E": "Nintendo",
+    "00248D": "Sony Interactive Entertainment",
+    "0024BE": "Sony",
+    "002500": "Apple",
+    "00259E": "Huawei",
+    "002637": "Samsung",
+    "0050F2": "Microsoft",
+    "00E04C": "Realtek",
+    "00E0FC": "Huawei",
+    "0452C7": "Bose",
+    "10683F": "LG Electronics",
+    "240AC4": "Espressif",
+    "246F28": "Espressif",
+    "281878": "Microsoft",
+    "28CDC1": "Raspberry Pi Trading",
+    "28CFE9": "Apple",
+    "2C41A1": "Bose",
+    "30AEA4": "Espressif",
+    "3C0754": "Apple",
+    "3C5AB4": "Google",
+    "44650D": "Amazon",
+    "4C875D": "Bose",
+    "546009": "Google",
+    "546C0E": "Texas Instruments",
+    "58BDA3": "Nintendo",
+    "60ABD2": "Bose",
+    "6837E9": "Amazon",
+    "709E29": "Sony Interactive Entertainment",
+    "74C246": "Amazon",
+    "782B64": "Bose",
+    "7C1E52": "Microsoft",
+    "7C6D62": "Apple",
+    "985FD3": "Microsoft",
+    "98B6E9": "Nintendo",
+    "A4CF12": "Espressif",
+    "A8E3EE": "Sony Interactive Entertainment",
+    "ACBC32": "Apple",
+    "B0B448": "Texas Instruments",
+    "B827EB": "Raspberry Pi Foundation",
+    "BC60A7": "Sony Interactive Entertainment",
+    "D83ADD": "Raspberry Pi Trading",
+    "DCA632": "Raspberry Pi Trading",
+    "E45F01": "Raspberry Pi Trading",
+    "F0D1A9": "Apple",
+    "F4F5D8": "Google",
+    "F4F5E8": "Google",
+    "F8461C": "Sony Interactive Entertainment",
+    "FC65DE": "Amazon",
+}
+
+
+def registry(source):
+    if source is None:
+        request = urllib.request.Request(URL, headers={"User-Agent": "bluetui"})
+        with urllib.request.urlopen(request) as response:
+            text = response.read().decode("utf-8")
+    else:
+        text = Path(source).read_text(encoding="utf-8")
+    return {
+        row["Assignment"].strip().upper(): row["Organization Name"]
+        for row in csv.DictReader(io.StringIO(text))
+        if row["Registry"] == "MA-L"
+    }
+
+
+# The first word of the short name is expected in the organization name, "Sony Interactive
+# Entertainment" for "Sony Interactive Entertainment Inc."
+def check(assignments):
+    errors = []
+    for prefix, vendor in SELECTION.items():
+        organization = assignments.get(prefix)
+        if organization is None:
+            errors.append(f"{prefix} is not assigned")
+        elif vendor.split()[0].lower() not in organization.lower():
+            errors.append(f"{prefix} is assigned to {organization}, not {vendor}")
+    return errors
+
+
+def main():
+    args = sys.argv[1:]
+    if "--no-check" not in args:
+        errors = check(registry(args[0] if args else None))
+        if errors:
+            print("\n".join(errors), file=sys.stderr)
+            sys.exit(1)
+
+    lines = [
+        f"# Vendors of some of the IEEE MA-L assignments, from {URL}",
+        "#",
+        "# Generated by scripts/oui.py, that explains the selection. Do not edit by hand.",
+        "# One `<prefix>\\t<vendor>` line per assignment, sorted by prefix.",
+    ]
+    lines += [f"{prefix}\t{SELECTION[prefix]}" for prefix in sorted(SELECTION)]
+    OUTPUT.write_text("\n".join(lines) + "\n", encoding="utf-8")
+    print(f"{len(SELECTION)} assignments written to {OUTPUT}")
+
+
+if __name__ == "__main__":
+    main()
@@ -0,0 +1,123 @@
+use bluer::{Address, AddressType};
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, BorderType, Clear, Padding, Paragraph},
+};
+use tui_input::Input;
+
+// Address typed in to reach a device that is not discovered, e.g. a LE device that advertises
+// rarely or not at all
+#[derive(Debug)]
+pub struct AddDevice {
+    // Name of the adapter the device is added to
+    pub adapter: String,
+    pub input: Input,
+    pub address_type: AddressType,
+}
+
+impl AddDevice {
+    pub fn new(adapter: String) -> Self {
+        Self {
+            adapter,
+            input: Input::default(),
+            address_type: AddressType::LePublic,
+        }
+    }
+
+    pub fn next_address_type(&mut self) {
+        self.address_type = match self.address_type {
+            AddressType::LePublic => AddressType::LeRandom,
+            AddressType::LeRandom => AddressType::BrEdr,
+            AddressType::BrEdr => AddressType::LePublic,
+        };
+    }
+
+    pub fn address(&self) -> Result<Address, String> {
+        let value = self.input.value().trim();
+        value
+            .parse()
+            .map_err(|_| format!("{value} is not a valid address, e.g. AA:BB:CC:DD:EE:FF"))
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        let center_cutout = area.centered(Constraint::Max(70), Constraint::Length(8));
+
+        let [_, message_area, type_area, full_input_area, _] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(2),
+            Constraint::Length(2),
+            Constraint::Length(1),
+            Constraint::Length(2),
+        ])
+        .areas(center_cutout);
+
+        let input_area = full_input_area.centered_horizontally(Constraint::Percentage(60));
+
+        frame.render_widget(Clear, center_cutout);
+        frame.render_widget(
+            Block::bordered()
+                .border_type(BorderType::Thick)
+                .border_style(Style::default().green()),
+            center_cutout,
+        );
+
+        let message = Paragraph::new(Line::from(vec![
+            "Enter the address of the device to add to ".into(),
+            self.adapter.as_str().bold().italic(),
+        ]))
+        .centered()
+        .block(Block::new().padding(Padding::horizontal(2)));
+
+        let address_type = Paragraph::new(Line::from(vec![
+            "Address type: ".into(),
+            match self.address_type {
+                AddressType::LePublic => "LE public",
+                AddressType::LeRandom => "LE random",
+                AddressType::BrEdr => "BR/EDR",
+            }
+            .bold(),
+        ]))
+        .centered()
+        .block(Block::new().padding(Padding::horizontal(2)));
+
+        let input = Paragraph::new(self.input.value())
+            .alignment(Alignment::Left)
+            .style(Style::default().fg(Color::White))
+            .block(Block::new().on_dark_gray().padding(Padding::horizontal(2)));
+
+        frame.render_widget(message, message_area);
+        frame.render_widget(address_type, type_area);
+        frame.render_widget(input, input_area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn addresses() {
+        let mut add_device = AddDevice::new("hci0".to_string());
+        add_device.input = Input::new(" aa:bb:cc:dd:ee:ff ".to_string());
+        assert_eq!(
+            add_device.address(),
+            Ok("AA:BB:CC:DD:EE:FF".parse().unwrap())
+        );
+        add_device.input = Input::new("AA:BB:CC".to_string());
+        assert!(add_device.address().is_err());
+    }
+
+    #[test]
+    fn address_types() {
+        let mut add_device = AddDevice::new("hci0".to_string());
+        add_device.next_address_type();
+        assert_eq!(add_device.address_type, AddressType::LeRandom);
+        add_device.next_address_type();
+        assert_eq!(add_device.address_type, AddressType::BrEdr);
+        add_device.next_address_type();
+        assert_eq!(add_device.address_type, AddressType::LePublic);
+    }
+}
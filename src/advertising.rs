@@ -0,0 +1,499 @@
+use std::{fmt::Write, time::Duration};
+
+use bluer::{Uuid, adv::Advertisement};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Clear, List, ListState, Padding, Paragraph},
+};
+use serde::{Deserialize, Serialize};
+use tui_input::{Input, backend::crossterm::EventHandler};
+
+use crate::requests::pad_string;
+
+// An advertisement saved in the config as a `[[advertisement]]` table
+#[derive(Deserialize, Serialize, Debug, Clone, PartialEq)]
+pub struct AdvertisementProfile {
+    pub name: String,
+
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub local_name: String,
+
+    // Full UUIDs, or their 16 or 32 bit short form, e.g. "180d"
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub service_uuids: Vec<String>,
+
+    pub company_id: Option<u16>,
+
+    // Hex encoded
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub manufacturer_data: String,
+
+    pub service_data_uuid: Option<String>,
+
+    // Hex encoded
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub service_data: String,
+
+    // dBm
+    pub tx_power: Option<i16>,
+
+    // Milliseconds
+    pub interval: Option<u64>,
+
+    #[serde(default = "default_discoverable")]
+    pub discoverable: bool,
+}
+
+fn default_discoverable() -> bool {
+    true
+}
+
+impl Default for AdvertisementProfile {
+    fn default() -> Self {
+        Self {
+            name: String::new(),
+            local_name: String::new(),
+            service_uuids: Vec::new(),
+            company_id: None,
+            manufacturer_data: String::new(),
+            service_data_uuid: None,
+            service_data: String::new(),
+            tx_power: None,
+            interval: None,
+            discoverable: default_discoverable(),
+        }
+    }
+}
+
+// Full UUID from its 16 or 32 bit short form, full UUIDs are taken as they are
+pub fn parse_uuid(s: &str) -> Result<Uuid, String> {
+    let s = s.trim().trim_start_matches("0x");
+    let full = match s.len() {
+        4 => format!("0000{s}-0000-1000-8000-00805f9b34fb"),
+        8 => format!("{s}-0000-1000-8000-00805f9b34fb"),
+        _ => s.to_string(),
+    };
+    full.parse()
+        .map_err(|_| format!("`{s}` is not a valid UUID"))
+}
+
+// Hex bytes, optionally separated by spaces or colons
+pub fn parse_hex(s: &str) -> Result<Vec<u8>, String> {
+    let digits = s
+        .trim()
+        .trim_start_matches("0x")
+        .chars()
+        .filter(|c| !matches!(c, ' ' | ':'))
+        .map(|c| c.to_digit(16).map(|d| d as u8))
+        .collect::<Option<Vec<u8>>>()
+        .ok_or_else(|| format!("`{s}` is not hex"))?;
+    if !digits.len().is_multiple_of(2) {
+        return Err(format!("`{s}` has an odd number of hex digits"));
+    }
+    Ok(digits
+        .chunks(2)
+        .map(|pair| pair[0] << 4 | pair[1])
+        .collect())
+}
+
+pub fn format_hex(bytes: &[u8]) -> String {
+    bytes.iter().fold(String::new(), |mut hex, b| {
+        let _ = write!(hex, "{b:02x}");
+        hex
+    })
+}
+
+impl AdvertisementProfile {
+    pub fn label(&self) -> &str {
+        if !self.name.is_empty() {
+            &self.name
+        } else if !self.local_name.is_empty() {
+            &self.local_name
+        } else {
+            "unnamed"
+        }
+    }
+
+    pub fn advertisement(&self) -> Result<Advertisement, String> {
+        let mut advertisement = Advertisement {
+            local_name: Some(self.local_name.clone()).filter(|name| !name.is_empty()),
+            service_uuids: self
+                .service_uuids
+                .iter()
+                .map(|uuid| parse_uuid(uuid))
+                .collect::<Result<_, _>>()?,
+            discoverable: Some(self.discoverable),
+            tx_power: self.tx_power,
+            min_interval: self.interval.map(Duration::from_millis),
+            max_interval: self.interval.map(Duration::from_millis),
+            ..Default::default()
+        };
+        if let Some(company_id) = self.company_id {
+            advertisement
+                .manufacturer_data
+                .insert(company_id, parse_hex(&self.manufacturer_data)?);
+        }
+        if let Some(uuid) = &self.service_data_uuid {
+            advertisement
+                .service_data
+                .insert(parse_uuid(uuid)?, parse_hex(&self.service_data)?);
+        }
+        Ok(advertisement)
+    }
+}
+
+const FIELDS: [&str; 9] = [
+    "Profile name",
+    "Local name",
+    "Service UUIDs",
+    "Company ID",
+    "Manufacturer data",
+    "Service data UUID",
+    "Service data",
+    "TX power (dBm)",
+    "Interval (ms)",
+];
+
+// Focus of the editor: the saved profiles, the fields, then the discoverable toggle
+const PROFILES: usize = 0;
+const DISCOVERABLE: usize = FIELDS.len() + 1;
+
+pub enum EditorAction {
+    ToggleAdvertising,
+    Save,
+}
+
+#[derive(Debug)]
+pub struct AdvertisingEditor {
+    pub adapter: String,
+    pub profiles: Vec<AdvertisementProfile>,
+    // The first entry is a new advertisement
+    pub state: ListState,
+    fields: [Input; FIELDS.len()],
+    discoverable: bool,
+    focused: usize,
+    pub error: Option<String>,
+}
+
+impl AdvertisingEditor {
+    pub fn new(adapter: String, profiles: Vec<AdvertisementProfile>) -> Self {
+        let mut editor = Self {
+            adapter,
+            profiles,
+            state: ListState::default().with_selected(Some(0)),
+            fields: Default::default(),
+            discoverable: true,
+            focused: 1,
+            error: None,
+        };
+        editor.load(&AdvertisementProfile::default());
+        editor
+    }
+
+    fn load(&mut self, profile: &AdvertisementProfile) {
+        let values = [
+            profile.name.clone(),
+            profile.local_name.clone(),
+            profile.service_uuids.join(", "),
+            profile
+                .company_id
+                .map(|id| format!("{id:04x}"))
+                .unwrap_or_default(),
+            profile.manufacturer_data.clone(),
+            profile.service_data_uuid.clone().unwrap_or_default(),
+            profile.service_data.clone(),
+            profile
+                .tx_power
+                .map(|power| power.to_string())
+                .unwrap_or_default(),
+            profile
+                .interval
+                .map(|interval| interval.to_string())
+                .unwrap_or_default(),
+        ];
+        for (field, value) in self.fields.iter_mut().zip(values) {
+            *field = Input::new(value);
+        }
+        self.discoverable = profile.discoverable;
+        self.error = None;
+    }
+
+    fn select(&mut self, index: usize) {
+        self.state.select(Some(index));
+        let profile = index
+            .checked_sub(1)
+            .and_then(|i| self.profiles.get(i))
+            .cloned()
+            .unwrap_or_default();
+        self.load(&profile);
+    }
+
+    fn value(&self, field: usize) -> Option<&str> {
+        Some(self.fields[field].value().trim()).filter(|value| !value.is_empty())
+    }
+
+    // The profile described by the form, with the advertisement that checks it
+    pub fn profile(&self) -> Result<(AdvertisementProfile, Advertisement), String> {
+        let number = |field: usize| -> Result<Option<i64>, String> {
+            self.value(field)
+                .map(|value| {
+                    value
+                        .parse()
+                        .map_err(|_| format!("{} must be a number", FIELDS[field]))
+                })
+                .transpose()
+        };
+        let company_id = self
+            .value(3)
+            .map(|id| {
+                u16::from_str_radix(id.trim_start_matches("0x"), 16)
+                    .map_err(|_| "Company ID must be 4 hex digits, e.g. 004c".to_string())
+            })
+            .transpose()?;
+        let tx_power = number(7)?
+            .map(|power| i16::try_from(power).map_err(|_| "TX power is out of range".to_string()))
+            .transpose()?;
+        let interval = number(8)?
+            .map(|interval| {
+                u64::try_from(interval).map_err(|_| "Interval can not be negative".to_string())
+            })
+            .transpose()?;
+
+        let profile = AdvertisementProfile {
+            name: self.value(0).unwrap_or_default().to_string(),
+            local_name: self.value(1).unwrap_or_default().to_string(),
+            service_uuids: self
+                .value(2)
+                .unwrap_or_default()
+                .split(',')
+                .map(str::trim)
+                .filter(|uuid| !uuid.is_empty())
+                .map(str::to_string)
+                .collect(),
+            company_id,
+            manufacturer_data: self.value(4).unwrap_or_default().to_string(),
+            service_data_uuid: self.value(5).map(str::to_string),
+            service_data: self.value(6).unwrap_or_default().to_string(),
+            tx_power,
+            interval,
+            discoverable: self.discoverable,
+        };
+        let advertisement = profile.advertisement()?;
+        Ok((profile, advertisement))
+    }
+
+    pub fn handle_key_events(&mut self, key_event: KeyEvent) -> Option<EditorAction> {
+        match key_event.code {
+            KeyCode::Tab => self.focused = (self.focused + 1) % (DISCOVERABLE + 1),
+            KeyCode::BackTab => self.focused = (self.focused + DISCOVERABLE) % (DISCOVERABLE + 1),
+            KeyCode::Enter => return Some(EditorAction::ToggleAdvertising),
+            KeyCode::Char('s') if key_event.modifiers.contains(KeyModifiers::CONTROL) => {
+                return Some(EditorAction::Save);
+            }
+            code if self.focused == PROFILES => match code {
+                KeyCode::Char('j') | KeyCode::Down => {
+                    let i = self.state.selected().unwrap_or(0);
+                    self.select((i + 1).min(self.profiles.len()));
+                }
+                KeyCode::Char('k') | KeyCode::Up => {
+                    let i = self.state.selected().unwrap_or(0);
+                    self.select(i.saturating_sub(1));
+                }
+                _ => {}
+            },
+            KeyCode::Char(' ') if self.focused == DISCOVERABLE => {
+                self.discoverable = !self.discoverable;
+            }
+            _ if self.focused != DISCOVERABLE => {
+                self.fields[self.focused - 1]
+                    .handle_event(&crossterm::event::Event::Key(key_event));
+            }
+            _ => {}
+        }
+        None
+    }
+
+    // `advertising` is the profile being advertised by the adapter
+    pub fn render(&mut self, advertising: Option<&str>, frame: &mut Frame, area: Rect) {
+        let area = area.centered(Constraint::Max(80), Constraint::Length(15));
+
+        let title = match advertising {
+            Some(name) => format!(" Advertise on {} · advertising {name} ", self.adapter),
+            None => format!(" Advertise on {} ", self.adapter),
+        };
+        let block = Block::bordered()
+            .title(title)
+            .title_style(Style::default().bold())
+            .padding(Padding::horizontal(1))
+            .border_type(BorderType::Thick)
+            .border_style(Style::default().green());
+        let inner = block.inner(area);
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+
+        let [main_area, _, status_area] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(inner);
+        let [profiles_area, _, form_area] = Layout::horizontal([
+            Constraint::Length(20),
+            Constraint::Length(1),
+            Constraint::Fill(1),
+        ])
+        .areas(main_area);
+
+        let title = |label: &'static str, focused: bool| {
+            if focused {
+                Span::from(label).green().bold()
+            } else {
+                Span::from(label).bold()
+            }
+        };
+
+        let [profiles_title, profiles_list] =
+            Layout::vertical([Constraint::Length(1), Constraint::Fill(1)]).areas(profiles_area);
+        frame.render_widget(title("Profiles", self.focused == PROFILES), profiles_title);
+        let items: Vec<Line> = std::iter::once("New advertisement")
+            .chain(self.profiles.iter().map(AdvertisementProfile::label))
+            .map(|name| Line::from(format!(" {name}")))
+            .collect();
+        frame.render_stateful_widget(
+            List::new(items).highlight_style(Style::default().white().on_dark_gray()),
+            profiles_list,
+            &mut self.state,
+        );
+
+        let width = form_area.width.saturating_sub(19) as usize;
+        let mut lines: Vec<Line> = FIELDS
+            .iter()
+            .zip(&self.fields)
+            .enumerate()
+            .map(|(i, (label, input))| {
+                let label = format!("{label:<18}");
+                Line::from(vec![
+                    if self.focused == i + 1 {
+                        Span::from(label).green().bold()
+                    } else {
+                        Span::from(label)
+                    },
+                    Span::from(pad_string(format!(" {}", input.value()), width))
+                        .bg(Color::DarkGray),
+                ])
+            })
+            .collect();
+        lines.push(Line::from(""));
+        lines.push(Line::from(vec![
+            if self.focused == DISCOVERABLE {
+                Span::from(format!("{:<18}", "Discoverable")).green().bold()
+            } else {
+                Span::from(format!("{:<18}", "Discoverable"))
+            },
+            Span::from(if self.discoverable { " [x]" } else { " [ ]" }),
+        ]));
+        frame.render_widget(Paragraph::new(lines), form_area);
+
+        let status = match &self.error {
+            Some(error) => Line::from(error.as_str()).red(),
+            None => Line::from(if advertising.is_some() {
+                "↵ stop advertising · ctrl+s save the profile"
+            } else {
+                "↵ start advertising · ctrl+s save the profile"
+            })
+            .dark_gray(),
+        };
+        frame.render_widget(status, status_area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_snapshot;
+    use ratatui::{Terminal, backend::TestBackend};
+
+    fn heart_rate() -> AdvertisementProfile {
+        AdvertisementProfile {
+            name: "Heart rate".to_string(),
+            local_name: "bluetui".to_string(),
+            service_uuids: vec!["180d".to_string()],
+            company_id: Some(0xffff),
+            manufacturer_data: "01 02 ff".to_string(),
+            tx_power: Some(-4),
+            interval: Some(100),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn short_and_full_uuids() {
+        assert_eq!(
+            parse_uuid("180d").unwrap().to_string(),
+            "0000180d-0000-1000-8000-00805f9b34fb"
+        );
+        assert_eq!(
+            parse_uuid("6e400001-b5a3-f393-e0a9-e50e24dcca9e")
+                .unwrap()
+                .to_string(),
+            "6e400001-b5a3-f393-e0a9-e50e24dcca9e"
+        );
+        assert!(parse_uuid("18d").is_err());
+    }
+
+    #[test]
+    fn hex_data() {
+        assert_eq!(parse_hex("0x0102ff").unwrap(), [1, 2, 255]);
+        assert_eq!(parse_hex("01:02 ff").unwrap(), [1, 2, 255]);
+        assert!(parse_hex("012").is_err());
+        assert!(parse_hex("zz").is_err());
+        assert!(parse_hex("aéb").is_err());
+        assert!(parse_hex("éé").is_err());
+    }
+
+    #[test]
+    fn advertisement_from_a_profile() {
+        let advertisement = heart_rate().advertisement().unwrap();
+        assert_eq!(advertisement.local_name.as_deref(), Some("bluetui"));
+        assert!(
+            advertisement
+                .service_uuids
+                .contains(&parse_uuid("180d").unwrap())
+        );
+        assert_eq!(advertisement.manufacturer_data[&0xffff], [1, 2, 255]);
+        assert_eq!(advertisement.tx_power, Some(-4));
+        assert_eq!(advertisement.min_interval, Some(Duration::from_millis(100)));
+        assert_eq!(advertisement.discoverable, Some(true));
+    }
+
+    #[test]
+    fn form_round_trip() {
+        let mut editor = AdvertisingEditor::new("hci0".to_string(), vec![heart_rate()]);
+        editor.select(1);
+        assert_eq!(editor.profile().unwrap().0, heart_rate());
+
+        editor.fields[7] = Input::new("loud".to_string());
+        assert_eq!(
+            editor.profile().map(|(profile, _)| profile).unwrap_err(),
+            "TX power (dBm) must be a number"
+        );
+        editor.select(0);
+        assert_eq!(editor.profile().unwrap().0, AdvertisementProfile::default());
+    }
+
+    #[test]
+    fn render() {
+        let mut terminal = Terminal::new(TestBackend::new(90, 20)).unwrap();
+        let mut editor = AdvertisingEditor::new("hci0".to_string(), vec![heart_rate()]);
+        editor.select(1);
+        terminal
+            .draw(|frame| editor.render(Some("Heart rate"), frame, frame.area()))
+            .unwrap();
+        assert_snapshot!(terminal.backend());
+    }
+}
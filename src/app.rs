@@ -1,12 +1,14 @@
 use crate::{
+    advertising::{AdvertisementProfile, AdvertisingEditor},
     agent::{
         display_passkey, display_pin_code, request_confirmation, request_passkey, request_pin_code,
     },
     event::Event,
-    help::Help,
+    help::{Help, render_key_bindings},
 };
 use bluer::{
-    Address, Session,
+    Adapter, Address, Session,
+    adv::AdvertisementHandle,
     agent::{Agent, AgentHandle},
 };
 use futures::FutureExt;
@@ -14,7 +16,7 @@ use ratatui::{
     Frame,
     layout::{Constraint, Layout, Margin, Rect},
     style::{Style, Stylize},
-    text::{Line, ToLine, ToSpan},
+    text::{Line, Span, ToLine},
     widgets::{
         Block, BorderType, Cell, Clear, Padding, Row, Scrollbar, ScrollbarOrientation,
         ScrollbarState, Table, TableState,
@@ -24,16 +26,41 @@ use tokio::sync::mpsc::UnboundedSender;
 use tui_input::Input;
 
 use crate::{
+    add_device::AddDevice,
     agent::AuthAgent,
     alias::render_set_alias,
-    bluetooth::Controller,
-    config::{Config, Width},
-    favorite::{read_favorite_devices_from_disk, save_favorite_devices_to_disk},
-    notification::Notification,
-    requests::Requests,
+    battery::BatteryAlerts,
+    beacon::{self, Beacon, render_beacons},
+    bluetooth::{Controller, Device, find_paired_device},
+    bus::{BLUEZ, Bus, ManagedObjects},
+    config::{BindingContext, Config, Width},
+    details::{Connections, render_device_details},
+    favorite::Favorites,
+    filter::{DeviceFilter, next_shown},
+    group::{GroupProgress, Groups, render_add_to_group, render_groups},
+    history::{History, Observation, format_ago, format_duration},
+    media::{self, MediaBus, Player, Transport, render_media},
+    network::{self, Network, NetworkBus},
+    notification::{Notification, NotificationLevel},
+    obex::{
+        ObexClient, Transfer, TransferStatus, TransferUpdate, agent::ReceiveAgent,
+        phone::PhoneData, render_transfers,
+    },
+    palette::Palette,
+    peripheral::GattServer,
+    picker::FilePicker,
+    recorder::{RecorderPicker, Recording},
+    requests::{Requests, confirmation::Confirmation},
+    sensors::SensorDashboard,
+    serial::SerialTerminal,
     spinner::Spinner,
+    storage,
+};
+use std::{
+    collections::{HashMap, HashSet},
+    sync::{Arc, atomic::Ordering},
+    time::Instant,
 };
-use std::sync::{Arc, atomic::Ordering};
 
 pub type AppResult<T> = anyhow::Result<T>;
 
@@ -47,10 +74,87 @@ pub enum FocusedBlock {
     SetDeviceAliasBox,
     RequestConfirmation,
     UnpairConfirmation { confirm: bool },
+    DeleteGroupConfirmation { confirm: bool },
     EnterPinCode,
     EnterPasskey,
     DisplayPinCode,
     DisplayPasskey,
+    ReceiveFile,
+    Groups,
+    AddToGroup,
+    Search,
+    KeyBindings,
+    CommandPalette,
+    DeviceDetails,
+    Media,
+    FilePicker,
+    PhoneData,
+    SerialTerminal,
+    Advertising,
+    GattServer,
+    Beacons,
+    Sensors,
+    Recorder,
+    AddDevice,
+}
+
+impl FocusedBlock {
+    // Context of the key bindings that apply to the block
+    pub fn binding_context(self) -> BindingContext {
+        match self {
+            FocusedBlock::Adapter => BindingContext::Adapter,
+            FocusedBlock::PairedDevices => BindingContext::PairedDevices,
+            FocusedBlock::NewDevices => BindingContext::NewDevices,
+            FocusedBlock::SetDeviceAliasBox
+            | FocusedBlock::AddDevice
+            | FocusedBlock::AddToGroup => BindingContext::Input,
+            FocusedBlock::UnpairConfirmation { .. }
+            | FocusedBlock::DeleteGroupConfirmation { .. } => BindingContext::Confirmation,
+            FocusedBlock::RequestConfirmation
+            | FocusedBlock::EnterPinCode
+            | FocusedBlock::EnterPasskey
+            | FocusedBlock::DisplayPinCode
+            | FocusedBlock::DisplayPasskey
+            | FocusedBlock::ReceiveFile => BindingContext::Requests,
+            FocusedBlock::Groups => BindingContext::Groups,
+            FocusedBlock::Search => BindingContext::Search,
+            FocusedBlock::KeyBindings => BindingContext::KeyBindings,
+            FocusedBlock::CommandPalette => BindingContext::CommandPalette,
+            FocusedBlock::DeviceDetails => BindingContext::DeviceDetails,
+            FocusedBlock::Media => BindingContext::Media,
+            FocusedBlock::FilePicker => BindingContext::FilePicker,
+            FocusedBlock::PhoneData => BindingContext::PhoneData,
+            FocusedBlock::SerialTerminal => BindingContext::SerialTerminal,
+            FocusedBlock::Advertising => BindingContext::Advertising,
+            FocusedBlock::GattServer => BindingContext::GattServer,
+            FocusedBlock::Beacons => BindingContext::Beacons,
+            FocusedBlock::Sensors => BindingContext::Sensors,
+            FocusedBlock::Recorder => BindingContext::Recorder,
+        }
+    }
+}
+
+// Lines between the header of the tables and their first row
+pub const HEADER_MARGIN: u16 = 1;
+
+// Where the rows of a bordered table with a one line header are drawn in `area`
+pub fn table_rows(area: Rect) -> Rect {
+    let [_, rows] = Layout::vertical([Constraint::Length(1 + HEADER_MARGIN), Constraint::Fill(1)])
+        .areas(Block::bordered().inner(area));
+    rows
+}
+
+// Areas of the last rendered frame, used to find what the mouse points at
+#[derive(Debug, Default, Clone, Copy)]
+pub struct ClickAreas {
+    pub adapter: Rect,
+    pub adapter_offset: usize,
+    pub paired_devices: Rect,
+    pub paired_devices_offset: usize,
+    pub new_devices: Rect,
+    pub new_devices_offset: usize,
+    // "No" and "Yes" buttons of the confirmation popup
+    pub choices: Option<(Rect, Rect)>,
 }
 
 #[derive(Debug)]
@@ -63,15 +167,83 @@ pub struct App {
     pub controllers: Vec<Controller>,
     pub controller_state: TableState,
     pub paired_devices_state: TableState,
-    pub favorite_devices: Vec<Address>,
+    pub favorites: Favorites,
     pub new_devices_state: TableState,
     pub focused_block: FocusedBlock,
     pub new_alias: Input,
+    pub groups: Groups,
+    pub groups_state: TableState,
+    pub group_progress: HashMap<String, GroupProgress>,
+    pub group_name: Input,
+    pub filter: DeviceFilter,
+    pub search: Input,
+    // Block to focus back once the search, the key bindings or the command palette is closed
+    pub previous_focused_block: FocusedBlock,
+    pub key_bindings_search: Input,
+    pub key_bindings_scroll: u16,
+    pub palette: Palette,
+    pub history: History,
+    pub battery_alerts: BatteryAlerts,
+    // Device shown in the details popup
+    pub details: Option<Address>,
+    // System bus, for the objects of BlueZ read in a single call
+    pub bus: Bus,
+    pub media: MediaBus,
+    // Media players and audio transports of the connected devices
+    pub players: Vec<Player>,
+    pub transports: Vec<Transport>,
+    // Device shown in the media player popup
+    pub media_device: Option<Address>,
+    pub network: NetworkBus,
+    // PAN networks of the connected devices
+    pub networks: Vec<Network>,
+    // Adapters sharing the connection of the machine
+    pub sharing: HashSet<String>,
+    // Advertisements of the adapters, with the label of their profile. Dropping the handle
+    // stops the advertisement
+    pub advertisements: HashMap<String, (String, AdvertisementHandle)>,
+    pub advertisement_profiles: Vec<AdvertisementProfile>,
+    pub advertising_editor: Option<AdvertisingEditor>,
+    // A single GATT application is served at a time, the panel is shown when it is focused
+    pub gatt_server: Option<GattServer>,
+    // Advertisements heard by the selected adapter, refreshed while the popup is open
+    pub beacons: Vec<Beacon>,
+    pub beacons_state: TableState,
+    // `None` when obexd can not be reached on the session bus
+    pub obex: Option<ObexClient>,
+    // Accepts the files pushed by the devices, `None` when it could not be registered to obexd
+    pub receive_agent: Option<ReceiveAgent>,
+    pub transfers: Vec<Transfer>,
+    pub file_picker: FilePicker,
+    // Device the file chosen in the picker is sent to
+    pub send_to: Option<Address>,
+    pub phone_data: Option<PhoneData>,
+    pub serial: Option<SerialTerminal>,
+    // Live readings of a LE device, the notifications stop when it is dropped
+    pub sensors: Option<SensorDashboard>,
+    pub recorder: Option<RecorderPicker>,
+    // Running in the background until they are stopped
+    pub recordings: Vec<Recording>,
+    pub add_device: Option<AddDevice>,
+    pub click_areas: ClickAreas,
+    // Time and position of the last click, to detect double clicks
+    pub last_click: Option<(Instant, u16, u16)>,
     pub config: Arc<Config>,
     pub requests: Requests,
     pub auth_agent: AuthAgent,
 }
 
+// Keeps the selection of a device table on a shown device, and returns its row in the table
+fn shown_selection(state: &mut TableState, shown: &[usize], focused: bool) -> Option<usize> {
+    let selected = state.selected();
+    if selected.is_some_and(|i| !shown.contains(&i)) || (focused && selected.is_none()) {
+        state.select(shown.first().copied());
+    }
+    state
+        .selected()
+        .and_then(|i| shown.iter().position(|&row| row == i))
+}
+
 impl App {
     pub async fn new(config: Arc<Config>, sender: UnboundedSender<Event>) -> AppResult<Self> {
         let session = Arc::new(bluer::Session::new().await?);
@@ -103,11 +275,32 @@ impl App {
             ..Default::default()
         };
 
-        let favorite_devices = read_favorite_devices_from_disk().unwrap_or_default();
+        // A file that cannot be read is left as is for the user to fix it
+        let favorites = Favorites::load().unwrap_or_else(|e| {
+            let _ = Notification::send(
+                format!("{e:#}, the favorites are not saved until it is fixed").into(),
+                NotificationLevel::Error,
+                sender.clone(),
+            );
+            Favorites::unreadable()
+        });
+        let groups = Groups::load().unwrap_or_else(|e| {
+            let _ = Notification::send(
+                format!("{e:#}, the groups are not saved until it is fixed").into(),
+                NotificationLevel::Error,
+                sender.clone(),
+            );
+            Groups::unreadable()
+        });
 
         let handle = session.register_agent(agent).await?;
-        let controllers: Vec<Controller> =
-            Controller::get_all(session.clone(), &favorite_devices).await?;
+        let system_bus = Bus::system()?;
+        let controllers: Vec<Controller> = Controller::get_all(
+            &session,
+            &system_bus.managed_objects(BLUEZ).await?,
+            &favorites,
+            &config.devices,
+        )?;
 
         let mut controller_state = TableState::default();
         if controllers.is_empty() {
@@ -116,7 +309,13 @@ impl App {
             controller_state.select(Some(0));
         }
 
-        Ok(Self {
+        let session_bus = Bus::session().ok();
+        let receive_agent = match session_bus.clone() {
+            Some(bus) => ReceiveAgent::register(bus, sender.clone()).await.ok(),
+            None => None,
+        };
+
+        let mut app = Self {
             running: true,
             session,
             agent: handle,
@@ -125,30 +324,322 @@ impl App {
             controllers,
             controller_state,
             paired_devices_state: TableState::default(),
-            favorite_devices,
+            favorites,
             new_devices_state: TableState::default(),
             focused_block: FocusedBlock::PairedDevices,
             new_alias: Input::default(),
+            groups,
+            groups_state: TableState::default(),
+            group_progress: HashMap::new(),
+            group_name: Input::default(),
+            filter: DeviceFilter::default(),
+            search: Input::default(),
+            previous_focused_block: FocusedBlock::PairedDevices,
+            key_bindings_search: Input::default(),
+            key_bindings_scroll: 0,
+            palette: Palette::default(),
+            history: History::load().unwrap_or_default(),
+            battery_alerts: BatteryAlerts::new(&config.battery_alerts),
+            details: None,
+            bus: system_bus.clone(),
+            media: MediaBus::new(system_bus.clone()),
+            players: Vec::new(),
+            transports: Vec::new(),
+            media_device: None,
+            network: NetworkBus::new(system_bus),
+            networks: Vec::new(),
+            sharing: HashSet::new(),
+            advertisements: HashMap::new(),
+            advertisement_profiles: config.advertisements.clone(),
+            advertising_editor: None,
+            gatt_server: None,
+            beacons: Vec::new(),
+            beacons_state: TableState::default(),
+            obex: session_bus.map(ObexClient::new),
+            receive_agent,
+            transfers: Vec::new(),
+            file_picker: FilePicker::default(),
+            send_to: None,
+            phone_data: None,
+            serial: None,
+            sensors: None,
+            recorder: None,
+            recordings: Vec::new(),
+            add_device: None,
+            click_areas: ClickAreas::default(),
+            last_click: None,
             config,
             requests: Requests::default(),
             auth_agent,
-        })
+        };
+
+        let controllers = app.controllers.clone();
+        app.observe_history(&controllers);
+        app.auto_connect(&sender);
+
+        Ok(app)
+    }
+
+    // Adapters to reach the device with, only its preferred one if it is set in the config
+    pub fn adapters_for(&self, address: Address) -> Vec<Arc<Adapter>> {
+        let preferred = self
+            .config
+            .device(address)
+            .and_then(|settings| settings.adapter.as_ref());
+
+        self.controllers
+            .iter()
+            .filter(|c| preferred.is_none_or(|name| c.name == *name))
+            .map(|c| c.adapter.clone())
+            .collect()
+    }
+
+    fn auto_connect(&self, sender: &UnboundedSender<Event>) {
+        for settings in self.config.devices.iter().filter(|s| s.auto_connect) {
+            let address = settings.address;
+            let adapters = self.adapters_for(address);
+            let sender = sender.clone();
+
+            tokio::spawn(async move {
+                let Some(device) = find_paired_device(&adapters, address).await else {
+                    return;
+                };
+                if device.is_connected().await.unwrap_or(false) {
+                    return;
+                }
+                match device.connect().await {
+                    Ok(()) => {
+                        let alias = device.alias().await.unwrap_or(address.to_string());
+                        let _ = Notification::send(
+                            format!("{alias} connected").into(),
+                            NotificationLevel::Info,
+                            sender.clone(),
+                        );
+                    }
+                    Err(e) => {
+                        let _ =
+                            Notification::send(e.into(), NotificationLevel::Error, sender.clone());
+                    }
+                }
+            });
+        }
+    }
+
+    // Record the changes of the devices in the history, before they are filtered
+    fn observe_history(&mut self, controllers: &[Controller]) {
+        let observations: Vec<Observation> = controllers
+            .iter()
+            .flat_map(|controller| {
+                controller
+                    .paired_devices
+                    .iter()
+                    .chain(&controller.new_devices)
+                    .map(|d| Observation {
+                        address: d.addr,
+                        adapter: controller.name.clone(),
+                        is_paired: d.is_paired,
+                        is_connected: d.is_connected,
+                        rssi: d.rssi,
+                        battery: d.battery_percentage,
+                    })
+            })
+            .collect();
+        let adapters: Vec<String> = controllers.iter().map(|c| c.name.clone()).collect();
+
+        let _ = self
+            .history
+            .observe(&observations, &adapters, storage::now());
+
+        for device in controllers.iter().flat_map(|c| &c.paired_devices) {
+            if let Some(percentage) = device.battery_percentage
+                && self.battery_alerts.check(device.addr, percentage).is_some()
+            {
+                // Longer than the other notifications so that it is not missed
+                self.notifications.push(Notification {
+                    message: format!("{} battery is low ({percentage}%)", device.display_name())
+                        .into(),
+                    level: NotificationLevel::Warning,
+                    ttl: 10,
+                });
+            }
+        }
+    }
+
+    pub fn player(&self, address: Address) -> Option<&Player> {
+        self.players.iter().find(|p| p.device == address)
+    }
+
+    // Name of a known device, its address otherwise
+    pub fn device_name(&self, address: Address) -> String {
+        self.controllers
+            .iter()
+            .flat_map(|c| c.paired_devices.iter().chain(&c.new_devices))
+            .find(|d| d.addr == address)
+            .map_or_else(|| address.to_string(), |d| d.display_name().to_string())
+    }
+
+    pub fn update_transfer(&mut self, update: &TransferUpdate) {
+        let Some(index) = self.transfers.iter().position(|t| t.path == update.path) else {
+            return;
+        };
+        self.transfers[index].apply(update);
+
+        let transfer = &self.transfers[index];
+        let name = self.device_name(transfer.device);
+        let notification = match (transfer.status, transfer.incoming) {
+            (TransferStatus::Complete, false) => Some((
+                format!("{} sent to {name}", transfer.file_name),
+                NotificationLevel::Info,
+            )),
+            (TransferStatus::Complete, true) => Some((
+                format!("{} received from {name}", transfer.file_name),
+                NotificationLevel::Info,
+            )),
+            (TransferStatus::Error, false) => Some((
+                format!("Sending {} to {name} failed", transfer.file_name),
+                NotificationLevel::Error,
+            )),
+            (TransferStatus::Error, true) => Some((
+                format!("Receiving {} from {name} failed", transfer.file_name),
+                NotificationLevel::Error,
+            )),
+            _ => None,
+        };
+        if let Some((message, level)) = notification {
+            self.notifications.push(Notification {
+                message: message.into(),
+                level,
+                ttl: 2,
+            });
+            self.transfers.remove(index);
+        }
+    }
+
+    pub fn transport(&self, address: Address) -> Option<&Transport> {
+        self.transports.iter().find(|t| t.device == address)
+    }
+
+    pub fn network(&self, address: Address) -> Option<&Network> {
+        self.networks.iter().find(|n| n.device == address)
+    }
+
+    fn adapter_title(&self, adapter: &str) -> String {
+        let mut title = vec!["Adapter".to_string()];
+        if self.sharing.contains(adapter) {
+            title.push(format!("sharing on {}", self.config.network_bridge));
+        }
+        if let Some((label, _)) = self.advertisements.get(adapter) {
+            title.push(format!("advertising {label}"));
+        }
+        if self
+            .gatt_server
+            .as_ref()
+            .is_some_and(|server| server.adapter == adapter && server.is_serving())
+        {
+            title.push("GATT server".to_string());
+        }
+        format!(" {} ", title.join(" · "))
     }
 
     pub fn reset_devices_state(&mut self) {
         if let Some(selected_controller) = self.controller_state.selected() {
             let controller = &self.controllers[selected_controller];
-            if controller.paired_devices.is_empty() {
-                self.paired_devices_state.select(None);
-            } else {
-                self.paired_devices_state.select(Some(0));
-            }
+            self.paired_devices_state.select(
+                self.shown_devices(&controller.paired_devices)
+                    .first()
+                    .copied(),
+            );
+            self.new_devices_state
+                .select(self.shown_devices(&controller.new_devices).first().copied());
+        }
+    }
 
-            if controller.new_devices.is_empty() {
-                self.new_devices_state.select(None);
-            } else {
-                self.new_devices_state.select(Some(0));
+    // Positions of the devices shown in a device table, the filter hides the others. The
+    // selections are positions in the full lists
+    pub fn shown_devices(&self, devices: &[Device]) -> Vec<usize> {
+        devices
+            .iter()
+            .enumerate()
+            .filter(|(_, device)| self.filter.matches(device))
+            .map(|(i, _)| i)
+            .collect()
+    }
+
+    // Select the next or previous shown device of the focused table
+    pub fn select_next_device(&mut self, forward: bool) {
+        let Some(controller) = self
+            .controller_state
+            .selected()
+            .and_then(|i| self.controllers.get(i))
+        else {
+            return;
+        };
+        let (shown, state) = match self.focused_block {
+            FocusedBlock::PairedDevices => (
+                self.shown_devices(&controller.paired_devices),
+                &mut self.paired_devices_state,
+            ),
+            FocusedBlock::NewDevices => (
+                self.shown_devices(&controller.new_devices),
+                &mut self.new_devices_state,
+            ),
+            _ => return,
+        };
+        if let Some(i) = next_shown(&shown, state.selected(), forward) {
+            state.select(Some(i));
+        }
+    }
+
+    // Select the next or previous match of the search, going from the paired devices to the
+    // discovered ones and back
+    pub fn jump_to_match(&mut self, forward: bool) {
+        let Some(controller) = self
+            .controller_state
+            .selected()
+            .and_then(|i| self.controllers.get(i))
+        else {
+            return;
+        };
+        let current = match self.focused_block {
+            FocusedBlock::PairedDevices => self.paired_devices_state.selected(),
+            FocusedBlock::NewDevices => self.new_devices_state.selected(),
+            _ => return,
+        }
+        .map(|i| (self.focused_block, i));
+
+        let mut matches: Vec<(FocusedBlock, usize)> = self
+            .shown_devices(&controller.paired_devices)
+            .into_iter()
+            .map(|i| (FocusedBlock::PairedDevices, i))
+            .collect();
+        // The discovered devices are only shown while scanning
+        if controller.is_scanning.load(Ordering::Relaxed) {
+            matches.extend(
+                self.shown_devices(&controller.new_devices)
+                    .into_iter()
+                    .map(|i| (FocusedBlock::NewDevices, i)),
+            );
+        }
+        if matches.is_empty() {
+            return;
+        }
+
+        let next = match current.and_then(|current| matches.iter().position(|m| *m == current)) {
+            Some(p) if forward => (p + 1) % matches.len(),
+            Some(p) => (p + matches.len() - 1) % matches.len(),
+            None => 0,
+        };
+        let (block, i) = matches[next];
+        if block != self.focused_block {
+            match self.focused_block {
+                FocusedBlock::PairedDevices => self.paired_devices_state.select(None),
+                _ => self.new_devices_state.select(None),
             }
+            self.focused_block = block;
+        }
+        match block {
+            FocusedBlock::PairedDevices => self.paired_devices_state.select(Some(i)),
+            _ => self.new_devices_state.select(Some(i)),
         }
     }
 
@@ -214,11 +705,11 @@ impl App {
                 } else {
                     CONTROLLER_TABLE_HEADER.map(Cell::new)
                 })
-                .bottom_margin(1),
+                .bottom_margin(HEADER_MARGIN),
             )
             .block(
                 Block::bordered()
-                    .title(" Adapter ")
+                    .title(self.adapter_title(&self.controllers[selected_controller_index].name))
                     .title_style({
                         if self.focused_block == FocusedBlock::Adapter {
                             Style::default().bold()
@@ -253,6 +744,7 @@ impl App {
             controller_block,
             &mut self.controller_state,
         );
+        self.click_areas.adapter_offset = self.controller_state.offset();
 
         if rows_len > controller_block.height.saturating_sub(4) as usize {
             let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
@@ -277,21 +769,39 @@ impl App {
         selected_controller_index: usize,
         frame: &mut Frame,
     ) {
+        let shown = self.shown_devices(&self.controllers[selected_controller_index].paired_devices);
+        let selected_row = shown_selection(
+            &mut self.paired_devices_state,
+            &shown,
+            self.focused_block == FocusedBlock::PairedDevices,
+        );
         let selected_controller = &self.controllers[selected_controller_index];
-        let rows: Vec<Row> = selected_controller
+
+        let show_battery_column = selected_controller
             .paired_devices
             .iter()
+            .any(|device| device.battery_percentage.is_some());
+
+        // The history columns only fit in wide windows, it is always in the details
+        let show_history_columns = paired_devices_block.width >= 110;
+        let now = storage::now();
+
+        let rows: Vec<Row> = shown
+            .iter()
+            .map(|&i| &selected_controller.paired_devices[i])
             .map(|d| {
-                Row::new(vec![
-                    if d.is_favorite {
+                let mut name = self.filter.highlight(d.display_name());
+                name.spans.insert(0, Span::from(format!("{} ", d.icon)));
+                let mut cells = vec![
+                    Cell::from(if d.is_favorite {
                         STAR_SYMBOL.to_string()
                     } else {
                         String::new()
-                    },
-                    format!("{} {}", &d.icon, &d.alias),
-                    d.is_trusted.to_string(),
-                    d.is_connected.to_string(),
-                    {
+                    }),
+                    Cell::from(name),
+                    Cell::from(d.is_trusted.to_string()),
+                    Cell::from(d.is_connected.to_string()),
+                    Cell::from({
                         if let Some(battery_percentage) = d.battery_percentage {
                             match battery_percentage {
                                 n if n >= 90 => {
@@ -328,57 +838,73 @@ impl App {
                         } else {
                             String::new()
                         }
-                    },
-                ])
+                    }),
+                ];
+                if !show_battery_column {
+                    cells.pop();
+                }
+                if show_history_columns {
+                    let history = self.history.device(d.addr);
+                    cells.extend([
+                        Cell::from(
+                            history
+                                .and_then(|h| h.last_connected(now))
+                                .map(|time| format_ago(time, now))
+                                .unwrap_or_default(),
+                        ),
+                        Cell::from(
+                            history
+                                .and_then(|h| h.last_seen(now))
+                                .map(|time| format_ago(time, now))
+                                .unwrap_or_default(),
+                        ),
+                        Cell::from(
+                            history
+                                .map(|h| format_duration(h.total_connected_time(now)))
+                                .unwrap_or_default(),
+                        ),
+                    ]);
+                }
+                Row::new(cells)
             })
             .collect();
         let rows_len = rows.len();
 
-        if rows_len > 0
-            && self.focused_block == FocusedBlock::PairedDevices
-            && self.paired_devices_state.selected().is_none()
-        {
-            self.paired_devices_state.select(Some(0));
-        }
-
-        let show_battery_column = selected_controller
-            .paired_devices
-            .iter()
-            .any(|device| device.battery_percentage.is_some());
-
         let mut widths = vec![
             Constraint::Length(1),
             Constraint::Max(25),
             Constraint::Length(7),
             Constraint::Length(9),
         ];
+        let mut header = vec!["", "Name", "Trusted", "Connected"];
 
         if show_battery_column {
             widths.push(Constraint::Length(10));
+            header.push("Battery");
+        }
+
+        if show_history_columns {
+            widths.extend([
+                Constraint::Length(14),
+                Constraint::Length(10),
+                Constraint::Length(14),
+            ]);
+            header.extend(["Last connected", "Last seen", "Connected for"]);
         }
 
         let paired_devices_table = Table::new(rows, widths)
             .header(
-                if show_battery_column {
-                    if self.focused_block == FocusedBlock::PairedDevices {
-                        Row::new(["", "Name", "Trusted", "Connected", "Battery"])
-                            .yellow()
-                            .bold()
-                    } else {
-                        Row::new(["", "Name", "Trusted", "Connected", "Battery"])
-                    }
-                } else if self.focused_block == FocusedBlock::PairedDevices {
-                    Row::new(["", "Name", "Trusted", "Connected"])
-                        .yellow()
-                        .bold()
+                if self.focused_block == FocusedBlock::PairedDevices {
+                    Row::new(header).yellow().bold()
                 } else {
-                    Row::new(["", "Name", "Trusted", "Connected", "Battery"])
+                    Row::new(header)
                 }
-                .bottom_margin(1),
+                .bottom_margin(HEADER_MARGIN),
             )
             .block(
                 Block::bordered()
                     .title(" Paired Devices ")
+                    .title_bottom(self.filter_title())
                     .title_style(if self.focused_block == FocusedBlock::PairedDevices {
                         Style::default().bold()
                     } else {
@@ -406,18 +932,22 @@ impl App {
                 Style::default()
             });
 
+        let mut paired_devices_state = TableState::default()
+            .with_offset(self.click_areas.paired_devices_offset)
+            .with_selected(selected_row);
         frame.render_stateful_widget(
             paired_devices_table,
             paired_devices_block,
-            &mut self.paired_devices_state.clone(),
+            &mut paired_devices_state,
         );
+        self.click_areas.paired_devices_offset = paired_devices_state.offset();
 
         if rows_len > paired_devices_block.height.saturating_sub(4) as usize {
             let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
                 .begin_symbol(Some("↑"))
                 .end_symbol(Some("↓"));
-            let mut scrollbar_state = ScrollbarState::new(rows_len)
-                .position(self.paired_devices_state.selected().unwrap_or_default());
+            let mut scrollbar_state =
+                ScrollbarState::new(rows_len).position(selected_row.unwrap_or_default());
             frame.render_stateful_widget(
                 scrollbar,
                 paired_devices_block.inner(Margin {
@@ -435,14 +965,22 @@ impl App {
         selected_controller_index: usize,
         frame: &mut Frame,
     ) {
+        let shown = self.shown_devices(&self.controllers[selected_controller_index].new_devices);
+        let selected_row = shown_selection(
+            &mut self.new_devices_state,
+            &shown,
+            self.focused_block == FocusedBlock::NewDevices,
+        );
         let selected_controller = &self.controllers[selected_controller_index];
-        let rows: Vec<Row> = selected_controller
-            .new_devices
+        let rows: Vec<Row> = shown
             .iter()
+            .map(|&i| &selected_controller.new_devices[i])
             .map(|d| {
+                let mut name = self.filter.highlight(d.display_name());
+                name.spans.insert(0, Span::from(format!("{} ", d.icon)));
                 Row::new(vec![
-                    d.addr.to_string(),
-                    format!("{} {}", &d.icon, &d.alias),
+                    Cell::from(self.filter.highlight(&d.addr.to_string())),
+                    Cell::from(name),
                 ])
             })
             .collect();
@@ -464,7 +1002,7 @@ impl App {
                     ]
                 })
                 .bold()
-                .bottom_margin(1),
+                .bottom_margin(HEADER_MARGIN),
             )
             .block(
                 Block::bordered()
@@ -474,6 +1012,7 @@ impl App {
                     } else {
                         String::from(" Discovered devices ")
                     })
+                    .title_bottom(self.filter_title())
                     .title_style(if self.focused_block == FocusedBlock::NewDevices {
                         Style::default().bold()
                     } else {
@@ -497,25 +1036,18 @@ impl App {
                 Style::default()
             });
 
-        if rows_len > 0
-            && self.focused_block == FocusedBlock::NewDevices
-            && self.new_devices_state.selected().is_none()
-        {
-            self.new_devices_state.select(Some(0));
-        }
-
-        frame.render_stateful_widget(
-            new_devices_table,
-            new_devices_block,
-            &mut self.new_devices_state,
-        );
+        let mut new_devices_state = TableState::default()
+            .with_offset(self.click_areas.new_devices_offset)
+            .with_selected(selected_row);
+        frame.render_stateful_widget(new_devices_table, new_devices_block, &mut new_devices_state);
+        self.click_areas.new_devices_offset = new_devices_state.offset();
 
         if rows_len > new_devices_block.height.saturating_sub(4) as usize {
             let scrollbar = Scrollbar::new(ScrollbarOrientation::VerticalRight)
                 .begin_symbol(Some("↑"))
                 .end_symbol(Some("↓"));
-            let mut scrollbar_state = ScrollbarState::new(rows_len)
-                .position(self.new_devices_state.selected().unwrap_or_default());
+            let mut scrollbar_state =
+                ScrollbarState::new(rows_len).position(selected_row.unwrap_or_default());
             frame.render_stateful_widget(
                 scrollbar,
                 new_devices_block.inner(Margin {
@@ -527,57 +1059,65 @@ impl App {
         }
     }
 
-    fn render_unpair_confirmation(&self, confirm: bool, frame: &mut Frame, area: Rect) {
-        let center_area = area.centered(Constraint::Max(70), Constraint::Length(6));
-        frame.render_widget(Clear, center_area);
-
-        let block = Block::bordered()
-            .border_type(BorderType::Thick)
-            .border_style(Style::default().green());
-        frame.render_widget(&block, center_area);
-
-        let inside_area = block.inner(center_area);
+    // Search prompt and enabled quick filters, shown at the bottom of the device tables
+    fn filter_title(&self) -> Line<'static> {
+        let mut spans: Vec<Span> = Vec::new();
+        if self.focused_block == FocusedBlock::Search {
+            spans.push(format!("/{}", self.search.value()).yellow().bold());
+            spans.push("█".yellow());
+        } else if !self.filter.query.is_empty() {
+            spans.push(format!("/{}", self.filter.query).bold());
+        }
 
-        let [message_block, choices_block] = inside_area.layout(&Layout::vertical([
-            Constraint::Length(1),
-            Constraint::Fill(1),
-        ]));
+        for name in self.filter.quick_filters() {
+            if !spans.is_empty() {
+                spans.push(" ".into());
+            }
+            spans.push(format!("[{name}]").into());
+        }
 
-        let Some(selected_controller) = self.controller_state.selected() else {
-            frame.render_widget("Trying to unpair with no controller selected!", inside_area);
-            return;
-        };
-        let controller = &self.controllers[selected_controller];
+        if spans.is_empty() {
+            return Line::default();
+        }
+        spans.insert(0, " ".into());
+        spans.push(" ".into());
+        Line::from(spans)
+    }
 
-        let Some(selected_device) = self.paired_devices_state.selected() else {
-            frame.render_widget("No device selected for unpairing.", inside_area);
-            return;
+    fn render_unpair_confirmation(&mut self, confirm: bool, frame: &mut Frame, area: Rect) {
+        let message = if let Some(selected_controller) = self.controller_state.selected() {
+            let controller = &self.controllers[selected_controller];
+            match self.paired_devices_state.selected() {
+                Some(selected_device) => Line::from(vec![
+                    " Are you sure you want to unpair ".into(),
+                    controller.paired_devices[selected_device]
+                        .display_name()
+                        .to_string()
+                        .italic(),
+                    " ".into(),
+                ]),
+                None => Line::from("No device selected for unpairing."),
+            }
+        } else {
+            Line::from("Trying to unpair with no controller selected!")
         };
+        self.click_areas.choices = Some(render_confirmation(message, confirm, frame, area));
+    }
 
-        let device_alias = &controller.paired_devices[selected_device].alias;
-        frame.render_widget(
-            Line::from(vec![
-                " Are you sure you want to unpair ".into(),
-                device_alias.to_span().italic(),
+    fn render_delete_group_confirmation(&mut self, confirm: bool, frame: &mut Frame, area: Rect) {
+        let message = match self
+            .groups_state
+            .selected()
+            .and_then(|index| self.groups.entries.get(index))
+        {
+            Some(group) => Line::from(vec![
+                " Are you sure you want to delete the group ".into(),
+                group.name.clone().italic(),
                 " ".into(),
-            ])
-            .centered(),
-            message_block,
-        );
-
-        let [no_block, yes_block] = choices_block
-            .layout(&Layout::horizontal([Constraint::Fill(1); 2]))
-            .map(|b| b.centered_vertically(Constraint::Length(1)));
-
-        let no = "No".to_line().centered();
-        let yes = "Yes".to_line().centered();
-        if confirm {
-            frame.render_widget(no, no_block);
-            frame.render_widget(yes.on_dark_gray().bold(), yes_block);
-        } else {
-            frame.render_widget(no.on_dark_gray().bold(), no_block);
-            frame.render_widget(yes, yes_block);
-        }
+            ]),
+            None => Line::from("No group selected for deletion."),
+        };
+        self.click_areas.choices = Some(render_confirmation(message, confirm, frame, area));
     }
 
     pub fn render(&mut self, frame: &mut Frame) {
@@ -595,17 +1135,24 @@ impl App {
             }
 
             let adapter_block_height = self.controllers.len() as u16 + 4;
+            let transfers_block_height = if self.transfers.is_empty() {
+                0
+            } else {
+                self.transfers.len() as u16 + 2
+            };
 
             let [
                 paired_devices_block,
                 new_devices_block,
                 controller_block,
+                transfers_block,
                 help_block,
             ] = Layout::vertical(if render_new_devices {
                 [
                     Constraint::Length(paired_devices_block_height),
                     Constraint::Fill(1),
                     Constraint::Length(adapter_block_height),
+                    Constraint::Length(transfers_block_height),
                     Constraint::Length(2),
                 ]
             } else {
@@ -613,12 +1160,27 @@ impl App {
                     Constraint::Fill(1),
                     Constraint::Length(0),
                     Constraint::Length(adapter_block_height),
+                    Constraint::Length(transfers_block_height),
                     Constraint::Length(2),
                 ]
             })
             .margin(1)
             .areas(self.area(frame));
 
+            self.click_areas = ClickAreas {
+                adapter: controller_block,
+                paired_devices: paired_devices_block,
+                new_devices: if render_new_devices {
+                    new_devices_block
+                } else {
+                    Rect::default()
+                },
+                // The tables keep scrolling from where they were
+                paired_devices_offset: self.click_areas.paired_devices_offset,
+                new_devices_offset: self.click_areas.new_devices_offset,
+                ..ClickAreas::default()
+            };
+
             self.render_paired_devices(paired_devices_block, selected_controller_index, frame);
 
             if render_new_devices {
@@ -627,6 +1189,15 @@ impl App {
 
             self.render_controllers(controller_block, selected_controller_index, frame);
 
+            if !self.transfers.is_empty() {
+                render_transfers(
+                    &self.transfers,
+                    |address| self.device_name(address),
+                    frame,
+                    transfers_block,
+                );
+            }
+
             let popup_area = self.area(frame);
 
             Help::render(
@@ -648,9 +1219,193 @@ impl App {
                 );
             }
 
+            if self.focused_block == FocusedBlock::AddDevice
+                && let Some(add_device) = &self.add_device
+            {
+                add_device.render(frame, popup_area);
+            }
+
+            if matches!(
+                self.focused_block,
+                FocusedBlock::Groups | FocusedBlock::DeleteGroupConfirmation { .. }
+            ) {
+                let connected_devices: Vec<Address> = self
+                    .controllers
+                    .iter()
+                    .flat_map(|c| c.paired_devices.iter())
+                    .filter(|d| d.is_connected)
+                    .map(|d| d.addr)
+                    .collect();
+                render_groups(
+                    &self.groups,
+                    &mut self.groups_state,
+                    &self.group_progress,
+                    &connected_devices,
+                    frame,
+                    popup_area,
+                );
+            }
+
+            if self.focused_block == FocusedBlock::AddToGroup
+                && let Some(index) = self.paired_devices_state.selected()
+            {
+                let device = &self.controllers[selected_controller_index].paired_devices[index];
+                render_add_to_group(
+                    device.display_name(),
+                    &self.groups,
+                    &self.group_name,
+                    frame,
+                    popup_area,
+                );
+            }
+
+            if self.focused_block == FocusedBlock::DeviceDetails
+                && let Some(address) = self.details
+                && let Some((device, adapter)) = self.controllers.iter().find_map(|c| {
+                    c.paired_devices
+                        .iter()
+                        .chain(&c.new_devices)
+                        .find(|d| d.addr == address)
+                        .map(|d| (d, &c.name))
+                })
+            {
+                render_device_details(
+                    device,
+                    adapter,
+                    self.history.device(address),
+                    Connections {
+                        transport: self.transport(address),
+                        network: self.network(address),
+                    },
+                    storage::now(),
+                    frame,
+                    popup_area,
+                );
+            }
+
+            if self.focused_block == FocusedBlock::Media
+                && let Some(address) = self.media_device
+                && let Some(device) = self
+                    .controllers
+                    .iter()
+                    .find_map(|c| c.paired_devices.iter().find(|d| d.addr == address))
+            {
+                render_media(
+                    device.display_name(),
+                    self.player(address),
+                    frame,
+                    popup_area,
+                );
+            }
+
+            if self.focused_block == FocusedBlock::FilePicker
+                && let Some(address) = self.send_to
+            {
+                let title = format!("Send a file to {}", self.device_name(address));
+                self.file_picker.render(&title, frame, popup_area);
+            }
+
+            if self.focused_block == FocusedBlock::PhoneData
+                && let Some(address) = self.phone_data.as_ref().map(|p| p.device)
+            {
+                let name = self.device_name(address);
+                if let Some(phone_data) = &mut self.phone_data {
+                    phone_data.render(&name, frame, popup_area);
+                }
+            }
+
+            if self.focused_block == FocusedBlock::SerialTerminal
+                && let Some(address) = self.serial.as_ref().map(|s| s.device)
+            {
+                let name = self.device_name(address);
+                if let Some(serial) = &mut self.serial {
+                    serial.render(&name, frame, popup_area);
+                }
+            }
+
+            if self.focused_block == FocusedBlock::Sensors
+                && let Some(sensors) = &self.sensors
+            {
+                sensors.render(
+                    &self.device_name(sensors.device),
+                    storage::now(),
+                    frame,
+                    popup_area,
+                );
+            }
+
+            if self.focused_block == FocusedBlock::Recorder
+                && let Some(picker) = &mut self.recorder
+            {
+                let name = self
+                    .controllers
+                    .iter()
+                    .flat_map(|c| c.paired_devices.iter())
+                    .find(|d| d.addr == picker.device)
+                    .map_or_else(
+                        || picker.device.to_string(),
+                        |d| d.display_name().to_string(),
+                    );
+                let recording = self.recordings.iter().find(|r| r.device == picker.device);
+                picker.render(&name, recording, frame, popup_area);
+            }
+
+            if self.focused_block == FocusedBlock::Advertising
+                && let Some(editor) = &mut self.advertising_editor
+            {
+                let advertising = self
+                    .advertisements
+                    .get(&editor.adapter)
+                    .map(|(label, _)| label.as_str());
+                editor.render(advertising, frame, popup_area);
+            }
+
+            if self.focused_block == FocusedBlock::GattServer
+                && let Some(server) = &self.gatt_server
+            {
+                let connected = |address: Address| {
+                    self.controllers
+                        .iter()
+                        .filter(|controller| controller.name == server.adapter)
+                        .flat_map(|controller| {
+                            controller
+                                .paired_devices
+                                .iter()
+                                .chain(&controller.new_devices)
+                        })
+                        .any(|device| device.addr == address && device.is_connected)
+                };
+                server.render(connected, frame, popup_area);
+            }
+
+            if self.focused_block == FocusedBlock::Beacons {
+                render_beacons(
+                    &self.beacons,
+                    render_new_devices,
+                    &mut self.beacons_state,
+                    frame,
+                    popup_area,
+                );
+            }
+
+            if self.focused_block == FocusedBlock::CommandPalette {
+                self.palette.render(frame, popup_area);
+            }
+
+            if self.focused_block == FocusedBlock::KeyBindings {
+                render_key_bindings(
+                    &self.config,
+                    &self.key_bindings_search,
+                    &mut self.key_bindings_scroll,
+                    frame,
+                    popup_area,
+                );
+            }
+
             // Request Confirmation
             if let Some(req) = &self.requests.confirmation {
                 req.render(frame, popup_area);
+                self.click_areas.choices = Some(Confirmation::choices(popup_area));
             }
 
             // Unpair Confirmation
@@ -658,6 +1413,11 @@ impl App {
                 self.render_unpair_confirmation(confirm, frame, popup_area);
             }
 
+            // Group deletion Confirmation
+            if let FocusedBlock::DeleteGroupConfirmation { confirm } = self.focused_block {
+                self.render_delete_group_confirmation(confirm, frame, popup_area);
+            }
+
             // Request to enter pin code
             if let Some(req) = &self.requests.enter_pin_code {
                 req.render(frame, popup_area);
@@ -677,6 +1437,11 @@ impl App {
             if let Some(req) = &self.requests.display_passkey {
                 req.render(frame, popup_area);
             }
+
+            // File pushed by a device
+            if let Some(req) = &self.requests.receive_file {
+                req.render(&self.device_name(req.device), frame, popup_area);
+            }
         } else {
             frame.render_widget("No Bluetooth adapters found. This will automatically refresh when any adapter is detected!".red().bold(), self.area(frame));
         }
@@ -689,13 +1454,41 @@ impl App {
         if self.spinner.active {
             self.spinner.update();
         }
+
+        self.group_progress
+            .retain(|_, progress| !progress.is_done());
         self.refresh().await?;
         Ok(())
     }
 
     pub async fn refresh(&mut self) -> AppResult<()> {
-        let refreshed_controllers =
-            Controller::get_all(self.session.clone(), &self.favorite_devices).await?;
+        // The adapters, the devices, the media, the networks and the beacons are read from the
+        // same objects, in a single call on each tick
+        let objects = self.bus.managed_objects(BLUEZ).await?;
+        let refreshed_controllers = Controller::get_all(
+            &self.session,
+            &objects,
+            &self.favorites,
+            &self.config.devices,
+        )?;
+
+        // Keep the alias and adapter of the favorites up to date
+        let mut favorites_changed = false;
+        for controller in &refreshed_controllers {
+            for device in &controller.paired_devices {
+                favorites_changed |=
+                    self.favorites
+                        .update(device.addr, &device.alias, &controller.name);
+            }
+        }
+        if favorites_changed {
+            let _ = self.favorites.save();
+        }
+
+        self.observe_history(&refreshed_controllers);
+
+        (self.players, self.transports) = media::from_objects(&objects);
+        self.networks = network::from_objects(&objects);
 
         // Remove unplugged adapters in a single pass
         let mut adapter_removed = false;
@@ -768,11 +1561,103 @@ impl App {
             }
         }
 
+        if self.focused_block == FocusedBlock::Beacons {
+            self.update_beacons(&objects);
+        }
+
         Ok(())
     }
 
+    pub async fn refresh_beacons(&mut self) {
+        let objects = self.bus.managed_objects(BLUEZ).await.unwrap_or_default();
+        self.update_beacons(&objects);
+    }
+
+    fn update_beacons(&mut self, objects: &ManagedObjects) {
+        let Some(controller) = self
+            .controller_state
+            .selected()
+            .and_then(|i| self.controllers.get(i))
+        else {
+            return;
+        };
+        self.beacons = beacon::from_objects(objects, &controller.name);
+
+        let rows = self.beacons.iter().map(|b| b.frames.len()).sum::<usize>();
+        if rows == 0 {
+            self.beacons_state.select(None);
+        } else if self.beacons_state.selected().is_none_or(|i| i >= rows) {
+            self.beacons_state.select(Some(0));
+        }
+    }
+
+    pub fn toggle_favorite(&mut self, address: Address, sender: UnboundedSender<Event>) {
+        let device = self.controllers.iter().find_map(|controller| {
+            controller
+                .paired_devices
+                .iter()
+                .find(|d| d.addr == address)
+                .map(|d| (d.alias.clone(), controller.name.clone()))
+        });
+        let (alias, adapter) = device.unzip();
+
+        self.favorites.toggle(address, alias, adapter);
+
+        if let Err(e) = self.favorites.save() {
+            let _ = Notification::send(e.into(), NotificationLevel::Error, sender);
+        }
+
+        for controller in &mut self.controllers {
+            for device in &mut controller.paired_devices {
+                if device.addr == address {
+                    device.is_favorite = self.favorites.contains(address);
+                }
+            }
+        }
+    }
+
     pub fn quit(&mut self) {
-        let _ = save_favorite_devices_to_disk(&self.favorite_devices);
+        let _ = self.history.stop(storage::now());
         self.running = false;
     }
 }
+
+// Yes/No popup, "No" is selected unless `confirm` is set. Returns the areas of the choices
+fn render_confirmation(
+    message: Line,
+    confirm: bool,
+    frame: &mut Frame,
+    area: Rect,
+) -> (Rect, Rect) {
+    let center_area = area.centered(Constraint::Max(70), Constraint::Length(6));
+    frame.render_widget(Clear, center_area);
+
+    let block = Block::bordered()
+        .border_type(BorderType::Thick)
+        .border_style(Style::default().green());
+    frame.render_widget(&block, center_area);
+
+    let inside_area = block.inner(center_area);
+
+    let [message_block, choices_block] = inside_area.layout(&Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Fill(1),
+    ]));
+
+    frame.render_widget(message.centered(), message_block);
+
+    let [no_block, yes_block] = choices_block
+        .layout(&Layout::horizontal([Constraint::Fill(1); 2]))
+        .map(|b| b.centered_vertically(Constraint::Length(1)));
+
+    let no = "No".to_line().centered();
+    let yes = "Yes".to_line().centered();
+    if confirm {
+        frame.render_widget(no, no_block);
+        frame.render_widget(yes.on_dark_gray().bold(), yes_block);
+    } else {
+        frame.render_widget(no.on_dark_gray().bold(), no_block);
+        frame.render_widget(yes, yes_block);
+    }
+    (no_block, yes_block)
+}
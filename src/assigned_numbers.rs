@@ -0,0 +1,349 @@
+// Names of the identifiers assigned by the Bluetooth SIG, from
+// https://bitbucket.org/bluetooth-SIG/public/src/main/assigned_numbers/
+//
+// The services, characteristics, companies and appearances are generated in `sig.rs`, the classes
+// of device are kept here.
+
+mod sig;
+
+use bluer::Uuid;
+
+use sig::{APPEARANCE_CATEGORIES, APPEARANCE_SUBCATEGORIES, CHARACTERISTICS, COMPANIES, SERVICES};
+
+// Suffix of the UUIDs derived from the Bluetooth base UUID
+pub const BASE_UUID_SUFFIX: &str = "-0000-1000-8000-00805f9b34fb";
+
+// Major classes of the class of device, bits 12 to 8
+const MAJOR_CLASSES: &[(u32, &str)] = &[
+    (0x00, "Miscellaneous"),
+    (0x01, "Computer"),
+    (0x02, "Phone"),
+    (0x03, "Network Access Point"),
+    (0x04, "Audio/Video"),
+    (0x05, "Peripheral"),
+    (0x06, "Imaging"),
+    (0x07, "Wearable"),
+    (0x08, "Toy"),
+    (0x09, "Health"),
+    (0x1f, "Uncategorized"),
+];
+
+// Minor classes, bits 7 to 2, by major class. The peripherals and imaging devices combine bit
+// fields and are decoded apart
+const MINOR_CLASSES: &[(u32, u32, &str)] = &[
+    (0x01, 0x01, "Desktop Workstation"),
+    (0x01, 0x02, "Server-class Computer"),
+    (0x01, 0x03, "Laptop"),
+    (0x01, 0x04, "Handheld PC/PDA"),
+    (0x01, 0x05, "Palm-size PC/PDA"),
+    (0x01, 0x06, "Wearable Computer"),
+    (0x01, 0x07, "Tablet"),
+    (0x02, 0x01, "Cellular"),
+    (0x02, 0x02, "Cordless"),
+    (0x02, 0x03, "Smartphone"),
+    (0x02, 0x04, "Wired Modem or Voice Gateway"),
+    (0x02, 0x05, "Common ISDN Access"),
+    (0x04, 0x01, "Wearable Headset"),
+    (0x04, 0x02, "Hands-free Device"),
+    (0x04, 0x04, "Microphone"),
+    (0x04, 0x05, "Loudspeaker"),
+    (0x04, 0x06, "Headphones"),
+    (0x04, 0x07, "Portable Audio"),
+    (0x04, 0x08, "Car Audio"),
+    (0x04, 0x09, "Set-top Box"),
+    (0x04, 0x0a, "HiFi Audio Device"),
+    (0x04, 0x0b, "VCR"),
+    (0x04, 0x0c, "Video Camera"),
+    (0x04, 0x0d, "Camcorder"),
+    (0x04, 0x0e, "Video Monitor"),
+    (0x04, 0x0f, "Video Display and Loudspeaker"),
+    (0x04, 0x10, "Video Conferencing"),
+    (0x04, 0x12, "Gaming/Toy"),
+    (0x07, 0x01, "Wristwatch"),
+    (0x07, 0x02, "Pager"),
+    (0x07, 0x03, "Jacket"),
+    (0x07, 0x04, "Helmet"),
+    (0x07, 0x05, "Glasses"),
+    (0x08, 0x01, "Robot"),
+    (0x08, 0x02, "Vehicle"),
+    (0x08, 0x03, "Doll/Action Figure"),
+    (0x08, 0x04, "Controller"),
+    (0x08, 0x05, "Game"),
+    (0x09, 0x01, "Blood Pressure Monitor"),
+    (0x09, 0x02, "Thermometer"),
+    (0x09, 0x03, "Weighing Scale"),
+    (0x09, 0x04, "Glucose Meter"),
+    (0x09, 0x05, "Pulse Oximeter"),
+    (0x09, 0x06, "Heart/Pulse Rate Monitor"),
+    (0x09, 0x07, "Health Data Display"),
+    (0x09, 0x08, "Step Counter"),
+    (0x09, 0x09, "Body Composition Analyzer"),
+    (0x09, 0x0a, "Peak Flow Monitor"),
+    (0x09, 0x0b, "Medication Monitor"),
+    (0x09, 0x0c, "Knee Prosthesis"),
+    (0x09, 0x0d, "Ankle Prosthesis"),
+    (0x09, 0x0e, "Generic Health Manager"),
+    (0x09, 0x0f, "Personal Mobility Device"),
+];
+
+const PERIPHERAL: u32 = 0x05;
+const IMAGING: u32 = 0x06;
+
+const PERIPHERAL_KINDS: [&str; 4] = [
+    "",
+    "Keyboard",
+    "Pointing Device",
+    "Keyboard/Pointing Device",
+];
+const PERIPHERAL_TYPES: [&str; 10] = [
+    "",
+    "Joystick",
+    "Gamepad",
+    "Remote Control",
+    "Sensing Device",
+    "Digitizer Tablet",
+    "Card Reader",
+    "Digital Pen",
+    "Handheld Scanner",
+    "Gestural Input Device",
+];
+const IMAGING_KINDS: [&str; 4] = ["Display", "Camera", "Scanner", "Printer"];
+
+// The tables are sorted by their keys
+fn lookup<K: Ord + Copy>(table: &[(K, &'static str)], key: K) -> Option<&'static str> {
+    table
+        .binary_search_by_key(&key, |(entry, _)| *entry)
+        .ok()
+        .map(|index| table[index].1)
+}
+
+// The UUID derived from the base UUID, e.g. 0000180d-0000-1000-8000-00805f9b34fb for 0x180d
+pub fn uuid16(short: u16) -> Uuid {
+    Uuid::from_u128((u128::from(short) << 96) | 0x0000_0000_0000_1000_8000_0080_5f9b_34fb)
+}
+
+// The 16 bit UUID of the UUIDs derived from the base UUID
+pub fn short_uuid(uuid: &Uuid) -> Option<u16> {
+    let uuid = uuid.to_string();
+    if !uuid.starts_with("0000") || !uuid.ends_with(BASE_UUID_SUFFIX) {
+        return None;
+    }
+    u16::from_str_radix(&uuid[4..8], 16).ok()
+}
+
+pub fn service_name(uuid: &Uuid) -> Option<&'static str> {
+    lookup(SERVICES, short_uuid(uuid)?)
+}
+
+// The name of the service, its UUID otherwise
+pub fn service_label(uuid: &Uuid) -> String {
+    match service_name(uuid) {
+        Some(name) => name.to_string(),
+        None => match short_uuid(uuid) {
+            Some(short) => format!("{short:#06x}"),
+            None => uuid.to_string(),
+        },
+    }
+}
+
+pub fn characteristic_name(uuid: &Uuid) -> Option<&'static str> {
+    lookup(CHARACTERISTICS, short_uuid(uuid)?)
+}
+
+pub fn company_name(id: u16) -> Option<&'static str> {
+    lookup(COMPANIES, id)
+}
+
+// e.g. "Keyboard (Human Interface Device)"
+pub fn appearance(value: u16) -> Option<String> {
+    let (category, subcategory) = (value >> 6, value & 0x3f);
+    let category_name = lookup(APPEARANCE_CATEGORIES, category)?;
+    let subcategory_name = APPEARANCE_SUBCATEGORIES
+        .binary_search_by_key(&(category, subcategory), |(c, s, _)| (*c, *s))
+        .ok()
+        .map(|index| APPEARANCE_SUBCATEGORIES[index].2);
+    Some(match subcategory_name {
+        Some(name) => format!("{name} ({category_name})"),
+        None => category_name.to_string(),
+    })
+}
+
+fn minor_class(major: u32, minor: u32) -> Option<String> {
+    match major {
+        PERIPHERAL => {
+            let names: Vec<&str> = [
+                PERIPHERAL_KINDS[(minor >> 4) as usize & 0x03],
+                PERIPHERAL_TYPES.get((minor & 0x0f) as usize).copied()?,
+            ]
+            .into_iter()
+            .filter(|name| !name.is_empty())
+            .collect();
+            (!names.is_empty()).then(|| names.join(" "))
+        }
+        IMAGING => {
+            let names: Vec<&str> = IMAGING_KINDS
+                .iter()
+                .enumerate()
+                .filter(|(bit, _)| minor >> 2 & (1 << bit) != 0)
+                .map(|(_, name)| *name)
+                .collect();
+            (!names.is_empty()).then(|| names.join("/"))
+        }
+        _ => MINOR_CLASSES
+            .iter()
+            .find(|(c, m, _)| *c == major && *m == minor)
+            .map(|(_, _, name)| (*name).to_string()),
+    }
+}
+
+// e.g. "Headphones (Audio/Video)"
+pub fn class_of_device(class: u32) -> Option<String> {
+    let (major, minor) = ((class >> 8) & 0x1f, (class >> 2) & 0x3f);
+    let major_name = lookup(MAJOR_CLASSES, major)?;
+    Some(match minor_class(major, minor) {
+        Some(name) => format!("{name} ({major_name})"),
+        None => major_name.to_string(),
+    })
+}
+
+// Freedesktop icon name of the device, as BlueZ would have chosen it
+pub fn icon_name(class: Option<u32>, appearance: Option<u16>) -> Option<&'static str> {
+    class
+        .and_then(class_icon_name)
+        .or_else(|| appearance.and_then(appearance_icon_name))
+}
+
+fn class_icon_name(class: u32) -> Option<&'static str> {
+    let (major, minor) = ((class >> 8) & 0x1f, (class >> 2) & 0x3f);
+    match major {
+        0x01 => Some("computer"),
+        0x02 => Some("phone"),
+        0x03 => Some("network-wireless"),
+        0x04 => match minor {
+            0x01 | 0x02 => Some("audio-headset"),
+            0x06 => Some("audio-headphones"),
+            0x04 => Some("audio-input-microphone"),
+            0x0b..=0x0d => Some("camera-video"),
+            0x12 => Some("input-gaming"),
+            _ => Some("audio-card"),
+        },
+        PERIPHERAL => match (minor >> 4, minor & 0x0f) {
+            (_, 0x01 | 0x02) => Some("input-gaming"),
+            (_, 0x05) => Some("input-tablet"),
+            (0x01 | 0x03, _) => Some("input-keyboard"),
+            (0x02, _) => Some("input-mouse"),
+            _ => None,
+        },
+        IMAGING if minor >> 2 & 0x08 != 0 => Some("printer"),
+        IMAGING if minor >> 2 & 0x02 != 0 => Some("camera-photo"),
+        _ => None,
+    }
+}
+
+fn appearance_icon_name(appearance: u16) -> Option<&'static str> {
+    match (appearance >> 6, appearance & 0x3f) {
+        (0x01, _) => Some("phone"),
+        (0x02, _) => Some("computer"),
+        (0x0f, 0x01) => Some("input-keyboard"),
+        (0x0f, 0x02 | 0x09) => Some("input-mouse"),
+        (0x0f, 0x03 | 0x04) | (0x2a, _) => Some("input-gaming"),
+        (0x0f, 0x05) => Some("input-tablet"),
+        (0x21, _) => Some("audio-card"),
+        (0x22, 0x01) => Some("audio-input-microphone"),
+        (0x25, 0x03) => Some("audio-headphones"),
+        (0x25, _) => Some("audio-headset"),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn services() {
+        let heart_rate: Uuid = "0000180d-0000-1000-8000-00805f9b34fb".parse().unwrap();
+        assert_eq!(uuid16(0x180d), heart_rate);
+        assert_eq!(service_name(&heart_rate), Some("Heart Rate"));
+        assert_eq!(service_label(&heart_rate), "Heart Rate");
+
+        let unknown: Uuid = "0000ffe0-0000-1000-8000-00805f9b34fb".parse().unwrap();
+        assert_eq!(service_label(&unknown), "0xffe0");
+
+        let vendor: Uuid = "6e400001-b5a3-f393-e0a9-e50e24dcca9e".parse().unwrap();
+        assert_eq!(short_uuid(&vendor), None);
+        assert_eq!(
+            service_label(&vendor),
+            "6e400001-b5a3-f393-e0a9-e50e24dcca9e"
+        );
+    }
+
+    #[test]
+    fn companies_and_characteristics() {
+        assert_eq!(company_name(0x0000), Some("Ericsson"));
+        assert_eq!(company_name(0x02e5), Some("Espressif"));
+        assert_eq!(company_name(0xfffe), None);
+        assert_eq!(characteristic_name(&uuid16(0x2a19)), Some("Battery Level"));
+        assert_eq!(characteristic_name(&uuid16(0x180d)), None);
+    }
+
+    #[test]
+    fn tables_are_sorted() {
+        fn keys<K: Copy, V>(table: &[(K, V)]) -> Vec<K> {
+            table.iter().map(|(key, _)| *key).collect()
+        }
+        for table in [SERVICES, CHARACTERISTICS, COMPANIES, APPEARANCE_CATEGORIES] {
+            assert!(keys(table).is_sorted_by(|a, b| a < b));
+        }
+        assert!(keys(MAJOR_CLASSES).is_sorted_by(|a, b| a < b));
+        assert!(
+            APPEARANCE_SUBCATEGORIES
+                .iter()
+                .map(|(category, subcategory, _)| (category, subcategory))
+                .is_sorted_by(|a, b| a < b)
+        );
+    }
+
+    #[test]
+    fn appearances() {
+        assert_eq!(
+            appearance(0x03c1).as_deref(),
+            Some("Keyboard (Human Interface Device)")
+        );
+        assert_eq!(appearance(0x0040).as_deref(), Some("Phone"));
+        assert_eq!(appearance(0xffc0), None);
+        assert_eq!(icon_name(None, Some(0x03c2)), Some("input-mouse"));
+        assert_eq!(icon_name(None, Some(0x0941)), Some("audio-headset"));
+    }
+
+    #[test]
+    fn classes_of_device() {
+        // Headphones with the audio and rendering services
+        assert_eq!(
+            class_of_device(0x24_0418).as_deref(),
+            Some("Headphones (Audio/Video)")
+        );
+        assert_eq!(
+            class_of_device(0x00_05c0).as_deref(),
+            Some("Keyboard/Pointing Device (Peripheral)")
+        );
+        assert_eq!(
+            class_of_device(0x00_0508).as_deref(),
+            Some("Gamepad (Peripheral)")
+        );
+        assert_eq!(
+            class_of_device(0x04_0680).as_deref(),
+            Some("Printer (Imaging)")
+        );
+        assert_eq!(
+            class_of_device(0x5a_020c).as_deref(),
+            Some("Smartphone (Phone)")
+        );
+
+        assert_eq!(icon_name(Some(0x24_0418), None), Some("audio-headphones"));
+        assert_eq!(icon_name(Some(0x00_0540), None), Some("input-keyboard"));
+        // The class comes first
+        assert_eq!(icon_name(Some(0x5a_020c), Some(0x03c1)), Some("phone"));
+        assert_eq!(icon_name(Some(0x00_0900), Some(0x0080)), Some("computer"));
+    }
+}
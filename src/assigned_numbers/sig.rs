@@ -0,0 +1,439 @@
+// Generated by `python3 scripts/assigned_numbers.py` from
+// https://bitbucket.org/bluetooth-SIG/public/src/main/assigned_numbers/, do not edit.
+//
+// Every table is sorted by its keys, they are searched by dichotomy.
+
+pub(super) const SERVICES: &[(u16, &str)] = &[
+    (0x1000, "Service Discovery Server"),
+    (0x1001, "Browse Group Descriptor"),
+    (0x1101, "Serial Port"),
+    (0x1102, "LAN Access Using PPP"),
+    (0x1103, "Dial-up Networking"),
+    (0x1104, "IrMC Sync"),
+    (0x1105, "OBEX Object Push"),
+    (0x1106, "OBEX File Transfer"),
+    (0x1107, "IrMC Sync Command"),
+    (0x1108, "Headset"),
+    (0x1109, "Cordless Telephony"),
+    (0x110a, "Audio Source"),
+    (0x110b, "Audio Sink"),
+    (0x110c, "A/V Remote Control Target"),
+    (0x110d, "Advanced Audio Distribution"),
+    (0x110e, "A/V Remote Control"),
+    (0x110f, "A/V Remote Control Controller"),
+    (0x1110, "Intercom"),
+    (0x1111, "Fax"),
+    (0x1112, "Headset Audio Gateway"),
+    (0x1113, "WAP"),
+    (0x1114, "WAP Client"),
+    (0x1115, "PAN User"),
+    (0x1116, "Network Access Point"),
+    (0x1117, "Group Ad-hoc Network"),
+    (0x1118, "Direct Printing"),
+    (0x1119, "Reference Printing"),
+    (0x111a, "Basic Imaging"),
+    (0x111b, "Imaging Responder"),
+    (0x111c, "Imaging Automatic Archive"),
+    (0x111d, "Imaging Referenced Objects"),
+    (0x111e, "Handsfree"),
+    (0x111f, "Handsfree Audio Gateway"),
+    (0x1120, "Direct Printing Reference Objects"),
+    (0x1121, "Reflected UI"),
+    (0x1122, "Basic Printing"),
+    (0x1123, "Printing Status"),
+    (0x1124, "Human Interface Device"),
+    (0x1125, "Hardcopy Cable Replacement"),
+    (0x1126, "HCR Print"),
+    (0x1127, "HCR Scan"),
+    (0x1128, "Common ISDN Access"),
+    (0x112d, "SIM Access"),
+    (0x112e, "Phonebook Access Client"),
+    (0x112f, "Phonebook Access Server"),
+    (0x1130, "Phonebook Access"),
+    (0x1131, "Headset HS"),
+    (0x1132, "Message Access Server"),
+    (0x1133, "Message Notification Server"),
+    (0x1134, "Message Access"),
+    (0x1135, "GNSS"),
+    (0x1136, "GNSS Server"),
+    (0x1137, "3D Display"),
+    (0x1138, "3D Glasses"),
+    (0x1139, "3D Synchronization"),
+    (0x113a, "Multi-Profile Specification"),
+    (0x113b, "Multi-Profile Specification Service"),
+    (0x113c, "Calendar, Task and Notes Access"),
+    (0x113d, "Calendar, Task and Notes Notification"),
+    (0x113e, "Calendar, Task and Notes"),
+    (0x1200, "PnP Information"),
+    (0x1201, "Generic Networking"),
+    (0x1202, "Generic File Transfer"),
+    (0x1203, "Generic Audio"),
+    (0x1204, "Generic Telephony"),
+    (0x1205, "UPnP"),
+    (0x1206, "UPnP IP"),
+    (0x1300, "ESDP UPnP IP PAN"),
+    (0x1301, "ESDP UPnP IP LAP"),
+    (0x1302, "ESDP UPnP L2CAP"),
+    (0x1303, "Video Source"),
+    (0x1304, "Video Sink"),
+    (0x1305, "Video Distribution"),
+    (0x1400, "Health Device"),
+    (0x1401, "Health Device Source"),
+    (0x1402, "Health Device Sink"),
+    (0x1800, "Generic Access"),
+    (0x1801, "Generic Attribute"),
+    (0x1802, "Immediate Alert"),
+    (0x1803, "Link Loss"),
+    (0x1804, "Tx Power"),
+    (0x1805, "Current Time"),
+    (0x1806, "Reference Time Update"),
+    (0x1807, "Next DST Change"),
+    (0x1808, "Glucose"),
+    (0x1809, "Health Thermometer"),
+    (0x180a, "Device Information"),
+    (0x180d, "Heart Rate"),
+    (0x180e, "Phone Alert Status"),
+    (0x180f, "Battery"),
+    (0x1810, "Blood Pressure"),
+    (0x1811, "Alert Notification"),
+    (0x1812, "Human Interface Device"),
+    (0x1813, "Scan Parameters"),
+    (0x1814, "Running Speed and Cadence"),
+    (0x1815, "Automation IO"),
+    (0x1816, "Cycling Speed and Cadence"),
+    (0x1818, "Cycling Power"),
+    (0x1819, "Location and Navigation"),
+    (0x181a, "Environmental Sensing"),
+    (0x181b, "Body Composition"),
+    (0x181c, "User Data"),
+    (0x181d, "Weight Scale"),
+    (0x181e, "Bond Management"),
+    (0x181f, "Continuous Glucose Monitoring"),
+    (0x1820, "Internet Protocol Support"),
+    (0x1821, "Indoor Positioning"),
+    (0x1822, "Pulse Oximeter"),
+    (0x1823, "HTTP Proxy"),
+    (0x1824, "Transport Discovery"),
+    (0x1825, "Object Transfer"),
+    (0x1826, "Fitness Machine"),
+    (0x1827, "Mesh Provisioning"),
+    (0x1828, "Mesh Proxy"),
+    (0x1829, "Reconnection Configuration"),
+    (0x183a, "Insulin Delivery"),
+    (0x183b, "Binary Sensor"),
+    (0x183c, "Emergency Configuration"),
+    (0x183e, "Physical Activity Monitor"),
+    (0x1843, "Audio Input Control"),
+    (0x1844, "Volume Control"),
+    (0x1845, "Volume Offset Control"),
+    (0x1846, "Coordinated Set Identification"),
+    (0x1847, "Device Time"),
+    (0x1848, "Media Control"),
+    (0x1849, "Generic Media Control"),
+    (0x184a, "Constant Tone Extension"),
+    (0x184b, "Telephone Bearer"),
+    (0x184c, "Generic Telephone Bearer"),
+    (0x184d, "Microphone Control"),
+    (0x184e, "Audio Stream Control"),
+    (0x184f, "Broadcast Audio Scan"),
+    (0x1850, "Published Audio Capabilities"),
+    (0x1851, "Basic Audio Announcement"),
+    (0x1852, "Broadcast Audio Announcement"),
+    (0x1853, "Common Audio"),
+    (0x1854, "Hearing Access"),
+    (0x1855, "Telephony and Media Audio"),
+    (0x1856, "Public Broadcast Announcement"),
+    (0xfd6f, "Exposure Notification"),
+    (0xfe2c, "Google Fast Pair"),
+    (0xfe59, "Nordic Secure DFU"),
+    (0xfeaa, "Eddystone"),
+];
+
+pub(super) const CHARACTERISTICS: &[(u16, &str)] = &[
+    (0x2a00, "Device Name"),
+    (0x2a01, "Appearance"),
+    (0x2a04, "Peripheral Preferred Connection Parameters"),
+    (0x2a05, "Service Changed"),
+    (0x2a19, "Battery Level"),
+    (0x2a1c, "Temperature Measurement"),
+    (0x2a1d, "Temperature Type"),
+    (0x2a1e, "Intermediate Temperature"),
+    (0x2a21, "Measurement Interval"),
+    (0x2a23, "System ID"),
+    (0x2a24, "Model Number"),
+    (0x2a25, "Serial Number"),
+    (0x2a26, "Firmware Revision"),
+    (0x2a27, "Hardware Revision"),
+    (0x2a28, "Software Revision"),
+    (0x2a29, "Manufacturer Name"),
+    (0x2a37, "Heart Rate Measurement"),
+    (0x2a38, "Body Sensor Location"),
+    (0x2a39, "Heart Rate Control Point"),
+    (0x2a4a, "HID Information"),
+    (0x2a4b, "Report Map"),
+    (0x2a4c, "HID Control Point"),
+    (0x2a4d, "Report"),
+    (0x2a4e, "Protocol Mode"),
+    (0x2a50, "PnP ID"),
+    (0x2a5b, "CSC Measurement"),
+    (0x2a5c, "CSC Feature"),
+    (0x2a5d, "Sensor Location"),
+    (0x2a63, "Cycling Power Measurement"),
+    (0x2a6d, "Pressure"),
+    (0x2a6e, "Temperature"),
+    (0x2a6f, "Humidity"),
+    (0x2a76, "UV Index"),
+];
+
+pub(super) const COMPANIES: &[(u16, &str)] = &[
+    (0x0000, "Ericsson"),
+    (0x0001, "Nokia"),
+    (0x0002, "Intel"),
+    (0x0003, "IBM"),
+    (0x0004, "Toshiba"),
+    (0x0005, "3Com"),
+    (0x0006, "Microsoft"),
+    (0x0007, "Lucent"),
+    (0x0008, "Motorola"),
+    (0x0009, "Infineon Technologies"),
+    (0x000a, "Qualcomm Technologies International"),
+    (0x000b, "Silicon Wave"),
+    (0x000c, "Digianswer"),
+    (0x000d, "Texas Instruments"),
+    (0x000e, "Parthus Technologies"),
+    (0x000f, "Broadcom"),
+    (0x0010, "Mitel Semiconductor"),
+    (0x0011, "Widcomm"),
+    (0x0012, "Zeevo"),
+    (0x0013, "Atmel"),
+    (0x0014, "Mitsubishi Electric"),
+    (0x0015, "RTX Telecom"),
+    (0x0016, "KC Technology"),
+    (0x0017, "Newlogic"),
+    (0x0018, "Transilica"),
+    (0x0019, "Rohde & Schwarz"),
+    (0x001a, "TTPCom"),
+    (0x001b, "Signia Technologies"),
+    (0x001c, "Conexant Systems"),
+    (0x001d, "Qualcomm"),
+    (0x001e, "Inventel"),
+    (0x001f, "AVM Berlin"),
+    (0x0020, "BandSpeed"),
+    (0x0021, "Mansella"),
+    (0x0022, "NEC"),
+    (0x0023, "WavePlus Technology"),
+    (0x0024, "Alcatel"),
+    (0x0025, "NXP Semiconductors"),
+    (0x0026, "C Technologies"),
+    (0x0027, "Open Interface"),
+    (0x0028, "RF Micro Devices"),
+    (0x0029, "Hitachi"),
+    (0x002a, "Symbol Technologies"),
+    (0x002b, "Tenovis"),
+    (0x002c, "Macronix International"),
+    (0x002d, "GCT Semiconductor"),
+    (0x002e, "Norwood Systems"),
+    (0x002f, "MewTel Technology"),
+    (0x0030, "STMicroelectronics"),
+    (0x0031, "Synopsys"),
+    (0x0032, "Red-M"),
+    (0x0033, "Commil"),
+    (0x0034, "CATC"),
+    (0x0035, "Eclipse"),
+    (0x0036, "Renesas Electronics"),
+    (0x0037, "Mobilian"),
+    (0x0038, "Syntronix"),
+    (0x0039, "Integrated System Solution"),
+    (0x003a, "Panasonic"),
+    (0x003b, "Gennum"),
+    (0x003c, "BlackBerry"),
+    (0x003d, "IPextreme"),
+    (0x003e, "Systems and Chips"),
+    (0x003f, "Bluetooth SIG"),
+    (0x0040, "Seiko Epson"),
+    (0x0041, "Integrated Silicon Solution Taiwan"),
+    (0x0042, "CONWISE Technology"),
+    (0x0043, "Parrot Automotive"),
+    (0x0044, "Socket Mobile"),
+    (0x0045, "Atheros Communications"),
+    (0x0046, "MediaTek"),
+    (0x0047, "Bluegiga"),
+    (0x0048, "Marvell"),
+    (0x0049, "3DSP"),
+    (0x004a, "Accel Semiconductor"),
+    (0x004b, "Continental Automotive Systems"),
+    (0x004c, "Apple"),
+    (0x004d, "Staccato Communications"),
+    (0x004e, "Avago Technologies"),
+    (0x004f, "APT"),
+    (0x0050, "SiRF Technology"),
+    (0x0051, "Tzero Technologies"),
+    (0x0052, "J&M"),
+    (0x0053, "Free2move"),
+    (0x0054, "3DiJoy"),
+    (0x0055, "Plantronics"),
+    (0x0056, "Sony Ericsson"),
+    (0x0057, "Harman International"),
+    (0x0058, "Vizio"),
+    (0x0059, "Nordic Semiconductor"),
+    (0x005a, "EM Microelectronic-Marin"),
+    (0x005b, "Ralink Technology"),
+    (0x005c, "Belkin International"),
+    (0x005d, "Realtek Semiconductor"),
+    (0x005e, "Stonestreet One"),
+    (0x005f, "Wicentric"),
+    (0x0060, "RivieraWaves"),
+    (0x0065, "HP"),
+    (0x0067, "GN Audio"),
+    (0x0068, "General Motors"),
+    (0x006b, "Polar Electro"),
+    (0x0075, "Samsung Electronics"),
+    (0x0076, "Creative Technology"),
+    (0x0077, "Laird Technologies"),
+    (0x0078, "Nike"),
+    (0x0082, "Sennheiser Communications"),
+    (0x0087, "Garmin"),
+    (0x0089, "GN Hearing"),
+    (0x009e, "Bose"),
+    (0x009f, "Suunto"),
+    (0x00c3, "adidas"),
+    (0x00c4, "LG Electronics"),
+    (0x00cc, "Beats Electronics"),
+    (0x00cd, "Microchip Technology"),
+    (0x00d2, "Dialog Semiconductor"),
+    (0x00d7, "Qualcomm Technologies"),
+    (0x00e0, "Google"),
+    (0x0118, "Radius Networks"),
+    (0x012d, "Sony"),
+    (0x0131, "Cypress Semiconductor"),
+    (0x0154, "Pebble Technology"),
+    (0x0157, "Huami"),
+    (0x0171, "Amazon"),
+    (0x01da, "Logitech"),
+    (0x027d, "Huawei"),
+    (0x02e5, "Espressif"),
+    (0x02ff, "Silicon Labs"),
+    (0x038f, "Xiaomi"),
+    (0x0499, "Ruuvi Innovations"),
+    (0x05a7, "Sonos"),
+];
+
+// Categories of the GAP appearance, the 10 upper bits of the value
+pub(super) const APPEARANCE_CATEGORIES: &[(u16, &str)] = &[
+    (0x00, "Unknown"),
+    (0x01, "Phone"),
+    (0x02, "Computer"),
+    (0x03, "Watch"),
+    (0x04, "Clock"),
+    (0x05, "Display"),
+    (0x06, "Remote Control"),
+    (0x07, "Eyeglasses"),
+    (0x08, "Tag"),
+    (0x09, "Keyring"),
+    (0x0a, "Media Player"),
+    (0x0b, "Barcode Scanner"),
+    (0x0c, "Thermometer"),
+    (0x0d, "Heart Rate Sensor"),
+    (0x0e, "Blood Pressure"),
+    (0x0f, "Human Interface Device"),
+    (0x10, "Glucose Meter"),
+    (0x11, "Running Walking Sensor"),
+    (0x12, "Cycling"),
+    (0x13, "Control Device"),
+    (0x14, "Network Device"),
+    (0x15, "Sensor"),
+    (0x16, "Light Fixtures"),
+    (0x17, "Fan"),
+    (0x18, "HVAC"),
+    (0x19, "Air Conditioning"),
+    (0x1a, "Humidifier"),
+    (0x1b, "Heating"),
+    (0x1c, "Access Control"),
+    (0x1d, "Motorized Device"),
+    (0x1e, "Power Device"),
+    (0x1f, "Light Source"),
+    (0x20, "Window Covering"),
+    (0x21, "Audio Sink"),
+    (0x22, "Audio Source"),
+    (0x23, "Motorized Vehicle"),
+    (0x24, "Domestic Appliance"),
+    (0x25, "Wearable Audio Device"),
+    (0x26, "Aircraft"),
+    (0x27, "AV Equipment"),
+    (0x28, "Display Equipment"),
+    (0x29, "Hearing Aid"),
+    (0x2a, "Gaming"),
+    (0x2b, "Signage"),
+    (0x31, "Pulse Oximeter"),
+    (0x32, "Weight Scale"),
+    (0x33, "Personal Mobility Device"),
+    (0x34, "Continuous Glucose Monitor"),
+    (0x35, "Insulin Pump"),
+    (0x36, "Medication Delivery"),
+    (0x37, "Spirometer"),
+    (0x51, "Outdoor Sports Activity"),
+];
+
+// Subcategories, the 6 lower bits, by category
+pub(super) const APPEARANCE_SUBCATEGORIES: &[(u16, u16, &str)] = &[
+    (0x02, 0x01, "Desktop Workstation"),
+    (0x02, 0x02, "Server-class Computer"),
+    (0x02, 0x03, "Laptop"),
+    (0x02, 0x04, "Handheld PC/PDA"),
+    (0x02, 0x05, "Palm-size PC/PDA"),
+    (0x02, 0x06, "Wearable Computer"),
+    (0x02, 0x07, "Tablet"),
+    (0x02, 0x08, "Docking Station"),
+    (0x02, 0x09, "All in One"),
+    (0x02, 0x0a, "Blade Server"),
+    (0x02, 0x0b, "Convertible"),
+    (0x02, 0x0c, "Detachable"),
+    (0x02, 0x0d, "IoT Gateway"),
+    (0x02, 0x0e, "Mini PC"),
+    (0x02, 0x0f, "Stick PC"),
+    (0x03, 0x01, "Sports Watch"),
+    (0x03, 0x02, "Smartwatch"),
+    (0x0c, 0x01, "Ear Thermometer"),
+    (0x0d, 0x01, "Heart Rate Belt"),
+    (0x0e, 0x01, "Arm Blood Pressure"),
+    (0x0e, 0x02, "Wrist Blood Pressure"),
+    (0x0f, 0x01, "Keyboard"),
+    (0x0f, 0x02, "Mouse"),
+    (0x0f, 0x03, "Joystick"),
+    (0x0f, 0x04, "Gamepad"),
+    (0x0f, 0x05, "Digitizer Tablet"),
+    (0x0f, 0x06, "Card Reader"),
+    (0x0f, 0x07, "Digital Pen"),
+    (0x0f, 0x08, "Barcode Scanner"),
+    (0x0f, 0x09, "Touchpad"),
+    (0x0f, 0x0a, "Presentation Remote"),
+    (0x11, 0x01, "In-Shoe Running Walking Sensor"),
+    (0x11, 0x02, "On-Shoe Running Walking Sensor"),
+    (0x11, 0x03, "On-Hip Running Walking Sensor"),
+    (0x12, 0x01, "Cycling Computer"),
+    (0x12, 0x02, "Speed Sensor"),
+    (0x12, 0x03, "Cadence Sensor"),
+    (0x12, 0x04, "Power Sensor"),
+    (0x12, 0x05, "Speed and Cadence Sensor"),
+    (0x21, 0x01, "Standalone Speaker"),
+    (0x21, 0x02, "Soundbar"),
+    (0x21, 0x03, "Bookshelf Speaker"),
+    (0x21, 0x04, "Standmounted Speaker"),
+    (0x21, 0x05, "Speakerphone"),
+    (0x22, 0x01, "Microphone"),
+    (0x22, 0x02, "Alarm"),
+    (0x22, 0x03, "Bell"),
+    (0x22, 0x04, "Horn"),
+    (0x22, 0x05, "Broadcasting Device"),
+    (0x25, 0x01, "Earbud"),
+    (0x25, 0x02, "Headset"),
+    (0x25, 0x03, "Headphones"),
+    (0x25, 0x04, "Neck Band"),
+    (0x29, 0x01, "In-ear Hearing Aid"),
+    (0x29, 0x02, "Behind-ear Hearing Aid"),
+    (0x29, 0x03, "Cochlear Implant"),
+    (0x2a, 0x01, "Home Video Game Console"),
+    (0x2a, 0x02, "Portable Handheld Console"),
+    (0x31, 0x01, "Fingertip Pulse Oximeter"),
+    (0x31, 0x02, "Wrist Worn Pulse Oximeter"),
+];
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use bluer::Address;
+
+// A threshold is alerted again once the battery is charged this much above it
+const REARM_MARGIN: u8 = 5;
+
+// Warn once per discharge cycle when the battery of a device drops to a threshold
+#[derive(Debug, Default)]
+pub struct BatteryAlerts {
+    thresholds: Vec<u8>,
+    // Thresholds that have not been alerted yet in the current discharge cycle
+    armed: HashMap<Address, Vec<u8>>,
+}
+
+impl BatteryAlerts {
+    pub fn new(thresholds: &[u8]) -> Self {
+        Self {
+            thresholds: thresholds.to_vec(),
+            armed: HashMap::new(),
+        }
+    }
+
+    // The lowest threshold crossed by this reading, if it was not alerted yet
+    pub fn check(&mut self, address: Address, level: u8) -> Option<u8> {
+        let armed = self
+            .armed
+            .entry(address)
+            .or_insert_with(|| self.thresholds.clone());
+
+        for threshold in &self.thresholds {
+            if level >= threshold.saturating_add(REARM_MARGIN) && !armed.contains(threshold) {
+                armed.push(*threshold);
+            }
+        }
+
+        let crossed = armed.iter().filter(|t| level <= **t).min().copied();
+        armed.retain(|t| level > *t);
+        crossed
+    }
+}
+
+// Samples of the current discharge cycle, i.e. since the level last went up
+fn discharge_cycle(samples: &[(u64, u8)]) -> &[(u64, u8)] {
+    let start = samples
+        .windows(2)
+        .rposition(|w| w[1].1 > w[0].1)
+        .map_or(0, |i| i + 1);
+    &samples[start..]
+}
+
+// Seconds until the battery is empty at the rate of the current discharge cycle
+pub fn estimate_remaining(samples: &[(u64, u8)], now: u64) -> Option<u64> {
+    let cycle = discharge_cycle(samples);
+    let (first_time, first_level) = cycle.first()?;
+    let (last_time, last_level) = cycle.last()?;
+
+    let drop = u64::from(first_level.checked_sub(*last_level)?);
+    let duration = last_time.checked_sub(*first_time)?;
+    if drop == 0 || duration == 0 {
+        return None;
+    }
+
+    let remaining = u64::from(*last_level) * duration / drop;
+    Some(remaining.saturating_sub(now.saturating_sub(*last_time)))
+}
+
+// Level at the end of each of `buckets` equal periods of the last `span` seconds, 0 before
+// the first sample
+pub fn battery_series(samples: &[(u64, u8)], now: u64, span: u64, buckets: usize) -> Vec<u64> {
+    let start = now.saturating_sub(span);
+    let bucket_span = (span / buckets.max(1) as u64).max(1);
+
+    (1..=buckets as u64)
+        .map(|i| {
+            let end = start + i * bucket_span;
+            samples
+                .iter()
+                .take_while(|(time, _)| *time <= end)
+                .last()
+                .map_or(0, |(_, level)| u64::from(*level))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn alerts_once_per_cycle() {
+        let address = Address::new(*b"headst");
+        let mut alerts = BatteryAlerts::new(&[20, 10]);
+
+        assert_eq!(alerts.check(address, 50), None);
+        assert_eq!(alerts.check(address, 20), Some(20));
+        assert_eq!(alerts.check(address, 21), None);
+        assert_eq!(alerts.check(address, 20), None);
+        assert_eq!(alerts.check(address, 5), Some(10));
+        assert_eq!(alerts.check(address, 5), None);
+
+        // Charged
+        assert_eq!(alerts.check(address, 80), None);
+        assert_eq!(alerts.check(address, 15), Some(20));
+    }
+
+    #[test]
+    fn remaining_time() {
+        // Charged to 100% then 1% every 60 seconds
+        let samples = [(0, 40), (100, 100), (160, 99), (220, 98), (280, 97)];
+        assert_eq!(estimate_remaining(&samples, 280), Some(97 * 60));
+        assert_eq!(estimate_remaining(&samples, 340), Some(96 * 60));
+        assert_eq!(estimate_remaining(&samples[..2], 100), None);
+    }
+
+    #[test]
+    fn series() {
+        let samples = [(150, 80), (250, 70)];
+        assert_eq!(battery_series(&samples, 400, 400, 4), vec![0, 80, 70, 70]);
+    }
+}
@@ -0,0 +1,620 @@
+use bluer::{Address, Uuid};
+use dbus::arg::{ArgType, PropMap, RefArg};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Rect},
+    style::{Style, Stylize},
+    widgets::{Block, BorderType, Clear, Padding, Paragraph, Row, Table, TableState},
+};
+
+use crate::{
+    advertising::{format_hex, parse_uuid},
+    assigned_numbers::{company_name, service_label},
+    bus::ManagedObjects,
+    media::device_address,
+};
+
+const DEVICE_INTERFACE: &str = "org.bluez.Device1";
+
+const APPLE: u16 = 0x004c;
+
+// Eddystone frames are service data of this 16 bit UUID
+const EDDYSTONE: &str = "feaa";
+
+// Signal lost between 0 and 1 meter, Eddystone advertises its TX power at 0 meter
+const LOSS_AT_1M: i16 = 41;
+
+// Path loss exponent of the distance estimate, 2 in free space
+const PATH_LOSS: f64 = 2.0;
+
+const URL_SCHEMES: [&str; 4] = ["http://www.", "https://www.", "http://", "https://"];
+const URL_EXPANSIONS: [&str; 14] = [
+    ".com/", ".org/", ".edu/", ".net/", ".info/", ".biz/", ".gov/", ".com", ".org", ".edu", ".net",
+    ".info", ".biz", ".gov",
+];
+
+// A frame decoded from the advertising data of a device
+#[derive(Debug, Clone, PartialEq)]
+pub enum AdvertisedFrame {
+    IBeacon {
+        uuid: Uuid,
+        major: u16,
+        minor: u16,
+        // RSSI at 1 meter
+        measured_power: i8,
+    },
+    EddystoneUid {
+        namespace: [u8; 10],
+        instance: [u8; 6],
+        tx_power: i8,
+    },
+    EddystoneUrl {
+        url: String,
+        tx_power: i8,
+    },
+    EddystoneTlm {
+        // Millivolts
+        battery: u16,
+        temperature: Option<f32>,
+        advertisements: u32,
+        // Seconds since the beacon was powered on
+        uptime: u32,
+    },
+    Manufacturer {
+        company: u16,
+        data: Vec<u8>,
+    },
+    Service {
+        uuid: Uuid,
+        data: Vec<u8>,
+    },
+    // Advertising data types BlueZ does not decode
+    Raw {
+        ad_type: u8,
+        data: Vec<u8>,
+    },
+}
+
+fn be_u16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_be_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn be_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+pub fn decode_manufacturer(company: u16, data: &[u8]) -> AdvertisedFrame {
+    if company == APPLE
+        && data.len() == 23
+        && data.starts_with(&[0x02, 0x15])
+        && let Ok(uuid) = <[u8; 16]>::try_from(&data[2..18])
+    {
+        return AdvertisedFrame::IBeacon {
+            uuid: Uuid::from_bytes(uuid),
+            major: u16::from_be_bytes([data[18], data[19]]),
+            minor: u16::from_be_bytes([data[20], data[21]]),
+            measured_power: data[22].cast_signed(),
+        };
+    }
+    AdvertisedFrame::Manufacturer {
+        company,
+        data: data.to_vec(),
+    }
+}
+
+fn decode_url(data: &[u8]) -> Option<String> {
+    let mut url = (*URL_SCHEMES.get(*data.first()? as usize)?).to_string();
+    for byte in &data[1..] {
+        match URL_EXPANSIONS.get(*byte as usize) {
+            Some(expansion) => url.push_str(expansion),
+            None if byte.is_ascii_graphic() => url.push(*byte as char),
+            None => return None,
+        }
+    }
+    Some(url)
+}
+
+fn decode_eddystone(data: &[u8]) -> Option<AdvertisedFrame> {
+    match *data.first()? {
+        0x00 => Some(AdvertisedFrame::EddystoneUid {
+            tx_power: data.get(1)?.cast_signed(),
+            namespace: data.get(2..12)?.try_into().ok()?,
+            instance: data.get(12..18)?.try_into().ok()?,
+        }),
+        0x10 => Some(AdvertisedFrame::EddystoneUrl {
+            tx_power: data.get(1)?.cast_signed(),
+            url: decode_url(data.get(2..)?)?,
+        }),
+        // Unencrypted TLM, the temperature is a signed 8.8 fixed point number
+        0x20 if data.get(1) == Some(&0) => {
+            let temperature = be_u16(data, 4)?;
+            Some(AdvertisedFrame::EddystoneTlm {
+                battery: be_u16(data, 2)?,
+                temperature: (temperature != 0x8000)
+                    .then(|| f32::from(temperature.cast_signed()) / 256.0),
+                advertisements: be_u32(data, 6)?,
+                uptime: be_u32(data, 10)? / 10,
+            })
+        }
+        _ => None,
+    }
+}
+
+pub fn decode_service(uuid: Uuid, data: &[u8]) -> AdvertisedFrame {
+    if parse_uuid(EDDYSTONE).is_ok_and(|eddystone| eddystone == uuid)
+        && let Some(frame) = decode_eddystone(data)
+    {
+        return frame;
+    }
+    AdvertisedFrame::Service {
+        uuid,
+        data: data.to_vec(),
+    }
+}
+
+impl AdvertisedFrame {
+    pub fn kind(&self) -> String {
+        match self {
+            AdvertisedFrame::IBeacon { .. } => "iBeacon".to_string(),
+            AdvertisedFrame::EddystoneUid { .. } => "Eddystone UID".to_string(),
+            AdvertisedFrame::EddystoneUrl { .. } => "Eddystone URL".to_string(),
+            AdvertisedFrame::EddystoneTlm { .. } => "Eddystone TLM".to_string(),
+            AdvertisedFrame::Manufacturer { company, .. } => match company_name(*company) {
+                Some(name) => name.to_string(),
+                None => format!("Company {company:#06x}"),
+            },
+            AdvertisedFrame::Service { .. } => "Service data".to_string(),
+            AdvertisedFrame::Raw { ad_type, .. } => format!("AD type {ad_type:#04x}"),
+        }
+    }
+
+    pub fn details(&self) -> String {
+        match self {
+            AdvertisedFrame::IBeacon {
+                uuid, major, minor, ..
+            } => format!("{uuid} major {major} minor {minor}"),
+            AdvertisedFrame::EddystoneUid {
+                namespace,
+                instance,
+                ..
+            } => format!(
+                "namespace {} instance {}",
+                format_hex(namespace),
+                format_hex(instance)
+            ),
+            AdvertisedFrame::EddystoneUrl { url, .. } => url.clone(),
+            AdvertisedFrame::EddystoneTlm {
+                battery,
+                temperature,
+                advertisements,
+                uptime,
+            } => {
+                let temperature = temperature
+                    .map(|t| format!("{t:.1}°C "))
+                    .unwrap_or_default();
+                format!(
+                    "{battery} mV {temperature}{advertisements} adv. up {}",
+                    crate::history::format_duration(u64::from(*uptime))
+                )
+            }
+            AdvertisedFrame::Manufacturer { data, .. } | AdvertisedFrame::Raw { data, .. } => {
+                format_hex(data)
+            }
+            AdvertisedFrame::Service { uuid, data } => {
+                format!("{} {}", service_label(uuid), format_hex(data))
+            }
+        }
+    }
+
+    // Expected RSSI at 1 meter
+    fn power_at_1m(&self) -> Option<i16> {
+        match self {
+            AdvertisedFrame::IBeacon { measured_power, .. } => Some(i16::from(*measured_power)),
+            AdvertisedFrame::EddystoneUid { tx_power, .. }
+            | AdvertisedFrame::EddystoneUrl { tx_power, .. } => {
+                Some(i16::from(*tx_power) - LOSS_AT_1M)
+            }
+            _ => None,
+        }
+    }
+}
+
+// Log-distance path loss model, rough indoors
+pub fn estimate_distance(rssi: i16, power_at_1m: i16) -> f64 {
+    10f64.powf(f64::from(power_at_1m - rssi) / (10.0 * PATH_LOSS))
+}
+
+// A device and its advertising data, one row per frame
+#[derive(Debug, Clone, PartialEq)]
+pub struct Beacon {
+    pub address: Address,
+    pub name: Option<String>,
+    pub rssi: i16,
+    // TX power advertised by the device
+    pub tx_power: Option<i16>,
+    pub frames: Vec<AdvertisedFrame>,
+}
+
+impl Beacon {
+    // The calibrated power of a frame is used first, the TX power of the device otherwise
+    pub fn distance(&self, frame: &AdvertisedFrame) -> Option<f64> {
+        let power = frame
+            .power_at_1m()
+            .or_else(|| self.frames.iter().find_map(AdvertisedFrame::power_at_1m))
+            .or_else(|| self.tx_power.map(|tx_power| tx_power - LOSS_AT_1M))?;
+        Some(estimate_distance(self.rssi, power))
+    }
+
+    fn from_properties(path: &str, properties: &PropMap) -> Option<Self> {
+        let rssi = properties.get("RSSI").and_then(RefArg::as_i64)?;
+
+        let mut manufacturer_data: Vec<(u16, Vec<u8>)> =
+            byte_entries(properties, "ManufacturerData")
+                .into_iter()
+                .filter_map(|(company, data)| Some((u16::try_from(company.as_u64()?).ok()?, data)))
+                .collect();
+        manufacturer_data.sort();
+        let mut service_data: Vec<(Uuid, Vec<u8>)> = byte_entries(properties, "ServiceData")
+            .into_iter()
+            .filter_map(|(uuid, data)| Some((uuid.as_str()?.parse().ok()?, data)))
+            .collect();
+        service_data.sort();
+        let mut advertising_data: Vec<(u8, Vec<u8>)> = byte_entries(properties, "AdvertisingData")
+            .into_iter()
+            .filter_map(|(ad_type, data)| Some((u8::try_from(ad_type.as_u64()?).ok()?, data)))
+            .collect();
+        advertising_data.sort();
+
+        let frames: Vec<AdvertisedFrame> = manufacturer_data
+            .iter()
+            .map(|(company, data)| decode_manufacturer(*company, data))
+            .chain(
+                service_data
+                    .iter()
+                    .map(|(uuid, data)| decode_service(*uuid, data)),
+            )
+            .chain(
+                advertising_data
+                    .into_iter()
+                    .map(|(ad_type, data)| AdvertisedFrame::Raw { ad_type, data }),
+            )
+            .collect();
+        if frames.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            address: device_address(path)?,
+            name: properties
+                .get("Name")
+                .and_then(|v| v.as_str())
+                .map(String::from),
+            rssi: i16::try_from(rssi).ok()?,
+            tx_power: properties
+                .get("TxPower")
+                .and_then(RefArg::as_i64)
+                .and_then(|tx_power| i16::try_from(tx_power).ok()),
+            frames,
+        })
+    }
+}
+
+// Entries of a dictionary of byte arrays, the values are wrapped in variants
+fn byte_entries<'a>(properties: &'a PropMap, name: &str) -> Vec<(&'a dyn RefArg, Vec<u8>)> {
+    let Some(entries) = properties.get(name).and_then(|v| v.0.as_iter()) else {
+        return Vec::new();
+    };
+    let entries: Vec<&dyn RefArg> = entries.collect();
+    entries
+        .chunks_exact(2)
+        .filter_map(|entry| {
+            let mut value = entry[1];
+            if value.arg_type() == ArgType::Variant {
+                value = value.as_iter()?.next()?;
+            }
+            let data = value
+                .as_iter()?
+                .map(|byte| byte.as_u64().and_then(|byte| u8::try_from(byte).ok()))
+                .collect::<Option<_>>()?;
+            Some((entry[0], data))
+        })
+        .collect()
+}
+
+// The devices the adapter heard recently, the closest first
+pub fn from_objects(objects: &ManagedObjects, adapter: &str) -> Vec<Beacon> {
+    let prefix = format!("/org/bluez/{adapter}/");
+    let mut beacons: Vec<Beacon> = objects
+        .iter()
+        .filter(|(path, _)| path.starts_with(&prefix))
+        .filter_map(|(path, interfaces)| {
+            Beacon::from_properties(path, interfaces.get(DEVICE_INTERFACE)?)
+        })
+        .collect();
+    beacons.sort_by_key(|beacon| (-beacon.rssi, beacon.address));
+    beacons
+}
+
+pub fn render_beacons(
+    beacons: &[Beacon],
+    scanning: bool,
+    state: &mut TableState,
+    frame: &mut Frame,
+    area: Rect,
+) {
+    let area = area.centered(Constraint::Percentage(90), Constraint::Percentage(80));
+    let block = Block::bordered()
+        .title(" Beacons ")
+        .title_style(Style::default().bold())
+        .padding(Padding::horizontal(1))
+        .border_type(BorderType::Thick)
+        .border_style(Style::default().green());
+
+    frame.render_widget(Clear, area);
+    if beacons.is_empty() {
+        let message = if scanning {
+            "\n\nListening to the advertisements…"
+        } else {
+            "\n\nNo advertisements yet, start scanning to hear them"
+        };
+        frame.render_widget(
+            Paragraph::new(message).centered().dark_gray().block(block),
+            area,
+        );
+        return;
+    }
+
+    let rows: Vec<Row> = beacons
+        .iter()
+        .flat_map(|beacon| {
+            let name = beacon
+                .name
+                .clone()
+                .unwrap_or_else(|| beacon.address.to_string());
+            beacon.frames.iter().map(move |advertised| {
+                let distance = beacon
+                    .distance(advertised)
+                    .map(|distance| format!("~{distance:.1} m"))
+                    .unwrap_or_default();
+                Row::new(vec![
+                    name.clone(),
+                    advertised.kind(),
+                    advertised.details(),
+                    format!("{} dBm", beacon.rssi),
+                    distance,
+                ])
+            })
+        })
+        .collect();
+
+    let table = Table::new(
+        rows,
+        [
+            Constraint::Length(20),
+            Constraint::Length(14),
+            Constraint::Fill(1),
+            Constraint::Length(8),
+            Constraint::Length(8),
+        ],
+    )
+    .header(
+        Row::new(["Device", "Type", "Data", "RSSI", "Distance"])
+            .yellow()
+            .bold()
+            .bottom_margin(1),
+    )
+    .block(block)
+    .row_highlight_style(Style::default().white().on_dark_gray());
+    frame.render_stateful_widget(table, area, state);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use dbus::{MethodErr, arg::Variant};
+    use dbus_crossroads::Crossroads;
+
+    use super::*;
+    use crate::{
+        advertising::parse_hex,
+        bus::{BLUEZ, Bus, tests::PrivateBus},
+    };
+    use insta::assert_snapshot;
+    use ratatui::{Terminal, backend::TestBackend};
+
+    fn ibeacon() -> AdvertisedFrame {
+        decode_manufacturer(
+            APPLE,
+            &parse_hex("0215 f7826da64fa24e988024bc5b71e0893e 0001 0002 c5").unwrap(),
+        )
+    }
+
+    #[test]
+    fn decode_ibeacon() {
+        assert_eq!(
+            ibeacon(),
+            AdvertisedFrame::IBeacon {
+                uuid: "f7826da6-4fa2-4e98-8024-bc5b71e0893e".parse().unwrap(),
+                major: 1,
+                minor: 2,
+                measured_power: -59,
+            }
+        );
+        // Other Apple frames, e.g. AirPods, are kept as they are
+        assert_eq!(ibeacon().kind(), "iBeacon");
+        assert_eq!(decode_manufacturer(APPLE, &[0x07, 0x19]).kind(), "Apple");
+    }
+
+    #[test]
+    fn decode_eddystone_frames() {
+        let eddystone = parse_uuid(EDDYSTONE).unwrap();
+        assert_eq!(
+            decode_service(
+                eddystone,
+                &parse_hex("00 e7 00010203040506070809 0a0b0c0d0e0f").unwrap()
+            )
+            .details(),
+            "namespace 00010203040506070809 instance 0a0b0c0d0e0f"
+        );
+        assert_eq!(
+            decode_service(eddystone, &parse_hex("10 ee 03 676f6f676c65 07").unwrap()),
+            AdvertisedFrame::EddystoneUrl {
+                url: "https://google.com".to_string(),
+                tx_power: -18,
+            }
+        );
+        assert_eq!(
+            decode_service(
+                eddystone,
+                &parse_hex("20 00 0bb8 1780 00000064 00000e10").unwrap()
+            ),
+            AdvertisedFrame::EddystoneTlm {
+                battery: 3000,
+                temperature: Some(23.5),
+                advertisements: 100,
+                uptime: 360,
+            }
+        );
+        assert!(matches!(
+            decode_service(eddystone, &[0x20, 0x01]),
+            AdvertisedFrame::Service { .. }
+        ));
+    }
+
+    #[test]
+    fn distance_from_the_calibrated_power() {
+        assert!((estimate_distance(-59, -59) - 1.0).abs() < 1e-9);
+        assert!((estimate_distance(-79, -59) - 10.0).abs() < 1e-9);
+
+        let beacon = Beacon {
+            address: Address::any(),
+            name: None,
+            rssi: -59,
+            tx_power: None,
+            frames: vec![ibeacon(), decode_manufacturer(0x0006, &[1])],
+        };
+        // The frames without calibration use the one of the device
+        assert!((beacon.distance(&beacon.frames[1]).unwrap() - 1.0).abs() < 1e-9);
+    }
+
+    #[derive(Clone)]
+    struct StandInDevice {
+        rssi: Option<i16>,
+        manufacturer_data: HashMap<u16, Variant<Vec<u8>>>,
+        service_data: HashMap<String, Variant<Vec<u8>>>,
+    }
+
+    fn stand_in_bluez() -> Crossroads {
+        let mut crossroads = Crossroads::new();
+        let device = crossroads.register(DEVICE_INTERFACE, |b| {
+            b.property("Name")
+                .get(|_, _: &mut StandInDevice| Ok("Tag".to_string()));
+            b.property("RSSI").get(|_, device: &mut StandInDevice| {
+                device.rssi.ok_or_else(|| MethodErr::no_property("RSSI"))
+            });
+            b.property("TxPower")
+                .get(|_, _: &mut StandInDevice| Ok(4_i16));
+            b.property("ManufacturerData")
+                .get(|_, device: &mut StandInDevice| Ok(device.manufacturer_data.clone()));
+            b.property("ServiceData")
+                .get(|_, device: &mut StandInDevice| Ok(device.service_data.clone()));
+        });
+
+        let ibeacon = StandInDevice {
+            rssi: Some(-70),
+            manufacturer_data: HashMap::from([(
+                APPLE,
+                Variant(parse_hex("0215 f7826da64fa24e988024bc5b71e0893e 0001 0002 c5").unwrap()),
+            )]),
+            service_data: HashMap::new(),
+        };
+        let eddystone = StandInDevice {
+            rssi: Some(-50),
+            manufacturer_data: HashMap::new(),
+            service_data: HashMap::from([(
+                "0000feaa-0000-1000-8000-00805f9b34fb".to_string(),
+                Variant(parse_hex("10 eb 03 6578616d706c65 00").unwrap()),
+            )]),
+        };
+
+        let object_manager = crossroads.object_manager();
+        crossroads.insert("/", &[object_manager], ());
+        crossroads.insert(
+            "/org/bluez/hci0/dev_AA_BB_CC_DD_EE_FF",
+            &[device],
+            ibeacon.clone(),
+        );
+        crossroads.insert(
+            "/org/bluez/hci0/dev_11_22_33_44_55_66",
+            &[device],
+            eddystone.clone(),
+        );
+        // Not heard recently
+        crossroads.insert(
+            "/org/bluez/hci0/dev_66_55_44_33_22_11",
+            &[device],
+            StandInDevice {
+                rssi: None,
+                ..ibeacon.clone()
+            },
+        );
+        // Heard by another adapter
+        crossroads.insert("/org/bluez/hci1/dev_AA_BB_CC_DD_EE_FF", &[device], ibeacon);
+        crossroads
+    }
+
+    #[tokio::test]
+    async fn beacons_from_a_stand_in_bluez() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon is not available, skipping");
+            return;
+        };
+        bus.serve(BLUEZ, stand_in_bluez());
+
+        let objects = Bus::open(&bus.address)
+            .unwrap()
+            .managed_objects(BLUEZ)
+            .await
+            .unwrap();
+        let beacons = from_objects(&objects, "hci0");
+        let addresses: Vec<String> = beacons.iter().map(|b| b.address.to_string()).collect();
+        assert_eq!(addresses, ["11:22:33:44:55:66", "AA:BB:CC:DD:EE:FF"]);
+
+        assert_eq!(beacons[0].name.as_deref(), Some("Tag"));
+        assert_eq!(beacons[0].rssi, -50);
+        assert_eq!(beacons[0].tx_power, Some(4));
+        assert_eq!(
+            beacons[0].frames,
+            [AdvertisedFrame::EddystoneUrl {
+                url: "https://example.com/".to_string(),
+                tx_power: -21,
+            }]
+        );
+        assert_eq!(beacons[1].frames, [ibeacon()]);
+    }
+
+    #[test]
+    fn render() {
+        let mut terminal = Terminal::new(TestBackend::new(120, 12)).unwrap();
+        let beacons = [Beacon {
+            address: Address::new(*b"DEADBE"),
+            name: Some("Kontakt".to_string()),
+            rssi: -69,
+            tx_power: None,
+            frames: vec![ibeacon(), decode_manufacturer(0x00e0, &[0xbe, 0xef])],
+        }];
+        terminal
+            .draw(|frame| {
+                render_beacons(
+                    &beacons,
+                    true,
+                    &mut TableState::default(),
+                    frame,
+                    frame.area(),
+                );
+            })
+            .unwrap();
+        assert_snapshot!(terminal.backend());
+    }
+}
@@ -1,10 +1,18 @@
 use std::sync::{Arc, atomic::AtomicBool};
 
-use bluer::{Adapter, Address, Session};
+use bluer::{Adapter, Address, Session, Uuid};
 
 use bluer::Device as BTDevice;
+use dbus::arg::{PropMap, RefArg};
 
-use crate::app::AppResult;
+use crate::{
+    app::AppResult, assigned_numbers, bus::ManagedObjects, config::DeviceSettings,
+    favorite::Favorites, oui::AddressKind,
+};
+
+const ADAPTER_INTERFACE: &str = "org.bluez.Adapter1";
+const DEVICE_INTERFACE: &str = "org.bluez.Device1";
+const BATTERY_INTERFACE: &str = "org.bluez.Battery1";
 
 #[derive(Debug, Clone)]
 pub struct Controller {
@@ -24,24 +32,72 @@ pub struct Controller {
 pub struct Device {
     device: BTDevice,
     pub addr: Address,
-    pub icon: &'static str,
+    pub address_kind: AddressKind,
+    pub icon: String,
+    // Freedesktop icon name reported by BlueZ, e.g. "audio-headset"
+    pub icon_name: Option<String>,
+    // Class of device of BR/EDR devices and GAP appearance of LE devices
+    pub class: Option<u32>,
+    pub appearance: Option<u16>,
+    // Services found by the SDP or GATT discovery of the device
+    pub uuids: Vec<Uuid>,
     pub alias: String,
+    pub name: Option<String>,
+    // Vendor or kind of address shown for the devices without a name
+    pub placeholder: Option<String>,
     pub is_paired: bool,
     pub is_favorite: bool,
     pub is_trusted: bool,
     pub is_connected: bool,
     pub battery_percentage: Option<u8>,
+    pub rssi: Option<i16>,
 }
 
 impl Device {
+    pub fn display_name(&self) -> &str {
+        self.name
+            .as_deref()
+            .or(self.placeholder.as_deref())
+            .unwrap_or(&self.alias)
+    }
+
+    // BlueZ uses the address as alias for the devices that did not advertise a name
+    pub fn is_named(&self) -> bool {
+        self.name.is_some() || !is_mac_addr(&self.alias)
+    }
+
     pub async fn set_alias(&self, alias: String) -> AppResult<()> {
         self.device.set_alias(alias).await?;
         Ok(())
     }
 
+    pub async fn connect_profile(&self, uuid: &Uuid) -> AppResult<()> {
+        self.device.connect_profile(uuid).await?;
+        Ok(())
+    }
+
+    // GATT services of the connected LE devices
+    pub async fn gatt_services(&self) -> AppResult<Vec<bluer::gatt::remote::Service>> {
+        Ok(self.device.services().await?)
+    }
+
+    // BlueZ's icon name, guessed from the class or the appearance when BlueZ did not set it
+    pub fn kind(&self) -> Option<&str> {
+        self.icon_name
+            .as_deref()
+            .or_else(|| assigned_numbers::icon_name(self.class, self.appearance))
+    }
+
     // https://specifications.freedesktop.org/icon-naming/latest/
-    pub fn get_icon(name: &str) -> &'static str {
-        match name {
+    pub fn get_icon(
+        name: Option<&str>,
+        class: Option<u32>,
+        appearance: Option<u16>,
+    ) -> &'static str {
+        match name
+            .or_else(|| assigned_numbers::icon_name(class, appearance))
+            .unwrap_or_default()
+        {
             "audio-card" => "󰓃 ",
             "audio-input-microphone" => " ",
             "audio-headphones" | "audio-headset" => "󰋋 ",
@@ -58,95 +114,190 @@ impl Device {
 }
 
 impl Controller {
-    pub async fn get_all(
-        session: Arc<Session>,
-        favorite_devices: &[Address],
+    // Built from the objects of BlueZ, read in a single call on each refresh
+    pub fn get_all(
+        session: &Session,
+        objects: &ManagedObjects,
+        favorites: &Favorites,
+        device_settings: &[DeviceSettings],
     ) -> AppResult<Vec<Controller>> {
         let mut controllers: Vec<Controller> = Vec::new();
 
-        // let session = bluer::Session::new().await?;
-        let adapter_names = session.adapter_names().await?;
-        for adapter_name in adapter_names {
-            if let Ok(adapter) = session.adapter(&adapter_name) {
-                let name = adapter.name().to_owned();
-                let alias = adapter.alias().await?;
-                let is_powered = adapter.is_powered().await?;
-                let is_pairable = adapter.is_pairable().await?;
-                let is_discoverable = adapter.is_discoverable().await?;
-                let is_scanning = adapter.is_discovering().await?;
-
-                let (paired_devices, new_devices) =
-                    Controller::get_all_devices(&adapter, favorite_devices).await?;
-
-                let controller = Controller {
-                    adapter: Arc::new(adapter),
-                    name,
-                    alias,
-                    is_powered,
-                    is_pairable,
-                    is_discoverable,
-                    is_scanning: Arc::new(AtomicBool::new(is_scanning)),
-                    paired_devices,
-                    new_devices,
-                };
-
-                controllers.push(controller);
-            }
+        for (path, interfaces) in objects {
+            let (Some(properties), Some(name)) = (
+                interfaces.get(ADAPTER_INTERFACE),
+                path.strip_prefix("/org/bluez/"),
+            ) else {
+                continue;
+            };
+            let adapter = session.adapter(name)?;
+
+            let (paired_devices, new_devices) =
+                Controller::get_all_devices(&adapter, objects, favorites, device_settings);
+
+            controllers.push(Controller {
+                adapter: Arc::new(adapter),
+                name: name.to_string(),
+                alias: string(properties, "Alias").unwrap_or_default(),
+                is_powered: flag(properties, "Powered"),
+                is_pairable: flag(properties, "Pairable"),
+                is_discoverable: flag(properties, "Discoverable"),
+                is_scanning: Arc::new(AtomicBool::new(flag(properties, "Discovering"))),
+                paired_devices,
+                new_devices,
+            });
         }
+        controllers.sort_by(|a, b| a.name.cmp(&b.name));
 
         Ok(controllers)
     }
 
-    pub async fn get_all_devices(
+    fn get_all_devices(
         adapter: &Adapter,
-        favorite_devices: &[Address],
-    ) -> AppResult<(Vec<Device>, Vec<Device>)> {
+        objects: &ManagedObjects,
+        favorites: &Favorites,
+        device_settings: &[DeviceSettings],
+    ) -> (Vec<Device>, Vec<Device>) {
         let mut paired_devices: Vec<Device> = Vec::new();
         let mut new_devices: Vec<Device> = Vec::new();
         let mut devices_without_aliases: Vec<Device> = Vec::new();
 
-        let connected_devices_addresses = adapter.device_addresses().await?;
-        for addr in connected_devices_addresses {
-            let device = adapter.device(addr)?;
+        let prefix = format!("/org/bluez/{}/", adapter.name());
+        for (path, interfaces) in objects {
+            if !path.starts_with(&prefix) {
+                continue;
+            }
+            let Some(properties) = interfaces.get(DEVICE_INTERFACE) else {
+                continue;
+            };
+            let Some(addr) = string(properties, "Address").and_then(|a| a.parse().ok()) else {
+                continue;
+            };
+            let settings = device_settings.iter().find(|s| s.address == addr);
+            if settings.is_some_and(|s| s.hidden) {
+                continue;
+            }
+            let Ok(device) = adapter.device(addr) else {
+                continue;
+            };
 
-            let alias = device.alias().await?;
-            let icon = Device::get_icon(device.icon().await?.unwrap_or("-".to_string()).as_str());
-            let is_paired = device.is_paired().await?;
-            let is_trusted = device.is_trusted().await?;
-            let is_connected = device.is_connected().await?;
-            let is_favorite = favorite_devices.contains(&addr);
-            let battery_percentage = device.battery_percentage().await?;
+            let alias = string(properties, "Alias").unwrap_or_else(|| addr.to_string());
+            let address_type = string(properties, "AddressType")
+                .and_then(|t| t.parse().ok())
+                .unwrap_or_default();
+            let address_kind = AddressKind::new(addr, address_type);
+            let placeholder = if is_mac_addr(&alias) {
+                address_kind.placeholder(addr)
+            } else {
+                None
+            };
+            let icon_name = string(properties, "Icon");
+            let class = number(properties, "Class");
+            let appearance = number(properties, "Appearance");
+            let icon = match settings.and_then(|s| s.icon.clone()) {
+                Some(icon) => icon,
+                None => Device::get_icon(icon_name.as_deref(), class, appearance).to_string(),
+            };
+            let mut uuids: Vec<Uuid> = properties
+                .get("UUIDs")
+                .and_then(|uuids| uuids.0.as_iter())
+                .map(|uuids| {
+                    uuids
+                        .filter_map(|uuid| uuid.as_str()?.parse().ok())
+                        .collect()
+                })
+                .unwrap_or_default();
+            uuids.sort();
+            let name = settings.and_then(|s| s.name.clone());
+            let is_paired = flag(properties, "Paired");
+            let is_trusted = flag(properties, "Trusted");
+            let is_connected = flag(properties, "Connected");
+            let is_favorite = favorites.contains(addr);
+            let battery_percentage = interfaces
+                .get(BATTERY_INTERFACE)
+                .and_then(|battery| number(battery, "Percentage"));
+            let rssi = number(properties, "RSSI");
 
             let dev = Device {
                 device,
                 addr,
+                address_kind,
                 icon,
+                icon_name,
+                class,
+                appearance,
+                uuids,
                 alias,
+                name,
+                placeholder,
                 is_paired,
                 is_favorite,
                 is_trusted,
                 is_connected,
                 battery_percentage,
+                rssi,
             };
 
             if dev.is_paired {
                 paired_devices.push(dev);
-            } else if is_mac_addr(&dev.alias) {
+            } else if !dev.is_named() {
                 devices_without_aliases.push(dev);
             } else {
                 new_devices.push(dev);
             }
         }
 
-        paired_devices.sort_by_key(|i| (!i.is_favorite, i.addr));
-        new_devices.sort_by(|a, b| a.alias.cmp(&b.alias));
+        let position = |addr: Address| {
+            device_settings
+                .iter()
+                .find(|s| s.address == addr)
+                .and_then(|s| s.position)
+                .unwrap_or(u16::MAX)
+        };
+
+        paired_devices.sort_by_key(|i| {
+            (
+                position(i.addr),
+                favorites.position(i.addr).unwrap_or(usize::MAX),
+                i.addr,
+            )
+        });
+        new_devices.sort_by(|a, b| a.display_name().cmp(b.display_name()));
         devices_without_aliases.sort_by_key(|i| i.addr);
         new_devices.extend(devices_without_aliases);
 
-        Ok((paired_devices, new_devices))
+        (paired_devices, new_devices)
     }
 }
 
+fn string(properties: &PropMap, name: &str) -> Option<String> {
+    properties.get(name)?.as_str().map(String::from)
+}
+
+fn flag(properties: &PropMap, name: &str) -> bool {
+    properties
+        .get(name)
+        .and_then(RefArg::as_u64)
+        .is_some_and(|value| value != 0)
+}
+
+fn number<T: TryFrom<i64>>(properties: &PropMap, name: &str) -> Option<T> {
+    T::try_from(properties.get(name)?.as_i64()?).ok()
+}
+
+// The first of the adapters the device is paired with
+pub async fn find_paired_device(adapters: &[Arc<Adapter>], address: Address) -> Option<BTDevice> {
+    for adapter in adapters {
+        let Ok(device) = adapter.device(address) else {
+            continue;
+        };
+        if device.is_paired().await.unwrap_or(false) {
+            return Some(device);
+        }
+    }
+    None
+}
+
 fn is_mac_addr(s: &str) -> bool {
     if s.len() != 17 {
         return false;
@@ -166,3 +317,36 @@ fn is_mac_addr(s: &str) -> bool {
         && matches!(chars.next(), Some(c) if c.is_ascii_hexdigit())
         && chars.next().is_none()
 }
+
+#[cfg(test)]
+mod tests {
+    use dbus::arg::Variant;
+
+    use super::*;
+
+    #[test]
+    fn device_properties() {
+        let mut properties = PropMap::new();
+        let mut insert = |name: &str, value: Box<dyn RefArg>| {
+            properties.insert(name.to_string(), Variant(value));
+        };
+        insert("Address", Box::new("4C:87:5D:01:02:03".to_string()));
+        insert("Paired", Box::new(true));
+        insert("Trusted", Box::new(false));
+        insert("RSSI", Box::new(-67_i16));
+        insert("Class", Box::new(0x0024_0404_u32));
+        insert("Appearance", Box::new(0x03c1_u16));
+
+        assert_eq!(
+            string(&properties, "Address").and_then(|a| a.parse::<Address>().ok()),
+            Some(Address::new([0x4c, 0x87, 0x5d, 0x01, 0x02, 0x03]))
+        );
+        assert!(flag(&properties, "Paired"));
+        assert!(!flag(&properties, "Trusted"));
+        assert!(!flag(&properties, "Connected"));
+        assert_eq!(number::<i16>(&properties, "RSSI"), Some(-67));
+        assert_eq!(number::<u32>(&properties, "Class"), Some(0x0024_0404));
+        assert_eq!(number::<u16>(&properties, "Appearance"), Some(0x03c1));
+        assert_eq!(number::<u8>(&properties, "RSSI"), None);
+    }
+}
@@ -0,0 +1,160 @@
+use std::{collections::HashMap, sync::Arc, time::Duration};
+
+use dbus::{
+    Path,
+    arg::PropMap,
+    channel::Channel,
+    nonblock::{Proxy, SyncConnection, stdintf::org_freedesktop_dbus::ObjectManager},
+};
+use dbus_tokio::connection::IOResource;
+
+use crate::app::AppResult;
+
+const TIMEOUT: Duration = Duration::from_secs(5);
+
+pub const BLUEZ: &str = "org.bluez";
+
+// The objects of a service with the properties of their interfaces
+pub type ManagedObjects = HashMap<Path<'static>, HashMap<String, PropMap>>;
+
+// Connection to a D-Bus bus, for the services and interfaces bluer does not support
+#[derive(Clone)]
+pub struct Bus {
+    connection: Arc<SyncConnection>,
+}
+
+impl std::fmt::Debug for Bus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("Bus")
+    }
+}
+
+impl Bus {
+    pub fn system() -> AppResult<Self> {
+        let (resource, connection) = dbus_tokio::connection::new_system_sync()?;
+        Ok(Self::spawn(resource, connection))
+    }
+
+    pub fn session() -> AppResult<Self> {
+        let (resource, connection) = dbus_tokio::connection::new_session_sync()?;
+        Ok(Self::spawn(resource, connection))
+    }
+
+    // Any other bus, e.g. a private bus in the tests
+    pub fn open(address: &str) -> AppResult<Self> {
+        let mut channel = Channel::open_private(address)?;
+        channel.register()?;
+        let (resource, connection) = dbus_tokio::connection::from_channel(channel)?;
+        Ok(Self::spawn(resource, connection))
+    }
+
+    fn spawn(resource: IOResource<SyncConnection>, connection: Arc<SyncConnection>) -> Self {
+        tokio::spawn(async move {
+            let _ = resource.await;
+        });
+        Self { connection }
+    }
+
+    pub fn connection(&self) -> &Arc<SyncConnection> {
+        &self.connection
+    }
+
+    pub fn proxy<'a>(
+        &self,
+        service: &'a str,
+        path: impl Into<Path<'a>>,
+    ) -> Proxy<'a, Arc<SyncConnection>> {
+        Proxy::new(service, path, TIMEOUT, self.connection.clone())
+    }
+
+    // All the objects of the service in a single call
+    pub async fn managed_objects(&self, service: &str) -> AppResult<ManagedObjects> {
+        Ok(self.proxy(service, "/").get_managed_objects().await?)
+    }
+}
+
+#[cfg(test)]
+pub mod tests {
+    use std::{
+        io::{BufRead, BufReader},
+        process::{Child, Command, Stdio},
+        thread,
+    };
+
+    use dbus::{blocking::Connection, channel::MatchingReceiver, message::MatchRule};
+    use dbus_crossroads::Crossroads;
+
+    use super::*;
+
+    const BUS_CONFIG: &str = r#"<busconfig>
+  <type>session</type>
+  <listen>unix:tmpdir=/tmp</listen>
+  <auth>EXTERNAL</auth>
+  <policy context="default">
+    <allow send_destination="*" eavesdrop="true"/>
+    <allow eavesdrop="true"/>
+    <allow own="*"/>
+  </policy>
+</busconfig>"#;
+
+    // A private bus run by `dbus-daemon`, stopped when dropped
+    pub struct PrivateBus {
+        daemon: Child,
+        pub address: String,
+    }
+
+    impl PrivateBus {
+        // `None` when `dbus-daemon` is not installed
+        pub fn start() -> Option<Self> {
+            let config =
+                std::env::temp_dir().join(format!("bluetui-bus-{}.conf", std::process::id()));
+            std::fs::write(&config, BUS_CONFIG).ok()?;
+
+            let mut daemon = Command::new("dbus-daemon")
+                .arg(format!("--config-file={}", config.display()))
+                .args(["--nofork", "--print-address"])
+                .stdout(Stdio::piped())
+                .stderr(Stdio::null())
+                .spawn()
+                .ok()?;
+
+            let mut address = String::new();
+            BufReader::new(daemon.stdout.take()?)
+                .read_line(&mut address)
+                .ok()?;
+            Some(Self {
+                daemon,
+                address: address.trim().to_string(),
+            })
+        }
+
+        // Serve `crossroads` as `service` on the bus from a background thread
+        pub fn serve(&self, service: &'static str, crossroads: Crossroads) {
+            let mut channel = Channel::open_private(&self.address).unwrap();
+            channel.register().unwrap();
+            let connection = Connection::from(channel);
+            connection
+                .request_name(service, false, true, false)
+                .unwrap();
+
+            thread::spawn(move || {
+                let mut crossroads = crossroads;
+                connection.start_receive(
+                    MatchRule::new_method_call(),
+                    Box::new(move |message, connection| {
+                        let _ = crossroads.handle_message(message, connection);
+                        true
+                    }),
+                );
+                while connection.process(Duration::from_secs(1)).is_ok() {}
+            });
+        }
+    }
+
+    impl Drop for PrivateBus {
+        fn drop(&mut self) {
+            let _ = self.daemon.kill();
+            let _ = self.daemon.wait();
+        }
+    }
+}
@@ -1,9 +1,82 @@
-use clap::Parser;
+use bluer::{Address, Uuid};
+use clap::{Parser, Subcommand};
 use std::path::PathBuf;
 
+use crate::{advertising::parse_uuid, recorder::RecordFormat};
+
 #[derive(Parser)]
 #[command(version, about)]
 pub struct Args {
-    #[arg(short, long)]
+    #[arg(short, long, global = true)]
     pub config_path: Option<PathBuf>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Inspect the config file
+    Config {
+        #[command(subcommand)]
+        command: ConfigCommand,
+    },
+
+    /// Manage the favorite devices, including the ones that are not known anymore
+    Favorites {
+        #[command(subcommand)]
+        command: FavoritesCommand,
+    },
+
+    /// Print the recorded connection, pairing, sighting and battery events
+    History {
+        /// Only print the events of this device
+        address: Option<Address>,
+    },
+
+    /// Record the GATT notifications of a device until ctrl+c is pressed
+    Record {
+        address: Address,
+
+        /// Characteristic to record, 16 bit or full UUID. All the notifying ones by default
+        #[arg(long = "characteristic", value_parser = parse_uuid)]
+        characteristics: Vec<Uuid>,
+
+        /// File the values are written to, the standard output by default
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+
+        /// Guessed from the extension of the output file, csv otherwise
+        #[arg(short, long, value_enum)]
+        format: Option<RecordFormat>,
+
+        /// Only write the raw values, without decoding the standard characteristics
+        #[arg(long)]
+        raw: bool,
+
+        /// Stop recording after this many seconds
+        #[arg(short, long)]
+        duration: Option<u64>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigCommand {
+    /// Report invalid values, unknown keys and conflicting key bindings
+    Check,
+
+    /// Print the effective config with the defaults filled in
+    Dump,
+}
+
+#[derive(Subcommand)]
+pub enum FavoritesCommand {
+    /// List the favorite devices in the order they are displayed
+    List,
+
+    /// Remove a device from the favorites
+    Remove { address: Address },
+
+    /// Move a favorite device to a new position, starting at 0
+    Move { address: Address, position: usize },
 }
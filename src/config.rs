@@ -1,18 +1,31 @@
 use core::fmt;
-use std::{path::PathBuf, process::exit};
+use std::{fs::OpenOptions, io::Write, path::PathBuf};
 
+use anyhow::Context;
+use bluer::{Address, Uuid};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
 use ratatui::layout::Flex;
 use toml;
 
+use crate::{advertising::AdvertisementProfile, recorder::RecordFormat, serial::LineEnding};
+
 use dirs;
 use serde::{
-    Deserialize, Deserializer,
+    Deserialize, Deserializer, Serialize, Serializer,
     de::{self, Unexpected, Visitor},
 };
 
-#[derive(Deserialize, Debug)]
+pub mod check;
+
+pub use check::{Diagnostic, Severity};
+
+#[derive(Deserialize, Serialize, Debug)]
 pub struct Config {
-    #[serde(default = "default_layout", deserialize_with = "deserialize_layout")]
+    #[serde(
+        default = "default_layout",
+        deserialize_with = "deserialize_layout",
+        serialize_with = "serialize_layout"
+    )]
     pub layout: Flex,
 
     #[serde(default = "Width::default")]
@@ -24,11 +37,64 @@ pub struct Config {
     #[serde(default = "default_esc_quit")]
     pub esc_quit: bool,
 
+    #[serde(default = "default_groups")]
+    pub groups: char,
+
+    #[serde(default = "default_device_details")]
+    pub device_details: char,
+
+    #[serde(default = "default_cancel_transfers")]
+    pub cancel_transfers: char,
+
+    #[serde(default = "default_beacons")]
+    pub beacons: char,
+
     #[serde(default)]
     pub adapter: Adapter,
 
     #[serde(default)]
     pub paired_device: PairedDevice,
+
+    #[serde(default)]
+    pub filter: Filter,
+
+    // Battery percentages at which a warning is shown, once per discharge
+    #[serde(default = "default_battery_alerts")]
+    pub battery_alerts: Vec<u8>,
+
+    // Bridge the devices connecting to the shared connection are added to
+    #[serde(default = "default_network_bridge")]
+    pub network_bridge: String,
+
+    // Appended to the lines sent from the serial terminal
+    #[serde(default)]
+    pub serial_line_ending: LineEnding,
+
+    // Vendor serial services offered by the serial terminal, by their 128 bit UUID
+    #[serde(default)]
+    pub serial_services: Vec<Uuid>,
+
+    // Format of the notifications recorded from the recorder popup
+    #[serde(default)]
+    pub recording_format: RecordFormat,
+
+    // TOML file of the GATT application served by the adapters
+    #[serde(default = "default_gatt_application")]
+    pub gatt_application: PathBuf,
+
+    #[serde(default, rename = "device", skip_serializing_if = "Vec::is_empty")]
+    pub devices: Vec<DeviceSettings>,
+
+    #[serde(
+        default,
+        rename = "advertisement",
+        skip_serializing_if = "Vec::is_empty"
+    )]
+    pub advertisements: Vec<AdvertisementProfile>,
+
+    // File the config was loaded from
+    #[serde(skip)]
+    pub file_path: PathBuf,
 }
 
 #[derive(Debug, Default)]
@@ -81,6 +147,18 @@ impl Visitor<'_> for WidthVisitor {
     }
 }
 
+impl Serialize for Width {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        match self {
+            Width::Auto => serializer.serialize_str("auto"),
+            Width::Size(v) => serializer.serialize_u16(*v),
+        }
+    }
+}
+
 impl<'de> Deserialize<'de> for Width {
     fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
     where
@@ -90,7 +168,7 @@ impl<'de> Deserialize<'de> for Width {
     }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct Adapter {
     #[serde(default = "default_toggle_adapter_pairing")]
     pub toggle_pairing: char,
@@ -100,6 +178,18 @@ pub struct Adapter {
 
     #[serde(default = "default_toggle_adapter_discovery")]
     pub toggle_discovery: char,
+
+    #[serde(default = "default_toggle_adapter_sharing")]
+    pub toggle_sharing: char,
+
+    #[serde(default = "default_advertise")]
+    pub advertise: char,
+
+    #[serde(default = "default_gatt_server")]
+    pub gatt_server: char,
+
+    #[serde(default = "default_add_device")]
+    pub add_device: char,
 }
 
 impl Default for Adapter {
@@ -108,11 +198,15 @@ impl Default for Adapter {
             toggle_pairing: 'p',
             toggle_power: 'o',
             toggle_discovery: 'd',
+            toggle_sharing: 'b',
+            advertise: 'a',
+            gatt_server: 'v',
+            add_device: 'A',
         }
     }
 }
 
-#[derive(Deserialize, Debug)]
+#[derive(Deserialize, Serialize, Debug)]
 pub struct PairedDevice {
     #[serde(default = "default_unpair_device")]
     pub unpair: char,
@@ -125,6 +219,30 @@ pub struct PairedDevice {
 
     #[serde(default = "default_set_new_name")]
     pub rename: char,
+
+    #[serde(default = "default_toggle_group")]
+    pub toggle_group: char,
+
+    #[serde(default = "default_media")]
+    pub media: char,
+
+    #[serde(default = "default_send_file")]
+    pub send_file: char,
+
+    #[serde(default = "default_phone_data")]
+    pub phone_data: char,
+
+    #[serde(default = "default_tether")]
+    pub tether: char,
+
+    #[serde(default = "default_serial")]
+    pub serial: char,
+
+    #[serde(default = "default_sensors")]
+    pub sensors: char,
+
+    #[serde(default = "default_record")]
+    pub record: char,
 }
 
 impl Default for PairedDevice {
@@ -134,10 +252,67 @@ impl Default for PairedDevice {
             toggle_trust: 't',
             toggle_favorite: 'f',
             rename: 'e',
+            toggle_group: 'g',
+            media: 'm',
+            send_file: 'F',
+            phone_data: 'P',
+            tether: 'T',
+            serial: 'S',
+            sensors: 'R',
+            record: 'c',
+        }
+    }
+}
+
+// Quick filters of the device tables
+#[derive(Deserialize, Serialize, Debug)]
+pub struct Filter {
+    #[serde(default = "default_filter_audio")]
+    pub audio: char,
+
+    #[serde(default = "default_filter_input")]
+    pub input: char,
+
+    #[serde(default = "default_filter_named")]
+    pub named: char,
+
+    #[serde(default = "default_filter_connected")]
+    pub connected: char,
+}
+
+impl Default for Filter {
+    fn default() -> Self {
+        Self {
+            audio: '1',
+            input: '2',
+            named: '3',
+            connected: '4',
         }
     }
 }
 
+#[derive(Deserialize, Serialize, Debug, Clone)]
+pub struct DeviceSettings {
+    pub address: Address,
+
+    // Displayed instead of the alias, the alias in BlueZ is left untouched
+    pub name: Option<String>,
+
+    pub icon: Option<String>,
+
+    #[serde(default)]
+    pub auto_connect: bool,
+
+    #[serde(default)]
+    pub hidden: bool,
+
+    // Adapter used to auto connect the device
+    pub adapter: Option<String>,
+
+    // Devices with a position are listed first, in ascending order
+    pub position: Option<u16>,
+}
+
 fn deserialize_layout<'de, D>(deserializer: D) -> Result<Flex, D::Error>
 where
     D: Deserializer<'de>,
@@ -151,16 +326,37 @@ where
         "Center" => Ok(Flex::Center),
         "SpaceAround" => Ok(Flex::SpaceAround),
         "SpaceBetween" => Ok(Flex::SpaceBetween),
-        _ => {
-            eprintln!("Wrong config: unknown layout variant {}", s);
-            eprintln!(
-                "The possible values are: Legacy, Start, End, Center, SpaceAround, SpaceBetween"
-            );
-            std::process::exit(1);
-        }
+        _ => Err(de::Error::unknown_variant(&s, LAYOUT_VARIANTS)),
     }
 }
 
+// serde requires `serialize_with` functions to take the value by reference
+#[allow(clippy::trivially_copy_pass_by_ref)]
+fn serialize_layout<S>(layout: &Flex, serializer: S) -> Result<S::Ok, S::Error>
+where
+    S: Serializer,
+{
+    let s = match layout {
+        Flex::Legacy => "Legacy",
+        Flex::Start => "Start",
+        Flex::End => "End",
+        Flex::Center => "Center",
+        Flex::SpaceAround => "SpaceAround",
+        Flex::SpaceBetween => "SpaceBetween",
+        Flex::SpaceEvenly => "SpaceEvenly",
+    };
+    serializer.serialize_str(s)
+}
+
+const LAYOUT_VARIANTS: &[&str] = &[
+    "Legacy",
+    "Start",
+    "End",
+    "Center",
+    "SpaceAround",
+    "SpaceBetween",
+];
+
 fn default_layout() -> Flex {
     Flex::SpaceAround
 }
@@ -177,6 +373,14 @@ fn default_esc_quit() -> bool {
     false
 }
 
+fn default_groups() -> char {
+    'G'
+}
+
+fn default_device_details() -> char {
+    'i'
+}
+
 fn default_toggle_adapter_pairing() -> char {
     'p'
 }
@@ -189,6 +393,22 @@ fn default_toggle_adapter_discovery() -> char {
     'd'
 }
 
+fn default_toggle_adapter_sharing() -> char {
+    'b'
+}
+
+fn default_advertise() -> char {
+    'a'
+}
+
+fn default_gatt_server() -> char {
+    'v'
+}
+
+fn default_add_device() -> char {
+    'A'
+}
+
 fn default_unpair_device() -> char {
     'u'
 }
@@ -201,24 +421,794 @@ fn default_toggle_device_favorite() -> char {
     'f'
 }
 
+fn default_toggle_group() -> char {
+    'g'
+}
+
+fn default_media() -> char {
+    'm'
+}
+
+fn default_send_file() -> char {
+    'F'
+}
+
+fn default_phone_data() -> char {
+    'P'
+}
+
+fn default_tether() -> char {
+    'T'
+}
+
+fn default_serial() -> char {
+    'S'
+}
+
+fn default_sensors() -> char {
+    'R'
+}
+
+fn default_record() -> char {
+    'c'
+}
+
+fn default_cancel_transfers() -> char {
+    'X'
+}
+
+fn default_beacons() -> char {
+    'B'
+}
+
+fn default_filter_audio() -> char {
+    '1'
+}
+
+fn default_filter_input() -> char {
+    '2'
+}
+
+fn default_filter_named() -> char {
+    '3'
+}
+
+fn default_filter_connected() -> char {
+    '4'
+}
+
+fn default_battery_alerts() -> Vec<u8> {
+    vec![20, 10]
+}
+
+fn default_network_bridge() -> String {
+    "br0".to_string()
+}
+
+fn default_gatt_application() -> PathBuf {
+    dirs::config_dir()
+        .unwrap_or_default()
+        .join("bluetui")
+        .join("gatt.toml")
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            layout: default_layout(),
+            width: Width::default(),
+            toggle_scanning: default_toggle_scanning(),
+            esc_quit: default_esc_quit(),
+            groups: default_groups(),
+            device_details: default_device_details(),
+            cancel_transfers: default_cancel_transfers(),
+            beacons: default_beacons(),
+            adapter: Adapter::default(),
+            paired_device: PairedDevice::default(),
+            filter: Filter::default(),
+            battery_alerts: default_battery_alerts(),
+            network_bridge: default_network_bridge(),
+            serial_line_ending: LineEnding::default(),
+            serial_services: Vec::new(),
+            recording_format: RecordFormat::default(),
+            gatt_application: default_gatt_application(),
+            devices: Vec::new(),
+            advertisements: Vec::new(),
+            file_path: PathBuf::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BindingContext {
+    Global,
+    Adapter,
+    PairedDevices,
+    NewDevices,
+    Search,
+    CommandPalette,
+    KeyBindings,
+    Groups,
+    Media,
+    DeviceDetails,
+    FilePicker,
+    PhoneData,
+    SerialTerminal,
+    Sensors,
+    Recorder,
+    Beacons,
+    Advertising,
+    GattServer,
+    // The rename, group name and device address inputs
+    Input,
+    // The unpair and group deletion confirmations
+    Confirmation,
+    // The pairing and file transfer requests
+    Requests,
+}
+
+impl BindingContext {
+    // In the order of the key bindings overlay
+    pub const ALL: [BindingContext; 21] = [
+        BindingContext::Global,
+        BindingContext::PairedDevices,
+        BindingContext::NewDevices,
+        BindingContext::Adapter,
+        BindingContext::Search,
+        BindingContext::CommandPalette,
+        BindingContext::KeyBindings,
+        BindingContext::Groups,
+        BindingContext::Media,
+        BindingContext::DeviceDetails,
+        BindingContext::FilePicker,
+        BindingContext::PhoneData,
+        BindingContext::SerialTerminal,
+        BindingContext::Sensors,
+        BindingContext::Recorder,
+        BindingContext::Beacons,
+        BindingContext::Advertising,
+        BindingContext::GattServer,
+        BindingContext::Input,
+        BindingContext::Confirmation,
+        BindingContext::Requests,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            BindingContext::Global => "Global",
+            BindingContext::Adapter => "Adapter",
+            BindingContext::PairedDevices => "Paired devices",
+            BindingContext::NewDevices => "New devices",
+            BindingContext::Search => "Search",
+            BindingContext::CommandPalette => "Command palette",
+            BindingContext::KeyBindings => "Key bindings",
+            BindingContext::Groups => "Device groups",
+            BindingContext::Media => "Media player",
+            BindingContext::DeviceDetails => "Device details",
+            BindingContext::FilePicker => "File picker",
+            BindingContext::PhoneData => "Phonebook and messages",
+            BindingContext::SerialTerminal => "Serial terminal",
+            BindingContext::Sensors => "Sensor readings",
+            BindingContext::Recorder => "Recorder",
+            BindingContext::Beacons => "Beacons",
+            BindingContext::Advertising => "Advertising",
+            BindingContext::GattServer => "GATT server",
+            BindingContext::Input => "Text inputs",
+            BindingContext::Confirmation => "Confirmations",
+            BindingContext::Requests => "Pairing and file requests",
+        }
+    }
+
+    // The global keys only apply to the sections, the popups handle all the keys
+    pub fn has_global_keys(self) -> bool {
+        matches!(
+            self,
+            BindingContext::Global
+                | BindingContext::Adapter
+                | BindingContext::PairedDevices
+                | BindingContext::NewDevices
+        )
+    }
+
+    // The text fields of these contexts take the keys that are not bindings
+    pub fn has_text_input(self) -> bool {
+        matches!(
+            self,
+            BindingContext::Search
+                | BindingContext::CommandPalette
+                | BindingContext::KeyBindings
+                | BindingContext::PhoneData
+                | BindingContext::SerialTerminal
+                | BindingContext::Advertising
+                | BindingContext::Input
+                | BindingContext::Requests
+        )
+    }
+}
+
+// Key of a binding, only the characters can be changed in the config
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    Ctrl(char),
+    Enter,
+    Tab,
+    BackTab,
+    Esc,
+    Backspace,
+    Up,
+    Down,
+    Left,
+    Right,
+    PageUp,
+    PageDown,
+    F(u8),
+}
+
+impl Key {
+    // `None` for the keys that can not be bound, e.g. the Alt combinations
+    pub fn from_event(event: &KeyEvent) -> Option<Self> {
+        if event.modifiers.contains(KeyModifiers::ALT) {
+            return None;
+        }
+        Some(match event.code {
+            KeyCode::Char(c) if event.modifiers.contains(KeyModifiers::CONTROL) => Key::Ctrl(c),
+            KeyCode::Char(c) => Key::Char(c),
+            KeyCode::Enter => Key::Enter,
+            KeyCode::Tab => Key::Tab,
+            KeyCode::BackTab => Key::BackTab,
+            KeyCode::Esc => Key::Esc,
+            KeyCode::Backspace => Key::Backspace,
+            KeyCode::Up => Key::Up,
+            KeyCode::Down => Key::Down,
+            KeyCode::Left => Key::Left,
+            KeyCode::Right => Key::Right,
+            KeyCode::PageUp => Key::PageUp,
+            KeyCode::PageDown => Key::PageDown,
+            KeyCode::F(n) => Key::F(n),
+            _ => return None,
+        })
+    }
+}
+
+impl From<char> for Key {
+    fn from(c: char) -> Self {
+        Key::Char(c)
+    }
+}
+
+impl fmt::Display for Key {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Key::Char(' ') => f.write_str("Space"),
+            Key::Char(c) => write!(f, "{c}"),
+            Key::Ctrl(c) => write!(f, "Ctrl-{c}"),
+            Key::Enter => f.write_str("Enter"),
+            Key::Tab => f.write_str("Tab"),
+            Key::BackTab => f.write_str("Shift-Tab"),
+            Key::Esc => f.write_str("Esc"),
+            Key::Backspace => f.write_str("Backspace"),
+            Key::Up => f.write_str("Up"),
+            Key::Down => f.write_str("Down"),
+            Key::Left => f.write_str("Left"),
+            Key::Right => f.write_str("Right"),
+            Key::PageUp => f.write_str("PageUp"),
+            Key::PageDown => f.write_str("PageDown"),
+            Key::F(n) => write!(f, "F{n}"),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Binding {
+    pub context: BindingContext,
+    pub key: Key,
+    pub action: &'static str,
+    // Path of the option in the config file, `None` for the keys that can not be changed
+    pub option: Option<&'static str>,
+}
+
+impl Binding {
+    fn fixed(context: BindingContext, key: impl Into<Key>, action: &'static str) -> Self {
+        Self {
+            context,
+            key: key.into(),
+            action,
+            option: None,
+        }
+    }
+
+    // The global bindings apply to the sections too
+    pub fn applies_to(&self, context: BindingContext) -> bool {
+        self.context == context
+            || (self.context == BindingContext::Global && context.has_global_keys())
+    }
+
+    fn configurable(
+        context: BindingContext,
+        key: char,
+        action: &'static str,
+        option: &'static str,
+    ) -> Self {
+        Self {
+            context,
+            key: Key::Char(key),
+            action,
+            option: Some(option),
+        }
+    }
+}
+
 impl Config {
-    pub fn new(config_file_path: Option<PathBuf>) -> Self {
-        let conf_path = config_file_path.unwrap_or(
+    pub fn path(config_file_path: Option<PathBuf>) -> PathBuf {
+        config_file_path.unwrap_or(
             dirs::config_dir()
                 .unwrap()
                 .join("bluetui")
                 .join("config.toml"),
-        );
-
-        let config = std::fs::read_to_string(conf_path).unwrap_or_default();
-        let app_config: Config = match toml::from_str(&config) {
-            Ok(c) => c,
-            Err(e) => {
-                eprintln!("{}", e);
-                exit(1);
-            }
+        )
+    }
+
+    pub fn new(config_file_path: Option<PathBuf>) -> Self {
+        Self::load(config_file_path).0
+    }
+
+    // Invalid entries are reported and replaced by their default value
+    pub fn load(config_file_path: Option<PathBuf>) -> (Self, Vec<Diagnostic>) {
+        let file_path = Self::path(config_file_path);
+        let content = std::fs::read_to_string(&file_path).unwrap_or_default();
+        let (mut config, diagnostics) = check::parse(&content);
+        config.file_path = file_path;
+        (config, diagnostics)
+    }
+
+    // Appended as a `[[advertisement]]` table, the rest of the file is kept as it is
+    pub fn save_advertisement(&self, profile: &AdvertisementProfile) -> anyhow::Result<()> {
+        #[derive(Serialize)]
+        struct Table<'a> {
+            advertisement: [&'a AdvertisementProfile; 1],
+        }
+
+        let content = std::fs::read_to_string(&self.file_path).unwrap_or_default();
+        let separator = if content.is_empty() || content.ends_with("\n\n") {
+            ""
+        } else if content.ends_with('\n') {
+            "\n"
+        } else {
+            "\n\n"
+        };
+        let table = toml::to_string(&Table {
+            advertisement: [profile],
+        })?;
+
+        // Appended in place rather than rewritten, a symlinked config stays a link
+        if let Some(parent) = self.file_path.parent() {
+            std::fs::create_dir_all(parent).context("unable to create parent dir(s)")?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&self.file_path)
+            .with_context(|| format!("error writing {}", self.file_path.display()))?;
+        file.write_all(format!("{separator}{table}").as_bytes())
+            .with_context(|| format!("error writing {}", self.file_path.display()))?;
+        Ok(())
+    }
+
+    pub fn device(&self, address: Address) -> Option<&DeviceSettings> {
+        self.devices.iter().find(|device| device.address == address)
+    }
+
+    pub fn dump(&self) -> anyhow::Result<String> {
+        Ok(toml::to_string_pretty(self)?)
+    }
+
+    // Whether `key` has a binding in `context`, the global ones included
+    pub fn is_bound(&self, context: BindingContext, key: Key) -> bool {
+        self.bindings()
+            .iter()
+            .any(|binding| binding.key == key && binding.applies_to(context))
+    }
+
+    // The bindings of each context in the order they are matched by `handle_key_events`
+    pub fn bindings(&self) -> Vec<Binding> {
+        use BindingContext::{
+            Adapter, Advertising, Beacons, CommandPalette, Confirmation, DeviceDetails, FilePicker,
+            GattServer, Global, Groups, Input, KeyBindings, Media, NewDevices, PairedDevices,
+            PhoneData, Recorder, Requests, Search, Sensors, SerialTerminal,
+        };
+
+        vec![
+            Binding::fixed(Global, Key::Ctrl('c'), "Quit"),
+            Binding::fixed(Global, 'q', "Quit"),
+            Binding::fixed(Global, Key::Ctrl('p'), "Command palette"),
+            Binding::fixed(
+                Global,
+                Key::Esc,
+                "Clear the search, quit when `esc_quit` is set",
+            ),
+            Binding::fixed(Global, Key::Tab, "Focus next section"),
+            Binding::fixed(Global, 'l', "Focus next section"),
+            Binding::fixed(Global, Key::BackTab, "Focus previous section"),
+            Binding::fixed(Global, 'h', "Focus previous section"),
+            Binding::fixed(Global, 'j', "Scroll down"),
+            Binding::fixed(Global, Key::Down, "Scroll down"),
+            Binding::fixed(Global, 'k', "Scroll up"),
+            Binding::fixed(Global, Key::Up, "Scroll up"),
+            Binding::configurable(
+                Global,
+                self.toggle_scanning,
+                "Start/Stop scanning",
+                "toggle_scanning",
+            ),
+            Binding::configurable(Global, self.beacons, "Beacons nearby", "beacons"),
+            Binding::configurable(Global, self.groups, "Device groups", "groups"),
+            Binding::configurable(
+                Global,
+                self.cancel_transfers,
+                "Cancel the file transfers",
+                "cancel_transfers",
+            ),
+            Binding::fixed(Global, ':', "Command palette"),
+            Binding::fixed(Global, '?', "Show all key bindings"),
+            Binding::fixed(Global, '/', "Search devices"),
+            Binding::fixed(Global, 'n', "Next match"),
+            Binding::fixed(Global, 'N', "Previous match"),
+            Binding::configurable(
+                Global,
+                self.filter.audio,
+                "Show only audio devices",
+                "filter.audio",
+            ),
+            Binding::configurable(
+                Global,
+                self.filter.input,
+                "Show only input devices",
+                "filter.input",
+            ),
+            Binding::configurable(
+                Global,
+                self.filter.named,
+                "Show only named devices",
+                "filter.named",
+            ),
+            Binding::configurable(
+                Global,
+                self.filter.connected,
+                "Show only connected devices",
+                "filter.connected",
+            ),
+            Binding::configurable(
+                PairedDevices,
+                self.paired_device.unpair,
+                "Unpair",
+                "paired_device.unpair",
+            ),
+            Binding::fixed(PairedDevices, Key::Enter, "Connect/Disconnect"),
+            Binding::fixed(PairedDevices, ' ', "Connect/Disconnect"),
+            Binding::configurable(
+                PairedDevices,
+                self.paired_device.toggle_trust,
+                "Trust/Untrust",
+                "paired_device.toggle_trust",
+            ),
+            Binding::configurable(
+                PairedDevices,
+                self.paired_device.toggle_favorite,
+                "Favorite/Unfavorite",
+                "paired_device.toggle_favorite",
+            ),
+            Binding::configurable(
+                PairedDevices,
+                self.paired_device.rename,
+                "Rename",
+                "paired_device.rename",
+            ),
+            Binding::configurable(
+                PairedDevices,
+                self.paired_device.toggle_group,
+                "Add to/Remove from a group",
+                "paired_device.toggle_group",
+            ),
+            Binding::configurable(
+                PairedDevices,
+                self.device_details,
+                "Device details and history",
+                "device_details",
+            ),
+            Binding::configurable(
+                PairedDevices,
+                self.paired_device.media,
+                "Media player controls",
+                "paired_device.media",
+            ),
+            Binding::configurable(
+                PairedDevices,
+                self.paired_device.send_file,
+                "Send a file",
+                "paired_device.send_file",
+            ),
+            Binding::configurable(
+                PairedDevices,
+                self.paired_device.phone_data,
+                "Export the phonebook and messages",
+                "paired_device.phone_data",
+            ),
+            Binding::configurable(
+                PairedDevices,
+                self.paired_device.tether,
+                "Connect to/Disconnect from the network",
+                "paired_device.tether",
+            ),
+            Binding::configurable(
+                PairedDevices,
+                self.paired_device.serial,
+                "Serial terminal",
+                "paired_device.serial",
+            ),
+            Binding::configurable(
+                PairedDevices,
+                self.paired_device.sensors,
+                "Live sensor readings",
+                "paired_device.sensors",
+            ),
+            Binding::configurable(
+                PairedDevices,
+                self.paired_device.record,
+                "Record the notifications",
+                "paired_device.record",
+            ),
+            Binding::configurable(
+                Adapter,
+                self.adapter.toggle_pairing,
+                "Enable/Disable pairing",
+                "adapter.toggle_pairing",
+            ),
+            Binding::configurable(
+                Adapter,
+                self.adapter.toggle_power,
+                "Power on/off",
+                "adapter.toggle_power",
+            ),
+            Binding::configurable(
+                Adapter,
+                self.adapter.toggle_sharing,
+                "Share/Stop sharing the connection",
+                "adapter.toggle_sharing",
+            ),
+            Binding::configurable(
+                Adapter,
+                self.adapter.advertise,
+                "Advertise as a LE peripheral",
+                "adapter.advertise",
+            ),
+            Binding::configurable(
+                Adapter,
+                self.adapter.gatt_server,
+                "Serve the GATT application",
+                "adapter.gatt_server",
+            ),
+            Binding::configurable(
+                Adapter,
+                self.adapter.add_device,
+                "Add a device by its address",
+                "adapter.add_device",
+            ),
+            Binding::configurable(
+                Adapter,
+                self.adapter.toggle_discovery,
+                "Enable/Disable discovery",
+                "adapter.toggle_discovery",
+            ),
+            Binding::fixed(NewDevices, Key::Enter, "Pair"),
+            Binding::fixed(NewDevices, ' ', "Pair"),
+            Binding::configurable(
+                NewDevices,
+                self.device_details,
+                "Device details and history",
+                "device_details",
+            ),
+            Binding::fixed(Input, Key::Enter, "Confirm"),
+            Binding::fixed(Input, Key::Tab, "Next address type"),
+            Binding::fixed(Input, Key::Esc, "Cancel"),
+            Binding::fixed(Groups, Key::Esc, "Close"),
+            Binding::configurable(Groups, self.groups, "Close", "groups"),
+            Binding::fixed(Groups, 'j', "Next group"),
+            Binding::fixed(Groups, Key::Down, "Next group"),
+            Binding::fixed(Groups, 'k', "Previous group"),
+            Binding::fixed(Groups, Key::Up, "Previous group"),
+            Binding::fixed(Groups, 'c', "Connect all the devices"),
+            Binding::fixed(Groups, 'd', "Disconnect all the devices"),
+            Binding::fixed(Groups, 't', "Trust all the devices"),
+            Binding::fixed(Groups, 'x', "Delete the group"),
+            Binding::fixed(Confirmation, Key::Esc, "Cancel"),
+            Binding::fixed(Confirmation, Key::Tab, "Switch between yes and no"),
+            Binding::fixed(Confirmation, Key::Enter, "Confirm the choice"),
+            Binding::fixed(DeviceDetails, Key::Esc, "Close"),
+            Binding::fixed(DeviceDetails, Key::Enter, "Close"),
+            Binding::configurable(
+                DeviceDetails,
+                self.device_details,
+                "Close",
+                "device_details",
+            ),
+            Binding::fixed(DeviceDetails, '+', "Raise the volume"),
+            Binding::fixed(DeviceDetails, '=', "Raise the volume"),
+            Binding::fixed(DeviceDetails, '-', "Lower the volume"),
+            Binding::fixed(DeviceDetails, '1', "Set the volume to 10%"),
+            Binding::fixed(DeviceDetails, '2', "Set the volume to 20%"),
+            Binding::fixed(DeviceDetails, '3', "Set the volume to 30%"),
+            Binding::fixed(DeviceDetails, '4', "Set the volume to 40%"),
+            Binding::fixed(DeviceDetails, '5', "Set the volume to 50%"),
+            Binding::fixed(DeviceDetails, '6', "Set the volume to 60%"),
+            Binding::fixed(DeviceDetails, '7', "Set the volume to 70%"),
+            Binding::fixed(DeviceDetails, '8', "Set the volume to 80%"),
+            Binding::fixed(DeviceDetails, '9', "Set the volume to 90%"),
+            Binding::fixed(DeviceDetails, '0', "Set the volume to 100%"),
+            Binding::fixed(FilePicker, Key::Esc, "Close"),
+            Binding::fixed(FilePicker, 'j', "Next file"),
+            Binding::fixed(FilePicker, Key::Down, "Next file"),
+            Binding::fixed(FilePicker, 'k', "Previous file"),
+            Binding::fixed(FilePicker, Key::Up, "Previous file"),
+            Binding::fixed(FilePicker, 'h', "Parent directory"),
+            Binding::fixed(FilePicker, Key::Left, "Parent directory"),
+            Binding::fixed(FilePicker, Key::Backspace, "Parent directory"),
+            Binding::fixed(FilePicker, 'l', "Open the directory or send the file"),
+            Binding::fixed(
+                FilePicker,
+                Key::Right,
+                "Open the directory or send the file",
+            ),
+            Binding::fixed(
+                FilePicker,
+                Key::Enter,
+                "Open the directory or send the file",
+            ),
+            Binding::fixed(PhoneData, Key::Esc, "Close, or leave the destination"),
+            Binding::fixed(PhoneData, 'j', "Next entry"),
+            Binding::fixed(PhoneData, Key::Down, "Next entry"),
+            Binding::fixed(PhoneData, 'k', "Previous entry"),
+            Binding::fixed(PhoneData, Key::Up, "Previous entry"),
+            Binding::fixed(PhoneData, Key::Tab, "Edit/Leave the destination"),
+            Binding::fixed(PhoneData, Key::Enter, "Export, or leave the destination"),
+            Binding::fixed(Sensors, Key::Esc, "Close"),
+            Binding::fixed(Recorder, Key::Esc, "Close, the recording keeps running"),
+            Binding::configurable(
+                Recorder,
+                self.paired_device.record,
+                "Close, the recording keeps running",
+                "paired_device.record",
+            ),
+            Binding::fixed(Recorder, 's', "Stop the recording"),
+            Binding::fixed(Recorder, 'j', "Next characteristic"),
+            Binding::fixed(Recorder, Key::Down, "Next characteristic"),
+            Binding::fixed(Recorder, 'k', "Previous characteristic"),
+            Binding::fixed(Recorder, Key::Up, "Previous characteristic"),
+            Binding::fixed(Recorder, ' ', "Pick/Unpick the characteristic"),
+            Binding::fixed(Recorder, Key::Enter, "Start the recording"),
+            Binding::fixed(Beacons, Key::Esc, "Close"),
+            Binding::configurable(Beacons, self.beacons, "Close", "beacons"),
+            Binding::configurable(
+                Beacons,
+                self.toggle_scanning,
+                "Start/Stop scanning",
+                "toggle_scanning",
+            ),
+            Binding::fixed(Beacons, 'j', "Next frame"),
+            Binding::fixed(Beacons, Key::Down, "Next frame"),
+            Binding::fixed(Beacons, 'k', "Previous frame"),
+            Binding::fixed(Beacons, Key::Up, "Previous frame"),
+            Binding::fixed(
+                GattServer,
+                Key::Esc,
+                "Close, the application keeps being served",
+            ),
+            Binding::fixed(GattServer, Key::Enter, "Start/Stop serving the application"),
+            Binding::fixed(
+                Advertising,
+                Key::Esc,
+                "Close, the advertisement keeps running",
+            ),
+            Binding::fixed(Advertising, Key::Tab, "Next field"),
+            Binding::fixed(Advertising, Key::BackTab, "Previous field"),
+            Binding::fixed(Advertising, Key::Enter, "Start/Stop advertising"),
+            Binding::fixed(Advertising, Key::Ctrl('s'), "Save the profile"),
+            Binding::fixed(Advertising, 'j', "Next profile"),
+            Binding::fixed(Advertising, Key::Down, "Next profile"),
+            Binding::fixed(Advertising, 'k', "Previous profile"),
+            Binding::fixed(Advertising, Key::Up, "Previous profile"),
+            Binding::fixed(Advertising, ' ', "Discoverable/Not discoverable"),
+            Binding::fixed(SerialTerminal, Key::Esc, "Close the connection"),
+            Binding::fixed(SerialTerminal, 'j', "Next service"),
+            Binding::fixed(SerialTerminal, Key::Down, "Next service"),
+            Binding::fixed(SerialTerminal, 'k', "Previous service"),
+            Binding::fixed(SerialTerminal, Key::Up, "Previous service"),
+            Binding::fixed(SerialTerminal, 'l', "Next channel"),
+            Binding::fixed(SerialTerminal, Key::Right, "Next channel"),
+            Binding::fixed(SerialTerminal, 'h', "Previous channel"),
+            Binding::fixed(SerialTerminal, Key::Left, "Previous channel"),
+            Binding::fixed(SerialTerminal, Key::Enter, "Connect, then send the line"),
+            Binding::fixed(SerialTerminal, Key::F(2), "Switch between text and hex"),
+            Binding::fixed(SerialTerminal, Key::F(3), "Change the line ending"),
+            Binding::fixed(SerialTerminal, Key::F(4), "Start/Stop logging"),
+            Binding::fixed(Media, Key::Esc, "Close"),
+            Binding::configurable(
+                Media,
+                self.paired_device.media,
+                "Close",
+                "paired_device.media",
+            ),
+            Binding::fixed(Media, ' ', "Play/Pause"),
+            Binding::fixed(Media, 'n', "Next track"),
+            Binding::fixed(Media, 'p', "Previous track"),
+            Binding::fixed(Media, 'x', "Stop"),
+            Binding::fixed(CommandPalette, Key::Esc, "Close"),
+            Binding::fixed(CommandPalette, Key::Up, "Previous command"),
+            Binding::fixed(CommandPalette, Key::Down, "Next command"),
+            Binding::fixed(CommandPalette, Key::Ctrl('p'), "Previous command"),
+            Binding::fixed(CommandPalette, Key::Ctrl('n'), "Next command"),
+            Binding::fixed(CommandPalette, Key::Enter, "Run the command"),
+            Binding::fixed(KeyBindings, Key::Esc, "Close"),
+            Binding::fixed(KeyBindings, Key::Up, "Scroll up"),
+            Binding::fixed(KeyBindings, Key::Down, "Scroll down"),
+            Binding::fixed(KeyBindings, Key::PageUp, "Scroll up a page"),
+            Binding::fixed(KeyBindings, Key::PageDown, "Scroll down a page"),
+            Binding::fixed(Search, Key::Enter, "Keep the search"),
+            Binding::fixed(Search, Key::Esc, "Clear the search"),
+            Binding::fixed(Requests, Key::Tab, "Next choice"),
+            Binding::fixed(Requests, Key::BackTab, "Previous choice"),
+            Binding::fixed(Requests, Key::Enter, "Confirm the choice"),
+            Binding::fixed(Requests, Key::Esc, "Reject the request"),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn saved_advertisements_are_loaded_back() {
+        let path = std::env::temp_dir().join(format!("bluetui-config-{}.toml", std::process::id()));
+        std::fs::write(&path, "# My config\ntoggle_scanning = \"x\"\n").unwrap();
+        let (config, _) = Config::load(Some(path.clone()));
+
+        let profile = AdvertisementProfile {
+            name: "Beacon".to_string(),
+            company_id: Some(0x004c),
+            manufacturer_data: "0215".to_string(),
+            ..Default::default()
+        };
+        config.save_advertisement(&profile).unwrap();
+
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert!(content.starts_with("# My config\ntoggle_scanning = \"x\"\n\n[[advertisement]]\n"));
+        let (config, diagnostics) = Config::load(Some(path.clone()));
+        assert!(diagnostics.is_empty());
+        assert_eq!(config.toggle_scanning, 'x');
+        assert_eq!(config.advertisements, [profile]);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn saving_an_advertisement_keeps_a_symlinked_config() {
+        let dir = std::env::temp_dir().join(format!("bluetui-dotfiles-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let target = dir.join("config.toml");
+        let link = dir.join("link.toml");
+        std::fs::write(&target, "toggle_scanning = \"x\"\n").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        let (config, _) = Config::load(Some(link.clone()));
+
+        let profile = AdvertisementProfile {
+            name: "Beacon".to_string(),
+            ..Default::default()
         };
+        config.save_advertisement(&profile).unwrap();
 
-        app_config
+        assert!(std::fs::symlink_metadata(&link).unwrap().is_symlink());
+        let content = std::fs::read_to_string(&target).unwrap();
+        assert!(content.starts_with("toggle_scanning = \"x\"\n\n[[advertisement]]\n"));
+        std::fs::remove_dir_all(dir).unwrap();
     }
 }
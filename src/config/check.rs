@@ -0,0 +1,465 @@
+use std::{
+    fmt::{self, Write},
+    ops::Range,
+    path::Path,
+};
+
+use toml::{
+    Spanned,
+    de::{DeArray, DeTable, DeValue},
+};
+
+use crate::config::{Binding, Config};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub message: String,
+    // Line and column, both starting at 1
+    pub position: Option<(usize, usize)>,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let severity = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        if let Some((line, column)) = self.position {
+            write!(f, "{line}:{column}: {severity}: {}", self.message)
+        } else {
+            write!(f, "{severity}: {}", self.message)
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Segment {
+    Key(String),
+    Index(usize),
+}
+
+// Parse the config, collecting every problem instead of stopping at the first one.
+// Invalid values are dropped one at a time so that they fall back to their defaults,
+// an invalid entry of an array (e.g. a `[[device]]`) is dropped as a whole.
+pub fn parse(content: &str) -> (Config, Vec<Diagnostic>) {
+    let mut diagnostics = Vec::new();
+
+    let (mut root, errors) = DeTable::parse_recoverable(content);
+    let original = root.clone();
+    for error in errors {
+        diagnostics.push(Diagnostic {
+            severity: Severity::Error,
+            message: error.message().trim().to_string(),
+            position: error.span().map(|span| position(content, span.start)),
+        });
+    }
+
+    let config = loop {
+        let mut ignored = Vec::new();
+        let deserializer = toml::de::Deserializer::from(root.clone());
+        match serde_ignored::deserialize(deserializer, |path| ignored.push(segments(&path))) {
+            Ok(config) => {
+                for path in ignored {
+                    diagnostics.push(Diagnostic {
+                        severity: Severity::Warning,
+                        message: format!(
+                            "unknown key `{}`",
+                            display_path(&file_path(original.get_ref(), root.get_ref(), &path))
+                        ),
+                        position: key_span(root.get_ref(), &path)
+                            .map(|span| position(content, span.start)),
+                    });
+                }
+                break config;
+            }
+            Err(error) => {
+                let path = error.span().and_then(|span| locate(root.get_ref(), &span));
+
+                let mut message = error.message().trim().to_string();
+                if let Some(path) = &path {
+                    message = format!(
+                        "`{}`: {message}",
+                        display_path(&file_path(original.get_ref(), root.get_ref(), path))
+                    );
+                }
+                diagnostics.push(Diagnostic {
+                    severity: Severity::Error,
+                    message,
+                    position: error.span().map(|span| position(content, span.start)),
+                });
+
+                let path = path.map(|mut path| {
+                    if let Some(index) = path.iter().position(|s| matches!(s, Segment::Index(_))) {
+                        path.truncate(index + 1);
+                    }
+                    path
+                });
+
+                match path {
+                    Some(path) if remove(root.get_mut(), &path) => {}
+                    _ => break Config::default(),
+                }
+            }
+        }
+    };
+
+    for (binding, shadowed_by) in shadowed_bindings(&config.bindings()) {
+        let Some(option) = binding.option else {
+            continue;
+        };
+        let path: Vec<Segment> = option
+            .split('.')
+            .map(|key| Segment::Key(key.to_string()))
+            .collect();
+
+        let message = match shadowed_by.option {
+            Some(other) => format!(
+                "`{option}` is bound to '{}' which is already used by `{other}`",
+                binding.key
+            ),
+            None => format!(
+                "`{option}` is bound to '{}' which is reserved for \"{}\"",
+                binding.key, shadowed_by.action
+            ),
+        };
+
+        diagnostics.push(Diagnostic {
+            severity: Severity::Warning,
+            message,
+            position: value_span(root.get_ref(), &path).map(|span| position(content, span.start)),
+        });
+    }
+
+    diagnostics.sort_by_key(|d| d.position.unwrap_or((usize::MAX, 0)));
+
+    (config, diagnostics)
+}
+
+// Bindings that can never be triggered because an earlier binding with the same key
+// is matched first in the same context, or globally in the sections
+pub fn shadowed_bindings(bindings: &[Binding]) -> Vec<(&Binding, &Binding)> {
+    bindings
+        .iter()
+        .enumerate()
+        .filter_map(|(i, binding)| {
+            bindings[..i]
+                .iter()
+                .find(|other| other.key == binding.key && other.applies_to(binding.context))
+                .map(|other| (binding, other))
+        })
+        .collect()
+}
+
+pub fn run(config_file_path: &Path) -> i32 {
+    let Ok(content) = std::fs::read_to_string(config_file_path) else {
+        println!(
+            "No config file found at {}, the default config is used",
+            config_file_path.display()
+        );
+        return 0;
+    };
+
+    let (_, diagnostics) = parse(&content);
+
+    for diagnostic in &diagnostics {
+        println!("{}:{}", config_file_path.display(), diagnostic);
+    }
+
+    let errors = diagnostics
+        .iter()
+        .filter(|d| d.severity == Severity::Error)
+        .count();
+    let warnings = diagnostics.len() - errors;
+
+    if diagnostics.is_empty() {
+        println!("No problems found in {}", config_file_path.display());
+        0
+    } else {
+        println!("{errors} error(s), {warnings} warning(s)");
+        1
+    }
+}
+
+fn position(content: &str, offset: usize) -> (usize, usize) {
+    let before = &content[..offset.min(content.len())];
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map_or(0, |i| i + 1);
+    let column = before[line_start..].chars().count() + 1;
+    (line, column)
+}
+
+fn segments(path: &serde_ignored::Path) -> Vec<Segment> {
+    let mut segments = Vec::new();
+    let mut current = path;
+    loop {
+        match current {
+            serde_ignored::Path::Root => break,
+            serde_ignored::Path::Seq { parent, index } => {
+                segments.push(Segment::Index(*index));
+                current = parent;
+            }
+            serde_ignored::Path::Map { parent, key } => {
+                segments.push(Segment::Key(key.clone()));
+                current = parent;
+            }
+            serde_ignored::Path::Some { parent }
+            | serde_ignored::Path::NewtypeStruct { parent }
+            | serde_ignored::Path::NewtypeVariant { parent } => current = parent,
+        }
+    }
+    segments.reverse();
+    segments
+}
+
+fn display_path(path: &[Segment]) -> String {
+    let mut s = String::new();
+    for segment in path {
+        match segment {
+            Segment::Key(key) => {
+                if !s.is_empty() {
+                    s.push('.');
+                }
+                s.push_str(key);
+            }
+            Segment::Index(index) => {
+                let _ = write!(s, "[{index}]");
+            }
+        }
+    }
+    s
+}
+
+// Dropping entries of arrays shifts the indices of the following ones, map them back
+// to the index of the entry in the file using their spans
+fn file_path(original: &DeTable, current: &DeTable, path: &[Segment]) -> Vec<Segment> {
+    let mut mapped = Vec::with_capacity(path.len());
+    let (mut original_value, mut current_value) = match path.first() {
+        Some(Segment::Key(key)) => (original.get(key.as_str()), current.get(key.as_str())),
+        _ => return path.to_vec(),
+    };
+    mapped.push(path[0].clone());
+
+    for segment in &path[1..] {
+        let (Some(o), Some(c)) = (original_value, current_value) else {
+            mapped.push(segment.clone());
+            continue;
+        };
+        let next = child(c.get_ref(), segment);
+        if let (Segment::Index(_), DeValue::Array(array), Some(item)) = (segment, o.get_ref(), next)
+        {
+            let index = array.iter().position(|i| i.span() == item.span());
+            mapped.push(Segment::Index(index.unwrap_or_default()));
+            original_value = index.and_then(|i| array.get(i));
+        } else {
+            mapped.push(segment.clone());
+            original_value = child(o.get_ref(), segment);
+        }
+        current_value = next;
+    }
+    mapped
+}
+
+fn child<'a, 'i>(value: &'a DeValue<'i>, segment: &Segment) -> Option<&'a Spanned<DeValue<'i>>> {
+    match (value, segment) {
+        (DeValue::Table(table), Segment::Key(key)) => table.get(key.as_str()),
+        (DeValue::Array(array), Segment::Index(index)) => array.get(*index),
+        _ => None,
+    }
+}
+
+fn value_span(root: &DeTable, path: &[Segment]) -> Option<Range<usize>> {
+    let (first, rest) = path.split_first()?;
+    let Segment::Key(key) = first else {
+        return None;
+    };
+    let mut value = root.get(key.as_str())?;
+    for segment in rest {
+        value = child(value.get_ref(), segment)?;
+    }
+    Some(value.span())
+}
+
+fn key_span(root: &DeTable, path: &[Segment]) -> Option<Range<usize>> {
+    let (last, parent) = path.split_last()?;
+    let Segment::Key(key) = last else {
+        return value_span(root, path);
+    };
+    let table = if parent.is_empty() {
+        root
+    } else {
+        let mut value = match parent.first()? {
+            Segment::Key(k) => root.get(k.as_str())?,
+            Segment::Index(_) => return None,
+        };
+        for segment in &parent[1..] {
+            value = child(value.get_ref(), segment)?;
+        }
+        value.get_ref().as_table()?
+    };
+    table.get_key_value(key.as_str()).map(|(key, _)| key.span())
+}
+
+// Find the innermost entry whose key or value contains the span of an error.
+// The span of an array of tables only covers its headers so nested entries are searched first.
+fn locate(table: &DeTable, span: &Range<usize>) -> Option<Vec<Segment>> {
+    let contains = |outer: Range<usize>| outer.start <= span.start && span.end <= outer.end;
+
+    for (key, value) in table {
+        let nested = match value.get_ref() {
+            DeValue::Table(inner) => locate(inner, span),
+            DeValue::Array(array) => array.iter().enumerate().find_map(|(index, item)| {
+                let nested = match item.get_ref() {
+                    DeValue::Table(inner) => locate(inner, span),
+                    _ => None,
+                };
+                match nested {
+                    Some(rest) => Some([vec![Segment::Index(index)], rest].concat()),
+                    None => contains(item.span()).then(|| vec![Segment::Index(index)]),
+                }
+            }),
+            _ => None,
+        };
+
+        let segment = Segment::Key(key.get_ref().to_string());
+        if let Some(rest) = nested {
+            return Some([vec![segment], rest].concat());
+        }
+        if contains(key.span()) || contains(value.span()) {
+            return Some(vec![segment]);
+        }
+    }
+    None
+}
+
+fn remove(table: &mut DeTable, path: &[Segment]) -> bool {
+    match path {
+        [Segment::Key(key)] => table.remove(key.as_str()).is_some(),
+        [Segment::Key(key), Segment::Index(index)] => {
+            let Some((k, value)) = table.remove_entry(key.as_str()) else {
+                return false;
+            };
+            let span = value.span();
+            let DeValue::Array(array) = value.into_inner() else {
+                return false;
+            };
+            let array: DeArray = array
+                .into_iter()
+                .enumerate()
+                .filter_map(|(i, item)| (i != *index).then_some(item))
+                .collect();
+            table.insert(k, Spanned::new(span, DeValue::Array(array)));
+            true
+        }
+        [Segment::Key(key), rest @ ..] => match table.get_mut(key.as_str()).map(Spanned::get_mut) {
+            Some(DeValue::Table(inner)) => remove(inner, rest),
+            Some(DeValue::Array(array)) => match rest {
+                [Segment::Index(index), rest @ ..] => {
+                    match array.get_mut(*index).map(Spanned::get_mut) {
+                        Some(DeValue::Table(inner)) => remove(inner, rest),
+                        _ => false,
+                    }
+                }
+                _ => false,
+            },
+            _ => false,
+        },
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn valid_config() {
+        let (config, diagnostics) =
+            parse("toggle_scanning = \"x\"\n[adapter]\ntoggle_power = \"w\"\n");
+        assert!(diagnostics.is_empty());
+        assert_eq!(config.toggle_scanning, 'x');
+        assert_eq!(config.adapter.toggle_power, 'w');
+    }
+
+    #[test]
+    fn invalid_values_fall_back_to_defaults() {
+        let (config, diagnostics) =
+            parse("layout = \"Middle\"\nwidth = -3\n\n[paired_device]\nrename = \"r\"\n");
+
+        assert_eq!(config.layout, ratatui::layout::Flex::SpaceAround);
+        assert!(matches!(config.width, crate::config::Width::Auto));
+        assert_eq!(config.paired_device.rename, 'r');
+
+        let positions: Vec<_> = diagnostics
+            .iter()
+            .filter(|d| d.severity == Severity::Error)
+            .map(|d| d.position)
+            .collect();
+        assert_eq!(positions, vec![Some((1, 10)), Some((2, 9))]);
+    }
+
+    #[test]
+    fn unknown_keys() {
+        let (_, diagnostics) = parse("toggle_scan = \"x\"\n[adapter]\npower = \"w\"\n");
+        let messages: Vec<_> = diagnostics.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "1:1: warning: unknown key `toggle_scan`",
+                "3:1: warning: unknown key `adapter.power`",
+            ]
+        );
+    }
+
+    #[test]
+    fn shadowed_bindings_are_reported() {
+        let (_, diagnostics) = parse("[paired_device]\nrename = \"u\"\ntoggle_trust = \"j\"\n");
+        let messages: Vec<_> = diagnostics.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            messages,
+            vec![
+                "2:10: warning: `paired_device.rename` is bound to 'u' which is already used by `paired_device.unpair`",
+                "3:16: warning: `paired_device.toggle_trust` is bound to 'j' which is reserved for \"Scroll down\"",
+            ]
+        );
+    }
+
+    #[test]
+    fn invalid_device_entries_are_dropped() {
+        let (config, diagnostics) = parse(
+            "[[device]]\naddress = \"nope\"\n\n[[device]]\naddress = \"AA:BB:CC:DD:EE:FF\"\nhidden = true\ncolor = 1\n",
+        );
+        let messages: Vec<_> = diagnostics.iter().map(ToString::to_string).collect();
+
+        assert_eq!(config.devices.len(), 1);
+        assert!(config.devices[0].hidden);
+        assert_eq!(messages.len(), 2);
+        assert!(messages[0].starts_with("2:11: error: `device[0].address`"));
+        assert_eq!(messages[1], "7:1: warning: unknown key `device[1].color`");
+    }
+
+    #[test]
+    fn invalid_serial_services_are_dropped() {
+        let (config, diagnostics) =
+            parse("serial_services = [\"nope\", \"6e400001-b5a3-f393-e0a9-e50e24dcca9e\"]\n");
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(
+            config.serial_services,
+            ["6e400001-b5a3-f393-e0a9-e50e24dcca9e"
+                .parse::<bluer::Uuid>()
+                .unwrap()]
+        );
+    }
+
+    #[test]
+    fn same_key_in_different_contexts() {
+        let (_, diagnostics) = parse("[adapter]\ntoggle_power = \"u\"\n");
+        assert!(diagnostics.is_empty());
+    }
+}
@@ -0,0 +1,199 @@
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Clear, Padding, Paragraph, Sparkline},
+};
+
+use crate::{
+    assigned_numbers::{appearance, class_of_device, service_label},
+    battery::{battery_series, estimate_remaining},
+    bluetooth::Device,
+    history::{DeviceHistory, format_ago, format_duration, format_time},
+    media::Transport,
+    network::Network,
+};
+
+fn field(name: &str, value: String) -> Line<'static> {
+    Line::from(vec![
+        Span::from(format!("{name:<16}")).bold(),
+        Span::from(value),
+    ])
+}
+
+// The battery chart covers the last day
+const BATTERY_CHART_SPAN: u64 = 24 * 3600;
+const BATTERY_CHART_HEIGHT: u16 = 4;
+
+const VOLUME_GAUGE_WIDTH: usize = 20;
+
+fn volume_gauge(percentage: u8) -> Line<'static> {
+    let filled = usize::from(percentage) * VOLUME_GAUGE_WIDTH / 100;
+    Line::from(vec![
+        Span::from(format!("{:<16}", "Volume")).bold(),
+        Span::from("█".repeat(filled)).green(),
+        Span::from("░".repeat(VOLUME_GAUGE_WIDTH - filled)).dark_gray(),
+        Span::from(format!(" {percentage}%")),
+    ])
+}
+
+fn yes_no(value: bool) -> String {
+    if value { "yes" } else { "no" }.to_string()
+}
+
+// Audio and network connections of the device, BlueZ exposes them besides the device
+#[derive(Debug, Default, Clone, Copy)]
+pub struct Connections<'a> {
+    pub transport: Option<&'a Transport>,
+    pub network: Option<&'a Network>,
+}
+
+pub fn render_device_details(
+    device: &Device,
+    adapter: &str,
+    history: Option<&DeviceHistory>,
+    connections: Connections,
+    now: u64,
+    frame: &mut Frame,
+    area: Rect,
+) {
+    let mut lines = vec![
+        field("Name", device.display_name().to_string()),
+        field("Alias", device.alias.clone()),
+        field("Address", device.addr.to_string()),
+        field("Address type", device.address_kind.description()),
+        field("Adapter", adapter.to_string()),
+        field("Type", device.kind().unwrap_or("unknown").to_string()),
+    ];
+    if let Some(class) = device.class.and_then(class_of_device) {
+        lines.push(field("Class", class));
+    }
+    if let Some(appearance) = device.appearance.and_then(appearance) {
+        lines.push(field("Appearance", appearance));
+    }
+    lines.extend([
+        field("Paired", yes_no(device.is_paired)),
+        field("Trusted", yes_no(device.is_trusted)),
+        field("Connected", yes_no(device.is_connected)),
+    ]);
+    if !device.uuids.is_empty() {
+        let services: Vec<String> = device.uuids.iter().map(service_label).collect();
+        lines.push(field("Services", services.join(", ")));
+    }
+
+    let battery_samples: Vec<(u64, u8)> = history
+        .map(|history| history.battery.iter().copied().collect())
+        .unwrap_or_default();
+
+    if let Some(battery) = device.battery_percentage {
+        lines.push(field("Battery", format!("{battery}%")));
+        if let Some(remaining) = estimate_remaining(&battery_samples, now) {
+            lines.push(field(
+                "Time remaining",
+                format!("~{}", format_duration(remaining)),
+            ));
+        }
+    }
+    if let Some(rssi) = device.rssi {
+        lines.push(field("Signal", format!("{rssi} dBm")));
+    }
+    if let Some(transport) = connections.transport {
+        let state = match transport.state.as_str() {
+            "active" => "streaming",
+            "pending" => "pending",
+            _ => "idle",
+        };
+        lines.push(field("Audio", format!("{state} ({})", transport.codec)));
+        if let Some(volume) = transport.volume_percentage() {
+            lines.push(volume_gauge(volume));
+        }
+    }
+    if let Some(network) = connections.network {
+        lines.push(field("Network", network.state()));
+    }
+
+    let mut events = vec![Line::default()];
+    match history {
+        Some(history) => {
+            let ago = |time: Option<u64>| {
+                time.map_or("never".to_string(), |time| {
+                    format!("{} ({})", format_ago(time, now), format_time(time))
+                })
+            };
+            events.push(field("Last connected", ago(history.last_connected(now))));
+            events.push(field("Last seen", ago(history.last_seen(now))));
+            events.push(field(
+                "Connected for",
+                format_duration(history.total_connected_time(now)),
+            ));
+
+            events.push(Line::default());
+            events.push(Line::from("Recent events").yellow().bold());
+            for record in history.recent.iter().rev() {
+                events.push(Line::from(vec![
+                    Span::from(format_time(record.time)).dark_gray(),
+                    Span::from(format!("  {}", record.kind.description())),
+                ]));
+            }
+        }
+        None => events.push(Line::from("No history recorded yet").dark_gray()),
+    }
+
+    let chart_height = if battery_samples.is_empty() {
+        0
+    } else {
+        BATTERY_CHART_HEIGHT + 2
+    };
+    let height = (lines.len() as u16 + chart_height + events.len() as u16 + 2).min(area.height);
+    let area = area.centered(Constraint::Max(80), Constraint::Length(height));
+
+    let block = Block::bordered()
+        .title(format!(" {} ", device.display_name()))
+        .title_style(Style::default().bold())
+        .padding(Padding::horizontal(1))
+        .border_type(BorderType::Thick)
+        .border_style(Style::default().green());
+    let inner = block.inner(area);
+
+    let [fields_area, chart_area, events_area] = Layout::vertical([
+        Constraint::Length(lines.len() as u16),
+        Constraint::Length(chart_height),
+        Constraint::Fill(1),
+    ])
+    .areas(inner);
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+    frame.render_widget(Paragraph::new(lines), fields_area);
+    frame.render_widget(Paragraph::new(events), events_area);
+
+    if !battery_samples.is_empty() {
+        let [title_area, sparkline_area, _] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(BATTERY_CHART_HEIGHT),
+            Constraint::Length(1),
+        ])
+        .areas(chart_area);
+
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                Span::from("Battery").yellow().bold(),
+                Span::from(" (last 24h)").dark_gray(),
+            ])),
+            title_area,
+        );
+        frame.render_widget(
+            Sparkline::default()
+                .data(battery_series(
+                    &battery_samples,
+                    now,
+                    BATTERY_CHART_SPAN,
+                    sparkline_area.width as usize,
+                ))
+                .max(100)
+                .green(),
+            sparkline_area,
+        );
+    }
+}
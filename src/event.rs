@@ -1,5 +1,5 @@
 use anyhow::anyhow;
-use bluer::Address;
+use bluer::{Address, Uuid};
 use std::time::Duration;
 
 use crossterm::event::{Event as CrosstermEvent, KeyEvent, MouseEvent};
@@ -9,10 +9,13 @@ use tokio::sync::mpsc;
 use crate::{
     app::AppResult,
     notification::Notification,
+    obex::{Transfer, TransferEvent, TransferUpdate, phone::Folder},
+    peripheral::Activity,
+    recorder::RecordingState,
     requests::{
         confirmation::Confirmation, display_passkey::DisplayPasskey,
         display_pin_code::DisplayPinCode, enter_passkey::EnterPasskey,
-        enter_pin_code::EnterPinCode,
+        enter_pin_code::EnterPinCode, receive_file::ReceiveFile,
     },
 };
 
@@ -36,6 +39,33 @@ pub enum Event {
     PasskeySumitted,
     RequestDisplayPasskey(DisplayPasskey),
     DisplayPasskeyCanceled,
+    RequestReceiveFile(ReceiveFile),
+    ReceiveFileSubmitted,
+    TransferStarted(Transfer),
+    TransferUpdated(TransferUpdate),
+    PhoneFolders(Address, Vec<Folder>),
+    SerialConnected(Address, mpsc::UnboundedSender<Vec<u8>>),
+    SerialReceived(Address, Vec<u8>),
+    SerialClosed(Address, Option<String>),
+    GattActivity(Activity),
+    // Standard sensor characteristics found on a device, and their values
+    SensorsFound(Address, Vec<Uuid>),
+    SensorValue(Address, Uuid, Vec<u8>),
+    SensorsFailed(Address, String),
+    // Characteristics of a device that can be recorded, and the recordings running
+    RecordableCharacteristics(Address, Vec<Uuid>),
+    RecorderFailed(Address, String),
+    RecordingState(Address, RecordingState),
+    RecordingFailed(Address, String),
+}
+
+impl From<TransferEvent> for Event {
+    fn from(event: TransferEvent) -> Self {
+        match event {
+            TransferEvent::Started(transfer) => Event::TransferStarted(transfer),
+            TransferEvent::Updated(update) => Event::TransferUpdated(update),
+        }
+    }
 }
 
 #[allow(dead_code)]
@@ -1,19 +1,158 @@
-use crate::app::AppResult;
-use anyhow::Context;
+use crate::{app::AppResult, storage};
+use anyhow::{Context, bail};
 use bluer::Address;
+use serde::{Deserialize, Serialize};
 use std::{
     fs::File,
     io::{BufRead, BufReader},
+    path::Path,
     str::FromStr,
 };
 
-pub fn read_favorite_devices_from_disk() -> AppResult<Vec<Address>> {
-    let data_dir = dirs::data_dir()
-        .context("unable to find data_dir")?
-        .join("bluetui");
+const FAVORITES_FILE: &str = "favorites.toml";
+const LEGACY_FAVORITES_FILE: &str = "favorites.txt";
 
-    let file =
-        File::open(data_dir.join("favorites.txt")).context("unable to open favorites file")?;
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Favorite {
+    pub address: Address,
+
+    // Last known alias and adapter, kept for devices that BlueZ does not know anymore
+    pub alias: Option<String>,
+    pub adapter: Option<String>,
+
+    // Seconds since the unix epoch
+    pub added_at: u64,
+}
+
+// The order of the favorites is the order in which they are listed
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Favorites {
+    #[serde(default, rename = "favorite")]
+    pub entries: Vec<Favorite>,
+
+    // Set when the file could not be read, it is then never saved over
+    #[serde(skip)]
+    unreadable: bool,
+}
+
+impl Favorites {
+    pub fn load() -> AppResult<Self> {
+        Self::load_from(&storage::data_dir()?)
+    }
+
+    pub fn save(&self) -> AppResult<()> {
+        self.save_to(&storage::data_dir()?)
+    }
+
+    // Stand-in for the favorites of a file that could not be read
+    pub fn unreadable() -> Self {
+        Self {
+            unreadable: true,
+            ..Self::default()
+        }
+    }
+
+    // Read the favorites, migrating the list of addresses of older versions if needed
+    pub fn load_from(data_dir: &Path) -> AppResult<Self> {
+        let file_path = data_dir.join(FAVORITES_FILE);
+        if file_path.exists() {
+            let content =
+                std::fs::read_to_string(file_path).context("unable to read favorites file")?;
+            return toml::from_str(&content).context("unable to parse favorites file");
+        }
+
+        let legacy_file_path = data_dir.join(LEGACY_FAVORITES_FILE);
+        if !legacy_file_path.exists() {
+            return Ok(Self::default());
+        }
+
+        let favorites = Self {
+            entries: read_legacy_favorites(&legacy_file_path)?
+                .into_iter()
+                .map(|address| Favorite {
+                    address,
+                    alias: None,
+                    adapter: None,
+                    added_at: storage::now(),
+                })
+                .collect(),
+            unreadable: false,
+        };
+        favorites.save_to(data_dir)?;
+        std::fs::remove_file(legacy_file_path).context("unable to remove old favorites file")?;
+
+        Ok(favorites)
+    }
+
+    pub fn save_to(&self, data_dir: &Path) -> AppResult<()> {
+        if self.unreadable {
+            bail!("The favorites are not saved until {FAVORITES_FILE} is fixed");
+        }
+        let content = toml::to_string(self).context("unable to serialize favorites")?;
+        storage::write(&data_dir.join(FAVORITES_FILE), &content)
+    }
+
+    pub fn contains(&self, address: Address) -> bool {
+        self.entries.iter().any(|f| f.address == address)
+    }
+
+    pub fn position(&self, address: Address) -> Option<usize> {
+        self.entries.iter().position(|f| f.address == address)
+    }
+
+    // Returns true if the device is now a favorite
+    pub fn toggle(
+        &mut self,
+        address: Address,
+        alias: Option<String>,
+        adapter: Option<String>,
+    ) -> bool {
+        if let Some(pos) = self.position(address) {
+            self.entries.remove(pos);
+            false
+        } else {
+            self.entries.push(Favorite {
+                address,
+                alias,
+                adapter,
+                added_at: storage::now(),
+            });
+            true
+        }
+    }
+
+    pub fn remove(&mut self, address: Address) -> bool {
+        let len = self.entries.len();
+        self.entries.retain(|f| f.address != address);
+        len != self.entries.len()
+    }
+
+    pub fn move_to(&mut self, address: Address, index: usize) -> bool {
+        let Some(pos) = self.position(address) else {
+            return false;
+        };
+        let favorite = self.entries.remove(pos);
+        self.entries.insert(index.min(self.entries.len()), favorite);
+        true
+    }
+
+    // Returns true if the alias or the adapter of the favorite changed
+    pub fn update(&mut self, address: Address, alias: &str, adapter: &str) -> bool {
+        let Some(favorite) = self.entries.iter_mut().find(|f| f.address == address) else {
+            return false;
+        };
+        if favorite.alias.as_deref() == Some(alias) && favorite.adapter.as_deref() == Some(adapter)
+        {
+            return false;
+        }
+        favorite.alias = Some(alias.to_string());
+        favorite.adapter = Some(adapter.to_string());
+        true
+    }
+}
+
+fn read_legacy_favorites(file_path: &Path) -> AppResult<Vec<Address>> {
+    let file = File::open(file_path).context("unable to open favorites file")?;
 
     let lines = BufReader::new(file).lines();
 
@@ -26,27 +165,81 @@ pub fn read_favorite_devices_from_disk() -> AppResult<Vec<Address>> {
         .collect()
 }
 
-pub fn save_favorite_devices_to_disk(favorite_devices: &[Address]) -> AppResult<()> {
-    let data_dir = dirs::data_dir()
-        .context("unable to find data_dir")?
-        .join("bluetui");
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    fn data_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bluetui-{name}-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn migrate_legacy_file() {
+        let dir = data_dir("favorites-migration");
+        std::fs::write(
+            dir.join(LEGACY_FAVORITES_FILE),
+            "AA:BB:CC:DD:EE:FF\n11:22:33:44:55:66\n",
+        )
+        .unwrap();
 
-    if !data_dir.exists() {
-        std::fs::create_dir_all(&data_dir)
-            .context("unable to create parent dir(s) to favorites file")?;
+        let favorites = Favorites::load_from(&dir).unwrap();
+        let addresses: Vec<_> = favorites.entries.iter().map(|f| f.address).collect();
+
+        assert_eq!(
+            addresses,
+            vec![
+                Address::new([0xAA, 0xBB, 0xCC, 0xDD, 0xEE, 0xFF]),
+                Address::new([0x11, 0x22, 0x33, 0x44, 0x55, 0x66]),
+            ]
+        );
+        assert!(!dir.join(LEGACY_FAVORITES_FILE).exists());
+        assert!(dir.join(FAVORITES_FILE).exists());
+        assert!(!dir.join(format!("{FAVORITES_FILE}.tmp")).exists());
+
+        std::fs::remove_dir_all(dir).unwrap();
     }
 
-    let file_path = data_dir.join("favorites.txt");
+    #[test]
+    fn save_and_load() {
+        let dir = data_dir("favorites-roundtrip");
+        let address = Address::new(*b"DEADBE");
+
+        let mut favorites = Favorites::default();
+        favorites.toggle(
+            address,
+            Some("Headset".to_string()),
+            Some("hci0".to_string()),
+        );
+        favorites.toggle(Address::any(), None, None);
+        favorites.move_to(Address::any(), 0);
+        favorites.save_to(&dir).unwrap();
 
-    let mut contents =
-        String::with_capacity(favorite_devices.len() * (Address::any().to_string() + "\n").len());
+        let favorites = Favorites::load_from(&dir).unwrap();
+        assert_eq!(favorites.position(Address::any()), Some(0));
+        assert_eq!(favorites.entries[1].alias.as_deref(), Some("Headset"));
+        assert_eq!(favorites.entries[1].adapter.as_deref(), Some("hci0"));
 
-    for device in favorite_devices {
-        contents.push_str(&device.to_string());
-        contents.push('\n');
+        std::fs::remove_dir_all(dir).unwrap();
     }
 
-    std::fs::write(file_path, contents).context("error writing favorites file")?;
+    #[test]
+    fn unreadable_file_is_not_overwritten() {
+        let dir = data_dir("favorites-unreadable");
+        std::fs::write(dir.join(FAVORITES_FILE), "[[favorite]\n").unwrap();
+        assert!(Favorites::load_from(&dir).is_err());
 
-    Ok(())
+        let mut favorites = Favorites::unreadable();
+        favorites.toggle(Address::any(), None, None);
+        assert!(favorites.save_to(&dir).is_err());
+        assert_eq!(
+            std::fs::read_to_string(dir.join(FAVORITES_FILE)).unwrap(),
+            "[[favorite]\n"
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
 }
@@ -0,0 +1,152 @@
+use std::ops::Range;
+
+use ratatui::{
+    style::Stylize,
+    text::{Line, Span},
+};
+
+use crate::bluetooth::Device;
+
+// Search query and quick filters applied to the paired and new devices tables
+#[derive(Debug, Default, Clone)]
+#[allow(clippy::struct_excessive_bools)]
+pub struct DeviceFilter {
+    pub query: String,
+    pub audio: bool,
+    pub input: bool,
+    pub named: bool,
+    pub connected: bool,
+}
+
+impl DeviceFilter {
+    pub fn is_active(&self) -> bool {
+        !self.query.is_empty() || self.audio || self.input || self.named || self.connected
+    }
+
+    pub fn matches(&self, device: &Device) -> bool {
+        let icon_name = device.kind().unwrap_or_default();
+
+        if self.audio && !icon_name.starts_with("audio-") {
+            return false;
+        }
+        if self.input && !icon_name.starts_with("input-") {
+            return false;
+        }
+        if self.named && !device.is_named() {
+            return false;
+        }
+        if self.connected && !device.is_connected {
+            return false;
+        }
+
+        self.query.is_empty()
+            || [
+                device.display_name(),
+                device.alias.as_str(),
+                device.addr.to_string().as_str(),
+                icon_name,
+                device.address_kind.vendor().unwrap_or_default(),
+            ]
+            .iter()
+            .any(|text| find_ignore_case(text, &self.query).is_some())
+    }
+
+    pub fn quick_filters(&self) -> Vec<&'static str> {
+        [
+            (self.audio, "audio"),
+            (self.input, "input"),
+            (self.named, "named"),
+            (self.connected, "connected"),
+        ]
+        .into_iter()
+        .filter_map(|(enabled, name)| enabled.then_some(name))
+        .collect()
+    }
+
+    // The text with the first match of the query highlighted
+    pub fn highlight(&self, text: &str) -> Line<'static> {
+        match find_ignore_case(text, &self.query) {
+            Some(range) if !self.query.is_empty() => Line::from(vec![
+                Span::from(text[..range.start].to_string()),
+                Span::from(text[range.clone()].to_string())
+                    .black()
+                    .on_yellow(),
+                Span::from(text[range.end..].to_string()),
+            ]),
+            _ => Line::from(text.to_string()),
+        }
+    }
+}
+
+// The shown device after (or before) the selected one, wrapping around. `shown` holds the
+// positions of the shown devices in the full list, the selection can be a hidden device
+pub fn next_shown(shown: &[usize], selected: Option<usize>, forward: bool) -> Option<usize> {
+    let (first, last) = (*shown.first()?, *shown.last()?);
+    let Some(selected) = selected else {
+        return Some(first);
+    };
+    if forward {
+        Some(
+            shown
+                .iter()
+                .copied()
+                .find(|&i| i > selected)
+                .unwrap_or(first),
+        )
+    } else {
+        Some(
+            shown
+                .iter()
+                .rev()
+                .copied()
+                .find(|&i| i < selected)
+                .unwrap_or(last),
+        )
+    }
+}
+
+// Byte range of the first case insensitive occurrence of `needle` in `haystack`
+pub fn find_ignore_case(haystack: &str, needle: &str) -> Option<Range<usize>> {
+    if needle.is_empty() {
+        return Some(0..0);
+    }
+
+    haystack.char_indices().find_map(|(start, _)| {
+        let mut rest = haystack[start..].char_indices();
+        for expected in needle.chars() {
+            let (_, c) = rest.next()?;
+            if !c.to_lowercase().eq(expected.to_lowercase()) {
+                return None;
+            }
+        }
+        let end = rest.next().map_or(haystack.len(), |(i, _)| start + i);
+        Some(start..end)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn find_ignore_case_ranges() {
+        assert_eq!(find_ignore_case("WH-1000XM4", "xm"), Some(7..9));
+        assert_eq!(find_ignore_case("Écouteurs", "éco"), Some(0..4));
+        assert_eq!(find_ignore_case("keyboard", "mouse"), None);
+        assert_eq!(find_ignore_case("key", "keyboard"), None);
+        assert_eq!(find_ignore_case("AA:BB:CC", "bb:c"), Some(3..7));
+    }
+
+    #[test]
+    fn selection_skips_the_hidden_devices() {
+        let shown = [1, 4, 6];
+        assert_eq!(next_shown(&shown, None, true), Some(1));
+        assert_eq!(next_shown(&shown, Some(1), true), Some(4));
+        assert_eq!(next_shown(&shown, Some(6), true), Some(1));
+        assert_eq!(next_shown(&shown, Some(1), false), Some(6));
+        // From a device hidden since it was selected
+        assert_eq!(next_shown(&shown, Some(5), true), Some(6));
+        assert_eq!(next_shown(&shown, Some(5), false), Some(4));
+        assert_eq!(next_shown(&[], Some(5), true), None);
+    }
+}
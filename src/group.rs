@@ -0,0 +1,375 @@
+use std::{
+    collections::HashMap,
+    hash::BuildHasher,
+    path::Path,
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+};
+
+use anyhow::{Context, bail};
+use bluer::{Adapter, Address};
+use futures::future::join_all;
+use ratatui::{
+    Frame,
+    layout::{Alignment, Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::Line,
+    widgets::{Block, BorderType, Clear, Padding, Paragraph, Row, Table, TableState},
+};
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc::UnboundedSender;
+use tui_input::Input;
+
+use crate::{
+    app::AppResult,
+    bluetooth::find_paired_device,
+    event::Event,
+    notification::{Notification, NotificationLevel},
+    storage,
+};
+
+const GROUPS_FILE: &str = "groups.toml";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Group {
+    pub name: String,
+    pub members: Vec<Address>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Groups {
+    #[serde(default, rename = "group")]
+    pub entries: Vec<Group>,
+
+    // Set when the file could not be read, it is then never saved over
+    #[serde(skip)]
+    unreadable: bool,
+}
+
+impl Groups {
+    pub fn load() -> AppResult<Self> {
+        Self::load_from(&storage::data_dir()?)
+    }
+
+    pub fn save(&self) -> AppResult<()> {
+        self.save_to(&storage::data_dir()?)
+    }
+
+    // Stand-in for the groups of a file that could not be read
+    pub fn unreadable() -> Self {
+        Self {
+            unreadable: true,
+            ..Self::default()
+        }
+    }
+
+    pub fn load_from(data_dir: &Path) -> AppResult<Self> {
+        let file_path = data_dir.join(GROUPS_FILE);
+        if !file_path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(file_path).context("unable to read groups file")?;
+        toml::from_str(&content).context("unable to parse groups file")
+    }
+
+    pub fn save_to(&self, data_dir: &Path) -> AppResult<()> {
+        if self.unreadable {
+            bail!("The groups are not saved until {GROUPS_FILE} is fixed");
+        }
+        let content = toml::to_string(self).context("unable to serialize groups")?;
+        storage::write(&data_dir.join(GROUPS_FILE), &content)
+    }
+
+    // Add the device to the group, creating the group if needed, or remove it if it is
+    // already a member. Groups left without members are deleted.
+    // Returns true if the device is now a member of the group
+    pub fn toggle_member(&mut self, name: &str, address: Address) -> bool {
+        let Some(group) = self.entries.iter_mut().find(|g| g.name == name) else {
+            self.entries.push(Group {
+                name: name.to_string(),
+                members: vec![address],
+            });
+            return true;
+        };
+
+        if let Some(pos) = group.members.iter().position(|m| *m == address) {
+            group.members.remove(pos);
+            self.entries.retain(|g| !g.members.is_empty());
+            false
+        } else {
+            group.members.push(address);
+            true
+        }
+    }
+
+    pub fn remove(&mut self, name: &str) {
+        self.entries.retain(|g| g.name != name);
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum GroupAction {
+    Connect,
+    Disconnect,
+    Trust,
+}
+
+impl GroupAction {
+    fn in_progress(self) -> &'static str {
+        match self {
+            GroupAction::Connect => "Connecting",
+            GroupAction::Disconnect => "Disconnecting",
+            GroupAction::Trust => "Trusting",
+        }
+    }
+
+    fn done(self) -> &'static str {
+        match self {
+            GroupAction::Connect => "connected",
+            GroupAction::Disconnect => "disconnected",
+            GroupAction::Trust => "trusted",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct GroupProgress {
+    pub action: GroupAction,
+    pub done: Arc<AtomicUsize>,
+    pub total: usize,
+}
+
+impl GroupProgress {
+    pub fn is_done(&self) -> bool {
+        self.done.load(Ordering::Relaxed) >= self.total
+    }
+}
+
+// Run the action on all the members at once, each member comes with the adapters it can be
+// reached with. The failures are reported in a single notification once every member is done.
+pub fn run(
+    action: GroupAction,
+    group: &Group,
+    members: Vec<(Address, Vec<Arc<Adapter>>)>,
+    sender: UnboundedSender<Event>,
+) -> GroupProgress {
+    let progress = GroupProgress {
+        action,
+        done: Arc::new(AtomicUsize::new(0)),
+        total: members.len(),
+    };
+
+    let name = group.name.clone();
+    let done = progress.done.clone();
+
+    tokio::spawn(async move {
+        let total = members.len();
+        let results = join_all(members.into_iter().map(|(address, adapters)| {
+            let done = done.clone();
+            async move {
+                let result = match find_paired_device(&adapters, address).await {
+                    Some(device) => {
+                        let alias = device.alias().await.unwrap_or(address.to_string());
+                        let result = match action {
+                            GroupAction::Connect => device.connect().await,
+                            GroupAction::Disconnect => device.disconnect().await,
+                            GroupAction::Trust => device.set_trusted(true).await,
+                        };
+                        result.map_err(|e| format!("{alias}: {e}"))
+                    }
+                    None => Err(format!("{address}: not paired")),
+                };
+                done.fetch_add(1, Ordering::Relaxed);
+                result
+            }
+        }))
+        .await;
+
+        let failures: Vec<String> = results.into_iter().filter_map(Result::err).collect();
+        let summary = format!(
+            "{name}: {}/{total} {}",
+            total - failures.len(),
+            action.done()
+        );
+
+        let _ = if failures.is_empty() {
+            Notification::send(summary.into(), NotificationLevel::Info, sender)
+        } else {
+            Notification::send(
+                format!("{summary}\n{}", failures.join("\n")).into(),
+                NotificationLevel::Warning,
+                sender,
+            )
+        };
+    });
+
+    progress
+}
+
+pub fn render_groups<S: BuildHasher>(
+    groups: &Groups,
+    groups_state: &mut TableState,
+    progress: &HashMap<String, GroupProgress, S>,
+    connected_devices: &[Address],
+    frame: &mut Frame,
+    area: Rect,
+) {
+    let height = groups.entries.len().max(1) as u16 + 5;
+    let center_area = area.centered(Constraint::Max(70), Constraint::Length(height));
+
+    let rows: Vec<Row> = groups
+        .entries
+        .iter()
+        .map(|group| {
+            let status = match progress.get(&group.name) {
+                Some(p) if !p.is_done() => format!(
+                    "{} {}/{}",
+                    p.action.in_progress(),
+                    p.done.load(Ordering::Relaxed),
+                    p.total
+                ),
+                _ => format!(
+                    "{}/{} connected",
+                    group
+                        .members
+                        .iter()
+                        .filter(|m| connected_devices.contains(m))
+                        .count(),
+                    group.members.len()
+                ),
+            };
+            Row::new(vec![group.name.clone(), status])
+        })
+        .collect();
+
+    let table = Table::new(rows, [Constraint::Fill(1), Constraint::Length(20)])
+        .header(
+            Row::new(["Name", "Members"])
+                .yellow()
+                .bold()
+                .bottom_margin(1),
+        )
+        .block(
+            Block::bordered()
+                .title(" Groups ")
+                .title_style(Style::default().bold())
+                .padding(Padding::horizontal(1))
+                .border_type(BorderType::Thick)
+                .border_style(Style::default().green()),
+        )
+        .row_highlight_style(Style::default().white().on_dark_gray());
+
+    frame.render_widget(Clear, center_area);
+    if groups.entries.is_empty() {
+        frame.render_widget(
+            Paragraph::new("\n\nNo groups yet, add a paired device to a group first")
+                .centered()
+                .block(
+                    Block::bordered()
+                        .title(" Groups ")
+                        .border_type(BorderType::Thick)
+                        .border_style(Style::default().green()),
+                ),
+            center_area,
+        );
+    } else {
+        frame.render_stateful_widget(table, center_area, groups_state);
+    }
+}
+
+pub fn render_add_to_group(
+    device_name: &str,
+    groups: &Groups,
+    group_name: &Input,
+    frame: &mut Frame,
+    area: Rect,
+) {
+    let center_cutout = area.centered(Constraint::Max(70), Constraint::Length(8));
+
+    let [_, message_area, groups_area, full_input_area, _] = Layout::vertical([
+        Constraint::Length(1),
+        Constraint::Length(2),
+        Constraint::Length(2),
+        Constraint::Length(1),
+        Constraint::Length(2),
+    ])
+    .areas(center_cutout);
+
+    let input_area = full_input_area.centered_horizontally(Constraint::Percentage(60));
+
+    frame.render_widget(Clear, center_cutout);
+    frame.render_widget(
+        Block::bordered()
+            .border_type(BorderType::Thick)
+            .border_style(Style::default().green()),
+        center_cutout,
+    );
+
+    let message = Paragraph::new(Line::from(vec![
+        "Add ".into(),
+        device_name.bold().italic(),
+        " to the group (or remove it if already a member)".into(),
+    ]))
+    .centered()
+    .block(Block::new().padding(Padding::horizontal(2)));
+
+    let names: Vec<&str> = groups.entries.iter().map(|g| g.name.as_str()).collect();
+    let existing = Paragraph::new(if names.is_empty() {
+        String::new()
+    } else {
+        format!("Groups: {}", names.join(", "))
+    })
+    .centered()
+    .dark_gray()
+    .block(Block::new().padding(Padding::horizontal(2)));
+
+    let input = Paragraph::new(group_name.value())
+        .alignment(Alignment::Left)
+        .style(Style::default().fg(Color::White))
+        .block(Block::new().on_dark_gray().padding(Padding::horizontal(2)));
+
+    frame.render_widget(message, message_area);
+    frame.render_widget(existing, groups_area);
+    frame.render_widget(input, input_area);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn toggle_member() {
+        let keyboard = Address::new(*b"keyboa");
+        let mouse = Address::new(*b"mouse!");
+
+        let mut groups = Groups::default();
+        assert!(groups.toggle_member("Desk", keyboard));
+        assert!(groups.toggle_member("Desk", mouse));
+        assert_eq!(groups.entries.len(), 1);
+        assert_eq!(groups.entries[0].members, vec![keyboard, mouse]);
+
+        assert!(!groups.toggle_member("Desk", keyboard));
+        assert!(!groups.toggle_member("Desk", mouse));
+        assert!(groups.entries.is_empty());
+    }
+
+    #[test]
+    fn unreadable_file_is_not_overwritten() {
+        let dir = std::env::temp_dir().join(format!("bluetui-groups-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        std::fs::write(dir.join(GROUPS_FILE), "[[group]\n").unwrap();
+        assert!(Groups::load_from(&dir).is_err());
+
+        let mut groups = Groups::unreadable();
+        groups.toggle_member("Desk", Address::any());
+        assert!(groups.save_to(&dir).is_err());
+        assert_eq!(
+            std::fs::read_to_string(dir.join(GROUPS_FILE)).unwrap(),
+            "[[group]\n"
+        );
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
@@ -1,13 +1,28 @@
+use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::atomic::Ordering;
+use std::time::{Duration, Instant};
 
+use crate::add_device::AddDevice;
+use crate::advertising::{AdvertisingEditor, EditorAction};
 use crate::app::FocusedBlock;
-use crate::app::{App, AppResult};
-use crate::config::Config;
+use crate::app::{App, AppResult, table_rows};
+use crate::config::{Config, Key};
 use crate::event::Event;
+use crate::group::{self, GroupAction};
+use crate::media::{self, PlayerCommand};
 use crate::notification::{Notification, NotificationLevel};
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crate::obex::phone::PhoneData;
+use crate::palette::{Action, Command, Palette, Target};
+use crate::peripheral::GattServer;
+use crate::picker::FilePicker;
+use crate::recorder::{RecorderPicker, Recording};
+use crate::sensors::SensorDashboard;
+use crate::serial::{self, SerialStatus, SerialTerminal};
+use bluer::{Address, AddressType};
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers, MouseButton, MouseEvent, MouseEventKind};
 use futures::StreamExt;
+use ratatui::layout::{Position, Rect};
 use tokio::sync::mpsc::UnboundedSender;
 
 use tui_input::backend::crossterm::EventHandler;
@@ -90,20 +105,823 @@ async fn pair(app: &mut App, sender: UnboundedSender<Event>) {
                             sender.clone(),
                         );
 
-                        tokio::spawn(async move {
-                            match device.pair().await {
-                                Ok(()) => {
-                                    let _ = Notification::send(
-                                        "Device paired".into(),
-                                        NotificationLevel::Info,
-                                        sender.clone(),
-                                    );
+                        tokio::spawn(pair_device(device, sender));
+                    }
+                    Err(e) => {
+                        let _ =
+                            Notification::send(e.into(), NotificationLevel::Error, sender.clone());
+                    }
+                },
+                Err(e) => {
+                    let _ = Notification::send(e.into(), NotificationLevel::Error, sender.clone());
+                }
+            }
+        }
+    }
+}
 
-                                    let _ = sender.send(Event::NewPairedDevice(device.address()));
-                                    match device.set_trusted(true).await {
+// Pairs, trusts and connects the device
+async fn pair_device(device: bluer::Device, sender: UnboundedSender<Event>) {
+    match device.pair().await {
+        Ok(()) => {
+            let _ = Notification::send(
+                "Device paired".into(),
+                NotificationLevel::Info,
+                sender.clone(),
+            );
+
+            let _ = sender.send(Event::NewPairedDevice(device.address()));
+            match device.set_trusted(true).await {
+                Ok(()) => {
+                    let _ = Notification::send(
+                        "Device trusted".into(),
+                        NotificationLevel::Info,
+                        sender.clone(),
+                    );
+                }
+                Err(e) => {
+                    let _ = Notification::send(e.into(), NotificationLevel::Error, sender.clone());
+                }
+            }
+            match device.connect().await {
+                Ok(()) => {
+                    let _ = Notification::send(
+                        "Device connected".into(),
+                        NotificationLevel::Info,
+                        sender.clone(),
+                    );
+                }
+                Err(e) => {
+                    let _ = Notification::send(e.into(), NotificationLevel::Error, sender.clone());
+                }
+            }
+        }
+        Err(e) => {
+            let _ = Notification::send(e.into(), NotificationLevel::Error, sender.clone());
+            let _ = sender.send(Event::FailedPairing(device.address()));
+        }
+    }
+}
+
+fn run_group_action(app: &mut App, action: GroupAction, sender: UnboundedSender<Event>) {
+    let Some(index) = app.groups_state.selected() else {
+        return;
+    };
+    let Some(group) = app.groups.entries.get(index) else {
+        return;
+    };
+
+    if app
+        .group_progress
+        .get(&group.name)
+        .is_some_and(|progress| !progress.is_done())
+    {
+        return;
+    }
+
+    let members = group
+        .members
+        .iter()
+        .map(|address| (*address, app.adapters_for(*address)))
+        .collect();
+
+    let progress = group::run(action, group, members, sender);
+    app.group_progress.insert(group.name.clone(), progress);
+}
+
+// The filter hides devices, the selections start over on the shown ones
+fn apply_filter(app: &mut App) {
+    app.reset_devices_state();
+}
+
+// Open the media player of the selected paired device, if it exposes one
+fn show_media(app: &mut App, sender: &UnboundedSender<Event>) {
+    let Some(selected_controller) = app.controller_state.selected() else {
+        return;
+    };
+    let Some(device) = app
+        .paired_devices_state
+        .selected()
+        .and_then(|i| app.controllers[selected_controller].paired_devices.get(i))
+    else {
+        return;
+    };
+
+    if app.player(device.addr).is_some() {
+        app.media_device = Some(device.addr);
+        app.focused_block = FocusedBlock::Media;
+    } else {
+        let _ = Notification::send(
+            "No media player on this device".into(),
+            NotificationLevel::Info,
+            sender.clone(),
+        );
+    }
+}
+
+fn open_file_picker(app: &mut App, sender: &UnboundedSender<Event>) {
+    if app.obex.is_none() {
+        let _ = Notification::send(
+            "obexd is not reachable on the session bus".into(),
+            NotificationLevel::Error,
+            sender.clone(),
+        );
+        return;
+    }
+    let Some(selected_controller) = app.controller_state.selected() else {
+        return;
+    };
+    let Some(device) = app
+        .paired_devices_state
+        .selected()
+        .and_then(|i| app.controllers[selected_controller].paired_devices.get(i))
+    else {
+        return;
+    };
+    let address = device.addr;
+
+    // Keep the directory of the previous file
+    if app.file_picker.dir.as_os_str().is_empty() {
+        let home = dirs::home_dir().unwrap_or_else(|| "/".into());
+        match FilePicker::open(&home) {
+            Ok(picker) => app.file_picker = picker,
+            Err(e) => {
+                let _ = Notification::send(
+                    e.to_string().into(),
+                    NotificationLevel::Error,
+                    sender.clone(),
+                );
+                return;
+            }
+        }
+    }
+    app.send_to = Some(address);
+    app.focused_block = FocusedBlock::FilePicker;
+}
+
+fn send_file(app: &App, address: Address, file: PathBuf, sender: UnboundedSender<Event>) {
+    let Some(obex) = app.obex.clone() else {
+        return;
+    };
+    tokio::spawn(async move {
+        let events = sender.clone();
+        if let Err(e) = obex
+            .send_file(address, &file, move |event| {
+                let _ = events.send(event.into());
+            })
+            .await
+        {
+            let _ = Notification::send(e.into(), NotificationLevel::Error, sender);
+        }
+    });
+}
+
+fn open_phone_data(app: &mut App, sender: &UnboundedSender<Event>) {
+    let Some(obex) = app.obex.clone() else {
+        let _ = Notification::send(
+            "obexd is not reachable on the session bus".into(),
+            NotificationLevel::Error,
+            sender.clone(),
+        );
+        return;
+    };
+    let Some(selected_controller) = app.controller_state.selected() else {
+        return;
+    };
+    let Some(device) = app
+        .paired_devices_state
+        .selected()
+        .and_then(|i| app.controllers[selected_controller].paired_devices.get(i))
+    else {
+        return;
+    };
+    if !device.is_connected {
+        let _ = Notification::send(
+            "The device is not connected".into(),
+            NotificationLevel::Info,
+            sender.clone(),
+        );
+        return;
+    }
+
+    // One directory per phone, in the downloads
+    let destination = dirs::download_dir()
+        .or_else(dirs::home_dir)
+        .unwrap_or_default()
+        .join(device.display_name().replace('/', "_"));
+    let address = device.addr;
+    app.phone_data = Some(PhoneData::new(address, &destination));
+    app.focused_block = FocusedBlock::PhoneData;
+
+    let sender = sender.clone();
+    tokio::spawn(async move {
+        let folders = match obex.phone_folders(address).await {
+            Ok(folders) => folders,
+            Err(e) => {
+                let _ = Notification::send(e.into(), NotificationLevel::Error, sender.clone());
+                Vec::new()
+            }
+        };
+        let _ = sender.send(Event::PhoneFolders(address, folders));
+    });
+}
+
+fn export_phone_data(app: &App, sender: UnboundedSender<Event>) {
+    let (Some(obex), Some(phone_data)) = (app.obex.clone(), &app.phone_data) else {
+        return;
+    };
+    let Some(folder) = phone_data.selected().cloned() else {
+        return;
+    };
+    let device = phone_data.device;
+    let dir = PathBuf::from(phone_data.destination.value().trim());
+    tokio::spawn(async move {
+        let events = sender.clone();
+        if let Err(e) = obex
+            .export(device, &folder, &dir, move |event| {
+                let _ = events.send(event.into());
+            })
+            .await
+        {
+            let _ = Notification::send(e.into(), NotificationLevel::Error, sender);
+        }
+    });
+}
+
+fn toggle_network(app: &App, sender: UnboundedSender<Event>) {
+    let Some(selected_controller) = app.controller_state.selected() else {
+        return;
+    };
+    let Some(device) = app
+        .paired_devices_state
+        .selected()
+        .and_then(|i| app.controllers[selected_controller].paired_devices.get(i))
+    else {
+        return;
+    };
+    let address = device.addr;
+    let name = device.display_name().to_string();
+    let network_bus = app.network.clone();
+    tokio::spawn(async move {
+        let network = match network_bus.networks().await {
+            Ok(networks) => networks.into_iter().find(|n| n.device == address),
+            Err(e) => {
+                let _ = Notification::send(e.into(), NotificationLevel::Error, sender);
+                return;
+            }
+        };
+        let (message, level) = match network {
+            None => (
+                format!("{name} does not offer a network"),
+                NotificationLevel::Info,
+            ),
+            Some(network) if network.connected => match network_bus.disconnect(&network).await {
+                Ok(()) => (
+                    format!("Disconnected from the network of {name}"),
+                    NotificationLevel::Info,
+                ),
+                Err(e) => (e.to_string(), NotificationLevel::Error),
+            },
+            Some(network) => match network_bus.connect(&network).await {
+                Ok(interface) => (
+                    format!("Connected to the network of {name} on {interface}"),
+                    NotificationLevel::Info,
+                ),
+                Err(e) => (e.to_string(), NotificationLevel::Error),
+            },
+        };
+        let _ = Notification::send(message.into(), level, sender);
+    });
+}
+
+// Register or unregister the access point of the selected adapter
+async fn toggle_sharing(app: &mut App, sender: UnboundedSender<Event>) {
+    let Some(selected_controller) = app.controller_state.selected() else {
+        return;
+    };
+    let adapter = app.controllers[selected_controller].name.clone();
+    let bridge = &app.config.network_bridge;
+    let (result, message) = if app.sharing.contains(&adapter) {
+        (
+            app.network.unregister_server(&adapter).await,
+            format!("{adapter} stopped sharing the connection"),
+        )
+    } else {
+        (
+            app.network.register_server(&adapter, bridge).await,
+            format!("{adapter} shares the connection through {bridge}"),
+        )
+    };
+    match result {
+        Ok(()) => {
+            if !app.sharing.remove(&adapter) {
+                app.sharing.insert(adapter);
+            }
+            let _ = Notification::send(message.into(), NotificationLevel::Info, sender);
+        }
+        Err(e) => {
+            let _ = Notification::send(e.into(), NotificationLevel::Error, sender);
+        }
+    }
+}
+
+fn open_advertising_editor(app: &mut App) {
+    let Some(selected_controller) = app.controller_state.selected() else {
+        return;
+    };
+    let adapter = app.controllers[selected_controller].name.clone();
+    app.advertising_editor = Some(AdvertisingEditor::new(
+        adapter,
+        app.advertisement_profiles.clone(),
+    ));
+    app.focused_block = FocusedBlock::Advertising;
+}
+
+async fn toggle_advertising(app: &mut App, sender: UnboundedSender<Event>) {
+    let Some(editor) = &mut app.advertising_editor else {
+        return;
+    };
+    if app.advertisements.remove(&editor.adapter).is_some() {
+        let _ = Notification::send(
+            format!("{} stopped advertising", editor.adapter).into(),
+            NotificationLevel::Info,
+            sender,
+        );
+        return;
+    }
+
+    let (profile, advertisement) = match editor.profile() {
+        Ok(built) => built,
+        Err(e) => {
+            editor.error = Some(e);
+            return;
+        }
+    };
+    let Some(controller) = app.controllers.iter().find(|c| c.name == editor.adapter) else {
+        return;
+    };
+    match controller.adapter.advertise(advertisement).await {
+        Ok(handle) => {
+            editor.error = None;
+            let _ = Notification::send(
+                format!("{} advertises {}", editor.adapter, profile.label()).into(),
+                NotificationLevel::Info,
+                sender,
+            );
+            app.advertisements.insert(
+                editor.adapter.clone(),
+                (profile.label().to_string(), handle),
+            );
+        }
+        Err(e) => editor.error = Some(e.to_string()),
+    }
+}
+
+fn save_advertisement(app: &mut App, sender: UnboundedSender<Event>) {
+    let Some(editor) = &mut app.advertising_editor else {
+        return;
+    };
+    let profile = match editor.profile() {
+        Ok((profile, _)) if profile.name.is_empty() => {
+            editor.error = Some("The profile needs a name".to_string());
+            return;
+        }
+        Ok((profile, _)) => profile,
+        Err(e) => {
+            editor.error = Some(e);
+            return;
+        }
+    };
+    // Saved profiles are appended to the config, changing them is done in the file
+    if app
+        .advertisement_profiles
+        .iter()
+        .any(|p| p.name == profile.name)
+    {
+        editor.error = Some(format!(
+            "{} is already saved, edit it in {}",
+            profile.name,
+            app.config.file_path.display()
+        ));
+        return;
+    }
+    match app.config.save_advertisement(&profile) {
+        Ok(()) => {
+            editor.error = None;
+            let _ = Notification::send(
+                format!(
+                    "{} saved to {}",
+                    profile.name,
+                    app.config.file_path.display()
+                )
+                .into(),
+                NotificationLevel::Info,
+                sender,
+            );
+            editor.profiles.push(profile.clone());
+            app.advertisement_profiles.push(profile);
+        }
+        Err(e) => editor.error = Some(e.to_string()),
+    }
+}
+
+fn toggle_scanning(app: &mut App, sender: UnboundedSender<Event>) -> AppResult<()> {
+    let Some(selected_controller) = app.controller_state.selected() else {
+        return Ok(());
+    };
+    let controller = &app.controllers[selected_controller];
+
+    if controller.is_scanning.load(Ordering::Relaxed) {
+        controller
+            .is_scanning
+            .store(false, std::sync::atomic::Ordering::Relaxed);
+
+        Notification::send("Scanning stopped".into(), NotificationLevel::Info, sender)?;
+
+        app.spinner.active = false;
+    } else {
+        controller
+            .is_scanning
+            .store(true, std::sync::atomic::Ordering::Relaxed);
+        app.spinner.active = true;
+        let adapter = controller.adapter.clone();
+        let is_scanning = controller.is_scanning.clone();
+        tokio::spawn(async move {
+            let _ = Notification::send(
+                "Scanning started".into(),
+                NotificationLevel::Info,
+                sender.clone(),
+            );
+
+            match adapter.discover_devices().await {
+                Ok(mut discover) => {
+                    while let Some(_evt) = discover.next().await {
+                        if !is_scanning.load(Ordering::Relaxed) {
+                            break;
+                        }
+                    }
+                }
+                Err(e) => {
+                    let _ = Notification::send(e.into(), NotificationLevel::Error, sender.clone());
+                }
+            }
+        });
+    }
+    Ok(())
+}
+
+// The panel shows the server already running, whichever adapter serves it
+fn open_gatt_server(app: &mut App) {
+    let Some(selected_controller) = app.controller_state.selected() else {
+        return;
+    };
+    let adapter = &app.controllers[selected_controller].name;
+    if app
+        .gatt_server
+        .as_ref()
+        .is_none_or(|server| !server.is_serving() && &server.adapter != adapter)
+    {
+        app.gatt_server = Some(GattServer::new(
+            adapter.clone(),
+            app.config.gatt_application.clone(),
+        ));
+    }
+    app.focused_block = FocusedBlock::GattServer;
+}
+
+fn open_add_device(app: &mut App) {
+    let Some(selected_controller) = app.controller_state.selected() else {
+        return;
+    };
+    let adapter = app.controllers[selected_controller].name.clone();
+    app.add_device = Some(AddDevice::new(adapter));
+    app.focused_block = FocusedBlock::AddDevice;
+}
+
+// Connects to the typed address without discovering it first. BlueZ creates the device on the
+// connection, the BR/EDR devices are then paired as they are of no use otherwise
+fn add_device(app: &mut App, sender: &UnboundedSender<Event>) {
+    let Some(add_device) = &app.add_device else {
+        return;
+    };
+    let address = match add_device.address() {
+        Ok(address) => address,
+        Err(e) => {
+            let _ = Notification::send(e.into(), NotificationLevel::Error, sender.clone());
+            return;
+        }
+    };
+    let Some(controller) = app
+        .controllers
+        .iter()
+        .find(|controller| controller.name == add_device.adapter)
+    else {
+        return;
+    };
+    let adapter = controller.adapter.clone();
+    let address_type = add_device.address_type;
+    let _ = Notification::send(
+        format!("Connecting to {address}").into(),
+        NotificationLevel::Info,
+        sender.clone(),
+    );
+    tokio::spawn({
+        let sender = sender.clone();
+        async move {
+            match adapter.connect_device(address, address_type).await {
+                Ok(device) if address_type == AddressType::BrEdr => {
+                    pair_device(device, sender).await;
+                }
+                Ok(_) => {
+                    let _ = Notification::send(
+                        "Device connected".into(),
+                        NotificationLevel::Info,
+                        sender,
+                    );
+                }
+                Err(e) => {
+                    let _ = Notification::send(e.into(), NotificationLevel::Error, sender);
+                }
+            }
+        }
+    });
+    app.add_device = None;
+    app.focused_block = FocusedBlock::Adapter;
+}
+
+async fn toggle_gatt_server(app: &mut App, sender: &UnboundedSender<Event>) {
+    let Some(server) = &mut app.gatt_server else {
+        return;
+    };
+    if server.is_serving() {
+        server.stop();
+        return;
+    }
+    if let Some(controller) = app.controllers.iter().find(|c| c.name == server.adapter) {
+        server.start(&controller.adapter, sender).await;
+    }
+}
+
+// The serial services are taken from the SDP records of the device
+fn open_serial(app: &mut App) {
+    let Some(selected_controller) = app.controller_state.selected() else {
+        return;
+    };
+    let Some(device) = app
+        .paired_devices_state
+        .selected()
+        .and_then(|i| app.controllers[selected_controller].paired_devices.get(i))
+    else {
+        return;
+    };
+    app.serial = Some(SerialTerminal::new(
+        device.addr,
+        &device.uuids,
+        &app.config.serial_services,
+        app.config.serial_line_ending,
+    ));
+    app.focused_block = FocusedBlock::SerialTerminal;
+}
+
+// The GATT services are only reachable while the device is connected
+fn open_sensors(app: &mut App, sender: &UnboundedSender<Event>) {
+    let Some(selected_controller) = app.controller_state.selected() else {
+        return;
+    };
+    let Some(device) = app
+        .paired_devices_state
+        .selected()
+        .and_then(|i| app.controllers[selected_controller].paired_devices.get(i))
+    else {
+        return;
+    };
+    if !device.is_connected {
+        let _ = Notification::send(
+            "Connect the device to read its sensors".into(),
+            NotificationLevel::Info,
+            sender.clone(),
+        );
+        return;
+    }
+    app.sensors = Some(SensorDashboard::new(device.clone(), sender.clone()));
+    app.focused_block = FocusedBlock::Sensors;
+}
+
+// Picks the characteristics to record, or shows the recording of the device
+fn open_recorder(app: &mut App, sender: &UnboundedSender<Event>) {
+    let Some(selected_controller) = app.controller_state.selected() else {
+        return;
+    };
+    let controller = &app.controllers[selected_controller];
+    let Some(device) = app
+        .paired_devices_state
+        .selected()
+        .and_then(|i| controller.paired_devices.get(i))
+    else {
+        return;
+    };
+    let recording = app.recordings.iter().any(|r| r.device == device.addr);
+    if !device.is_connected && !recording {
+        let _ = Notification::send(
+            "Connect the device to record its notifications".into(),
+            NotificationLevel::Info,
+            sender.clone(),
+        );
+        return;
+    }
+    match controller.adapter.device(device.addr) {
+        Ok(device) => {
+            app.recorder = Some(RecorderPicker::new(device, sender.clone()));
+            app.focused_block = FocusedBlock::Recorder;
+        }
+        Err(e) => {
+            let _ = Notification::send(e.into(), NotificationLevel::Error, sender.clone());
+        }
+    }
+}
+
+fn start_recording(app: &mut App, sender: &UnboundedSender<Event>) {
+    let Some(picker) = &app.recorder else {
+        return;
+    };
+    let characteristics = picker.chosen();
+    if characteristics.is_empty() {
+        return;
+    }
+    let Some(adapter) = app
+        .controllers
+        .iter()
+        .find(|c| c.paired_devices.iter().any(|d| d.addr == picker.device))
+        .map(|c| c.adapter.clone())
+    else {
+        return;
+    };
+    let result = adapter
+        .device(picker.device)
+        .map_err(Into::into)
+        .and_then(|device| {
+            Recording::start(
+                device,
+                characteristics,
+                app.config.recording_format,
+                sender.clone(),
+            )
+        });
+    let (message, level) = match result {
+        Ok(recording) => {
+            let message = format!("Recording to {}", recording.path.display());
+            app.recordings.push(recording);
+            (message, NotificationLevel::Info)
+        }
+        Err(e) => (e.to_string(), NotificationLevel::Error),
+    };
+    let _ = Notification::send(message.into(), level, sender.clone());
+}
+
+fn stop_recording(app: &mut App, address: Address, sender: &UnboundedSender<Event>) {
+    if let Some(index) = app.recordings.iter().position(|r| r.device == address) {
+        let recording = app.recordings.remove(index);
+        let _ = Notification::send(
+            format!(
+                "Recording stopped, {} values in {}",
+                recording.count(),
+                recording.path.display()
+            )
+            .into(),
+            NotificationLevel::Info,
+            sender.clone(),
+        );
+    }
+}
+
+fn connect_serial(app: &mut App, sender: UnboundedSender<Event>) {
+    let Some(serial) = &mut app.serial else {
+        return;
+    };
+    let Some(target) = serial.selected().cloned() else {
+        return;
+    };
+    let Some(device) = app
+        .controllers
+        .iter()
+        .flat_map(|controller| &controller.paired_devices)
+        .find(|device| device.addr == serial.device)
+        .cloned()
+    else {
+        return;
+    };
+    serial.status = SerialStatus::Connecting;
+    serial::open(app.session.clone(), device, target, sender);
+}
+
+fn cancel_transfers(app: &App, sender: &UnboundedSender<Event>) {
+    let Some(obex) = app.obex.clone() else {
+        return;
+    };
+    for transfer in &app.transfers {
+        let obex = obex.clone();
+        let transfer = transfer.clone();
+        let sender = sender.clone();
+        tokio::spawn(async move {
+            if let Err(e) = obex.cancel(&transfer).await {
+                let _ = Notification::send(e.into(), NotificationLevel::Error, sender);
+            }
+        });
+    }
+}
+
+fn control_player(app: &App, command: PlayerCommand, sender: UnboundedSender<Event>) {
+    let Some(player) = app.media_device.and_then(|a| app.player(a)) else {
+        return;
+    };
+    let media = app.media.clone();
+    let path = player.path.clone();
+    tokio::spawn(async move {
+        if let Err(e) = media.send(&path, command).await {
+            let _ = Notification::send(e.into(), NotificationLevel::Error, sender);
+        }
+    });
+}
+
+// Percentage of the volume changed by a key press
+const VOLUME_STEP: u8 = 10;
+
+enum VolumeChange {
+    Raise,
+    Lower,
+    Set(u8),
+}
+
+// Change the volume of the audio transport of the device shown in the details
+fn change_volume(app: &App, change: VolumeChange, sender: UnboundedSender<Event>) {
+    let Some(transport) = app.details.and_then(|a| app.transport(a)) else {
+        return;
+    };
+    let Some(current) = transport.volume_percentage() else {
+        let _ = Notification::send(
+            "The device does not support volume control".into(),
+            NotificationLevel::Info,
+            sender,
+        );
+        return;
+    };
+
+    let percentage = match change {
+        VolumeChange::Raise => current.saturating_add(VOLUME_STEP).min(100),
+        VolumeChange::Lower => current.saturating_sub(VOLUME_STEP),
+        VolumeChange::Set(percentage) => percentage,
+    };
+    let media = app.media.clone();
+    let path = transport.path.clone();
+    tokio::spawn(async move {
+        if let Err(e) = media
+            .set_volume(&path, media::volume_from_percentage(percentage))
+            .await
+        {
+            let _ = Notification::send(e.into(), NotificationLevel::Error, sender);
+        }
+    });
+}
+
+// Show the details of the device selected in the focused table
+fn show_details(app: &mut App) {
+    let Some(selected_controller) = app.controller_state.selected() else {
+        return;
+    };
+    let controller = &app.controllers[selected_controller];
+    let device = match app.focused_block {
+        FocusedBlock::PairedDevices => app
+            .paired_devices_state
+            .selected()
+            .and_then(|i| controller.paired_devices.get(i)),
+        FocusedBlock::NewDevices => app
+            .new_devices_state
+            .selected()
+            .and_then(|i| controller.new_devices.get(i)),
+        _ => None,
+    };
+    if let Some(device) = device {
+        app.details = Some(device.addr);
+        app.previous_focused_block = app.focused_block;
+        app.focused_block = FocusedBlock::DeviceDetails;
+    }
+}
+
+fn toggle_trust(app: &App, sender: UnboundedSender<Event>) {
+    if let Some(selected_controller) = app.controller_state.selected() {
+        let controller = &app.controllers[selected_controller];
+        if let Some(index) = app.paired_devices_state.selected() {
+            let addr = controller.paired_devices[index].addr;
+            match controller.adapter.device(addr) {
+                Ok(device) => {
+                    tokio::spawn(async move {
+                        match device.is_trusted().await {
+                            Ok(is_trusted) => {
+                                if is_trusted {
+                                    match device.set_trusted(false).await {
                                         Ok(()) => {
                                             let _ = Notification::send(
-                                                "Device trusted".into(),
+                                                "Device untrusted".into(),
                                                 NotificationLevel::Info,
                                                 sender.clone(),
                                             );
@@ -116,14 +934,16 @@ async fn pair(app: &mut App, sender: UnboundedSender<Event>) {
                                             );
                                         }
                                     }
-                                    match device.connect().await {
+                                } else {
+                                    match device.set_trusted(true).await {
                                         Ok(()) => {
                                             let _ = Notification::send(
-                                                "Device connected".into(),
+                                                "Device trusted".into(),
                                                 NotificationLevel::Info,
                                                 sender.clone(),
                                             );
                                         }
+
                                         Err(e) => {
                                             let _ = Notification::send(
                                                 e.into(),
@@ -133,68 +953,733 @@ async fn pair(app: &mut App, sender: UnboundedSender<Event>) {
                                         }
                                     }
                                 }
+                            }
+                            Err(e) => {
+                                let _ = Notification::send(
+                                    e.into(),
+                                    NotificationLevel::Error,
+                                    sender.clone(),
+                                );
+                            }
+                        }
+                    });
+                }
+                Err(e) => {
+                    let _ = Notification::send(e.into(), NotificationLevel::Error, sender.clone());
+                }
+            }
+        }
+    }
+}
+
+fn toggle_favorite(app: &App, sender: &UnboundedSender<Event>) {
+    if let Some(selected_controller) = app.controller_state.selected() {
+        let controller = &app.controllers[selected_controller];
+        if let Some(index) = app.paired_devices_state.selected() {
+            let address = controller.paired_devices[index].addr;
+            let _ = sender.send(Event::ToggleFavorite(address));
+        }
+    }
+}
+
+fn toggle_pairing(app: &App, sender: UnboundedSender<Event>) {
+    if let Some(selected_controller) = app.controller_state.selected() {
+        let adapter = &app.controllers[selected_controller].adapter;
+        tokio::spawn({
+            let adapter = adapter.clone();
+            async move {
+                match adapter.is_pairable().await {
+                    Ok(is_pairable) => {
+                        if is_pairable {
+                            match adapter.set_pairable(false).await {
+                                Ok(()) => {
+                                    let _ = Notification::send(
+                                        "Adapter unpairable".into(),
+                                        NotificationLevel::Info,
+                                        sender.clone(),
+                                    );
+                                }
                                 Err(e) => {
                                     let _ = Notification::send(
                                         e.into(),
                                         NotificationLevel::Error,
                                         sender.clone(),
                                     );
-                                    let _ = sender.send(Event::FailedPairing(device.address()));
                                 }
                             }
-                        });
+                        } else {
+                            match adapter.set_pairable(true).await {
+                                Ok(()) => {
+                                    let _ = Notification::send(
+                                        "Adapter pairable".into(),
+                                        NotificationLevel::Info,
+                                        sender.clone(),
+                                    );
+                                }
+                                Err(e) => {
+                                    let _ = Notification::send(
+                                        e.into(),
+                                        NotificationLevel::Error,
+                                        sender.clone(),
+                                    );
+                                }
+                            }
+                        }
                     }
                     Err(e) => {
                         let _ =
                             Notification::send(e.into(), NotificationLevel::Error, sender.clone());
                     }
-                },
-                Err(e) => {
-                    let _ = Notification::send(e.into(), NotificationLevel::Error, sender.clone());
+                }
+            }
+        });
+    }
+}
+
+fn toggle_power(app: &App, sender: UnboundedSender<Event>) {
+    if let Some(selected_controller) = app.controller_state.selected() {
+        let adapter = &app.controllers[selected_controller].adapter;
+        tokio::spawn({
+            let adapter = adapter.clone();
+            async move {
+                match adapter.is_powered().await {
+                    Ok(is_powered) => {
+                        if is_powered {
+                            match adapter.set_powered(false).await {
+                                Ok(()) => {
+                                    let _ = Notification::send(
+                                        "Adapter powered off".into(),
+                                        NotificationLevel::Info,
+                                        sender.clone(),
+                                    );
+                                }
+                                Err(e) => {
+                                    let _ = Notification::send(
+                                        e.into(),
+                                        NotificationLevel::Error,
+                                        sender.clone(),
+                                    );
+                                }
+                            }
+                        } else {
+                            match adapter.set_powered(true).await {
+                                Ok(()) => {
+                                    let _ = Notification::send(
+                                        "Adapter powered on".into(),
+                                        NotificationLevel::Info,
+                                        sender.clone(),
+                                    );
+                                }
+                                Err(e) => {
+                                    let _ = Notification::send(
+                                        e.into(),
+                                        NotificationLevel::Error,
+                                        sender.clone(),
+                                    );
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        let _ =
+                            Notification::send(e.into(), NotificationLevel::Error, sender.clone());
+                    }
+                }
+            }
+        });
+    }
+}
+
+fn toggle_discovery(app: &App, sender: UnboundedSender<Event>) {
+    if let Some(selected_controller) = app.controller_state.selected() {
+        let adapter = &app.controllers[selected_controller].adapter;
+        tokio::spawn({
+            let adapter = adapter.clone();
+            async move {
+                match adapter.is_discoverable().await {
+                    Ok(is_discoverable) => {
+                        if is_discoverable {
+                            match adapter.set_discoverable(false).await {
+                                Ok(()) => {
+                                    let _ = Notification::send(
+                                        "Adapter undiscoverable".into(),
+                                        NotificationLevel::Info,
+                                        sender.clone(),
+                                    );
+                                }
+                                Err(e) => {
+                                    let _ = Notification::send(
+                                        e.into(),
+                                        NotificationLevel::Error,
+                                        sender.clone(),
+                                    );
+                                }
+                            }
+                        } else {
+                            match adapter.set_discoverable(true).await {
+                                Ok(()) => {
+                                    let _ = Notification::send(
+                                        "Adapter discoverable".into(),
+                                        NotificationLevel::Info,
+                                        sender.clone(),
+                                    );
+                                }
+                                Err(e) => {
+                                    let _ = Notification::send(
+                                        e.into(),
+                                        NotificationLevel::Error,
+                                        sender.clone(),
+                                    );
+                                }
+                            }
+                        }
+                    }
+                    Err(e) => {
+                        let _ =
+                            Notification::send(e.into(), NotificationLevel::Error, sender.clone());
+                    }
+                }
+            }
+        });
+    }
+}
+
+async fn open_beacons(app: &mut App) {
+    app.focused_block = FocusedBlock::Beacons;
+    app.refresh_beacons().await;
+}
+
+fn open_groups(app: &mut App) {
+    app.focused_block = FocusedBlock::Groups;
+    if app.groups_state.selected().is_none() && !app.groups.entries.is_empty() {
+        app.groups_state.select(Some(0));
+    }
+}
+
+fn open_key_bindings(app: &mut App) {
+    app.previous_focused_block = app.focused_block;
+    app.focused_block = FocusedBlock::KeyBindings;
+}
+
+fn open_search(app: &mut App) {
+    app.previous_focused_block = if app.focused_block == FocusedBlock::NewDevices {
+        FocusedBlock::NewDevices
+    } else {
+        FocusedBlock::PairedDevices
+    };
+    app.search = app.search.clone().with_value(app.filter.query.clone());
+    app.focused_block = FocusedBlock::Search;
+}
+
+fn open_palette(app: &mut App) {
+    app.palette = Palette::new(app);
+    app.previous_focused_block = app.focused_block;
+    app.focused_block = FocusedBlock::CommandPalette;
+}
+
+// Select the target of the command then run its action
+async fn run_command(
+    app: &mut App,
+    command: Command,
+    sender: UnboundedSender<Event>,
+) -> AppResult<()> {
+    // The lists may have been refreshed since the palette was opened
+    let Some(location) = command.target.locate(&app.controllers) else {
+        let _ = Notification::send(
+            format!("{}: no longer available", command.label).into(),
+            NotificationLevel::Info,
+            sender,
+        );
+        return Ok(());
+    };
+
+    if let Some(block) = command.target.focused_block() {
+        app.focused_block = block;
+    }
+    if let Some(i) = location.adapter
+        && app.controller_state.selected() != Some(i)
+    {
+        app.controller_state.select(Some(i));
+        app.reset_devices_state();
+    }
+    match command.target {
+        Target::PairedDevice { .. } => app.paired_devices_state.select(location.device),
+        Target::NewDevice { .. } => app.new_devices_state.select(location.device),
+        Target::Global | Target::Adapter(_) => {}
+    }
+
+    match command.action {
+        Action::ToggleConnect => toggle_connect(app, sender),
+        Action::ToggleTrust => toggle_trust(app, sender),
+        Action::ToggleFavorite => toggle_favorite(app, &sender),
+        Action::Rename => app.focused_block = FocusedBlock::SetDeviceAliasBox,
+        Action::AddToGroup => app.focused_block = FocusedBlock::AddToGroup,
+        Action::Unpair => app.focused_block = FocusedBlock::UnpairConfirmation { confirm: false },
+        Action::ShowDetails => show_details(app),
+        Action::SendFile => open_file_picker(app, &sender),
+        Action::Serial => open_serial(app),
+        Action::Sensors => open_sensors(app, &sender),
+        Action::Record => open_recorder(app, &sender),
+        Action::Media => show_media(app, &sender),
+        Action::ToggleNetwork => toggle_network(app, sender),
+        Action::PhoneData => open_phone_data(app, &sender),
+        Action::Pair => pair(app, sender).await,
+        Action::TogglePower => toggle_power(app, sender),
+        Action::TogglePairing => toggle_pairing(app, sender),
+        Action::ToggleDiscovery => toggle_discovery(app, sender),
+        Action::ToggleSharing => toggle_sharing(app, sender).await,
+        Action::Advertise => open_advertising_editor(app),
+        Action::GattServer => open_gatt_server(app),
+        Action::AddDevice => open_add_device(app),
+        Action::CancelTransfers => cancel_transfers(app, &sender),
+        Action::ToggleScanning => toggle_scanning(app, sender)?,
+        Action::Search => open_search(app),
+        Action::Groups => open_groups(app),
+        Action::Beacons => open_beacons(app).await,
+        Action::KeyBindings => open_key_bindings(app),
+        Action::Quit => app.quit(),
+    }
+    Ok(())
+}
+
+// The keys do what the key bindings overlay lists, the other keys only go to the text fields
+fn takes_key(config: &Config, block: FocusedBlock, key_event: &KeyEvent) -> bool {
+    let context = block.binding_context();
+    context.has_text_input()
+        || Key::from_event(key_event).is_some_and(|key| config.is_bound(context, key))
+}
+
+pub async fn handle_key_events(
+    key_event: KeyEvent,
+    app: &mut App,
+    sender: UnboundedSender<Event>,
+    config: Arc<Config>,
+) -> AppResult<()> {
+    if !takes_key(&config, app.focused_block, &key_event) {
+        return Ok(());
+    }
+
+    match app.focused_block {
+        FocusedBlock::SetDeviceAliasBox => match key_event.code {
+            KeyCode::Enter => {
+                if let Some(selected_controller) = app.controller_state.selected() {
+                    let controller = &app.controllers[selected_controller];
+                    if let Some(index) = app.paired_devices_state.selected() {
+                        let device = &controller.paired_devices[index];
+                        match device.set_alias(app.new_alias.value().into()).await {
+                            Ok(()) => {
+                                Notification::send(
+                                    "Set New Alias".into(),
+                                    NotificationLevel::Info,
+                                    sender,
+                                )?;
+                            }
+                            Err(e) => {
+                                Notification::send(e.into(), NotificationLevel::Error, sender)?;
+                            }
+                        }
+                        app.focused_block = FocusedBlock::PairedDevices;
+                        app.new_alias.reset();
+                    }
+                }
+            }
+
+            KeyCode::Esc => {
+                app.focused_block = FocusedBlock::PairedDevices;
+                app.new_alias.reset();
+            }
+            _ => {
+                app.new_alias
+                    .handle_event(&crossterm::event::Event::Key(key_event));
+            }
+        },
+        FocusedBlock::AddDevice => match key_event.code {
+            KeyCode::Enter => add_device(app, &sender),
+            KeyCode::Tab => {
+                if let Some(add_device) = &mut app.add_device {
+                    add_device.next_address_type();
+                }
+            }
+            KeyCode::Esc => {
+                app.add_device = None;
+                app.focused_block = FocusedBlock::Adapter;
+            }
+            _ => {
+                if let Some(add_device) = &mut app.add_device {
+                    add_device
+                        .input
+                        .handle_event(&crossterm::event::Event::Key(key_event));
+                }
+            }
+        },
+        FocusedBlock::AddToGroup => match key_event.code {
+            KeyCode::Enter => {
+                let name = app.group_name.value().trim().to_string();
+                if let Some(selected_controller) = app.controller_state.selected()
+                    && let Some(index) = app.paired_devices_state.selected()
+                    && !name.is_empty()
+                {
+                    let device = &app.controllers[selected_controller].paired_devices[index];
+                    let message = if app.groups.toggle_member(&name, device.addr) {
+                        format!("{} added to {name}", device.display_name())
+                    } else {
+                        format!("{} removed from {name}", device.display_name())
+                    };
+                    match app.groups.save() {
+                        Ok(()) => {
+                            Notification::send(message.into(), NotificationLevel::Info, sender)?;
+                        }
+                        Err(e) => {
+                            Notification::send(e.into(), NotificationLevel::Error, sender)?;
+                        }
+                    }
+                }
+                app.focused_block = FocusedBlock::PairedDevices;
+                app.group_name.reset();
+            }
+
+            KeyCode::Esc => {
+                app.focused_block = FocusedBlock::PairedDevices;
+                app.group_name.reset();
+            }
+            _ => {
+                app.group_name
+                    .handle_event(&crossterm::event::Event::Key(key_event));
+            }
+        },
+        FocusedBlock::Groups => match key_event.code {
+            KeyCode::Esc => app.focused_block = FocusedBlock::PairedDevices,
+            KeyCode::Char(c) if c == config.groups => {
+                app.focused_block = FocusedBlock::PairedDevices;
+            }
+            KeyCode::Char('j') | KeyCode::Down if !app.groups.entries.is_empty() => {
+                let i = match app.groups_state.selected() {
+                    Some(i) if i < app.groups.entries.len() - 1 => i + 1,
+                    _ => 0,
+                };
+                app.groups_state.select(Some(i));
+            }
+            KeyCode::Char('k') | KeyCode::Up if !app.groups.entries.is_empty() => {
+                let i = match app.groups_state.selected() {
+                    Some(i) if i > 0 => i - 1,
+                    _ => app.groups.entries.len() - 1,
+                };
+                app.groups_state.select(Some(i));
+            }
+            KeyCode::Char('c') => run_group_action(app, GroupAction::Connect, sender),
+            KeyCode::Char('d') => run_group_action(app, GroupAction::Disconnect, sender),
+            KeyCode::Char('t') => run_group_action(app, GroupAction::Trust, sender),
+            KeyCode::Char('x') if app.groups_state.selected().is_some() => {
+                app.focused_block = FocusedBlock::DeleteGroupConfirmation { confirm: false };
+            }
+            _ => {}
+        },
+        FocusedBlock::DeleteGroupConfirmation { ref mut confirm } => match key_event.code {
+            KeyCode::Esc => app.focused_block = FocusedBlock::Groups,
+            KeyCode::Tab => *confirm = !*confirm,
+            KeyCode::Enter => {
+                let confirm = *confirm;
+                app.focused_block = FocusedBlock::Groups;
+                if confirm
+                    && let Some(index) = app.groups_state.selected()
+                    && let Some(group) = app.groups.entries.get(index)
+                {
+                    let name = group.name.clone();
+                    app.groups.remove(&name);
+                    if app.groups.entries.is_empty() {
+                        app.groups_state.select(None);
+                    } else {
+                        app.groups_state
+                            .select(Some(index.min(app.groups.entries.len() - 1)));
+                    }
+                    match app.groups.save() {
+                        Ok(()) => Notification::send(
+                            format!("Group {name} deleted").into(),
+                            NotificationLevel::Info,
+                            sender,
+                        )?,
+                        Err(e) => Notification::send(e.into(), NotificationLevel::Error, sender)?,
+                    }
+                }
+            }
+            _ => {}
+        },
+        FocusedBlock::DeviceDetails => match key_event.code {
+            KeyCode::Esc | KeyCode::Enter => {
+                app.focused_block = app.previous_focused_block;
+                app.details = None;
+            }
+            KeyCode::Char(c) if c == config.device_details => {
+                app.focused_block = app.previous_focused_block;
+                app.details = None;
+            }
+            KeyCode::Char('+' | '=') => change_volume(app, VolumeChange::Raise, sender),
+            KeyCode::Char('-') => change_volume(app, VolumeChange::Lower, sender),
+            KeyCode::Char(c @ '0'..='9') => {
+                // 1 to 9 set 10% to 90%, 0 sets 100%
+                let percentage = match c.to_digit(10).unwrap_or_default() {
+                    0 => 100,
+                    digit => digit as u8 * 10,
+                };
+                change_volume(app, VolumeChange::Set(percentage), sender);
+            }
+            _ => {}
+        },
+        FocusedBlock::FilePicker => match key_event.code {
+            KeyCode::Esc => {
+                app.focused_block = FocusedBlock::PairedDevices;
+                app.send_to = None;
+            }
+            KeyCode::Char('j') | KeyCode::Down => app.file_picker.next(),
+            KeyCode::Char('k') | KeyCode::Up => app.file_picker.previous(),
+            KeyCode::Char('h') | KeyCode::Left | KeyCode::Backspace => {
+                if let Err(e) = app.file_picker.parent() {
+                    let _ =
+                        Notification::send(e.to_string().into(), NotificationLevel::Error, sender);
+                }
+            }
+            KeyCode::Char('l') | KeyCode::Right | KeyCode::Enter => match app.file_picker.enter() {
+                Ok(Some(file)) => {
+                    app.focused_block = FocusedBlock::PairedDevices;
+                    if let Some(address) = app.send_to.take() {
+                        send_file(app, address, file, sender);
+                    }
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    let _ =
+                        Notification::send(e.to_string().into(), NotificationLevel::Error, sender);
+                }
+            },
+            _ => {}
+        },
+        FocusedBlock::PhoneData => {
+            if let Some(phone_data) = &mut app.phone_data {
+                if phone_data.editing {
+                    match key_event.code {
+                        KeyCode::Esc | KeyCode::Tab | KeyCode::Enter => phone_data.editing = false,
+                        _ => {
+                            phone_data
+                                .destination
+                                .handle_event(&crossterm::event::Event::Key(key_event));
+                        }
+                    }
+                } else {
+                    match key_event.code {
+                        KeyCode::Esc => {
+                            app.focused_block = FocusedBlock::PairedDevices;
+                            app.phone_data = None;
+                        }
+                        KeyCode::Char('j') | KeyCode::Down => phone_data.next(),
+                        KeyCode::Char('k') | KeyCode::Up => phone_data.previous(),
+                        KeyCode::Tab => phone_data.editing = true,
+                        KeyCode::Enter => export_phone_data(app, sender),
+                        _ => {}
+                    }
+                }
+            }
+        }
+        FocusedBlock::Sensors => {
+            if key_event.code == KeyCode::Esc {
+                app.sensors = None;
+                app.focused_block = FocusedBlock::PairedDevices;
+            }
+        }
+        FocusedBlock::Recorder => {
+            let Some(address) = app.recorder.as_ref().map(|picker| picker.device) else {
+                app.focused_block = FocusedBlock::PairedDevices;
+                return Ok(());
+            };
+            let recording = app.recordings.iter().any(|r| r.device == address);
+            match key_event.code {
+                // The recording keeps running
+                KeyCode::Esc => {
+                    app.recorder = None;
+                    app.focused_block = FocusedBlock::PairedDevices;
+                }
+                KeyCode::Char(c) if c == config.paired_device.record => {
+                    app.recorder = None;
+                    app.focused_block = FocusedBlock::PairedDevices;
+                }
+                KeyCode::Char('s') if recording => stop_recording(app, address, &sender),
+                KeyCode::Char('j') | KeyCode::Down if !recording => {
+                    if let Some(picker) = &mut app.recorder {
+                        picker.next();
+                    }
+                }
+                KeyCode::Char('k') | KeyCode::Up if !recording => {
+                    if let Some(picker) = &mut app.recorder {
+                        picker.previous();
+                    }
+                }
+                KeyCode::Char(' ') if !recording => {
+                    if let Some(picker) = &mut app.recorder {
+                        picker.toggle();
+                    }
+                }
+                KeyCode::Enter if !recording => start_recording(app, &sender),
+                _ => {}
+            }
+        }
+        FocusedBlock::Beacons => match key_event.code {
+            KeyCode::Esc => app.focused_block = FocusedBlock::PairedDevices,
+            KeyCode::Char(c) if c == config.beacons => {
+                app.focused_block = FocusedBlock::PairedDevices;
+            }
+            KeyCode::Char(c) if c == config.toggle_scanning => toggle_scanning(app, sender)?,
+            KeyCode::Char('j') | KeyCode::Down => app.beacons_state.select_next(),
+            KeyCode::Char('k') | KeyCode::Up => app.beacons_state.select_previous(),
+            _ => {}
+        },
+        FocusedBlock::GattServer => match key_event.code {
+            KeyCode::Esc => {
+                // The application keeps being served
+                app.focused_block = FocusedBlock::Adapter;
+            }
+            KeyCode::Enter => toggle_gatt_server(app, &sender).await,
+            _ => {}
+        },
+        FocusedBlock::Advertising => {
+            if key_event.code == KeyCode::Esc {
+                // The advertisement keeps running
+                app.focused_block = FocusedBlock::Adapter;
+                app.advertising_editor = None;
+            } else if let Some(editor) = &mut app.advertising_editor {
+                match editor.handle_key_events(key_event) {
+                    Some(EditorAction::ToggleAdvertising) => {
+                        toggle_advertising(app, sender).await;
+                    }
+                    Some(EditorAction::Save) => save_advertisement(app, sender),
+                    None => {}
                 }
             }
         }
-    }
-}
-
-pub async fn handle_key_events(
-    key_event: KeyEvent,
-    app: &mut App,
-    sender: UnboundedSender<Event>,
-    config: Arc<Config>,
-) -> AppResult<()> {
-    match app.focused_block {
-        FocusedBlock::SetDeviceAliasBox => match key_event.code {
-            KeyCode::Enter => {
-                if let Some(selected_controller) = app.controller_state.selected() {
-                    let controller = &app.controllers[selected_controller];
-                    if let Some(index) = app.paired_devices_state.selected() {
-                        let device = &controller.paired_devices[index];
-                        match device.set_alias(app.new_alias.value().into()).await {
-                            Ok(()) => {
-                                Notification::send(
-                                    "Set New Alias".into(),
-                                    NotificationLevel::Info,
-                                    sender,
-                                )?;
-                            }
-                            Err(e) => {
-                                Notification::send(e.into(), NotificationLevel::Error, sender)?;
-                            }
-                        }
+        FocusedBlock::SerialTerminal => {
+            if let Some(serial) = &mut app.serial {
+                match (&serial.status, key_event.code) {
+                    (_, KeyCode::Esc) => {
+                        // Dropping the terminal closes the stream
                         app.focused_block = FocusedBlock::PairedDevices;
-                        app.new_alias.reset();
+                        app.serial = None;
+                    }
+                    (SerialStatus::Choosing, KeyCode::Char('j') | KeyCode::Down) => serial.next(),
+                    (SerialStatus::Choosing, KeyCode::Char('k') | KeyCode::Up) => {
+                        serial.previous();
+                    }
+                    (SerialStatus::Choosing, KeyCode::Char('l') | KeyCode::Right) => {
+                        serial.change_channel(1);
+                    }
+                    (SerialStatus::Choosing, KeyCode::Char('h') | KeyCode::Left) => {
+                        serial.change_channel(-1);
+                    }
+                    (SerialStatus::Choosing, KeyCode::Enter) => connect_serial(app, sender),
+                    (SerialStatus::Choosing, _) => {}
+                    (_, KeyCode::F(2)) => serial.toggle_mode(),
+                    (_, KeyCode::F(3)) => serial.line_ending = serial.line_ending.next(),
+                    (_, KeyCode::F(4)) => {
+                        let (message, level) = match serial.toggle_log() {
+                            Ok(Some(path)) => (
+                                format!("Logging to {}", path.display()),
+                                NotificationLevel::Info,
+                            ),
+                            Ok(None) => ("Logging stopped".to_string(), NotificationLevel::Info),
+                            Err(e) => (e.to_string(), NotificationLevel::Error),
+                        };
+                        let _ = Notification::send(message.into(), level, sender);
+                    }
+                    (SerialStatus::Connected, KeyCode::Enter) => {
+                        if let Err(e) = serial.send() {
+                            let _ = Notification::send(e.into(), NotificationLevel::Error, sender);
+                        }
+                    }
+                    _ => {
+                        serial
+                            .input
+                            .handle_event(&crossterm::event::Event::Key(key_event));
                     }
                 }
             }
-
+        }
+        FocusedBlock::Media => match key_event.code {
             KeyCode::Esc => {
                 app.focused_block = FocusedBlock::PairedDevices;
-                app.new_alias.reset();
+                app.media_device = None;
+            }
+            KeyCode::Char(c) if c == config.paired_device.media => {
+                app.focused_block = FocusedBlock::PairedDevices;
+                app.media_device = None;
+            }
+            KeyCode::Char(' ') => {
+                if let Some(player) = app.media_device.and_then(|a| app.player(a)) {
+                    let command = if player.is_playing() {
+                        PlayerCommand::Pause
+                    } else {
+                        PlayerCommand::Play
+                    };
+                    control_player(app, command, sender);
+                }
+            }
+            KeyCode::Char('n') => control_player(app, PlayerCommand::Next, sender),
+            KeyCode::Char('p') => control_player(app, PlayerCommand::Previous, sender),
+            KeyCode::Char('x') => control_player(app, PlayerCommand::Stop, sender),
+            _ => {}
+        },
+        FocusedBlock::CommandPalette => match key_event.code {
+            KeyCode::Esc => app.focused_block = app.previous_focused_block,
+            KeyCode::Up => app.palette.previous(),
+            KeyCode::Down => app.palette.next(),
+            KeyCode::Char('p') if key_event.modifiers == KeyModifiers::CONTROL => {
+                app.palette.previous();
+            }
+            KeyCode::Char('n') if key_event.modifiers == KeyModifiers::CONTROL => {
+                app.palette.next();
+            }
+            KeyCode::Enter => {
+                app.focused_block = app.previous_focused_block;
+                if let Some(command) = app.palette.selected().cloned() {
+                    run_command(app, command, sender).await?;
+                }
             }
             _ => {
-                app.new_alias
+                app.palette
+                    .input
+                    .handle_event(&crossterm::event::Event::Key(key_event));
+                app.palette.update_matches();
+            }
+        },
+        FocusedBlock::KeyBindings => match key_event.code {
+            KeyCode::Esc => {
+                app.focused_block = app.previous_focused_block;
+                app.key_bindings_search.reset();
+                app.key_bindings_scroll = 0;
+            }
+            KeyCode::Up => app.key_bindings_scroll = app.key_bindings_scroll.saturating_sub(1),
+            KeyCode::Down => app.key_bindings_scroll = app.key_bindings_scroll.saturating_add(1),
+            KeyCode::PageUp => app.key_bindings_scroll = app.key_bindings_scroll.saturating_sub(10),
+            KeyCode::PageDown => {
+                app.key_bindings_scroll = app.key_bindings_scroll.saturating_add(10);
+            }
+            _ => {
+                app.key_bindings_search
+                    .handle_event(&crossterm::event::Event::Key(key_event));
+                app.key_bindings_scroll = 0;
+            }
+        },
+        FocusedBlock::Search => match key_event.code {
+            KeyCode::Enter => app.focused_block = app.previous_focused_block,
+            KeyCode::Esc => {
+                app.focused_block = app.previous_focused_block;
+                app.search.reset();
+                app.filter.query.clear();
+                apply_filter(app);
+            }
+            _ => {
+                app.search
                     .handle_event(&crossterm::event::Event::Key(key_event));
+                if app.filter.query != app.search.value() {
+                    app.filter.query = app.search.value().to_string();
+                    apply_filter(app);
+                }
             }
         },
         FocusedBlock::RequestConfirmation => match key_event.code {
@@ -249,6 +1734,19 @@ pub async fn handle_key_events(
                 req.submit(&app.auth_agent).await?;
             }
         }
+        FocusedBlock::ReceiveFile => {
+            if let (Some(req), Some(agent)) = (&mut app.requests.receive_file, &app.receive_agent) {
+                match key_event.code {
+                    KeyCode::Esc => {
+                        req.reject(agent)?;
+                    }
+
+                    _ => {
+                        req.handle_key_events(key_event, agent)?;
+                    }
+                }
+            }
+        }
         FocusedBlock::DisplayPasskey => {
             if let Some(req) = &mut app.requests.display_passkey
                 && key_event.code == KeyCode::Esc
@@ -302,6 +1800,18 @@ pub async fn handle_key_events(
                     app.quit();
                 }
 
+                // Command palette
+                KeyCode::Char('p') if key_event.modifiers == KeyModifiers::CONTROL => {
+                    open_palette(app);
+                }
+
+                // Clear the search
+                KeyCode::Esc if !app.filter.query.is_empty() => {
+                    app.search.reset();
+                    app.filter.query.clear();
+                    apply_filter(app);
+                }
+
                 KeyCode::Esc if app.config.esc_quit => {
                     app.quit();
                 }
@@ -364,34 +1874,8 @@ pub async fn handle_key_events(
                         app.controller_state.select(Some(i));
                     }
 
-                    FocusedBlock::PairedDevices => {
-                        if let Some(selected_controller) = app.controller_state.selected() {
-                            let controller = &mut app.controllers[selected_controller];
-
-                            if !controller.paired_devices.is_empty() {
-                                let i = match app.paired_devices_state.selected() {
-                                    Some(i) if i < controller.paired_devices.len() - 1 => i + 1,
-                                    _ => 0,
-                                };
-
-                                app.paired_devices_state.select(Some(i));
-                            }
-                        }
-                    }
-
-                    FocusedBlock::NewDevices => {
-                        if let Some(selected_controller) = app.controller_state.selected() {
-                            let controller = &mut app.controllers[selected_controller];
-
-                            if !controller.new_devices.is_empty() {
-                                let i = match app.new_devices_state.selected() {
-                                    Some(i) if i < controller.new_devices.len() - 1 => i + 1,
-                                    _ => 0,
-                                };
-
-                                app.new_devices_state.select(Some(i));
-                            }
-                        }
+                    FocusedBlock::PairedDevices | FocusedBlock::NewDevices => {
+                        app.select_next_device(true);
                     }
 
                     _ => {}
@@ -414,96 +1898,56 @@ pub async fn handle_key_events(
                         app.controller_state.select(Some(i));
                     }
 
-                    FocusedBlock::PairedDevices => {
-                        if let Some(selected_controller) = app.controller_state.selected() {
-                            let controller = &mut app.controllers[selected_controller];
-                            if !controller.paired_devices.is_empty() {
-                                let i = match app.paired_devices_state.selected() {
-                                    Some(i) => {
-                                        if i > 0 {
-                                            i - 1
-                                        } else {
-                                            controller.paired_devices.len() - 1
-                                        }
-                                    }
-                                    None => 0,
-                                };
-                                app.paired_devices_state.select(Some(i));
-                            }
-                        }
-                    }
-
-                    FocusedBlock::NewDevices => {
-                        if let Some(selected_controller) = app.controller_state.selected() {
-                            let controller = &mut app.controllers[selected_controller];
-                            if !controller.new_devices.is_empty() {
-                                let i = match app.new_devices_state.selected() {
-                                    Some(i) => {
-                                        if i > 0 {
-                                            i - 1
-                                        } else {
-                                            controller.new_devices.len() - 1
-                                        }
-                                    }
-                                    None => 0,
-                                };
-                                app.new_devices_state.select(Some(i));
-                            }
-                        }
+                    FocusedBlock::PairedDevices | FocusedBlock::NewDevices => {
+                        app.select_next_device(false);
                     }
                     _ => {}
                 },
 
                 // Start/Stop Scan
                 KeyCode::Char(c) if c == config.toggle_scanning => {
-                    if let Some(selected_controller) = app.controller_state.selected() {
-                        let controller = &app.controllers[selected_controller];
+                    toggle_scanning(app, sender)?;
+                }
 
-                        if controller.is_scanning.load(Ordering::Relaxed) {
-                            controller
-                                .is_scanning
-                                .store(false, std::sync::atomic::Ordering::Relaxed);
+                KeyCode::Char(c) if c == config.beacons => open_beacons(app).await,
 
-                            Notification::send(
-                                "Scanning stopped".into(),
-                                NotificationLevel::Info,
-                                sender,
-                            )?;
+                // Device groups
+                KeyCode::Char(c) if c == config.groups => open_groups(app),
 
-                            app.spinner.active = false;
-                        } else {
-                            controller
-                                .is_scanning
-                                .store(true, std::sync::atomic::Ordering::Relaxed);
-                            app.spinner.active = true;
-                            let adapter = controller.adapter.clone();
-                            let is_scanning = controller.is_scanning.clone();
-                            tokio::spawn(async move {
-                                let _ = Notification::send(
-                                    "Scanning started".into(),
-                                    NotificationLevel::Info,
-                                    sender.clone(),
-                                );
+                KeyCode::Char(c) if c == config.cancel_transfers => {
+                    cancel_transfers(app, &sender);
+                }
 
-                                match adapter.discover_devices().await {
-                                    Ok(mut discover) => {
-                                        while let Some(_evt) = discover.next().await {
-                                            if !is_scanning.load(Ordering::Relaxed) {
-                                                break;
-                                            }
-                                        }
-                                    }
-                                    Err(e) => {
-                                        let _ = Notification::send(
-                                            e.into(),
-                                            NotificationLevel::Error,
-                                            sender.clone(),
-                                        );
-                                    }
-                                }
-                            });
-                        }
-                    }
+                KeyCode::Char(':') => open_palette(app),
+
+                // Key bindings
+                KeyCode::Char('?') => open_key_bindings(app),
+
+                // Search
+                KeyCode::Char('/') => open_search(app),
+
+                KeyCode::Char('n') if !app.filter.query.is_empty() => app.jump_to_match(true),
+                KeyCode::Char('N') if !app.filter.query.is_empty() => app.jump_to_match(false),
+
+                // Quick filters
+                KeyCode::Char(c) if c == config.filter.audio => {
+                    app.filter.audio = !app.filter.audio;
+                    apply_filter(app);
+                }
+
+                KeyCode::Char(c) if c == config.filter.input => {
+                    app.filter.input = !app.filter.input;
+                    apply_filter(app);
+                }
+
+                KeyCode::Char(c) if c == config.filter.named => {
+                    app.filter.named = !app.filter.named;
+                    apply_filter(app);
+                }
+
+                KeyCode::Char(c) if c == config.filter.connected => {
+                    app.filter.connected = !app.filter.connected;
+                    apply_filter(app);
                 }
 
                 _ => {
@@ -521,101 +1965,57 @@ pub async fn handle_key_events(
 
                                 // Trust / Untrust
                                 KeyCode::Char(c) if c == config.paired_device.toggle_trust => {
-                                    if let Some(selected_controller) =
-                                        app.controller_state.selected()
-                                    {
-                                        let controller = &app.controllers[selected_controller];
-                                        if let Some(index) = app.paired_devices_state.selected() {
-                                            let addr = controller.paired_devices[index].addr;
-                                            match controller.adapter.device(addr) {
-                                                Ok(device) => {
-                                                    tokio::spawn(async move {
-                                                        match device.is_trusted().await {
-                                                            Ok(is_trusted) => {
-                                                                if is_trusted {
-                                                                    match device
-                                                                        .set_trusted(false)
-                                                                        .await
-                                                                    {
-                                                                        Ok(()) => {
-                                                                            let _ = Notification::send(
-                                                                        "Device untrusted"
-                                                                            .into(),
-                                                                        NotificationLevel::Info,
-                                                                        sender.clone(),
-                                                                    );
-                                                                        }
-                                                                        Err(e) => {
-                                                                            let _ = Notification::send(
-                                                                        e.into(),
-                                                                        NotificationLevel::Error,
-                                                                        sender.clone(),
-                                                                    );
-                                                                        }
-                                                                    }
-                                                                } else {
-                                                                    match device
-                                                                        .set_trusted(true)
-                                                                        .await
-                                                                    {
-                                                                        Ok(()) => {
-                                                                            let _ = Notification::send(
-                                                                        "Device trusted"
-                                                                            .into(),
-                                                                        NotificationLevel::Info,
-                                                                        sender.clone(),
-                                                                    );
-                                                                        }
-
-                                                                        Err(e) => {
-                                                                            let _ = Notification::send(
-                                                                        e.into(),
-                                                                        NotificationLevel::Error,
-                                                                        sender.clone(),
-                                                                    );
-                                                                        }
-                                                                    }
-                                                                }
-                                                            }
-                                                            Err(e) => {
-                                                                let _ = Notification::send(
-                                                                    e.into(),
-                                                                    NotificationLevel::Error,
-                                                                    sender.clone(),
-                                                                );
-                                                            }
-                                                        }
-                                                    });
-                                                }
-                                                Err(e) => {
-                                                    let _ = Notification::send(
-                                                        e.into(),
-                                                        NotificationLevel::Error,
-                                                        sender.clone(),
-                                                    );
-                                                }
-                                            }
-                                        }
-                                    }
+                                    toggle_trust(app, sender);
                                 }
 
                                 // Favorite / Unfavorite
                                 KeyCode::Char(c) if c == config.paired_device.toggle_favorite => {
-                                    if let Some(selected_controller) =
-                                        app.controller_state.selected()
-                                    {
-                                        let controller = &app.controllers[selected_controller];
-                                        if let Some(index) = app.paired_devices_state.selected() {
-                                            let address = controller.paired_devices[index].addr;
-                                            let _ = sender.send(Event::ToggleFavorite(address));
-                                        }
-                                    }
+                                    toggle_favorite(app, &sender);
                                 }
 
                                 KeyCode::Char(c) if c == config.paired_device.rename => {
                                     app.focused_block = FocusedBlock::SetDeviceAliasBox;
                                 }
 
+                                KeyCode::Char(c)
+                                    if c == config.paired_device.toggle_group
+                                        && app.paired_devices_state.selected().is_some() =>
+                                {
+                                    app.focused_block = FocusedBlock::AddToGroup;
+                                }
+
+                                KeyCode::Char(c) if c == config.device_details => {
+                                    show_details(app);
+                                }
+
+                                KeyCode::Char(c) if c == config.paired_device.media => {
+                                    show_media(app, &sender);
+                                }
+
+                                KeyCode::Char(c) if c == config.paired_device.send_file => {
+                                    open_file_picker(app, &sender);
+                                }
+
+                                KeyCode::Char(c) if c == config.paired_device.phone_data => {
+                                    open_phone_data(app, &sender);
+                                }
+
+                                KeyCode::Char(c) if c == config.paired_device.tether => {
+                                    toggle_network(app, sender.clone());
+                                }
+
+                                KeyCode::Char(c) if c == config.paired_device.serial => {
+                                    open_serial(app);
+                                }
+
+                                KeyCode::Char(c) if c == config.paired_device.sensors => {
+                                    open_sensors(app, &sender);
+                                }
+
+                                KeyCode::Char(c) if c == config.paired_device.record => {
+                                    open_recorder(app, &sender);
+                                }
+
                                 _ => {}
                             }
                         }
@@ -624,192 +2024,33 @@ pub async fn handle_key_events(
                             match key_event.code {
                                 // toggle pairing
                                 KeyCode::Char(c) if c == config.adapter.toggle_pairing => {
-                                    if let Some(selected_controller) =
-                                        app.controller_state.selected()
-                                    {
-                                        let adapter = &app.controllers[selected_controller].adapter;
-                                        tokio::spawn({
-                                            let adapter = adapter.clone();
-                                            async move {
-                                                match adapter.is_pairable().await {
-                                                    Ok(is_pairable) => {
-                                                        if is_pairable {
-                                                            match adapter.set_pairable(false).await
-                                                            {
-                                                                Ok(()) => {
-                                                                    let _ = Notification::send(
-                                                                        "Adapter unpairable".into(),
-                                                                        NotificationLevel::Info,
-                                                                        sender.clone(),
-                                                                    );
-                                                                }
-                                                                Err(e) => {
-                                                                    let _ = Notification::send(
-                                                                        e.into(),
-                                                                        NotificationLevel::Error,
-                                                                        sender.clone(),
-                                                                    );
-                                                                }
-                                                            }
-                                                        } else {
-                                                            match adapter.set_pairable(true).await {
-                                                                Ok(()) => {
-                                                                    let _ = Notification::send(
-                                                                        "Adapter pairable".into(),
-                                                                        NotificationLevel::Info,
-                                                                        sender.clone(),
-                                                                    );
-                                                                }
-                                                                Err(e) => {
-                                                                    let _ = Notification::send(
-                                                                        e.into(),
-                                                                        NotificationLevel::Error,
-                                                                        sender.clone(),
-                                                                    );
-                                                                }
-                                                            }
-                                                        }
-                                                    }
-                                                    Err(e) => {
-                                                        let _ = Notification::send(
-                                                            e.into(),
-                                                            NotificationLevel::Error,
-                                                            sender.clone(),
-                                                        );
-                                                    }
-                                                }
-                                            }
-                                        });
-                                    }
+                                    toggle_pairing(app, sender);
                                 }
 
                                 // toggle power
                                 KeyCode::Char(c) if c == config.adapter.toggle_power => {
-                                    if let Some(selected_controller) =
-                                        app.controller_state.selected()
-                                    {
-                                        let adapter = &app.controllers[selected_controller].adapter;
-                                        tokio::spawn({
-                                            let adapter = adapter.clone();
-                                            async move {
-                                                match adapter.is_powered().await {
-                                                    Ok(is_powered) => {
-                                                        if is_powered {
-                                                            match adapter.set_powered(false).await {
-                                                                Ok(()) => {
-                                                                    let _ = Notification::send(
-                                                                        "Adapter powered off"
-                                                                            .into(),
-                                                                        NotificationLevel::Info,
-                                                                        sender.clone(),
-                                                                    );
-                                                                }
-                                                                Err(e) => {
-                                                                    let _ = Notification::send(
-                                                                        e.into(),
-                                                                        NotificationLevel::Error,
-                                                                        sender.clone(),
-                                                                    );
-                                                                }
-                                                            }
-                                                        } else {
-                                                            match adapter.set_powered(true).await {
-                                                                Ok(()) => {
-                                                                    let _ = Notification::send(
-                                                                        "Adapter powered on".into(),
-                                                                        NotificationLevel::Info,
-                                                                        sender.clone(),
-                                                                    );
-                                                                }
-                                                                Err(e) => {
-                                                                    let _ = Notification::send(
-                                                                        e.into(),
-                                                                        NotificationLevel::Error,
-                                                                        sender.clone(),
-                                                                    );
-                                                                }
-                                                            }
-                                                        }
-                                                    }
-                                                    Err(e) => {
-                                                        let _ = Notification::send(
-                                                            e.into(),
-                                                            NotificationLevel::Error,
-                                                            sender.clone(),
-                                                        );
-                                                    }
-                                                }
-                                            }
-                                        });
-                                    }
+                                    toggle_power(app, sender);
+                                }
+
+                                KeyCode::Char(c) if c == config.adapter.toggle_sharing => {
+                                    toggle_sharing(app, sender.clone()).await;
+                                }
+
+                                KeyCode::Char(c) if c == config.adapter.advertise => {
+                                    open_advertising_editor(app);
+                                }
+
+                                KeyCode::Char(c) if c == config.adapter.gatt_server => {
+                                    open_gatt_server(app);
+                                }
+
+                                KeyCode::Char(c) if c == config.adapter.add_device => {
+                                    open_add_device(app);
                                 }
 
                                 // toggle discovery
                                 KeyCode::Char(c) if c == config.adapter.toggle_discovery => {
-                                    if let Some(selected_controller) =
-                                        app.controller_state.selected()
-                                    {
-                                        let adapter = &app.controllers[selected_controller].adapter;
-                                        tokio::spawn({
-                                            let adapter = adapter.clone();
-                                            async move {
-                                                match adapter.is_discoverable().await {
-                                                    Ok(is_discoverable) => {
-                                                        if is_discoverable {
-                                                            match adapter
-                                                                .set_discoverable(false)
-                                                                .await
-                                                            {
-                                                                Ok(()) => {
-                                                                    let _ = Notification::send(
-                                                                        "Adapter undiscoverable"
-                                                                            .into(),
-                                                                        NotificationLevel::Info,
-                                                                        sender.clone(),
-                                                                    );
-                                                                }
-                                                                Err(e) => {
-                                                                    let _ = Notification::send(
-                                                                        e.into(),
-                                                                        NotificationLevel::Error,
-                                                                        sender.clone(),
-                                                                    );
-                                                                }
-                                                            }
-                                                        } else {
-                                                            match adapter
-                                                                .set_discoverable(true)
-                                                                .await
-                                                            {
-                                                                Ok(()) => {
-                                                                    let _ = Notification::send(
-                                                                        "Adapter discoverable"
-                                                                            .into(),
-                                                                        NotificationLevel::Info,
-                                                                        sender.clone(),
-                                                                    );
-                                                                }
-                                                                Err(e) => {
-                                                                    let _ = Notification::send(
-                                                                        e.into(),
-                                                                        NotificationLevel::Error,
-                                                                        sender.clone(),
-                                                                    );
-                                                                }
-                                                            }
-                                                        }
-                                                    }
-                                                    Err(e) => {
-                                                        let _ = Notification::send(
-                                                            e.into(),
-                                                            NotificationLevel::Error,
-                                                            sender.clone(),
-                                                        );
-                                                    }
-                                                }
-                                            }
-                                        });
-                                    }
+                                    toggle_discovery(app, sender);
                                 }
 
                                 _ => {}
@@ -820,6 +2061,9 @@ pub async fn handle_key_events(
                             // Pair new device
                             match key_event.code {
                                 KeyCode::Enter | KeyCode::Char(' ') => pair(app, sender).await,
+                                KeyCode::Char(c) if c == config.device_details => {
+                                    show_details(app);
+                                }
                                 _ => {}
                             }
                         }
@@ -833,3 +2077,343 @@ pub async fn handle_key_events(
 
     Ok(())
 }
+
+const DOUBLE_CLICK_DELAY: Duration = Duration::from_millis(500);
+
+// Index of the row of the table rendered in `area` at the given line, `None` on its borders
+// and its header
+fn row_at(area: Rect, offset: usize, line: u16) -> Option<usize> {
+    let rows = table_rows(area);
+    (rows.y..rows.bottom())
+        .contains(&line)
+        .then(|| offset + usize::from(line - rows.y))
+}
+
+// A second click on the same cell soon after the first one
+fn is_double_click(
+    last_click: Option<(Instant, u16, u16)>,
+    now: Instant,
+    position: Position,
+) -> bool {
+    last_click.is_some_and(|(time, column, row)| {
+        now.duration_since(time) < DOUBLE_CLICK_DELAY && column == position.x && row == position.y
+    })
+}
+
+pub async fn handle_mouse_events(
+    mouse_event: MouseEvent,
+    app: &mut App,
+    sender: UnboundedSender<Event>,
+    config: Arc<Config>,
+) -> AppResult<()> {
+    let position = Position::new(mouse_event.column, mouse_event.row);
+    let areas = app.click_areas;
+
+    match app.focused_block {
+        FocusedBlock::KeyBindings => match mouse_event.kind {
+            MouseEventKind::ScrollDown => {
+                app.key_bindings_scroll = app.key_bindings_scroll.saturating_add(1);
+            }
+            MouseEventKind::ScrollUp => {
+                app.key_bindings_scroll = app.key_bindings_scroll.saturating_sub(1);
+            }
+            _ => {}
+        },
+
+        // Clicking a choice selects it and submits it
+        FocusedBlock::UnpairConfirmation { .. }
+        | FocusedBlock::DeleteGroupConfirmation { .. }
+        | FocusedBlock::RequestConfirmation => {
+            if mouse_event.kind != MouseEventKind::Down(MouseButton::Left) {
+                return Ok(());
+            }
+            let Some((no, yes)) = areas.choices else {
+                return Ok(());
+            };
+            let confirm = if yes.contains(position) {
+                true
+            } else if no.contains(position) {
+                false
+            } else {
+                return Ok(());
+            };
+
+            if let FocusedBlock::UnpairConfirmation { .. } = app.focused_block {
+                app.focused_block = FocusedBlock::UnpairConfirmation { confirm };
+            } else if let FocusedBlock::DeleteGroupConfirmation { .. } = app.focused_block {
+                app.focused_block = FocusedBlock::DeleteGroupConfirmation { confirm };
+            } else if let Some(req) = &mut app.requests.confirmation {
+                req.select(confirm);
+            }
+            handle_key_events(KeyCode::Enter.into(), app, sender, config).await?;
+        }
+
+        FocusedBlock::Adapter | FocusedBlock::PairedDevices | FocusedBlock::NewDevices => {
+            let (block, area, offset) = if areas.adapter.contains(position) {
+                (FocusedBlock::Adapter, areas.adapter, areas.adapter_offset)
+            } else if areas.paired_devices.contains(position) {
+                (
+                    FocusedBlock::PairedDevices,
+                    areas.paired_devices,
+                    areas.paired_devices_offset,
+                )
+            } else if areas.new_devices.contains(position) {
+                (
+                    FocusedBlock::NewDevices,
+                    areas.new_devices,
+                    areas.new_devices_offset,
+                )
+            } else {
+                return Ok(());
+            };
+
+            match mouse_event.kind {
+                MouseEventKind::ScrollDown => {
+                    app.focused_block = block;
+                    handle_key_events(KeyCode::Down.into(), app, sender, config).await?;
+                }
+                MouseEventKind::ScrollUp => {
+                    app.focused_block = block;
+                    handle_key_events(KeyCode::Up.into(), app, sender, config).await?;
+                }
+                MouseEventKind::Down(MouseButton::Left) => {
+                    app.focused_block = block;
+
+                    let Some(index) = row_at(area, offset, position.y) else {
+                        app.last_click = None;
+                        return Ok(());
+                    };
+
+                    match block {
+                        FocusedBlock::Adapter => {
+                            if index >= app.controllers.len() {
+                                return Ok(());
+                            }
+                            if app.controller_state.selected() != Some(index) {
+                                app.controller_state.select(Some(index));
+                                app.reset_devices_state();
+                            }
+                        }
+                        FocusedBlock::PairedDevices | FocusedBlock::NewDevices => {
+                            let Some(selected_controller) = app.controller_state.selected() else {
+                                return Ok(());
+                            };
+                            // The rows are the shown devices only
+                            let controller = &app.controllers[selected_controller];
+                            if block == FocusedBlock::PairedDevices {
+                                let shown = app.shown_devices(&controller.paired_devices);
+                                let Some(&i) = shown.get(index) else {
+                                    return Ok(());
+                                };
+                                app.paired_devices_state.select(Some(i));
+                            } else {
+                                let shown = app.shown_devices(&controller.new_devices);
+                                let Some(&i) = shown.get(index) else {
+                                    return Ok(());
+                                };
+                                app.new_devices_state.select(Some(i));
+                            }
+                        }
+                        _ => {}
+                    }
+
+                    // Double click connects or pairs the device, like Enter
+                    let now = Instant::now();
+                    if is_double_click(app.last_click, now, position) {
+                        app.last_click = None;
+                        handle_key_events(KeyCode::Enter.into(), app, sender, config).await?;
+                    } else {
+                        app.last_click = Some((now, position.x, position.y));
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        _ => {}
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use ratatui::{
+        Terminal,
+        backend::TestBackend,
+        layout::Constraint,
+        widgets::{Block, Row, Table},
+    };
+
+    use super::*;
+    use crate::app::HEADER_MARGIN;
+
+    fn key(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    fn ctrl(c: char) -> KeyEvent {
+        KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL)
+    }
+
+    #[test]
+    fn keys_without_a_binding_are_dropped() {
+        let config = Config::default();
+        for (block, event, taken) in [
+            // The global keys apply to the sections only
+            (FocusedBlock::PairedDevices, key(KeyCode::Char('q')), true),
+            (FocusedBlock::Adapter, ctrl('p'), true),
+            (FocusedBlock::NewDevices, key(KeyCode::Tab), true),
+            (FocusedBlock::Groups, key(KeyCode::Char('q')), false),
+            (FocusedBlock::Media, key(KeyCode::Tab), false),
+            // The keys of the section
+            (
+                FocusedBlock::PairedDevices,
+                key(KeyCode::Char(config.paired_device.unpair)),
+                true,
+            ),
+            (
+                FocusedBlock::Adapter,
+                key(KeyCode::Char(config.adapter.toggle_power)),
+                true,
+            ),
+            (
+                FocusedBlock::NewDevices,
+                key(KeyCode::Char(config.paired_device.unpair)),
+                false,
+            ),
+            (FocusedBlock::PairedDevices, key(KeyCode::F(2)), false),
+            // The keys of the popups
+            (FocusedBlock::Groups, key(KeyCode::Char('x')), true),
+            (FocusedBlock::Groups, ctrl('x'), false),
+            (FocusedBlock::DeviceDetails, key(KeyCode::Char('7')), true),
+            (
+                FocusedBlock::DeleteGroupConfirmation { confirm: true },
+                key(KeyCode::Tab),
+                true,
+            ),
+            (
+                FocusedBlock::Recorder,
+                key(KeyCode::Char(config.paired_device.record)),
+                true,
+            ),
+            (
+                FocusedBlock::Beacons,
+                key(KeyCode::Char(config.toggle_scanning)),
+                true,
+            ),
+            (FocusedBlock::Sensors, key(KeyCode::Enter), false),
+            (FocusedBlock::FilePicker, key(KeyCode::Home), false),
+            (
+                FocusedBlock::PairedDevices,
+                KeyEvent::new(KeyCode::Char('q'), KeyModifiers::ALT),
+                false,
+            ),
+            // The text fields take the other keys
+            (FocusedBlock::Search, key(KeyCode::Char('q')), true),
+            (FocusedBlock::SetDeviceAliasBox, key(KeyCode::Home), true),
+            (FocusedBlock::EnterPinCode, key(KeyCode::Char('4')), true),
+            (FocusedBlock::SerialTerminal, ctrl('w'), true),
+        ] {
+            assert_eq!(
+                takes_key(&config, block, &event),
+                taken,
+                "{:?} in {block:?}",
+                event.code
+            );
+        }
+    }
+
+    #[test]
+    fn keys_of_the_events() {
+        assert_eq!(
+            Key::from_event(&key(KeyCode::Char('N'))),
+            Some(Key::Char('N'))
+        );
+        assert_eq!(Key::from_event(&ctrl('s')), Some(Key::Ctrl('s')));
+        assert_eq!(
+            Key::from_event(&KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT)),
+            Some(Key::BackTab)
+        );
+        assert_eq!(Key::from_event(&key(KeyCode::F(4))), Some(Key::F(4)));
+        assert_eq!(Key::from_event(&key(KeyCode::Delete)), None);
+    }
+
+    #[test]
+    fn rows_follow_the_rendered_table() {
+        let area = Rect::new(2, 5, 30, 8);
+        let table = Table::new(
+            [Row::new(["first"]), Row::new(["second"])],
+            [Constraint::Fill(1)],
+        )
+        .header(Row::new(["Name"]).bottom_margin(HEADER_MARGIN))
+        .block(Block::bordered());
+        let mut terminal = Terminal::new(TestBackend::new(40, 20)).unwrap();
+        terminal
+            .draw(|frame| frame.render_widget(table, area))
+            .unwrap();
+
+        let line = |y: u16| {
+            (area.x..area.right())
+                .map(|x| terminal.backend().buffer()[(x, y)].symbol())
+                .collect::<String>()
+        };
+        let first_row = (area.y..area.bottom())
+            .find(|&y| line(y).contains("first"))
+            .unwrap();
+        assert_eq!(row_at(area, 0, first_row), Some(0));
+        assert_eq!(row_at(area, 0, first_row + 1), Some(1));
+        assert!(line(first_row + 1).contains("second"));
+    }
+
+    #[test]
+    fn clicked_rows() {
+        let area = Rect::new(0, 10, 40, 8);
+
+        // The top border, the header and its margin
+        assert_eq!(row_at(area, 0, 10), None);
+        assert_eq!(row_at(area, 0, 11), None);
+        assert_eq!(row_at(area, 0, 12), None);
+
+        assert_eq!(row_at(area, 0, 13), Some(0));
+        assert_eq!(row_at(area, 0, 16), Some(3));
+        // Scrolled tables start at their offset
+        assert_eq!(row_at(area, 5, 13), Some(5));
+
+        // The bottom border and below
+        assert_eq!(row_at(area, 0, 17), None);
+        assert_eq!(row_at(area, 0, 30), None);
+    }
+
+    #[test]
+    fn double_clicks() {
+        let now = Instant::now();
+        let position = Position::new(4, 13);
+
+        assert!(!is_double_click(None, now, position));
+        assert!(is_double_click(
+            Some((now, 4, 13)),
+            now + Duration::from_millis(200),
+            position
+        ));
+        // Too late
+        assert!(!is_double_click(
+            Some((now, 4, 13)),
+            now + DOUBLE_CLICK_DELAY,
+            position
+        ));
+        // On another cell
+        assert!(!is_double_click(
+            Some((now, 4, 14)),
+            now + Duration::from_millis(200),
+            position
+        ));
+    }
+
+    #[test]
+    fn default_bindings_are_not_shadowed() {
+        let bindings = Config::default().bindings();
+        let shadowed = crate::config::check::shadowed_bindings(&bindings);
+        assert!(shadowed.is_empty(), "{shadowed:#?}");
+    }
+}
@@ -2,13 +2,18 @@ use std::sync::Arc;
 
 use ratatui::{
     Frame,
-    layout::Rect,
-    style::Stylize,
+    layout::{Constraint, Rect},
+    style::{Style, Stylize},
     text::{Line, Span},
-    widgets::Paragraph,
+    widgets::{Block, BorderType, Clear, Padding, Paragraph},
 };
+use tui_input::Input;
 
-use crate::{app::FocusedBlock, config::Config};
+use crate::{
+    app::FocusedBlock,
+    config::{Binding, BindingContext, Config, check::shadowed_bindings},
+    filter::find_ignore_case,
+};
 
 pub struct Help;
 
@@ -22,7 +27,7 @@ impl Help {
     ) {
         let help = match focused_block {
             FocusedBlock::PairedDevices => {
-                if area.width > 120 {
+                if area.width > 140 {
                     vec![Line::from(vec![
                         Span::from("k,").bold(),
                         Span::from("  Up"),
@@ -48,6 +53,12 @@ impl Help {
                         Span::from(config.paired_device.rename.to_string()).bold(),
                         Span::from(" Rename"),
                         Span::from(" | "),
+                        Span::from(config.paired_device.toggle_group.to_string()).bold(),
+                        Span::from(" Group"),
+                        Span::from(" | "),
+                        Span::from(config.groups.to_string()).bold(),
+                        Span::from(" Groups"),
+                        Span::from(" | "),
                         Span::from("⇄").bold(),
                         Span::from(" Nav"),
                     ])]
@@ -73,6 +84,12 @@ impl Help {
                             Span::from(config.paired_device.rename.to_string()).bold(),
                             Span::from(" Rename"),
                             Span::from(" | "),
+                            Span::from(config.paired_device.toggle_group.to_string()).bold(),
+                            Span::from(" Group"),
+                            Span::from(" | "),
+                            Span::from(config.groups.to_string()).bold(),
+                            Span::from(" Groups"),
+                            Span::from(" | "),
                             Span::from("k,").bold(),
                             Span::from("  Up"),
                             Span::from(" | "),
@@ -95,6 +112,9 @@ impl Help {
                 Span::from("󱁐  or ↵ ").bold(),
                 Span::from(" Pair"),
                 Span::from(" | "),
+                Span::from("/").bold(),
+                Span::from(" Search"),
+                Span::from(" | "),
                 Span::from("s").bold(),
                 Span::from("  Scan on/off"),
                 Span::from(" | "),
@@ -141,7 +161,40 @@ impl Help {
                     ]
                 }
             }
-            FocusedBlock::SetDeviceAliasBox => {
+            FocusedBlock::Groups => {
+                vec![Line::from(vec![
+                    Span::from("k,").bold(),
+                    Span::from("  Up"),
+                    Span::from(" | "),
+                    Span::from("j,").bold(),
+                    Span::from("  Down"),
+                    Span::from(" | "),
+                    Span::from("c").bold(),
+                    Span::from(" Connect all"),
+                    Span::from(" | "),
+                    Span::from("d").bold(),
+                    Span::from(" Disconnect all"),
+                    Span::from(" | "),
+                    Span::from("t").bold(),
+                    Span::from(" Trust all"),
+                    Span::from(" | "),
+                    Span::from("x").bold(),
+                    Span::from(" Delete"),
+                    Span::from(" | "),
+                    Span::from("󱊷 ").bold(),
+                    Span::from(" Close"),
+                ])]
+            }
+            FocusedBlock::Search => {
+                vec![Line::from(vec![
+                    Span::from("󱊷 ").bold(),
+                    Span::from(" Clear"),
+                    Span::from(" | "),
+                    Span::from("↵ ").bold(),
+                    Span::from(" Apply"),
+                ])]
+            }
+            FocusedBlock::SetDeviceAliasBox | FocusedBlock::AddToGroup => {
                 vec![Line::from(vec![
                     Span::from("󱊷 ").bold(),
                     Span::from(" Discard"),
@@ -150,7 +203,9 @@ impl Help {
                     Span::from(" Apply"),
                 ])]
             }
-            FocusedBlock::RequestConfirmation | FocusedBlock::UnpairConfirmation { .. } => {
+            FocusedBlock::RequestConfirmation
+            | FocusedBlock::UnpairConfirmation { .. }
+            | FocusedBlock::DeleteGroupConfirmation { .. } => {
                 vec![Line::from(vec![
                     Span::from("↵ ").bold(),
                     Span::from(" Ok"),
@@ -180,18 +235,311 @@ impl Help {
                     Span::from(" Ok"),
                 ])]
             }
+            FocusedBlock::ReceiveFile => {
+                vec![Line::from(vec![
+                    Span::from("↵ ").bold(),
+                    Span::from(" Ok"),
+                    Span::from(" | "),
+                    Span::from("󱊷 ").bold(),
+                    Span::from(" Reject"),
+                    Span::from(" | "),
+                    Span::from("⇄").bold(),
+                    Span::from(" Nav"),
+                ])]
+            }
             FocusedBlock::DisplayPasskey => {
                 vec![Line::from(vec![
                     Span::from(" 󱊷  ").bold(),
                     Span::from(" Discard"),
                 ])]
             }
+            FocusedBlock::DeviceDetails => {
+                vec![Line::from(vec![
+                    Span::from("+,-").bold(),
+                    Span::from(" Volume"),
+                    Span::from(" | "),
+                    Span::from("0-9").bold(),
+                    Span::from(" Set volume"),
+                    Span::from(" | "),
+                    Span::from("󱊷 ").bold(),
+                    Span::from(" Close"),
+                ])]
+            }
+            FocusedBlock::Media => {
+                vec![Line::from(vec![
+                    Span::from("󱁐 ").bold(),
+                    Span::from(" Play/Pause"),
+                    Span::from(" | "),
+                    Span::from("n").bold(),
+                    Span::from(" Next"),
+                    Span::from(" | "),
+                    Span::from("p").bold(),
+                    Span::from(" Previous"),
+                    Span::from(" | "),
+                    Span::from("x").bold(),
+                    Span::from(" Stop"),
+                    Span::from(" | "),
+                    Span::from("󱊷 ").bold(),
+                    Span::from(" Close"),
+                ])]
+            }
+            FocusedBlock::FilePicker => {
+                vec![Line::from(vec![
+                    Span::from("k,").bold(),
+                    Span::from("  Up"),
+                    Span::from(" | "),
+                    Span::from("j,").bold(),
+                    Span::from("  Down"),
+                    Span::from(" | "),
+                    Span::from("↵ ").bold(),
+                    Span::from(" Open/Send"),
+                    Span::from(" | "),
+                    Span::from("⌫ ").bold(),
+                    Span::from(" Parent"),
+                    Span::from(" | "),
+                    Span::from("󱊷 ").bold(),
+                    Span::from(" Close"),
+                ])]
+            }
+            FocusedBlock::PhoneData => {
+                vec![Line::from(vec![
+                    Span::from("k,").bold(),
+                    Span::from("  Up"),
+                    Span::from(" | "),
+                    Span::from("j,").bold(),
+                    Span::from("  Down"),
+                    Span::from(" | "),
+                    Span::from("↵ ").bold(),
+                    Span::from(" Export"),
+                    Span::from(" | "),
+                    Span::from("⇄").bold(),
+                    Span::from(" Destination"),
+                    Span::from(" | "),
+                    Span::from("󱊷 ").bold(),
+                    Span::from(" Close"),
+                ])]
+            }
+            FocusedBlock::Sensors => {
+                vec![Line::from(vec![
+                    Span::from("󱊷 ").bold(),
+                    Span::from(" Close"),
+                ])]
+            }
+            FocusedBlock::Recorder => {
+                vec![Line::from(vec![
+                    Span::from("k,").bold(),
+                    Span::from("  Up"),
+                    Span::from(" | "),
+                    Span::from("j,").bold(),
+                    Span::from("  Down"),
+                    Span::from(" | "),
+                    Span::from("󱁐 ").bold(),
+                    Span::from(" Pick"),
+                    Span::from(" | "),
+                    Span::from("↵ ").bold(),
+                    Span::from(" Start"),
+                    Span::from(" | "),
+                    Span::from("s").bold(),
+                    Span::from(" Stop"),
+                    Span::from(" | "),
+                    Span::from("󱊷 ").bold(),
+                    Span::from(" Close"),
+                ])]
+            }
+            FocusedBlock::AddDevice => {
+                vec![Line::from(vec![
+                    Span::from("↵ ").bold(),
+                    Span::from(" Connect"),
+                    Span::from(" | "),
+                    Span::from("⇄").bold(),
+                    Span::from(" Address type"),
+                    Span::from(" | "),
+                    Span::from("󱊷 ").bold(),
+                    Span::from(" Cancel"),
+                ])]
+            }
+            FocusedBlock::Beacons => {
+                vec![Line::from(vec![
+                    Span::from("k,").bold(),
+                    Span::from("  Up"),
+                    Span::from(" | "),
+                    Span::from("j,").bold(),
+                    Span::from("  Down"),
+                    Span::from(" | "),
+                    Span::from(config.toggle_scanning.to_string()).bold(),
+                    Span::from(" Start/Stop scanning"),
+                    Span::from(" | "),
+                    Span::from("󱊷 ").bold(),
+                    Span::from(" Close"),
+                ])]
+            }
+            FocusedBlock::GattServer => {
+                vec![Line::from(vec![
+                    Span::from("↵ ").bold(),
+                    Span::from(" Start/Stop serving"),
+                    Span::from(" | "),
+                    Span::from("󱊷 ").bold(),
+                    Span::from(" Close"),
+                ])]
+            }
+            FocusedBlock::Advertising => {
+                vec![Line::from(vec![
+                    Span::from("⇄").bold(),
+                    Span::from(" Next field"),
+                    Span::from(" | "),
+                    Span::from("↵ ").bold(),
+                    Span::from(" Start/Stop"),
+                    Span::from(" | "),
+                    Span::from("ctrl+s").bold(),
+                    Span::from(" Save"),
+                    Span::from(" | "),
+                    Span::from("󱊷 ").bold(),
+                    Span::from(" Close"),
+                ])]
+            }
+            FocusedBlock::SerialTerminal => {
+                vec![
+                    Line::from(vec![
+                        Span::from("↵ ").bold(),
+                        Span::from(" Connect/Send"),
+                        Span::from(" | "),
+                        Span::from("←,→").bold(),
+                        Span::from(" Channel"),
+                        Span::from(" | "),
+                        Span::from("󱊷 ").bold(),
+                        Span::from(" Close"),
+                    ]),
+                    Line::from(vec![
+                        Span::from("F2").bold(),
+                        Span::from(" Text/Hex"),
+                        Span::from(" | "),
+                        Span::from("F3").bold(),
+                        Span::from(" Line ending"),
+                        Span::from(" | "),
+                        Span::from("F4").bold(),
+                        Span::from(" Log on/off"),
+                    ]),
+                ]
+            }
+            FocusedBlock::CommandPalette => {
+                vec![Line::from(vec![
+                    Span::from("↑,↓").bold(),
+                    Span::from(" Select"),
+                    Span::from(" | "),
+                    Span::from("↵ ").bold(),
+                    Span::from(" Run"),
+                    Span::from(" | "),
+                    Span::from("󱊷 ").bold(),
+                    Span::from(" Close"),
+                ])]
+            }
+            FocusedBlock::KeyBindings => {
+                vec![Line::from(vec![
+                    Span::from("↑,↓").bold(),
+                    Span::from(" Scroll"),
+                    Span::from(" | "),
+                    Span::from("Type").bold(),
+                    Span::from(" Search"),
+                    Span::from(" | "),
+                    Span::from("󱊷 ").bold(),
+                    Span::from(" Close"),
+                ])]
+            }
         };
         let help = Paragraph::new(help).centered().blue();
         frame.render_widget(help, rendering_block);
     }
 }
 
+// Lines of the key bindings overlay: the bindings that are not shadowed by another one,
+// grouped by context and filtered by the search
+fn key_bindings_lines(bindings: &[Binding], search: &str) -> Vec<Line<'static>> {
+    let shadowed = shadowed_bindings(bindings);
+    let is_active = |binding: &Binding| {
+        !shadowed
+            .iter()
+            .any(|(shadowed, _)| std::ptr::eq(*shadowed, binding))
+    };
+
+    let mut lines = Vec::new();
+    for context in BindingContext::ALL {
+        let context_lines: Vec<Line> = bindings
+            .iter()
+            .filter(|binding| binding.context == context && is_active(binding))
+            .filter_map(|binding| {
+                let key = binding.key.to_string();
+                let option = binding.option.unwrap_or_default();
+                let matches = [key.as_str(), binding.action, option, context.title()]
+                    .iter()
+                    .any(|text| find_ignore_case(text, search).is_some());
+
+                matches.then(|| {
+                    Line::from(vec![
+                        Span::from(format!("{key:>9}")).bold(),
+                        Span::from(format!("  {}", binding.action)),
+                        Span::from(if option.is_empty() {
+                            String::new()
+                        } else {
+                            format!("  ({option})")
+                        })
+                        .dark_gray(),
+                    ])
+                })
+            })
+            .collect();
+
+        if context_lines.is_empty() {
+            continue;
+        }
+        if !lines.is_empty() {
+            lines.push(Line::default());
+        }
+        lines.push(Line::from(context.title()).yellow().bold());
+        lines.extend(context_lines);
+    }
+
+    lines
+}
+
+// Full screen list of the key bindings, the scroll is clamped to the content
+pub fn render_key_bindings(
+    config: &Config,
+    search: &Input,
+    scroll: &mut u16,
+    frame: &mut Frame,
+    area: Rect,
+) {
+    let area = area.centered(Constraint::Max(80), Constraint::Fill(1));
+    let lines = key_bindings_lines(&config.bindings(), search.value());
+
+    let block = Block::bordered()
+        .title(" Key bindings ")
+        .title_bottom(
+            Line::from(format!(" Search: {} ", search.value()))
+                .yellow()
+                .bold(),
+        )
+        .title_style(Style::default().bold())
+        .padding(Padding::horizontal(1))
+        .border_type(BorderType::Thick)
+        .border_style(Style::default().green());
+
+    let visible_lines = block.inner(area).height;
+    let max_scroll = u16::try_from(lines.len())
+        .unwrap_or(u16::MAX)
+        .saturating_sub(visible_lines);
+    *scroll = (*scroll).min(max_scroll);
+
+    let content = if lines.is_empty() {
+        Paragraph::new("No matching key binding").centered()
+    } else {
+        Paragraph::new(lines).scroll((*scroll, 0))
+    };
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(content.block(block), area);
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -210,7 +558,22 @@ mod tests {
             FocusedBlock::EnterPinCode,
             FocusedBlock::EnterPasskey,
             FocusedBlock::DisplayPinCode,
-            FocusedBlock::DisplayPasskey
+            FocusedBlock::DisplayPasskey,
+            FocusedBlock::ReceiveFile,
+            FocusedBlock::Search,
+            FocusedBlock::KeyBindings,
+            FocusedBlock::CommandPalette,
+            FocusedBlock::DeviceDetails,
+            FocusedBlock::Media,
+            FocusedBlock::FilePicker,
+            FocusedBlock::PhoneData,
+            FocusedBlock::SerialTerminal,
+            FocusedBlock::Advertising,
+            FocusedBlock::GattServer,
+            FocusedBlock::Beacons,
+            FocusedBlock::Sensors,
+            FocusedBlock::Recorder,
+            FocusedBlock::AddDevice
         )]
         focused_block: FocusedBlock,
         #[values(80, 81, 120, 121)] width: u16,
@@ -231,4 +594,25 @@ mod tests {
         let snapshot_name = format!("{:?}-{}", focused_block, width);
         assert_snapshot!(snapshot_name, terminal.backend());
     }
+
+    #[test]
+    fn key_bindings_follow_the_config() {
+        let (config, _) =
+            crate::config::check::parse("[paired_device]\nrename = \"R\"\ntoggle_trust = \"u\"\n");
+        let lines: Vec<String> = key_bindings_lines(&config.bindings(), "rename")
+            .iter()
+            .map(ToString::to_string)
+            .collect();
+        assert_eq!(
+            lines,
+            vec![
+                "Paired devices",
+                "        R  Rename  (paired_device.rename)"
+            ]
+        );
+
+        // `toggle_trust` is shadowed by `unpair` so it is never triggered
+        let lines = key_bindings_lines(&config.bindings(), "trust/untrust");
+        assert!(lines.is_empty());
+    }
 }
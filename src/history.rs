@@ -0,0 +1,655 @@
+use std::{
+    collections::{HashMap, HashSet, VecDeque},
+    fs::OpenOptions,
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::Context;
+use bluer::Address;
+use serde::{Deserialize, Serialize};
+
+use crate::{app::AppResult, storage};
+
+const HISTORY_FILE: &str = "history.jsonl";
+
+// Sightings are recorded at most once per interval for each device
+const SIGHTING_INTERVAL: u64 = 60;
+
+// Number of events kept in memory for each device
+const RECENT_EVENTS: usize = 20;
+// Battery readings kept per device for the chart and the time remaining estimate
+const BATTERY_SAMPLES: usize = 500;
+
+// Sightings and battery readings older than this are dropped from the file when it is loaded, but
+// the latest ones of the devices that were connected or paired
+const RETENTION: u64 = 30 * 86400;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum EventKind {
+    Connected,
+    Disconnected,
+    Paired,
+    Unpaired,
+    Seen { rssi: i16 },
+    Battery { percentage: u8 },
+    // bluetui exited while the device was connected
+    Stopped,
+}
+
+impl EventKind {
+    pub fn description(&self) -> String {
+        match self {
+            EventKind::Connected => "Connected".to_string(),
+            EventKind::Disconnected => "Disconnected".to_string(),
+            EventKind::Paired => "Paired".to_string(),
+            EventKind::Unpaired => "Unpaired".to_string(),
+            EventKind::Seen { rssi } => format!("Seen at {rssi} dBm"),
+            EventKind::Battery { percentage } => format!("Battery at {percentage}%"),
+            EventKind::Stopped => "bluetui stopped".to_string(),
+        }
+    }
+}
+
+// One line of the history file
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Record {
+    // Seconds since the unix epoch
+    pub time: u64,
+    pub address: Address,
+    #[serde(flatten)]
+    pub kind: EventKind,
+}
+
+// What is known about a device, built from its records
+#[derive(Debug, Clone, Default)]
+pub struct DeviceHistory {
+    pub connected_since: Option<u64>,
+    // Total time of the finished connections
+    pub connected_time: u64,
+    pub last_disconnected: Option<u64>,
+    // Time of the last record, see `last_seen`
+    pub last_event: Option<u64>,
+    pub last_rssi: Option<i16>,
+    pub last_battery: Option<u8>,
+    pub is_paired: Option<bool>,
+    pub last_sighting: Option<u64>,
+    pub recent: VecDeque<Record>,
+    // (time, percentage), oldest first
+    pub battery: VecDeque<(u64, u8)>,
+}
+
+impl DeviceHistory {
+    fn apply(&mut self, record: &Record) {
+        self.last_event = Some(record.time);
+        match record.kind {
+            EventKind::Connected => {
+                self.connected_since.get_or_insert(record.time);
+            }
+            EventKind::Disconnected | EventKind::Stopped => {
+                if let Some(since) = self.connected_since.take() {
+                    self.connected_time += record.time.saturating_sub(since);
+                }
+                self.last_disconnected = Some(record.time);
+            }
+            EventKind::Paired => self.is_paired = Some(true),
+            EventKind::Unpaired => self.is_paired = Some(false),
+            EventKind::Seen { rssi } => {
+                self.last_rssi = Some(rssi);
+                self.last_sighting = Some(record.time);
+            }
+            EventKind::Battery { percentage } => {
+                self.last_battery = Some(percentage);
+                if self.battery.len() == BATTERY_SAMPLES {
+                    self.battery.pop_front();
+                }
+                self.battery.push_back((record.time, percentage));
+            }
+        }
+
+        if self.recent.len() == RECENT_EVENTS {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(record.clone());
+    }
+
+    // "now" while connected, the end of the last connection otherwise
+    pub fn last_connected(&self, now: u64) -> Option<u64> {
+        if self.connected_since.is_some() {
+            Some(now)
+        } else {
+            self.last_disconnected
+        }
+    }
+
+    // "now" while connected, nothing is recorded while a connection lasts
+    pub fn last_seen(&self, now: u64) -> Option<u64> {
+        if self.connected_since.is_some() {
+            Some(now)
+        } else {
+            self.last_event
+        }
+    }
+
+    pub fn total_connected_time(&self, now: u64) -> u64 {
+        self.connected_time
+            + self
+                .connected_since
+                .map_or(0, |since| now.saturating_sub(since))
+    }
+}
+
+// State of a device as seen on one refresh
+#[derive(Debug, Clone)]
+pub struct Observation {
+    pub address: Address,
+    pub adapter: String,
+    pub is_paired: bool,
+    pub is_connected: bool,
+    pub rssi: Option<i16>,
+    pub battery: Option<u8>,
+}
+
+#[derive(Debug, Default)]
+pub struct History {
+    // `None` keeps the history in memory only
+    file_path: Option<PathBuf>,
+    pub devices: HashMap<Address, DeviceHistory>,
+    // Devices seen on the previous refresh
+    observed: HashMap<Address, Observation>,
+}
+
+impl History {
+    pub fn load() -> AppResult<Self> {
+        Self::load_from(&storage::data_dir()?)
+    }
+
+    pub fn load_from(data_dir: &Path) -> AppResult<Self> {
+        let file_path = data_dir.join(HISTORY_FILE);
+        let mut history = Self {
+            file_path: Some(file_path.clone()),
+            ..Self::default()
+        };
+
+        let records = read_records(&file_path)?;
+        let count = records.len();
+        let records = compact(records, storage::now());
+        if records.len() < count {
+            let content: String = records
+                .iter()
+                .map(|record| Ok(serde_json::to_string(record)? + "\n"))
+                .collect::<AppResult<_>>()?;
+            // The link is kept if the file is one
+            let file_path = std::fs::canonicalize(&file_path).unwrap_or(file_path);
+            storage::write(&file_path, &content)?;
+        }
+
+        for record in records {
+            history
+                .devices
+                .entry(record.address)
+                .or_default()
+                .apply(&record);
+        }
+
+        Ok(history)
+    }
+
+    pub fn device(&self, address: Address) -> Option<&DeviceHistory> {
+        self.devices.get(&address)
+    }
+
+    fn record(&mut self, address: Address, kind: EventKind, time: u64) -> AppResult<()> {
+        let record = Record {
+            time,
+            address,
+            kind,
+        };
+        self.devices.entry(address).or_default().apply(&record);
+
+        let Some(file_path) = &self.file_path else {
+            return Ok(());
+        };
+        if let Some(parent) = file_path.parent()
+            && !parent.exists()
+        {
+            std::fs::create_dir_all(parent).context("unable to create parent dir(s)")?;
+        }
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(file_path)
+            .context("unable to open history file")?;
+        writeln!(file, "{}", serde_json::to_string(&record)?)
+            .context("unable to write history file")?;
+        Ok(())
+    }
+
+    // Record what changed since the previous observations. The first observation of a device
+    // is compared with its history, so that a connection that ended while bluetui was not
+    // running is closed. `adapters` are the adapters that are still present.
+    pub fn observe(
+        &mut self,
+        observations: &[Observation],
+        adapters: &[String],
+        time: u64,
+    ) -> AppResult<()> {
+        let mut current: HashMap<Address, Observation> = HashMap::new();
+        for observation in observations {
+            // A device known by several adapters is connected if any of them is connected
+            current
+                .entry(observation.address)
+                .and_modify(|o| {
+                    o.is_paired |= observation.is_paired;
+                    o.is_connected |= observation.is_connected;
+                    o.rssi = o.rssi.or(observation.rssi);
+                    o.battery = o.battery.or(observation.battery);
+                })
+                .or_insert_with(|| observation.clone());
+        }
+
+        let mut events = Vec::new();
+        for observation in current.values() {
+            let address = observation.address;
+            let known = self.devices.get(&address);
+            let previous = self.observed.get(&address);
+
+            let was_paired = previous.map_or(
+                known
+                    .and_then(|h| h.is_paired)
+                    .unwrap_or(observation.is_paired),
+                |p| p.is_paired,
+            );
+            if observation.is_paired != was_paired {
+                events.push((
+                    address,
+                    if observation.is_paired {
+                        EventKind::Paired
+                    } else {
+                        EventKind::Unpaired
+                    },
+                ));
+            }
+
+            let was_connected = previous
+                .map_or(known.is_some_and(|h| h.connected_since.is_some()), |p| {
+                    p.is_connected
+                });
+            if observation.is_connected != was_connected {
+                events.push((
+                    address,
+                    if observation.is_connected {
+                        EventKind::Connected
+                    } else {
+                        EventKind::Disconnected
+                    },
+                ));
+            }
+
+            // The devices nearby come and go, often with a new random address each time
+            if observation.is_paired
+                && let Some(rssi) = observation.rssi
+                && known
+                    .and_then(|h| h.last_sighting)
+                    .is_none_or(|last| time.saturating_sub(last) >= SIGHTING_INTERVAL)
+            {
+                events.push((address, EventKind::Seen { rssi }));
+            }
+
+            if let Some(percentage) = observation.battery
+                && known.and_then(|h| h.last_battery) != Some(percentage)
+            {
+                events.push((address, EventKind::Battery { percentage }));
+            }
+        }
+
+        // Paired devices that are gone have been removed, unless their adapter is gone
+        for previous in self.observed.values() {
+            if current.contains_key(&previous.address) {
+                continue;
+            }
+            if previous.is_connected {
+                events.push((previous.address, EventKind::Disconnected));
+            }
+            if previous.is_paired && adapters.contains(&previous.adapter) {
+                events.push((previous.address, EventKind::Unpaired));
+            }
+        }
+
+        self.observed = current;
+
+        for (address, kind) in events {
+            self.record(address, kind, time)?;
+        }
+        Ok(())
+    }
+
+    // Close the connections that are still open when bluetui exits
+    pub fn stop(&mut self, time: u64) -> AppResult<()> {
+        let connected: Vec<Address> = self
+            .observed
+            .values()
+            .filter(|o| o.is_connected)
+            .map(|o| o.address)
+            .collect();
+        for address in connected {
+            self.record(address, EventKind::Stopped, time)?;
+        }
+        Ok(())
+    }
+}
+
+pub fn read_records(file_path: &Path) -> AppResult<Vec<Record>> {
+    if !file_path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = std::fs::read_to_string(file_path).context("unable to read history file")?;
+
+    // A line that can not be parsed, e.g. cut by a crash, is skipped
+    Ok(content
+        .lines()
+        .filter_map(|line| serde_json::from_str(line).ok())
+        .collect())
+}
+
+// Keeps the connections and the pairings, that make the totals, and the sightings and battery
+// readings of the retention period. The latest sighting and reading of the devices that were
+// connected or paired are kept too, the devices that were only seen expire.
+fn compact(records: Vec<Record>, now: u64) -> Vec<Record> {
+    let mut latest: HashMap<(Address, bool), usize> = HashMap::new();
+    let mut known: HashSet<Address> = HashSet::new();
+    for (i, record) in records.iter().enumerate() {
+        match record.kind {
+            EventKind::Seen { .. } => {
+                latest.insert((record.address, true), i);
+            }
+            EventKind::Battery { .. } => {
+                latest.insert((record.address, false), i);
+            }
+            _ => {
+                known.insert(record.address);
+            }
+        }
+    }
+    let latest: HashSet<usize> = latest
+        .into_iter()
+        .filter(|((address, _), _)| known.contains(address))
+        .map(|(_, i)| i)
+        .collect();
+
+    let oldest = now.saturating_sub(RETENTION);
+    records
+        .into_iter()
+        .enumerate()
+        .filter(|(i, record)| {
+            record.time >= oldest
+                || !matches!(
+                    record.kind,
+                    EventKind::Seen { .. } | EventKind::Battery { .. }
+                )
+                || latest.contains(i)
+        })
+        .map(|(_, record)| record)
+        .collect()
+}
+
+pub fn file_path() -> AppResult<PathBuf> {
+    Ok(storage::data_dir()?.join(HISTORY_FILE))
+}
+
+// e.g. "5m ago"
+pub fn format_ago(time: u64, now: u64) -> String {
+    let elapsed = now.saturating_sub(time);
+    if elapsed < 60 {
+        "now".to_string()
+    } else {
+        format!("{} ago", format_duration(elapsed))
+    }
+}
+
+// The two most significant units, e.g. "2h 05m"
+pub fn format_duration(seconds: u64) -> String {
+    let (days, hours, minutes) = (seconds / 86400, seconds / 3600 % 24, seconds / 60 % 60);
+    if days > 0 {
+        format!("{days}d {hours:02}h")
+    } else if hours > 0 {
+        format!("{hours}h {minutes:02}m")
+    } else if minutes > 0 {
+        format!("{minutes}m")
+    } else {
+        format!("{seconds}s")
+    }
+}
+
+// e.g. "2026-10-18 14:03:12 UTC"
+pub fn format_time(time: u64) -> String {
+    let days = time / 86400;
+    let seconds = time % 86400;
+
+    // Civil date from the number of days since the epoch, see
+    // https://howardhinnant.github.io/date_algorithms.html#civil_from_days
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z - era * 146_097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{year:04}-{month:02}-{day:02} {:02}:{:02}:{:02} UTC",
+        seconds / 3600,
+        seconds / 60 % 60,
+        seconds % 60
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observation(is_connected: bool, rssi: Option<i16>) -> Observation {
+        Observation {
+            address: Address::new(*b"keyboa"),
+            adapter: "hci0".to_string(),
+            is_paired: true,
+            is_connected,
+            rssi,
+            battery: None,
+        }
+    }
+
+    #[test]
+    fn connections_are_recorded() {
+        let address = Address::new(*b"keyboa");
+        let adapters = vec!["hci0".to_string()];
+        let mut history = History::default();
+
+        history
+            .observe(&[observation(false, None)], &adapters, 0)
+            .unwrap();
+        history
+            .observe(&[observation(true, None)], &adapters, 100)
+            .unwrap();
+        history
+            .observe(&[observation(true, Some(-60))], &adapters, 200)
+            .unwrap();
+        history
+            .observe(&[observation(false, Some(-70))], &adapters, 250)
+            .unwrap();
+        history
+            .observe(&[observation(true, None)], &adapters, 1000)
+            .unwrap();
+
+        let device = history.device(address).unwrap();
+        let kinds: Vec<_> = device.recent.iter().map(|r| r.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            vec![
+                EventKind::Connected,
+                EventKind::Seen { rssi: -60 },
+                EventKind::Disconnected,
+                EventKind::Connected,
+            ]
+        );
+        assert_eq!(device.last_disconnected, Some(250));
+        assert_eq!(device.total_connected_time(1100), 150 + 100);
+        assert_eq!(device.last_connected(1100), Some(1100));
+        assert_eq!(device.last_seen(1100), Some(1100));
+
+        history.stop(1200).unwrap();
+        let device = history.device(address).unwrap();
+        assert_eq!(device.last_connected(2000), Some(1200));
+        assert_eq!(device.last_seen(2000), Some(1200));
+        assert_eq!(device.total_connected_time(2000), 150 + 200);
+    }
+
+    #[test]
+    fn unpaired_devices_are_recorded() {
+        let address = Address::new(*b"keyboa");
+        let adapters = vec!["hci0".to_string()];
+        let mut history = History::default();
+
+        history
+            .observe(&[observation(true, None)], &adapters, 0)
+            .unwrap();
+        history.observe(&[], &adapters, 10).unwrap();
+
+        let kinds: Vec<_> = history
+            .device(address)
+            .unwrap()
+            .recent
+            .iter()
+            .map(|r| r.kind.clone())
+            .collect();
+        assert_eq!(
+            kinds,
+            vec![
+                EventKind::Connected,
+                EventKind::Disconnected,
+                EventKind::Unpaired
+            ]
+        );
+    }
+
+    #[test]
+    fn unplugged_adapters_do_not_unpair() {
+        let address = Address::new(*b"keyboa");
+        let mut history = History::default();
+
+        history
+            .observe(&[observation(true, None)], &["hci0".to_string()], 0)
+            .unwrap();
+        history.observe(&[], &[], 10).unwrap();
+
+        let device = history.device(address).unwrap();
+        assert_eq!(device.recent.back().unwrap().kind, EventKind::Disconnected);
+        assert_eq!(device.is_paired, None);
+    }
+
+    #[test]
+    fn nearby_devices_are_not_recorded() {
+        let adapters = vec!["hci0".to_string()];
+        let mut history = History::default();
+        let passer = Observation {
+            address: Address::new(*b"passer"),
+            is_paired: false,
+            ..observation(false, Some(-80))
+        };
+
+        history
+            .observe(&[passer, observation(false, Some(-60))], &adapters, 0)
+            .unwrap();
+
+        assert!(history.device(Address::new(*b"passer")).is_none());
+        assert_eq!(
+            history.device(Address::new(*b"keyboa")).unwrap().last_rssi,
+            Some(-60)
+        );
+    }
+
+    #[test]
+    fn old_sightings_are_dropped_on_load() {
+        let dir = std::env::temp_dir().join(format!("bluetui-history-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+
+        let now = storage::now();
+        let old = now - RETENTION - 3600;
+        let record = |time, kind| Record {
+            time,
+            address: Address::new(*b"keyboa"),
+            kind,
+        };
+        let records = [
+            record(old, EventKind::Connected),
+            record(old + 1, EventKind::Seen { rssi: -60 }),
+            record(old + 2, EventKind::Battery { percentage: 80 }),
+            record(old + 3, EventKind::Seen { rssi: -61 }),
+            record(old + 4, EventKind::Battery { percentage: 79 }),
+            record(old + 5, EventKind::Disconnected),
+            record(now, EventKind::Battery { percentage: 100 }),
+            Record {
+                time: old,
+                address: Address::new(*b"passer"),
+                kind: EventKind::Seen { rssi: -90 },
+            },
+        ];
+        let content: String = records
+            .iter()
+            .map(|record| serde_json::to_string(record).unwrap() + "\n")
+            .collect();
+        std::fs::write(dir.join(HISTORY_FILE), content).unwrap();
+
+        let history = History::load_from(&dir).unwrap();
+        let kept = read_records(&dir.join(HISTORY_FILE)).unwrap();
+        assert_eq!(
+            kept,
+            [
+                records[0].clone(),
+                records[3].clone(),
+                records[5].clone(),
+                records[6].clone()
+            ]
+        );
+
+        assert!(history.device(Address::new(*b"passer")).is_none());
+        let device = history.device(Address::new(*b"keyboa")).unwrap();
+        assert_eq!(device.total_connected_time(now), 5);
+        assert_eq!(device.last_rssi, Some(-61));
+        assert_eq!(device.last_battery, Some(100));
+
+        // Nothing to drop the second time
+        History::load_from(&dir).unwrap();
+        assert_eq!(read_records(&dir.join(HISTORY_FILE)).unwrap(), kept);
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn records_roundtrip() {
+        let record = Record {
+            time: 1,
+            address: Address::new(*b"keyboa"),
+            kind: EventKind::Battery { percentage: 42 },
+        };
+        let line = serde_json::to_string(&record).unwrap();
+        assert_eq!(
+            line,
+            r#"{"time":1,"address":"6B:65:79:62:6F:61","event":"battery","percentage":42}"#
+        );
+        assert_eq!(serde_json::from_str::<Record>(&line).unwrap(), record);
+    }
+
+    #[test]
+    fn formatting() {
+        assert_eq!(format_time(0), "1970-01-01 00:00:00 UTC");
+        assert_eq!(format_time(1_792_332_192), "2026-10-18 14:03:12 UTC");
+        assert_eq!(format_duration(7500), "2h 05m");
+        assert_eq!(format_duration(90000), "1d 01h");
+        assert_eq!(format_ago(100, 130), "now");
+        assert_eq!(format_ago(100, 400), "5m ago");
+    }
+}
@@ -1,17 +1,38 @@
+pub mod add_device;
+pub mod advertising;
 pub mod agent;
 mod alias;
 pub mod app;
+pub mod assigned_numbers;
+pub mod battery;
+pub mod beacon;
 pub mod bluetooth;
+pub mod bus;
 pub mod cli;
 pub mod config;
+mod details;
 pub mod event;
 pub mod favorite;
+pub mod filter;
+pub mod group;
 pub mod handler;
 mod help;
+pub mod history;
+pub mod media;
+pub mod network;
 pub mod notification;
+pub mod obex;
+pub mod oui;
+pub mod palette;
+pub mod peripheral;
+pub mod picker;
+pub mod recorder;
 pub mod requests;
 pub mod rfkill;
+pub mod sensors;
+pub mod serial;
 pub mod spinner;
+pub mod storage;
 pub mod string_ref;
 pub mod tui;
 pub mod ui;
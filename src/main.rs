@@ -1,15 +1,19 @@
 use bluetui::{
     app::{App, AppResult},
     cli,
-    config::Config,
+    config::{self, Config},
     event::{Event, EventHandler},
-    handler::handle_key_events,
-    rfkill,
+    favorite::Favorites,
+    handler::{handle_key_events, handle_mouse_events},
+    history,
+    notification::{Notification, NotificationLevel},
+    recorder::{self, RecordFormat, Recorder, RecordingState},
+    rfkill, storage,
     tui::Tui,
 };
 use clap::Parser;
 use ratatui::{Terminal, backend::CrosstermBackend};
-use std::{io, process::exit, sync::Arc};
+use std::{io, process::exit, sync::Arc, time::Duration};
 
 #[tokio::main(flavor = "current_thread")]
 async fn main() -> AppResult<()> {
@@ -24,9 +28,103 @@ async fn main() -> AppResult<()> {
         }
     });
 
+    match args.command {
+        Some(cli::Command::Config { command }) => match command {
+            cli::ConfigCommand::Check => {
+                exit(config::check::run(&Config::path(config_file_path)));
+            }
+            cli::ConfigCommand::Dump => {
+                let (config, diagnostics) = Config::load(config_file_path);
+                if !diagnostics.is_empty() {
+                    eprintln!(
+                        "The config has {} problem(s), run `bluetui config check` for details",
+                        diagnostics.len()
+                    );
+                }
+                print!("{}", config.dump()?);
+                return Ok(());
+            }
+        },
+        Some(cli::Command::Favorites { command }) => {
+            let mut favorites = Favorites::load()?;
+            match command {
+                cli::FavoritesCommand::List => {
+                    for (position, favorite) in favorites.entries.iter().enumerate() {
+                        println!(
+                            "{position}\t{}\t{}\t{}",
+                            favorite.address,
+                            favorite.alias.as_deref().unwrap_or("-"),
+                            favorite.adapter.as_deref().unwrap_or("-"),
+                        );
+                    }
+                }
+                cli::FavoritesCommand::Remove { address } => {
+                    if !favorites.remove(address) {
+                        eprintln!("{address} is not a favorite device");
+                        exit(1);
+                    }
+                    favorites.save()?;
+                }
+                cli::FavoritesCommand::Move { address, position } => {
+                    if !favorites.move_to(address, position) {
+                        eprintln!("{address} is not a favorite device");
+                        exit(1);
+                    }
+                    favorites.save()?;
+                }
+            }
+            return Ok(());
+        }
+        Some(cli::Command::History { address }) => {
+            for record in history::read_records(&history::file_path()?)?
+                .iter()
+                .filter(|record| address.is_none_or(|address| record.address == address))
+            {
+                println!(
+                    "{}\t{}\t{}",
+                    history::format_time(record.time),
+                    record.address,
+                    record.kind.description()
+                );
+            }
+            return Ok(());
+        }
+        Some(cli::Command::Record {
+            address,
+            characteristics,
+            output,
+            format,
+            raw,
+            duration,
+        }) => {
+            let recorder = match output {
+                Some(path) => Recorder::create(
+                    &path,
+                    format.unwrap_or_else(|| RecordFormat::from_path(&path)),
+                    !raw,
+                )?,
+                None => Recorder::new(recorder::stdout(), format.unwrap_or_default(), !raw)?,
+            };
+            if let Err(e) = recorder::run(
+                address,
+                &characteristics,
+                recorder,
+                duration.map(Duration::from_secs),
+            )
+            .await
+            {
+                eprintln!("{e}");
+                exit(1);
+            }
+            return Ok(());
+        }
+        None => {}
+    }
+
     rfkill::check()?;
 
-    let config = Arc::new(Config::new(config_file_path));
+    let (config, diagnostics) = Config::load(config_file_path);
+    let config = Arc::new(config);
 
     let backend = CrosstermBackend::new(io::stdout());
     let terminal = Terminal::new(backend)?;
@@ -40,6 +138,18 @@ async fn main() -> AppResult<()> {
         return Ok(());
     };
 
+    if !diagnostics.is_empty() {
+        Notification::send(
+            format!(
+                "The config has {} problem(s), run `bluetui config check` for details",
+                diagnostics.len()
+            )
+            .into(),
+            NotificationLevel::Warning,
+            tui.events.sender.clone(),
+        )?;
+    }
+
     while app.running {
         tui.draw(&mut app)?;
         match tui.events.next().await? {
@@ -53,6 +163,15 @@ async fn main() -> AppResult<()> {
                 )
                 .await?;
             }
+            Event::Mouse(mouse_event) => {
+                handle_mouse_events(
+                    mouse_event,
+                    &mut app,
+                    tui.events.sender.clone(),
+                    config.clone(),
+                )
+                .await?;
+            }
             Event::Notification(notification) => {
                 app.notifications.push(notification);
             }
@@ -70,15 +189,7 @@ async fn main() -> AppResult<()> {
             }
 
             Event::ToggleFavorite(address) => {
-                if let Some(pos) = app
-                    .favorite_devices
-                    .iter()
-                    .position(|favorite| *favorite == address)
-                {
-                    app.favorite_devices.swap_remove(pos);
-                } else {
-                    app.favorite_devices.push(address);
-                }
+                app.toggle_favorite(address, tui.events.sender.clone());
             }
 
             Event::RequestConfirmation(request) => {
@@ -144,7 +255,126 @@ async fn main() -> AppResult<()> {
                 app.focused_block = bluetui::app::FocusedBlock::PairedDevices;
             }
 
-            Event::Mouse(_) | Event::Resize(_, _) => {}
+            Event::RequestReceiveFile(request) => {
+                app.requests.init_receive_file(request);
+                app.focused_block = bluetui::app::FocusedBlock::ReceiveFile;
+            }
+
+            Event::ReceiveFileSubmitted => {
+                app.requests.receive_file = None;
+                app.focused_block = bluetui::app::FocusedBlock::PairedDevices;
+            }
+
+            Event::TransferStarted(transfer) => {
+                app.transfers.push(transfer);
+            }
+
+            Event::TransferUpdated(update) => {
+                app.update_transfer(&update);
+            }
+
+            Event::PhoneFolders(address, folders) => {
+                if let Some(phone_data) = app
+                    .phone_data
+                    .as_mut()
+                    .filter(|phone_data| phone_data.device == address)
+                {
+                    phone_data.set_folders(folders);
+                }
+            }
+
+            Event::SerialConnected(address, writer) => {
+                if let Some(serial) = app.serial.as_mut().filter(|s| s.device == address) {
+                    serial.connected(writer);
+                }
+            }
+
+            Event::SerialReceived(address, bytes) => {
+                if let Some(serial) = app.serial.as_mut().filter(|s| s.device == address)
+                    && let Err(e) = serial.receive(&bytes)
+                {
+                    Notification::send(
+                        e.into(),
+                        NotificationLevel::Error,
+                        tui.events.sender.clone(),
+                    )?;
+                }
+            }
+
+            Event::GattActivity(activity) => {
+                if let Some(server) = &mut app.gatt_server {
+                    server.record(activity);
+                }
+            }
+
+            Event::SensorsFound(address, characteristics) => {
+                if let Some(sensors) = app.sensors.as_mut().filter(|s| s.device == address) {
+                    sensors.found(&characteristics);
+                }
+            }
+
+            Event::SensorValue(address, uuid, value) => {
+                if let Some(sensors) = app.sensors.as_mut().filter(|s| s.device == address) {
+                    sensors.update(&uuid, value, storage::now());
+                }
+            }
+
+            Event::SensorsFailed(address, reason) => {
+                if let Some(sensors) = app.sensors.as_mut().filter(|s| s.device == address) {
+                    sensors.failed(reason);
+                }
+            }
+
+            Event::RecordableCharacteristics(address, characteristics) => {
+                if let Some(picker) = app.recorder.as_mut().filter(|p| p.device == address) {
+                    picker.found(characteristics);
+                }
+            }
+
+            Event::RecorderFailed(address, reason) => {
+                if let Some(picker) = app.recorder.as_mut().filter(|p| p.device == address) {
+                    picker.failed(reason);
+                }
+            }
+
+            Event::RecordingState(address, state) => {
+                let name = app.device_name(address);
+                if let Some(recording) = app.recordings.iter_mut().find(|r| r.device == address)
+                    && let Some(message) = recording.update(state, &name)
+                {
+                    let level = if state == RecordingState::Disconnected {
+                        NotificationLevel::Warning
+                    } else {
+                        NotificationLevel::Info
+                    };
+                    Notification::send(message.into(), level, tui.events.sender.clone())?;
+                }
+            }
+
+            Event::RecordingFailed(address, reason) => {
+                if let Some(index) = app.recordings.iter().position(|r| r.device == address) {
+                    let recording = app.recordings.remove(index);
+                    Notification::send(
+                        format!(
+                            "Recording of {} stopped: {reason}, {} values in {}",
+                            app.device_name(address),
+                            recording.count(),
+                            recording.path.display()
+                        )
+                        .into(),
+                        NotificationLevel::Error,
+                        tui.events.sender.clone(),
+                    )?;
+                }
+            }
+
+            Event::SerialClosed(address, reason) => {
+                if let Some(serial) = app.serial.as_mut().filter(|s| s.device == address) {
+                    serial.closed(reason);
+                }
+            }
+
+            Event::Resize(_, _) => {}
         }
     }
 
@@ -0,0 +1,495 @@
+use std::collections::HashMap;
+
+use bluer::Address;
+use dbus::{
+    Path,
+    arg::{PropMap, RefArg},
+    nonblock::stdintf::org_freedesktop_dbus::Properties,
+};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Clear, LineGauge, Padding, Paragraph},
+};
+
+use crate::{
+    app::AppResult,
+    bus::{Bus, ManagedObjects},
+};
+
+const SERVICE: &str = "org.bluez";
+const PLAYER_INTERFACE: &str = "org.bluez.MediaPlayer1";
+const TRANSPORT_INTERFACE: &str = "org.bluez.MediaTransport1";
+// Volume of the transports goes from 0 to 127
+pub const MAX_VOLUME: u16 = 127;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PlayerCommand {
+    Play,
+    Pause,
+    Stop,
+    Next,
+    Previous,
+}
+
+impl PlayerCommand {
+    fn method(self) -> &'static str {
+        match self {
+            PlayerCommand::Play => "Play",
+            PlayerCommand::Pause => "Pause",
+            PlayerCommand::Stop => "Stop",
+            PlayerCommand::Next => "Next",
+            PlayerCommand::Previous => "Previous",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Track {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album: Option<String>,
+    // Milliseconds
+    pub duration: Option<u32>,
+}
+
+// A `org.bluez.MediaPlayer1` object, exposed by a device connected with AVRCP
+#[derive(Debug, Clone, PartialEq)]
+pub struct Player {
+    pub path: Path<'static>,
+    pub device: Address,
+    pub name: Option<String>,
+    // "playing", "stopped", "paused", "forward-seek", "reverse-seek" or "error"
+    pub status: String,
+    // Milliseconds
+    pub position: u32,
+    pub track: Track,
+}
+
+impl Player {
+    fn from_properties(path: Path<'static>, properties: &PropMap) -> Option<Self> {
+        let string = |name: &str| properties.get(name).and_then(|v| v.as_str());
+
+        let track = properties
+            .get("Track")
+            .map(|track| {
+                let metadata: HashMap<&str, &dyn RefArg> = track
+                    .0
+                    .as_iter()
+                    .map(|entries| {
+                        let entries: Vec<&dyn RefArg> = entries.collect();
+                        entries
+                            .chunks_exact(2)
+                            .filter_map(|entry| Some((entry[0].as_str()?, entry[1])))
+                            .collect()
+                    })
+                    .unwrap_or_default();
+                let text = |name: &str| {
+                    metadata
+                        .get(name)
+                        .and_then(|v| v.as_str())
+                        .map(String::from)
+                };
+
+                Track {
+                    title: text("Title"),
+                    artist: text("Artist"),
+                    album: text("Album"),
+                    duration: metadata.get("Duration").and_then(|v| number(*v)),
+                }
+            })
+            .unwrap_or_default();
+
+        Some(Self {
+            device: device_address(string("Device")?)?,
+            name: string("Name").map(String::from),
+            status: string("Status").unwrap_or("stopped").to_string(),
+            position: properties
+                .get("Position")
+                .and_then(|v| number(v))
+                .unwrap_or_default(),
+            track,
+            path,
+        })
+    }
+
+    pub fn is_playing(&self) -> bool {
+        self.status == "playing"
+    }
+}
+
+// A `org.bluez.MediaTransport1` object, the audio stream of a connected device
+#[derive(Debug, Clone, PartialEq)]
+pub struct Transport {
+    pub path: Path<'static>,
+    pub device: Address,
+    // "idle", "pending" or "active"
+    pub state: String,
+    pub codec: String,
+    // `None` when the device does not support absolute volume
+    pub volume: Option<u16>,
+}
+
+impl Transport {
+    fn from_properties(path: Path<'static>, properties: &PropMap) -> Option<Self> {
+        let device = properties.get("Device").and_then(|v| v.as_str())?;
+        let codec: u8 = number(properties.get("Codec")?)?;
+        let configuration: Vec<u8> = properties
+            .get("Configuration")
+            .and_then(|v| v.0.as_iter())
+            .map(|bytes| bytes.filter_map(number).collect())
+            .unwrap_or_default();
+
+        Some(Self {
+            device: device_address(device)?,
+            state: properties
+                .get("State")
+                .and_then(|v| v.as_str())
+                .unwrap_or("idle")
+                .to_string(),
+            codec: codec_name(codec, &configuration),
+            volume: properties.get("Volume").and_then(|v| number(v)),
+            path,
+        })
+    }
+
+    pub fn is_active(&self) -> bool {
+        self.state == "active"
+    }
+
+    // Volume in percent
+    pub fn volume_percentage(&self) -> Option<u8> {
+        self.volume
+            .map(|volume| (u32::from(volume.min(MAX_VOLUME)) * 100 / u32::from(MAX_VOLUME)) as u8)
+    }
+}
+
+// Volume of a transport from a percentage
+pub fn volume_from_percentage(percentage: u8) -> u16 {
+    ((u32::from(percentage.min(100)) * u32::from(MAX_VOLUME) + 50) / 100) as u16
+}
+
+// Name of an A2DP codec, the vendor codecs are identified by the start of their configuration
+fn codec_name(codec: u8, configuration: &[u8]) -> String {
+    match codec {
+        0x00 => "SBC".to_string(),
+        0x01 => "MP3".to_string(),
+        0x02 => "AAC".to_string(),
+        0x04 => "ATRAC".to_string(),
+        0x06 => "LC3".to_string(),
+        0xff if configuration.len() >= 6 => {
+            let vendor = u32::from_le_bytes([
+                configuration[0],
+                configuration[1],
+                configuration[2],
+                configuration[3],
+            ]);
+            let id = u16::from_le_bytes([configuration[4], configuration[5]]);
+            match (vendor, id) {
+                (0x004f, 0x0001) => "aptX".to_string(),
+                (0x00d7, 0x0024) => "aptX HD".to_string(),
+                (0x000a, 0x0002) => "aptX LL".to_string(),
+                (0x012d, 0x00aa) => "LDAC".to_string(),
+                (0x05f1, 0x1005) => "Opus".to_string(),
+                _ => format!("Vendor {vendor:#06x}:{id:#06x}"),
+            }
+        }
+        _ => format!("Unknown ({codec:#04x})"),
+    }
+}
+
+fn number<T: TryFrom<u64>>(value: &dyn RefArg) -> Option<T> {
+    value.as_u64().and_then(|v| T::try_from(v).ok())
+}
+
+// "/org/bluez/hci0/dev_AA_BB_CC_DD_EE_FF" -> AA:BB:CC:DD:EE:FF
+pub fn device_address(path: &str) -> Option<Address> {
+    path.rsplit('/')
+        .next()?
+        .strip_prefix("dev_")?
+        .replace('_', ":")
+        .parse()
+        .ok()
+}
+
+// The media players and the transports of all the devices
+pub fn from_objects(objects: &ManagedObjects) -> (Vec<Player>, Vec<Transport>) {
+    let mut players = Vec::new();
+    let mut transports = Vec::new();
+    for (path, interfaces) in objects {
+        if let Some(properties) = interfaces.get(PLAYER_INTERFACE) {
+            players.extend(Player::from_properties(path.clone(), properties));
+        }
+        if let Some(properties) = interfaces.get(TRANSPORT_INTERFACE) {
+            transports.extend(Transport::from_properties(path.clone(), properties));
+        }
+    }
+    players.sort_by(|a, b| a.path.cmp(&b.path));
+    transports.sort_by(|a, b| a.path.cmp(&b.path));
+    (players, transports)
+}
+
+// Media interfaces of BlueZ, bluer does not support them
+#[derive(Debug, Clone)]
+pub struct MediaBus {
+    bus: Bus,
+}
+
+impl MediaBus {
+    pub fn new(bus: Bus) -> Self {
+        Self { bus }
+    }
+
+    pub async fn send(&self, player: &Path<'static>, command: PlayerCommand) -> AppResult<()> {
+        let proxy = self.bus.proxy(SERVICE, player.clone());
+        proxy
+            .method_call::<(), _, _, _>(PLAYER_INTERFACE, command.method(), ())
+            .await?;
+        Ok(())
+    }
+
+    pub async fn set_volume(&self, transport: &Path<'static>, volume: u16) -> AppResult<()> {
+        let proxy = self.bus.proxy(SERVICE, transport.clone());
+        proxy
+            .set(TRANSPORT_INTERFACE, "Volume", volume.min(MAX_VOLUME))
+            .await?;
+        Ok(())
+    }
+}
+
+// "m:ss" from milliseconds
+fn format_position(milliseconds: u32) -> String {
+    let seconds = milliseconds / 1000;
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+pub fn render_media(device_name: &str, player: Option<&Player>, frame: &mut Frame, area: Rect) {
+    let area = area.centered(Constraint::Max(60), Constraint::Length(9));
+
+    let block = Block::bordered()
+        .title(format!(" {device_name} "))
+        .title_style(Style::default().bold())
+        .padding(Padding::horizontal(1))
+        .border_type(BorderType::Thick)
+        .border_style(Style::default().green());
+    let inner = block.inner(area);
+
+    frame.render_widget(Clear, area);
+    frame.render_widget(block, area);
+
+    let Some(player) = player else {
+        frame.render_widget(
+            Paragraph::new("No media player on this device").dark_gray(),
+            inner,
+        );
+        return;
+    };
+
+    let [track_area, _, position_area] = Layout::vertical([
+        Constraint::Length(4),
+        Constraint::Length(1),
+        Constraint::Length(1),
+    ])
+    .areas(inner);
+
+    let track = &player.track;
+    let unknown = || "Unknown".to_string();
+    let status = match player.status.as_str() {
+        "playing" => "󰐊 Playing",
+        "paused" => "󰏤 Paused",
+        "stopped" => "󰓛 Stopped",
+        "forward-seek" => "󰒭 Seeking",
+        "reverse-seek" => "󰒮 Seeking",
+        _ => "Error",
+    };
+    frame.render_widget(
+        Paragraph::new(vec![
+            Line::from(vec![
+                Span::from(status).yellow().bold(),
+                Span::from(
+                    player
+                        .name
+                        .as_ref()
+                        .map(|name| format!("  {name}"))
+                        .unwrap_or_default(),
+                )
+                .dark_gray(),
+            ]),
+            Line::from(track.title.clone().unwrap_or_else(unknown)).bold(),
+            Line::from(track.artist.clone().unwrap_or_else(unknown)),
+            Line::from(track.album.clone().unwrap_or_else(unknown)).dark_gray(),
+        ]),
+        track_area,
+    );
+
+    let (ratio, label) = match track.duration {
+        Some(duration) if duration > 0 => (
+            (f64::from(player.position) / f64::from(duration)).clamp(0.0, 1.0),
+            format!(
+                "{} / {}",
+                format_position(player.position),
+                format_position(duration)
+            ),
+        ),
+        _ => (0.0, format_position(player.position)),
+    };
+    frame.render_widget(
+        LineGauge::default()
+            .ratio(ratio)
+            .label(label)
+            .filled_style(Style::default().green()),
+        position_area,
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use dbus::arg::Variant;
+    use dbus_crossroads::Crossroads;
+
+    use super::*;
+    use crate::bus::tests::PrivateBus;
+
+    struct StandInPlayer {
+        status: String,
+    }
+
+    fn stand_in_bluez() -> Crossroads {
+        let mut crossroads = Crossroads::new();
+        let player = crossroads.register(PLAYER_INTERFACE, |b| {
+            b.property("Device").get(|_, _: &mut StandInPlayer| {
+                Ok(Path::from("/org/bluez/hci0/dev_AA_BB_CC_DD_EE_FF"))
+            });
+            b.property("Name")
+                .get(|_, _: &mut StandInPlayer| Ok("Music".to_string()));
+            b.property("Status")
+                .get(|_, player: &mut StandInPlayer| Ok(player.status.clone()));
+            b.property("Position")
+                .get(|_, _: &mut StandInPlayer| Ok(61_000_u32));
+            b.property("Track").get(|_, _: &mut StandInPlayer| {
+                let mut track = PropMap::new();
+                track.insert("Title".into(), Variant(Box::new("Song".to_string())));
+                track.insert("Artist".into(), Variant(Box::new("Band".to_string())));
+                track.insert("Album".into(), Variant(Box::new("Record".to_string())));
+                track.insert("Duration".into(), Variant(Box::new(180_000_u32)));
+                Ok(track)
+            });
+            for (method, status) in [
+                ("Play", "playing"),
+                ("Pause", "paused"),
+                ("Stop", "stopped"),
+                ("Next", "playing"),
+                ("Previous", "playing"),
+            ] {
+                b.method(method, (), (), move |_, player: &mut StandInPlayer, ()| {
+                    player.status = status.to_string();
+                    Ok(())
+                });
+            }
+        });
+
+        let transport = crossroads.register(TRANSPORT_INTERFACE, |b| {
+            b.property("Device")
+                .get(|_, _: &mut u16| Ok(Path::from("/org/bluez/hci0/dev_AA_BB_CC_DD_EE_FF")));
+            b.property("State")
+                .get(|_, _: &mut u16| Ok("active".to_string()));
+            b.property("Codec").get(|_, _: &mut u16| Ok(0xff_u8));
+            // LDAC
+            b.property("Configuration")
+                .get(|_, _: &mut u16| Ok(vec![0x2d_u8, 0x01, 0, 0, 0xaa, 0, 0x24, 0x01]));
+            b.property("Volume")
+                .get(|_, volume: &mut u16| Ok(*volume))
+                .set(|_, volume, value| {
+                    *volume = value;
+                    Ok(Some(value))
+                });
+        });
+
+        let object_manager = crossroads.object_manager();
+        crossroads.insert("/", &[object_manager], ());
+        crossroads.insert(
+            "/org/bluez/hci0/dev_AA_BB_CC_DD_EE_FF/player0",
+            &[player],
+            StandInPlayer {
+                status: "paused".to_string(),
+            },
+        );
+        crossroads.insert(
+            "/org/bluez/hci0/dev_AA_BB_CC_DD_EE_FF/sep1/fd0",
+            &[transport],
+            64_u16,
+        );
+        crossroads
+    }
+
+    #[tokio::test]
+    async fn players_from_a_stand_in_bluez() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon is not available, skipping");
+            return;
+        };
+        bus.serve(SERVICE, stand_in_bluez());
+
+        let system = Bus::open(&bus.address).unwrap();
+        let media = MediaBus::new(system.clone());
+        let (players, _) = from_objects(&system.managed_objects(SERVICE).await.unwrap());
+        assert_eq!(players.len(), 1);
+
+        let player = &players[0];
+        assert_eq!(player.device, "AA:BB:CC:DD:EE:FF".parse().unwrap());
+        assert_eq!(player.name.as_deref(), Some("Music"));
+        assert_eq!(player.status, "paused");
+        assert_eq!(player.position, 61_000);
+        assert_eq!(
+            player.track,
+            Track {
+                title: Some("Song".to_string()),
+                artist: Some("Band".to_string()),
+                album: Some("Record".to_string()),
+                duration: Some(180_000),
+            }
+        );
+
+        media.send(&player.path, PlayerCommand::Play).await.unwrap();
+        let (players, _) = from_objects(&system.managed_objects(SERVICE).await.unwrap());
+        assert!(players[0].is_playing());
+    }
+
+    #[tokio::test]
+    async fn transports_from_a_stand_in_bluez() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon is not available, skipping");
+            return;
+        };
+        bus.serve(SERVICE, stand_in_bluez());
+
+        let system = Bus::open(&bus.address).unwrap();
+        let media = MediaBus::new(system.clone());
+        let (_, transports) = from_objects(&system.managed_objects(SERVICE).await.unwrap());
+        assert_eq!(transports.len(), 1);
+
+        let transport = &transports[0];
+        assert_eq!(transport.device, "AA:BB:CC:DD:EE:FF".parse().unwrap());
+        assert!(transport.is_active());
+        assert_eq!(transport.codec, "LDAC");
+        assert_eq!(transport.volume, Some(64));
+        assert_eq!(transport.volume_percentage(), Some(50));
+
+        media
+            .set_volume(&transport.path, volume_from_percentage(100))
+            .await
+            .unwrap();
+        let (_, transports) = from_objects(&system.managed_objects(SERVICE).await.unwrap());
+        assert_eq!(transports[0].volume, Some(MAX_VOLUME));
+    }
+
+    #[test]
+    fn codec_names() {
+        assert_eq!(codec_name(0x00, &[]), "SBC");
+        assert_eq!(codec_name(0x02, &[]), "AAC");
+        assert_eq!(codec_name(0xff, &[0x4f, 0, 0, 0, 0x01, 0, 0x2f]), "aptX");
+        assert_eq!(codec_name(0xff, &[]), "Unknown (0xff)");
+    }
+}
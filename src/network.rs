@@ -0,0 +1,304 @@
+use bluer::Address;
+use dbus::{
+    Path,
+    arg::{PropMap, RefArg},
+};
+
+use crate::{
+    app::AppResult,
+    bus::{Bus, ManagedObjects},
+    media::device_address,
+};
+
+const SERVICE: &str = "org.bluez";
+const DEVICE_INTERFACE: &str = "org.bluez.Device1";
+const NETWORK_INTERFACE: &str = "org.bluez.Network1";
+const NETWORK_SERVER_INTERFACE: &str = "org.bluez.NetworkServer1";
+
+// PAN roles a device can offer, from the most to the least useful to connect to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum PanRole {
+    // Network access point, e.g. a phone sharing its connection
+    Nap,
+    // Group ad-hoc network
+    Gn,
+    // User
+    Panu,
+}
+
+impl PanRole {
+    const ALL: [PanRole; 3] = [PanRole::Nap, PanRole::Gn, PanRole::Panu];
+
+    fn uuid(self) -> &'static str {
+        match self {
+            PanRole::Nap => "00001116-0000-1000-8000-00805f9b34fb",
+            PanRole::Gn => "00001117-0000-1000-8000-00805f9b34fb",
+            PanRole::Panu => "00001115-0000-1000-8000-00805f9b34fb",
+        }
+    }
+
+    // Name BlueZ expects
+    fn name(self) -> &'static str {
+        match self {
+            PanRole::Nap => "nap",
+            PanRole::Gn => "gn",
+            PanRole::Panu => "panu",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PanRole::Nap => "NAP",
+            PanRole::Gn => "GN",
+            PanRole::Panu => "PANU",
+        }
+    }
+}
+
+// A `org.bluez.Network1` object, exposed by the devices offering PAN
+#[derive(Debug, Clone, PartialEq)]
+pub struct Network {
+    pub path: Path<'static>,
+    pub device: Address,
+    pub roles: Vec<PanRole>,
+    pub connected: bool,
+    // Network interface of the connection, e.g. "bnep0"
+    pub interface: Option<String>,
+}
+
+impl Network {
+    fn from_properties(
+        path: Path<'static>,
+        network: &PropMap,
+        device: Option<&PropMap>,
+    ) -> Option<Self> {
+        let uuids: Vec<String> = device
+            .and_then(|device| device.get("UUIDs"))
+            .and_then(|uuids| uuids.0.as_iter())
+            .map(|uuids| {
+                uuids
+                    .filter_map(|uuid| uuid.as_str().map(str::to_lowercase))
+                    .collect()
+            })
+            .unwrap_or_default();
+        let roles = PanRole::ALL
+            .into_iter()
+            .filter(|role| uuids.iter().any(|uuid| uuid == role.uuid()))
+            .collect();
+
+        let connected = network
+            .get("Connected")
+            .and_then(RefArg::as_u64)
+            .is_some_and(|connected| connected != 0);
+        Some(Self {
+            device: device_address(&path)?,
+            path,
+            roles,
+            connected,
+            interface: network
+                .get("Interface")
+                .and_then(|v| v.as_str())
+                .filter(|interface| connected && !interface.is_empty())
+                .map(str::to_string),
+        })
+    }
+
+    // The role to connect with, devices not advertising any are tried as access points
+    pub fn role(&self) -> PanRole {
+        self.roles.first().copied().unwrap_or(PanRole::Nap)
+    }
+
+    pub fn state(&self) -> String {
+        match &self.interface {
+            Some(interface) => format!("connected on {interface}"),
+            None if self.connected => "connected".to_string(),
+            None => format!("{} available", self.role().label()),
+        }
+    }
+}
+
+// The networks of all the devices
+pub fn from_objects(objects: &ManagedObjects) -> Vec<Network> {
+    let mut networks: Vec<Network> = objects
+        .iter()
+        .filter_map(|(path, interfaces)| {
+            let network = interfaces.get(NETWORK_INTERFACE)?;
+            Network::from_properties(path.clone(), network, interfaces.get(DEVICE_INTERFACE))
+        })
+        .collect();
+    networks.sort_by(|a, b| a.path.cmp(&b.path));
+    networks
+}
+
+// Network interfaces of BlueZ, bluer does not support them
+#[derive(Debug, Clone)]
+pub struct NetworkBus {
+    bus: Bus,
+}
+
+impl NetworkBus {
+    pub fn new(bus: Bus) -> Self {
+        Self { bus }
+    }
+
+    // The networks of all the devices, read again
+    pub async fn networks(&self) -> AppResult<Vec<Network>> {
+        Ok(from_objects(&self.bus.managed_objects(SERVICE).await?))
+    }
+
+    // Returns the network interface of the connection
+    pub async fn connect(&self, network: &Network) -> AppResult<String> {
+        let (interface,): (String,) = self
+            .bus
+            .proxy(SERVICE, network.path.clone())
+            .method_call(NETWORK_INTERFACE, "Connect", (network.role().name(),))
+            .await?;
+        Ok(interface)
+    }
+
+    pub async fn disconnect(&self, network: &Network) -> AppResult<()> {
+        self.bus
+            .proxy(SERVICE, network.path.clone())
+            .method_call::<(), _, _, _>(NETWORK_INTERFACE, "Disconnect", ())
+            .await?;
+        Ok(())
+    }
+
+    // Let the devices connect to the adapter as an access point, bridged to `bridge`. BlueZ
+    // unregisters it when bluetui exits
+    pub async fn register_server(&self, adapter: &str, bridge: &str) -> AppResult<()> {
+        self.bus
+            .proxy(SERVICE, format!("/org/bluez/{adapter}"))
+            .method_call::<(), _, _, _>(
+                NETWORK_SERVER_INTERFACE,
+                "Register",
+                (PanRole::Nap.name(), bridge),
+            )
+            .await?;
+        Ok(())
+    }
+
+    pub async fn unregister_server(&self, adapter: &str) -> AppResult<()> {
+        self.bus
+            .proxy(SERVICE, format!("/org/bluez/{adapter}"))
+            .method_call::<(), _, _, _>(
+                NETWORK_SERVER_INTERFACE,
+                "Unregister",
+                (PanRole::Nap.name(),),
+            )
+            .await?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use dbus_crossroads::{Crossroads, IfaceBuilder};
+
+    use super::*;
+    use crate::bus::tests::PrivateBus;
+
+    const PHONE_PATH: &str = "/org/bluez/hci0/dev_AA_BB_CC_DD_EE_FF";
+
+    // Connected role of the phone, and the bridges of the servers
+    #[derive(Default)]
+    struct State {
+        connected: Option<String>,
+        bridges: Vec<String>,
+    }
+
+    type Shared = Arc<Mutex<State>>;
+
+    fn stand_in_bluez(state: Shared) -> Crossroads {
+        let mut crossroads = Crossroads::new();
+
+        let device = crossroads.register(DEVICE_INTERFACE, |b: &mut IfaceBuilder<Shared>| {
+            b.property("UUIDs").get(|_, _| {
+                Ok(vec![
+                    "0000110a-0000-1000-8000-00805f9b34fb".to_string(),
+                    PanRole::Panu.uuid().to_string(),
+                    PanRole::Nap.uuid().to_uppercase(),
+                ])
+            });
+        });
+
+        let network = crossroads.register(NETWORK_INTERFACE, |b: &mut IfaceBuilder<Shared>| {
+            b.property("Connected")
+                .get(|_, state| Ok(state.lock().unwrap().connected.is_some()));
+            b.property("Interface").get(|_, state| {
+                Ok(if state.lock().unwrap().connected.is_some() {
+                    "bnep0".to_string()
+                } else {
+                    String::new()
+                })
+            });
+            b.method(
+                "Connect",
+                ("uuid",),
+                ("interface",),
+                |_, state, (role,): (String,)| {
+                    state.lock().unwrap().connected = Some(role);
+                    Ok(("bnep0".to_string(),))
+                },
+            );
+            b.method("Disconnect", (), (), |_, state, ()| {
+                state.lock().unwrap().connected = None;
+                Ok(())
+            });
+        });
+
+        let server =
+            crossroads.register(NETWORK_SERVER_INTERFACE, |b: &mut IfaceBuilder<Shared>| {
+                b.method(
+                    "Register",
+                    ("uuid", "bridge"),
+                    (),
+                    |_, state, (_, bridge): (String, String)| {
+                        state.lock().unwrap().bridges.push(bridge);
+                        Ok(())
+                    },
+                );
+                b.method("Unregister", ("uuid",), (), |_, state, (_,): (String,)| {
+                    state.lock().unwrap().bridges.clear();
+                    Ok(())
+                });
+            });
+
+        let object_manager = crossroads.object_manager();
+        crossroads.insert("/", &[object_manager], state.clone());
+        crossroads.insert("/org/bluez/hci0", &[server], state.clone());
+        crossroads.insert(PHONE_PATH, &[device, network], state);
+        crossroads
+    }
+
+    #[tokio::test]
+    async fn connect_to_a_phone_and_share_the_connection() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon is not available, skipping");
+            return;
+        };
+        let state = Arc::new(Mutex::new(State::default()));
+        bus.serve(SERVICE, stand_in_bluez(state.clone()));
+
+        let networks = NetworkBus::new(Bus::open(&bus.address).unwrap());
+        let phone = networks.networks().await.unwrap().remove(0);
+        assert_eq!(phone.device, "AA:BB:CC:DD:EE:FF".parse().unwrap());
+        assert_eq!(phone.roles, [PanRole::Nap, PanRole::Panu]);
+        assert_eq!(phone.state(), "NAP available");
+
+        assert_eq!(networks.connect(&phone).await.unwrap(), "bnep0");
+        assert_eq!(state.lock().unwrap().connected.as_deref(), Some("nap"));
+        let phone = networks.networks().await.unwrap().remove(0);
+        assert_eq!(phone.state(), "connected on bnep0");
+
+        networks.disconnect(&phone).await.unwrap();
+        assert!(!networks.networks().await.unwrap()[0].connected);
+
+        networks.register_server("hci0", "br0").await.unwrap();
+        assert_eq!(state.lock().unwrap().bridges, ["br0"]);
+        networks.unregister_server("hci0").await.unwrap();
+        assert!(state.lock().unwrap().bridges.is_empty());
+    }
+}
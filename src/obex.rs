@@ -0,0 +1,483 @@
+use std::path::Path as FilePath;
+
+use async_channel::Sender;
+use bluer::Address;
+use dbus::{
+    Message, Path,
+    arg::{PropMap, RefArg, Variant},
+    message::MatchRule,
+    nonblock::{MsgMatch, stdintf::org_freedesktop_dbus::PropertiesPropertiesChanged},
+};
+use futures::{StreamExt, channel::mpsc::UnboundedReceiver};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::Style,
+    widgets::{Block, BorderType, LineGauge, Padding},
+};
+
+use crate::{app::AppResult, bus::Bus};
+
+pub mod agent;
+pub mod phone;
+
+const SERVICE: &str = "org.bluez.obex";
+const CLIENT_PATH: &str = "/org/bluez/obex";
+const CLIENT_INTERFACE: &str = "org.bluez.obex.Client1";
+const OBJECT_PUSH_INTERFACE: &str = "org.bluez.obex.ObjectPush1";
+const TRANSFER_INTERFACE: &str = "org.bluez.obex.Transfer1";
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TransferStatus {
+    Queued,
+    Active,
+    Suspended,
+    Complete,
+    Error,
+}
+
+impl TransferStatus {
+    fn parse(status: &str) -> Self {
+        match status {
+            "queued" => TransferStatus::Queued,
+            "active" => TransferStatus::Active,
+            "suspended" => TransferStatus::Suspended,
+            "complete" => TransferStatus::Complete,
+            _ => TransferStatus::Error,
+        }
+    }
+
+    pub fn is_finished(self) -> bool {
+        matches!(self, TransferStatus::Complete | TransferStatus::Error)
+    }
+}
+
+// A file being sent to, or received from, a device
+#[derive(Debug, Clone)]
+pub struct Transfer {
+    pub path: Path<'static>,
+    pub device: Address,
+    pub file_name: String,
+    pub size: Option<u64>,
+    pub transferred: u64,
+    pub status: TransferStatus,
+    pub incoming: bool,
+    // Stops the exports made of several transfers, see `ObexClient::cancel`
+    pub cancel: Option<Sender<()>>,
+}
+
+impl Transfer {
+    fn new(
+        path: Path<'static>,
+        device: Address,
+        file_name: String,
+        properties: &PropMap,
+        incoming: bool,
+    ) -> Self {
+        let mut transfer = Self {
+            path: path.clone(),
+            device,
+            file_name,
+            size: properties.get("Size").and_then(RefArg::as_u64),
+            transferred: 0,
+            status: TransferStatus::Queued,
+            incoming,
+            cancel: None,
+        };
+        transfer.apply(&TransferUpdate::from_properties(path, properties));
+        transfer
+    }
+
+    pub fn apply(&mut self, update: &TransferUpdate) {
+        if let Some(status) = update.status {
+            self.status = status;
+        }
+        if let Some(transferred) = update.transferred {
+            self.transferred = transferred;
+        }
+    }
+
+    #[allow(clippy::cast_precision_loss)]
+    pub fn ratio(&self) -> f64 {
+        match self.size {
+            Some(size) if size > 0 => (self.transferred as f64 / size as f64).clamp(0.0, 1.0),
+            _ => 0.0,
+        }
+    }
+}
+
+// Changed properties of a transfer
+#[derive(Debug, Clone)]
+pub struct TransferUpdate {
+    pub path: Path<'static>,
+    pub status: Option<TransferStatus>,
+    pub transferred: Option<u64>,
+}
+
+impl TransferUpdate {
+    fn from_properties(path: Path<'static>, properties: &PropMap) -> Self {
+        Self {
+            path,
+            status: properties
+                .get("Status")
+                .and_then(|v| v.as_str())
+                .map(TransferStatus::parse),
+            transferred: properties.get("Transferred").and_then(RefArg::as_u64),
+        }
+    }
+}
+
+// Reported to the caller of a transfer, the start always comes before the updates
+#[derive(Debug)]
+pub enum TransferEvent {
+    Started(Transfer),
+    Updated(TransferUpdate),
+}
+
+type Changes = UnboundedReceiver<(Message, PropertiesPropertiesChanged)>;
+
+// Changes of the properties of the objects under `namespace`
+async fn listen(bus: &Bus, namespace: Path<'static>) -> AppResult<(MsgMatch, Changes)> {
+    let rule = MatchRule::new_signal("org.freedesktop.DBus.Properties", "PropertiesChanged")
+        .with_sender(SERVICE)
+        .with_namespaced_path(namespace);
+    Ok(bus
+        .connection()
+        .add_match(rule.static_clone())
+        .await?
+        .stream::<PropertiesPropertiesChanged>())
+}
+
+// Pass the changes of the transfer to `on_update` until it is finished
+async fn follow(
+    bus: &Bus,
+    signal: MsgMatch,
+    mut changes: Changes,
+    path: &Path<'static>,
+    mut on_update: impl FnMut(TransferUpdate),
+) {
+    while let Some((message, change)) = changes.next().await {
+        if change.interface_name != TRANSFER_INTERFACE || message.path().is_none_or(|p| p != *path)
+        {
+            continue;
+        }
+        let update = TransferUpdate::from_properties(path.clone(), &change.changed_properties);
+        let is_finished = update.status.is_some_and(TransferStatus::is_finished);
+        on_update(update);
+        if is_finished {
+            break;
+        }
+    }
+    let _ = bus.connection().remove_match(signal.token()).await;
+}
+
+// The last component of a name given by a device, so what it sends cannot be saved outside of the
+// chosen directory, e.g. "../.ssh/authorized_keys" is saved as "authorized_keys"
+pub fn safe_file_name(name: &str) -> Option<&str> {
+    let file_name = FilePath::new(name).file_name()?.to_str()?;
+    (!matches!(file_name, "" | "." | "..")).then_some(file_name)
+}
+
+pub fn format_size(bytes: u64) -> String {
+    const UNITS: [&str; 4] = ["KB", "MB", "GB", "TB"];
+    if bytes < 1024 {
+        return format!("{bytes} B");
+    }
+    #[allow(clippy::cast_precision_loss)]
+    let mut size = bytes as f64 / 1024.0;
+    let mut unit = 0;
+    while size >= 1024.0 && unit < UNITS.len() - 1 {
+        size /= 1024.0;
+        unit += 1;
+    }
+    format!("{size:.1} {}", UNITS[unit])
+}
+
+// Client of obexd, on the session bus
+#[derive(Debug, Clone)]
+pub struct ObexClient {
+    bus: Bus,
+}
+
+impl ObexClient {
+    pub fn new(bus: Bus) -> Self {
+        Self { bus }
+    }
+
+    // Start sending the file, `on_event` is called with the transfer and then its changes until
+    // it is finished
+    pub async fn send_file(
+        &self,
+        device: Address,
+        file: &FilePath,
+        on_event: impl FnMut(TransferEvent) + Send + 'static,
+    ) -> AppResult<()> {
+        let file = std::fs::canonicalize(file)?;
+        let file_name = file
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+
+        let session = self.create_session(device, "opp").await?;
+        let proxy = self.bus.proxy(SERVICE, session.clone());
+        self.start_transfer(
+            session,
+            device,
+            file_name,
+            false,
+            || {
+                proxy.method_call(
+                    OBJECT_PUSH_INTERFACE,
+                    "SendFile",
+                    (file.to_string_lossy().to_string(),),
+                )
+            },
+            on_event,
+        )
+        .await
+    }
+
+    pub async fn cancel(&self, transfer: &Transfer) -> AppResult<()> {
+        if let Some(cancel) = &transfer.cancel {
+            let _ = cancel.try_send(());
+            return Ok(());
+        }
+        self.cancel_transfer(&transfer.path).await
+    }
+
+    async fn cancel_transfer(&self, transfer: &Path<'static>) -> AppResult<()> {
+        self.bus
+            .proxy(SERVICE, transfer.clone())
+            .method_call::<(), _, _, _>(TRANSFER_INTERFACE, "Cancel", ())
+            .await?;
+        Ok(())
+    }
+
+    async fn create_session(&self, device: Address, target: &str) -> AppResult<Path<'static>> {
+        let mut options = PropMap::new();
+        options.insert("Target".to_string(), Variant(Box::new(target.to_string())));
+        let (session,): (Path<'static>,) = self
+            .bus
+            .proxy(SERVICE, CLIENT_PATH)
+            .method_call(
+                CLIENT_INTERFACE,
+                "CreateSession",
+                (device.to_string(), options),
+            )
+            .await?;
+        Ok(session)
+    }
+
+    // Follow the transfer `start` makes in the session, which is removed once it is finished
+    async fn start_transfer<F>(
+        &self,
+        session: Path<'static>,
+        device: Address,
+        file_name: String,
+        incoming: bool,
+        start: impl FnOnce() -> F,
+        mut on_event: impl FnMut(TransferEvent) + Send + 'static,
+    ) -> AppResult<()>
+    where
+        F: Future<Output = Result<(Path<'static>, PropMap), dbus::Error>>,
+    {
+        // Listen before the transfer starts to not miss its first changes
+        let (signal, changes) = listen(&self.bus, session.clone()).await?;
+
+        let (path, properties) = match start().await {
+            Ok(started) => started,
+            Err(e) => {
+                let _ = self.bus.connection().remove_match(signal.token()).await;
+                self.remove_session(session).await;
+                return Err(e.into());
+            }
+        };
+        let transfer = Transfer::new(path.clone(), device, file_name, &properties, incoming);
+        on_event(TransferEvent::Started(transfer));
+
+        let client = self.clone();
+        tokio::spawn(async move {
+            follow(&client.bus, signal, changes, &path, |update| {
+                on_event(TransferEvent::Updated(update));
+            })
+            .await;
+            client.remove_session(session).await;
+        });
+
+        Ok(())
+    }
+
+    async fn remove_session(&self, session: Path<'static>) {
+        let _: Result<(), _> = self
+            .bus
+            .proxy(SERVICE, CLIENT_PATH)
+            .method_call(CLIENT_INTERFACE, "RemoveSession", (session,))
+            .await;
+    }
+}
+
+pub fn render_transfers(
+    transfers: &[Transfer],
+    device_name: impl Fn(Address) -> String,
+    frame: &mut Frame,
+    area: Rect,
+) {
+    let block = Block::bordered()
+        .title(" Transfers ")
+        .padding(Padding::horizontal(1))
+        .border_type(BorderType::Rounded);
+    let inner = block.inner(area);
+    frame.render_widget(block, area);
+
+    let rows = Layout::vertical(vec![Constraint::Length(1); transfers.len()]).split(inner);
+    for (transfer, row) in transfers.iter().zip(rows.iter()) {
+        frame.render_widget(
+            LineGauge::default()
+                .ratio(transfer.ratio())
+                .label(format!(
+                    "{} {} {} {:>3.0}%",
+                    transfer.file_name,
+                    if transfer.incoming { "←" } else { "→" },
+                    device_name(transfer.device),
+                    transfer.ratio() * 100.0
+                ))
+                .filled_style(Style::default().green()),
+            *row,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use dbus::message::SignalArgs;
+    use dbus_crossroads::Crossroads;
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::bus::tests::PrivateBus;
+
+    const SESSION_PATH: &str = "/org/bluez/obex/client/session0";
+    const TRANSFER_PATH: &str = "/org/bluez/obex/client/session0/transfer0";
+
+    // Stand-in obexd whose transfers stay queued until they are cancelled
+    fn stand_in_obexd(sessions: Arc<Mutex<Vec<String>>>) -> Crossroads {
+        let mut crossroads = Crossroads::new();
+
+        let client = crossroads.register(CLIENT_INTERFACE, |b| {
+            b.method(
+                "CreateSession",
+                ("destination", "args"),
+                ("session",),
+                |_, sessions: &mut Arc<Mutex<Vec<String>>>, (destination, _): (String, PropMap)| {
+                    sessions.lock().unwrap().push(destination);
+                    Ok((Path::from(SESSION_PATH),))
+                },
+            );
+            b.method(
+                "RemoveSession",
+                ("session",),
+                (),
+                |_, sessions: &mut Arc<Mutex<Vec<String>>>, (_,): (Path<'static>,)| {
+                    sessions.lock().unwrap().clear();
+                    Ok(())
+                },
+            );
+        });
+
+        let object_push = crossroads.register(OBJECT_PUSH_INTERFACE, |b| {
+            b.method(
+                "SendFile",
+                ("sourcefile",),
+                ("transfer", "properties"),
+                |_, (), (_,): (String,)| {
+                    let mut properties = PropMap::new();
+                    properties.insert("Status".into(), Variant(Box::new("queued".to_string())));
+                    properties.insert("Size".into(), Variant(Box::new(1024_u64)));
+                    Ok((Path::from(TRANSFER_PATH), properties))
+                },
+            );
+        });
+
+        let transfer = crossroads.register(TRANSFER_INTERFACE, |b| {
+            b.method("Cancel", (), (), |ctx, (), ()| {
+                let mut changed = PropMap::new();
+                changed.insert("Status".into(), Variant(Box::new("error".to_string())));
+                changed.insert("Transferred".into(), Variant(Box::new(512_u64)));
+                let signal = PropertiesPropertiesChanged {
+                    interface_name: TRANSFER_INTERFACE.to_string(),
+                    changed_properties: changed,
+                    invalidated_properties: Vec::new(),
+                };
+                ctx.push_msg(signal.to_emit_message(&Path::from(TRANSFER_PATH)));
+                Ok(())
+            });
+        });
+
+        crossroads.insert(CLIENT_PATH, &[client], sessions);
+        crossroads.insert(SESSION_PATH, &[object_push], ());
+        crossroads.insert(TRANSFER_PATH, &[transfer], ());
+        crossroads
+    }
+
+    #[test]
+    fn file_names_stay_in_the_directory() {
+        assert_eq!(safe_file_name("photo.jpg"), Some("photo.jpg"));
+        assert_eq!(
+            safe_file_name("../../.ssh/authorized_keys"),
+            Some("authorized_keys")
+        );
+        assert_eq!(safe_file_name("/etc/passwd"), Some("passwd"));
+        assert_eq!(safe_file_name(".."), None);
+        assert_eq!(safe_file_name("photos/.."), None);
+        assert_eq!(safe_file_name("."), None);
+        assert_eq!(safe_file_name(""), None);
+        assert_eq!(safe_file_name("/"), None);
+    }
+
+    #[tokio::test]
+    async fn send_and_cancel_a_file() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon is not available, skipping");
+            return;
+        };
+        let sessions = Arc::new(Mutex::new(Vec::new()));
+        bus.serve(SERVICE, stand_in_obexd(sessions.clone()));
+
+        let client = ObexClient::new(Bus::open(&bus.address).unwrap());
+        let (sender, mut events) = mpsc::unbounded_channel();
+        let device: Address = "AA:BB:CC:DD:EE:FF".parse().unwrap();
+        client
+            .send_file(device, FilePath::new("Cargo.toml"), move |event| {
+                let _ = sender.send(event);
+            })
+            .await
+            .unwrap();
+        let Some(TransferEvent::Started(mut transfer)) = events.recv().await else {
+            panic!("the transfer is not started first");
+        };
+
+        assert_eq!(transfer.file_name, "Cargo.toml");
+        assert_eq!(transfer.size, Some(1024));
+        assert_eq!(transfer.status, TransferStatus::Queued);
+        assert_eq!(*sessions.lock().unwrap(), vec![device.to_string()]);
+
+        client.cancel(&transfer).await.unwrap();
+        let Some(TransferEvent::Updated(update)) = events.recv().await else {
+            panic!("the transfer is not updated");
+        };
+        transfer.apply(&update);
+        assert_eq!(transfer.status, TransferStatus::Error);
+        assert!((transfer.ratio() - 0.5).abs() < f64::EPSILON);
+
+        // The session is removed once the transfer is finished
+        for _ in 0..100 {
+            if sessions.lock().unwrap().is_empty() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert!(sessions.lock().unwrap().is_empty());
+    }
+}
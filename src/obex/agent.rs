@@ -0,0 +1,359 @@
+use std::{
+    collections::HashMap,
+    path::PathBuf,
+    sync::{Arc, Mutex},
+};
+
+use bluer::Address;
+use dbus::{
+    MethodErr, Path,
+    arg::{PropMap, RefArg},
+    channel::MatchingReceiver,
+    message::MatchRule,
+    nonblock::stdintf::org_freedesktop_dbus::Properties,
+};
+use dbus_crossroads::{Crossroads, IfaceBuilder};
+use futures::channel::oneshot;
+use tokio::sync::mpsc::UnboundedSender;
+
+use super::{CLIENT_PATH, SERVICE, TRANSFER_INTERFACE, Transfer, follow, listen, safe_file_name};
+use crate::{app::AppResult, bus::Bus, event::Event, requests::receive_file::ReceiveFile};
+
+const AGENT_MANAGER_INTERFACE: &str = "org.bluez.obex.AgentManager1";
+const AGENT_INTERFACE: &str = "org.bluez.obex.Agent1";
+const SESSION_INTERFACE: &str = "org.bluez.obex.Session1";
+const AGENT_PATH: &str = "/org/bluetui/obex/agent";
+const REJECTED: &str = "org.bluez.obex.Error.Rejected";
+
+// Agent of obexd asking whether to accept the files pushed by the devices
+#[derive(Debug, Clone)]
+pub struct ReceiveAgent {
+    bus: Bus,
+    pub event_sender: UnboundedSender<Event>,
+    // Requests waiting for the user, by transfer path
+    pending: Arc<Mutex<HashMap<String, oneshot::Sender<Option<PathBuf>>>>>,
+}
+
+impl ReceiveAgent {
+    // Serve the agent on the bus and register it to obexd
+    pub async fn register(bus: Bus, event_sender: UnboundedSender<Event>) -> AppResult<Self> {
+        let agent = Self {
+            bus: bus.clone(),
+            event_sender,
+            pending: Arc::default(),
+        };
+
+        let mut crossroads = Crossroads::new();
+        crossroads.set_async_support(Some((
+            bus.connection().clone(),
+            Box::new(|future| {
+                tokio::spawn(future);
+            }),
+        )));
+        let interface = crossroads.register(AGENT_INTERFACE, |b: &mut IfaceBuilder<Self>| {
+            b.method("Release", (), (), |_, _, ()| Ok(()));
+            b.method_with_cr_async(
+                "AuthorizePush",
+                ("transfer",),
+                ("filename",),
+                |mut ctx, crossroads, (transfer,): (Path<'static>,)| {
+                    let agent = crossroads.data_mut::<Self>(ctx.path()).cloned();
+                    async move {
+                        let reply = match agent {
+                            Some(agent) => agent.authorize_push(transfer).await,
+                            None => Err(MethodErr::no_path(ctx.path())),
+                        };
+                        ctx.reply(reply.map(|file| (file,)))
+                    }
+                },
+            );
+            // obexd asks one request at a time, the cancel is for the pending one if any
+            b.method("Cancel", (), (), |_, agent: &mut Self, ()| {
+                let cancelled = agent.pending.lock().unwrap().drain().count();
+                if cancelled > 0 {
+                    let _ = agent.event_sender.send(Event::ReceiveFileSubmitted);
+                }
+                Ok(())
+            });
+        });
+        crossroads.insert(AGENT_PATH, &[interface], agent.clone());
+
+        bus.connection().start_receive(
+            MatchRule::new_method_call(),
+            Box::new(move |message, connection| {
+                let _ = crossroads.handle_message(message, connection);
+                true
+            }),
+        );
+
+        bus.proxy(SERVICE, CLIENT_PATH)
+            .method_call::<(), _, _, _>(
+                AGENT_MANAGER_INTERFACE,
+                "RegisterAgent",
+                (Path::from(AGENT_PATH),),
+            )
+            .await?;
+
+        Ok(agent)
+    }
+
+    // Answers the request of a transfer with the directory to save the file in, `None` to reject it
+    pub fn answer(&self, transfer: &str, dir: Option<PathBuf>) {
+        if let Some(reply) = self.pending.lock().unwrap().remove(transfer) {
+            let _ = reply.send(dir);
+        }
+    }
+
+    // Ask the user, and reply with the path to save the file to
+    async fn authorize_push(&self, path: Path<'static>) -> Result<String, MethodErr> {
+        let properties: PropMap = self
+            .bus
+            .proxy(SERVICE, path.clone())
+            .get_all(TRANSFER_INTERFACE)
+            .await?;
+        let Some(file_name) = safe_file_name(
+            properties
+                .get("Name")
+                .and_then(|v| v.as_str())
+                .unwrap_or("file"),
+        )
+        .map(str::to_string) else {
+            return Err((REJECTED, "Invalid file name").into());
+        };
+        let session = properties
+            .get("Session")
+            .and_then(|v| v.as_str())
+            .map(|session| Path::from(session.to_string()))
+            .ok_or_else(|| MethodErr::failed("The transfer has no session"))?;
+        let destination: String = self
+            .bus
+            .proxy(SERVICE, session)
+            .get(SESSION_INTERFACE, "Destination")
+            .await?;
+        let device: Address = destination
+            .parse()
+            .map_err(|_| MethodErr::failed(&format!("Invalid device {destination}")))?;
+
+        // Listen before accepting to not miss the first changes of the transfer
+        let (signal, changes) = listen(&self.bus, path.clone())
+            .await
+            .map_err(|e| MethodErr::failed(&e))?;
+
+        let (reply, answer) = oneshot::channel();
+        self.pending.lock().unwrap().insert(path.to_string(), reply);
+
+        let default_dir = dirs::download_dir()
+            .or_else(dirs::home_dir)
+            .unwrap_or_default();
+        let _ = self
+            .event_sender
+            .send(Event::RequestReceiveFile(ReceiveFile::new(
+                path.to_string(),
+                device,
+                file_name.clone(),
+                properties.get("Size").and_then(RefArg::as_u64),
+                &default_dir,
+            )));
+
+        // Cancelled when the sender is dropped
+        let Some(dir) = answer.await.ok().flatten() else {
+            let _ = self.bus.connection().remove_match(signal.token()).await;
+            return Err((REJECTED, "Not authorized").into());
+        };
+
+        let transfer = Transfer::new(path.clone(), device, file_name.clone(), &properties, true);
+        let _ = self.event_sender.send(Event::TransferStarted(transfer));
+
+        let bus = self.bus.clone();
+        let sender = self.event_sender.clone();
+        tokio::spawn(async move {
+            follow(&bus, signal, changes, &path, |update| {
+                let _ = sender.send(Event::TransferUpdated(update));
+            })
+            .await;
+        });
+
+        Ok(dir.join(file_name).to_string_lossy().to_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{
+        sync::{Arc, Mutex},
+        time::Duration,
+    };
+
+    use dbus::{blocking::Connection, channel::Channel};
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::bus::tests::PrivateBus;
+
+    const SESSION_PATH: &str = "/org/bluez/obex/server/session0";
+    const TRANSFER_PATH: &str = "/org/bluez/obex/server/session0/transfer0";
+    // Transfer of a device trying to write outside of the chosen directory
+    const HOSTILE_TRANSFER_PATH: &str = "/org/bluez/obex/server/session0/transfer1";
+
+    // Bus names and paths of the agents registered to obexd
+    type Agents = Arc<Mutex<Vec<(String, Path<'static>)>>>;
+
+    // Stand-in obexd keeping the agents registered to it
+    fn stand_in_obexd(agents: Agents) -> Crossroads {
+        let mut crossroads = Crossroads::new();
+
+        let manager = crossroads.register(AGENT_MANAGER_INTERFACE, |b| {
+            b.method(
+                "RegisterAgent",
+                ("agent",),
+                (),
+                |ctx, agents: &mut Agents, (agent,): (Path<'static>,)| {
+                    let sender = ctx.message().sender().unwrap().to_string();
+                    agents.lock().unwrap().push((sender, agent));
+                    Ok(())
+                },
+            );
+        });
+
+        let transfer = crossroads.register(TRANSFER_INTERFACE, |b: &mut IfaceBuilder<String>| {
+            b.property("Name").get(|_, name| Ok(name.clone()));
+            b.property("Size").get(|_, _| Ok(2048_u64));
+            b.property("Status").get(|_, _| Ok("queued".to_string()));
+            b.property("Session")
+                .get(|_, _| Ok(Path::from(SESSION_PATH)));
+        });
+
+        let session = crossroads.register(SESSION_INTERFACE, |b: &mut IfaceBuilder<()>| {
+            b.property("Destination")
+                .get(|_, ()| Ok("AA:BB:CC:DD:EE:FF".to_string()));
+        });
+
+        crossroads.insert(CLIENT_PATH, &[manager], agents);
+        crossroads.insert(TRANSFER_PATH, &[transfer], "photo.jpg".to_string());
+        crossroads.insert(
+            HOSTILE_TRANSFER_PATH,
+            &[transfer],
+            "../../.ssh/authorized_keys".to_string(),
+        );
+        crossroads.insert(SESSION_PATH, &[session], ());
+        crossroads
+    }
+
+    // Ask the agent for the file from another connection, like obexd does
+    fn authorize_push(
+        address: &str,
+        agent: (String, Path<'static>),
+        transfer: &'static str,
+    ) -> Result<String, dbus::Error> {
+        let mut channel = Channel::open_private(address)?;
+        channel.register()?;
+        let connection = Connection::from(channel);
+        connection
+            .with_proxy(agent.0, agent.1, Duration::from_secs(5))
+            .method_call(AGENT_INTERFACE, "AuthorizePush", (Path::from(transfer),))
+            .map(|(file,): (String,)| file)
+    }
+
+    fn cancel(address: &str, agent: (String, Path<'static>)) -> Result<(), dbus::Error> {
+        let mut channel = Channel::open_private(address)?;
+        channel.register()?;
+        let connection = Connection::from(channel);
+        connection
+            .with_proxy(agent.0, agent.1, Duration::from_secs(5))
+            .method_call(AGENT_INTERFACE, "Cancel", ())
+    }
+
+    #[tokio::test]
+    async fn accept_and_reject_pushed_files() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon is not available, skipping");
+            return;
+        };
+        let agents = Arc::new(Mutex::new(Vec::new()));
+        bus.serve(SERVICE, stand_in_obexd(agents.clone()));
+
+        let (sender, mut events) = mpsc::unbounded_channel();
+        let agent = ReceiveAgent::register(Bus::open(&bus.address).unwrap(), sender)
+            .await
+            .unwrap();
+        let registered = agents.lock().unwrap()[0].clone();
+        assert_eq!(registered.1, Path::from(AGENT_PATH));
+
+        // A cancel without pending request is not kept for the next one
+        let (address, target) = (bus.address.clone(), registered.clone());
+        tokio::task::spawn_blocking(move || cancel(&address, target))
+            .await
+            .unwrap()
+            .unwrap();
+
+        let (address, target) = (bus.address.clone(), registered.clone());
+        let reply =
+            tokio::task::spawn_blocking(move || authorize_push(&address, target, TRANSFER_PATH));
+        let Some(Event::RequestReceiveFile(request)) = events.recv().await else {
+            panic!("the user is not asked to accept the file");
+        };
+        assert_eq!(request.device.to_string(), "AA:BB:CC:DD:EE:FF");
+        assert_eq!(request.file_name, "photo.jpg");
+        assert_eq!(request.size, Some(2048));
+
+        let dir = PathBuf::from("/tmp/bluetui");
+        // The answer for another transfer is not taken for this one
+        agent.answer(HOSTILE_TRANSFER_PATH, None);
+        agent.answer(&request.transfer, Some(dir.clone()));
+        let Some(Event::TransferStarted(transfer)) = events.recv().await else {
+            panic!("the transfer is not tracked");
+        };
+        assert!(transfer.incoming);
+        assert_eq!(transfer.size, Some(2048));
+        assert_eq!(
+            reply.await.unwrap().unwrap(),
+            dir.join("photo.jpg").to_string_lossy()
+        );
+
+        let (address, target) = (bus.address.clone(), registered.clone());
+        let reply =
+            tokio::task::spawn_blocking(move || authorize_push(&address, target, TRANSFER_PATH));
+        let Some(Event::RequestReceiveFile(request)) = events.recv().await else {
+            panic!("the user is not asked to accept the file");
+        };
+        agent.answer(&request.transfer, None);
+        let error = reply.await.unwrap().unwrap_err();
+        assert_eq!(error.name(), Some(REJECTED));
+
+        // Only the name of the file is kept
+        let (address, target) = (bus.address.clone(), registered.clone());
+        let reply = tokio::task::spawn_blocking(move || {
+            authorize_push(&address, target, HOSTILE_TRANSFER_PATH)
+        });
+        let Some(Event::RequestReceiveFile(request)) = events.recv().await else {
+            panic!("the user is not asked to accept the file");
+        };
+        assert_eq!(request.file_name, "authorized_keys");
+        agent.answer(&request.transfer, Some(dir.clone()));
+        let Some(Event::TransferStarted(_)) = events.recv().await else {
+            panic!("the transfer is not tracked");
+        };
+        assert_eq!(
+            reply.await.unwrap().unwrap(),
+            dir.join("authorized_keys").to_string_lossy()
+        );
+
+        // A cancelled request is rejected and its popup closed
+        let (address, target) = (bus.address.clone(), registered.clone());
+        let reply =
+            tokio::task::spawn_blocking(move || authorize_push(&address, target, TRANSFER_PATH));
+        let Some(Event::RequestReceiveFile(_)) = events.recv().await else {
+            panic!("the user is not asked to accept the file");
+        };
+        let (address, target) = (bus.address.clone(), registered);
+        tokio::task::spawn_blocking(move || cancel(&address, target))
+            .await
+            .unwrap()
+            .unwrap();
+        let Some(Event::ReceiveFileSubmitted) = events.recv().await else {
+            panic!("the popup is not closed");
+        };
+        let error = reply.await.unwrap().unwrap_err();
+        assert_eq!(error.name(), Some(REJECTED));
+    }
+}
@@ -0,0 +1,670 @@
+use std::path::{Path as FilePath, PathBuf};
+
+use anyhow::bail;
+use async_channel::Receiver;
+use bluer::Address;
+use dbus::{
+    Path,
+    arg::{PropMap, RefArg},
+};
+use futures::StreamExt;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Clear, List, ListState, Padding, Paragraph},
+};
+use tui_input::Input;
+
+use super::{
+    Changes, ObexClient, SERVICE, TRANSFER_INTERFACE, Transfer, TransferEvent, TransferStatus,
+    TransferUpdate, listen, safe_file_name,
+};
+use crate::{app::AppResult, requests::pad_string};
+
+const PHONEBOOK_INTERFACE: &str = "org.bluez.obex.PhonebookAccess1";
+const MESSAGE_ACCESS_INTERFACE: &str = "org.bluez.obex.MessageAccess1";
+const MESSAGE_INTERFACE: &str = "org.bluez.obex.Message1";
+const MESSAGES_FOLDER: &str = "/telecom/msg";
+
+// Phonebooks of PBAP with their name and the file they are exported to
+const PHONEBOOKS: [(&str, &str, &str); 5] = [
+    ("pb", "Contacts", "contacts.vcf"),
+    ("ich", "Incoming calls", "incoming-calls.vcf"),
+    ("och", "Outgoing calls", "outgoing-calls.vcf"),
+    ("mch", "Missed calls", "missed-calls.vcf"),
+    ("cch", "Call history", "call-history.vcf"),
+];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Folder {
+    Phonebook {
+        id: &'static str,
+        name: &'static str,
+        file: &'static str,
+        size: u16,
+    },
+    Messages {
+        name: String,
+    },
+}
+
+impl Folder {
+    pub fn label(&self) -> String {
+        match self {
+            Folder::Phonebook { name, size, .. } => format!("{name} ({size})"),
+            Folder::Messages { name } => format!("Messages/{name}"),
+        }
+    }
+}
+
+impl ObexClient {
+    // Phonebooks and message folders of the phone, those of the profiles it does not support
+    // are left out
+    pub async fn phone_folders(&self, device: Address) -> AppResult<Vec<Folder>> {
+        match (
+            self.phonebooks(device).await,
+            self.message_folders(device).await,
+        ) {
+            (Err(e), Err(_)) => Err(e),
+            (phonebooks, messages) => Ok(phonebooks
+                .unwrap_or_default()
+                .into_iter()
+                .chain(messages.unwrap_or_default())
+                .collect()),
+        }
+    }
+
+    async fn phonebooks(&self, device: Address) -> AppResult<Vec<Folder>> {
+        let session = self.create_session(device, "pbap").await?;
+        let proxy = self.bus.proxy(SERVICE, session.clone());
+
+        let mut folders = Vec::new();
+        for (id, name, file) in PHONEBOOKS {
+            // Phones often lack some of the call histories
+            if proxy
+                .method_call::<(), _, _, _>(PHONEBOOK_INTERFACE, "Select", ("int", id))
+                .await
+                .is_err()
+            {
+                continue;
+            }
+            if let Ok((size,)) = proxy
+                .method_call::<(u16,), _, _, _>(PHONEBOOK_INTERFACE, "GetSize", ())
+                .await
+            {
+                folders.push(Folder::Phonebook {
+                    id,
+                    name,
+                    file,
+                    size,
+                });
+            }
+        }
+
+        self.remove_session(session).await;
+        Ok(folders)
+    }
+
+    async fn message_folders(&self, device: Address) -> AppResult<Vec<Folder>> {
+        let session = self.create_session(device, "map").await?;
+        let proxy = self.bus.proxy(SERVICE, session.clone());
+
+        let listed: Result<(Vec<PropMap>,), dbus::Error> = async {
+            proxy
+                .method_call::<(), _, _, _>(
+                    MESSAGE_ACCESS_INTERFACE,
+                    "SetFolder",
+                    (MESSAGES_FOLDER,),
+                )
+                .await?;
+            proxy
+                .method_call(MESSAGE_ACCESS_INTERFACE, "ListFolders", (PropMap::new(),))
+                .await
+        }
+        .await;
+
+        self.remove_session(session).await;
+        Ok(listed?
+            .0
+            .iter()
+            .filter_map(|folder| folder.get("Name").and_then(|v| v.as_str()))
+            .map(|name| Folder::Messages {
+                name: name.to_string(),
+            })
+            .collect())
+    }
+
+    // Start exporting the folder into `dir`, `on_event` is called with the export and then its
+    // progress until it is finished
+    pub async fn export(
+        &self,
+        device: Address,
+        folder: &Folder,
+        dir: &FilePath,
+        on_event: impl FnMut(TransferEvent) + Send + 'static,
+    ) -> AppResult<()> {
+        std::fs::create_dir_all(dir)?;
+        let dir = std::fs::canonicalize(dir)?;
+
+        match folder {
+            Folder::Phonebook { id, file, .. } => {
+                let session = self.create_session(device, "pbap").await?;
+                let proxy = self.bus.proxy(SERVICE, session.clone());
+                if let Err(e) = proxy
+                    .method_call::<(), _, _, _>(PHONEBOOK_INTERFACE, "Select", ("int", *id))
+                    .await
+                {
+                    self.remove_session(session).await;
+                    return Err(e.into());
+                }
+
+                let target = dir.join(file).to_string_lossy().to_string();
+                self.start_transfer(
+                    session,
+                    device,
+                    (*file).to_string(),
+                    true,
+                    || proxy.method_call(PHONEBOOK_INTERFACE, "PullAll", (target, PropMap::new())),
+                    on_event,
+                )
+                .await
+            }
+            Folder::Messages { name } => {
+                // The name comes from the phone, it must not lead out of the directory
+                let Some(subdir) = safe_file_name(name) else {
+                    bail!("Invalid folder name {name}");
+                };
+                self.export_messages(device, name, dir.join(subdir), on_event)
+                    .await
+            }
+        }
+    }
+
+    // The messages are downloaded one after the other, the progress is the number of messages
+    // downloaded
+    async fn export_messages(
+        &self,
+        device: Address,
+        folder: &str,
+        dir: PathBuf,
+        mut on_event: impl FnMut(TransferEvent) + Send + 'static,
+    ) -> AppResult<()> {
+        std::fs::create_dir_all(&dir)?;
+
+        let session = self.create_session(device, "map").await?;
+        let proxy = self.bus.proxy(SERVICE, session.clone());
+        let listed: Result<(Vec<(Path<'static>, PropMap)>,), dbus::Error> = async {
+            proxy
+                .method_call::<(), _, _, _>(
+                    MESSAGE_ACCESS_INTERFACE,
+                    "SetFolder",
+                    (MESSAGES_FOLDER,),
+                )
+                .await?;
+            proxy
+                .method_call(
+                    MESSAGE_ACCESS_INTERFACE,
+                    "ListMessages",
+                    (folder, PropMap::new()),
+                )
+                .await
+        }
+        .await;
+        let messages: Vec<Path<'static>> = match listed {
+            Ok((messages,)) => messages.into_iter().map(|(path, _)| path).collect(),
+            Err(e) => {
+                self.remove_session(session).await;
+                return Err(e.into());
+            }
+        };
+
+        let (signal, mut changes) = listen(&self.bus, session.clone()).await?;
+        let (cancel, canceled) = async_channel::bounded(1);
+        let transfer = Transfer {
+            path: session.clone(),
+            device,
+            file_name: folder.to_string(),
+            size: Some(messages.len() as u64),
+            transferred: 0,
+            status: TransferStatus::Active,
+            incoming: true,
+            cancel: Some(cancel),
+        };
+        on_event(TransferEvent::Started(transfer));
+
+        let client = self.clone();
+        tokio::spawn(async move {
+            let mut on_update = |update| on_event(TransferEvent::Updated(update));
+            let status = client
+                .get_messages(
+                    &session,
+                    &messages,
+                    &dir,
+                    &mut changes,
+                    &canceled,
+                    &mut on_update,
+                )
+                .await;
+            on_update(TransferUpdate {
+                path: session.clone(),
+                status: Some(status),
+                transferred: None,
+            });
+            let _ = client.bus.connection().remove_match(signal.token()).await;
+            client.remove_session(session).await;
+        });
+
+        Ok(())
+    }
+
+    // The export stops at the first message that fails or when it is cancelled
+    async fn get_messages(
+        &self,
+        session: &Path<'static>,
+        messages: &[Path<'static>],
+        dir: &FilePath,
+        changes: &mut Changes,
+        canceled: &Receiver<()>,
+        on_update: &mut impl FnMut(TransferUpdate),
+    ) -> TransferStatus {
+        for (downloaded, message) in (1..).zip(messages) {
+            let handle = message.rsplit('/').next().unwrap_or_default();
+            let target = dir.join(format!("{handle}.bmsg"));
+            let Ok((path, _)): Result<(Path<'static>, PropMap), _> = self
+                .bus
+                .proxy(SERVICE, message.clone())
+                .method_call(
+                    MESSAGE_INTERFACE,
+                    "Get",
+                    (target.to_string_lossy().to_string(), false),
+                )
+                .await
+            else {
+                return TransferStatus::Error;
+            };
+
+            let status = loop {
+                tokio::select! {
+                    change = changes.next() => {
+                        let Some((change_message, change)) = change else {
+                            break TransferStatus::Error;
+                        };
+                        if change.interface_name != TRANSFER_INTERFACE
+                            || change_message.path().is_none_or(|p| p != path)
+                        {
+                            continue;
+                        }
+                        if let Some(status) = TransferUpdate::from_properties(
+                            path.clone(),
+                            &change.changed_properties,
+                        )
+                        .status
+                        .filter(|status| status.is_finished())
+                        {
+                            break status;
+                        }
+                    }
+                    _ = canceled.recv() => {
+                        let _ = self.cancel_transfer(&path).await;
+                        break TransferStatus::Error;
+                    }
+                }
+            };
+            if status == TransferStatus::Error {
+                return status;
+            }
+
+            on_update(TransferUpdate {
+                path: session.clone(),
+                status: Some(TransferStatus::Active),
+                transferred: Some(downloaded),
+            });
+        }
+        TransferStatus::Complete
+    }
+}
+
+// Phonebooks and messages of a phone, to export them
+#[derive(Debug)]
+pub struct PhoneData {
+    pub device: Address,
+    // `None` while they are listed
+    pub folders: Option<Vec<Folder>>,
+    pub state: ListState,
+    pub destination: Input,
+    pub editing: bool,
+}
+
+impl PhoneData {
+    pub fn new(device: Address, destination: &FilePath) -> Self {
+        Self {
+            device,
+            folders: None,
+            state: ListState::default(),
+            destination: Input::new(destination.to_string_lossy().to_string()),
+            editing: false,
+        }
+    }
+
+    pub fn set_folders(&mut self, folders: Vec<Folder>) {
+        self.state
+            .select(if folders.is_empty() { None } else { Some(0) });
+        self.folders = Some(folders);
+    }
+
+    pub fn next(&mut self) {
+        if let Some(folders) = self.folders.as_ref().filter(|f| !f.is_empty()) {
+            let i = self
+                .state
+                .selected()
+                .map_or(0, |i| (i + 1).min(folders.len() - 1));
+            self.state.select(Some(i));
+        }
+    }
+
+    pub fn previous(&mut self) {
+        if self.folders.as_ref().is_some_and(|f| !f.is_empty()) {
+            let i = self.state.selected().map_or(0, |i| i.saturating_sub(1));
+            self.state.select(Some(i));
+        }
+    }
+
+    pub fn selected(&self) -> Option<&Folder> {
+        self.folders.as_ref()?.get(self.state.selected()?)
+    }
+
+    pub fn render(&mut self, device_name: &str, frame: &mut Frame, area: Rect) {
+        let area = area.centered(Constraint::Max(70), Constraint::Length(14));
+
+        let block = Block::bordered()
+            .title(format!(" Phonebook and messages of {device_name} "))
+            .title_style(Style::default().bold())
+            .padding(Padding::horizontal(1))
+            .border_type(BorderType::Thick)
+            .border_style(Style::default().green());
+        let inner = block.inner(area);
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+
+        let [folders_area, _, destination_area] = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(1),
+            Constraint::Length(1),
+        ])
+        .areas(inner);
+
+        match &self.folders {
+            None => frame.render_widget(Paragraph::new("Listing…").dark_gray(), folders_area),
+            Some(folders) if folders.is_empty() => frame.render_widget(
+                Paragraph::new("No phonebook or messages").dark_gray(),
+                folders_area,
+            ),
+            Some(folders) => {
+                let items: Vec<Line> = folders
+                    .iter()
+                    .map(|folder| Line::from(format!(" {}", folder.label())))
+                    .collect();
+                frame.render_stateful_widget(
+                    List::new(items).highlight_style(Style::default().white().on_dark_gray()),
+                    folders_area,
+                    &mut self.state,
+                );
+            }
+        }
+
+        let label = if self.editing {
+            Span::from("Export to").green().bold()
+        } else {
+            Span::from("Export to")
+        };
+        frame.render_widget(
+            Line::from(vec![
+                label,
+                Span::from("  "),
+                Span::from(pad_string(format!(" {}", self.destination.value()), 50))
+                    .bg(Color::DarkGray),
+            ]),
+            destination_area,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::{Arc, Mutex};
+
+    use dbus::nonblock::stdintf::org_freedesktop_dbus::PropertiesPropertiesChanged;
+    use dbus::{arg::Variant, message::SignalArgs};
+    use dbus_crossroads::{Crossroads, IfaceBuilder};
+    use tokio::sync::mpsc;
+
+    use super::*;
+    use crate::{
+        bus::{Bus, tests::PrivateBus},
+        obex::{CLIENT_INTERFACE, CLIENT_PATH},
+    };
+
+    const PBAP_SESSION: &str = "/org/bluez/obex/client/session0";
+    const MAP_SESSION: &str = "/org/bluez/obex/client/session1";
+
+    // Files written by the stand-in obexd
+    type Targets = Arc<Mutex<Vec<String>>>;
+
+    // The transfer, of the given session, completes right away
+    fn complete(ctx: &mut dbus_crossroads::Context, session: &str) -> Path<'static> {
+        let path = Path::from(format!("{session}/transfer0"));
+        let mut changed = PropMap::new();
+        changed.insert("Status".into(), Variant(Box::new("complete".to_string())));
+        let signal = PropertiesPropertiesChanged {
+            interface_name: TRANSFER_INTERFACE.to_string(),
+            changed_properties: changed,
+            invalidated_properties: Vec::new(),
+        };
+        ctx.push_msg(signal.to_emit_message(&path));
+        path
+    }
+
+    // Stand-in obexd of a phone with contacts, missed calls and two message folders
+    fn stand_in_obexd(targets: Targets) -> Crossroads {
+        let mut crossroads = Crossroads::new();
+
+        let client = crossroads.register(CLIENT_INTERFACE, |b| {
+            b.method(
+                "CreateSession",
+                ("destination", "args"),
+                ("session",),
+                |_, _: &mut Targets, (_, args): (String, PropMap)| {
+                    let target = args.get("Target").and_then(|v| v.as_str()).unwrap_or("");
+                    let session = if target == "pbap" {
+                        PBAP_SESSION
+                    } else {
+                        MAP_SESSION
+                    };
+                    Ok((Path::from(session),))
+                },
+            );
+            b.method(
+                "RemoveSession",
+                ("session",),
+                (),
+                |_, _, (_,): (Path<'static>,)| Ok(()),
+            );
+        });
+
+        let phonebook =
+            crossroads.register(PHONEBOOK_INTERFACE, |b: &mut IfaceBuilder<Targets>| {
+                b.method(
+                    "Select",
+                    ("location", "phonebook"),
+                    (),
+                    |_, _, (_, phonebook): (String, String)| match phonebook.as_str() {
+                        "pb" | "mch" => Ok(()),
+                        _ => Err(("org.bluez.obex.Error.Failed", "Not Found").into()),
+                    },
+                );
+                b.method("GetSize", (), ("size",), |_, _, ()| Ok((3_u16,)));
+                b.method(
+                    "PullAll",
+                    ("targetfile", "filters"),
+                    ("transfer", "properties"),
+                    |ctx, targets, (target, _): (String, PropMap)| {
+                        targets.lock().unwrap().push(target);
+                        Ok((complete(ctx, PBAP_SESSION), PropMap::new()))
+                    },
+                );
+            });
+
+        let messages =
+            crossroads.register(MESSAGE_ACCESS_INTERFACE, |b: &mut IfaceBuilder<Targets>| {
+                b.method("SetFolder", ("name",), (), |_, _, (_,): (String,)| Ok(()));
+                b.method(
+                    "ListFolders",
+                    ("filter",),
+                    ("folders",),
+                    |_, _, (_,): (PropMap,)| {
+                        let folders: Vec<PropMap> = ["inbox", "sent"]
+                            .into_iter()
+                            .map(|name| {
+                                let mut folder = PropMap::new();
+                                folder.insert("Name".into(), Variant(Box::new(name.to_string())));
+                                folder
+                            })
+                            .collect();
+                        Ok((folders,))
+                    },
+                );
+                b.method(
+                    "ListMessages",
+                    ("folder", "filter"),
+                    ("messages",),
+                    |_, _, (_, _): (String, PropMap)| {
+                        let messages: Vec<(Path<'static>, PropMap)> = ["message1", "message2"]
+                            .into_iter()
+                            .map(|handle| {
+                                (
+                                    Path::from(format!("{MAP_SESSION}/{handle}")),
+                                    PropMap::new(),
+                                )
+                            })
+                            .collect();
+                        Ok((messages,))
+                    },
+                );
+            });
+
+        let message = crossroads.register(MESSAGE_INTERFACE, |b: &mut IfaceBuilder<Targets>| {
+            b.method(
+                "Get",
+                ("targetfile", "attachment"),
+                ("transfer", "properties"),
+                |ctx, targets, (target, _): (String, bool)| {
+                    targets.lock().unwrap().push(target);
+                    Ok((complete(ctx, MAP_SESSION), PropMap::new()))
+                },
+            );
+        });
+
+        crossroads.insert(CLIENT_PATH, &[client], targets.clone());
+        crossroads.insert(PBAP_SESSION, &[phonebook], targets.clone());
+        crossroads.insert(MAP_SESSION, &[messages], targets.clone());
+        crossroads.insert(
+            format!("{MAP_SESSION}/message1"),
+            &[message],
+            targets.clone(),
+        );
+        crossroads.insert(format!("{MAP_SESSION}/message2"), &[message], targets);
+        crossroads
+    }
+
+    #[tokio::test]
+    async fn list_and_export_phone_data() {
+        let Some(bus) = PrivateBus::start() else {
+            eprintln!("dbus-daemon is not available, skipping");
+            return;
+        };
+        let targets = Arc::new(Mutex::new(Vec::new()));
+        bus.serve(SERVICE, stand_in_obexd(targets.clone()));
+
+        let client = ObexClient::new(Bus::open(&bus.address).unwrap());
+        let device: Address = "AA:BB:CC:DD:EE:FF".parse().unwrap();
+        let folders = client.phone_folders(device).await.unwrap();
+        let labels: Vec<String> = folders.iter().map(Folder::label).collect();
+        assert_eq!(
+            labels,
+            [
+                "Contacts (3)",
+                "Missed calls (3)",
+                "Messages/inbox",
+                "Messages/sent"
+            ]
+        );
+
+        let dir = std::env::temp_dir().join(format!("bluetui-phone-{}", std::process::id()));
+        let (sender, mut events) = mpsc::unbounded_channel();
+
+        let events_sender = sender.clone();
+        client
+            .export(device, &folders[0], &dir, move |event| {
+                let _ = events_sender.send(event);
+            })
+            .await
+            .unwrap();
+        let Some(TransferEvent::Started(contacts)) = events.recv().await else {
+            panic!("the export is not started first");
+        };
+        assert_eq!(contacts.file_name, "contacts.vcf");
+        assert!(contacts.incoming);
+        let Some(TransferEvent::Updated(update)) = events.recv().await else {
+            panic!("the export is not updated");
+        };
+        assert_eq!(update.status, Some(TransferStatus::Complete));
+
+        let events_sender = sender.clone();
+        client
+            .export(device, &folders[2], &dir, move |event| {
+                let _ = events_sender.send(event);
+            })
+            .await
+            .unwrap();
+        let Some(TransferEvent::Started(mut inbox)) = events.recv().await else {
+            panic!("the export is not started first");
+        };
+        assert_eq!(inbox.size, Some(2));
+        while !inbox.status.is_finished() {
+            let Some(TransferEvent::Updated(update)) = events.recv().await else {
+                panic!("the export is not updated");
+            };
+            inbox.apply(&update);
+        }
+        assert_eq!(inbox.status, TransferStatus::Complete);
+        assert_eq!(inbox.transferred, 2);
+
+        // A folder named by the phone to lead out of the directory is not exported
+        let hostile = Folder::Messages {
+            name: "..".to_string(),
+        };
+        assert!(
+            client
+                .export(device, &hostile, &dir, move |event| {
+                    let _ = sender.send(event);
+                })
+                .await
+                .is_err()
+        );
+
+        let dir = std::fs::canonicalize(&dir).unwrap();
+        assert_eq!(
+            *targets.lock().unwrap(),
+            [
+                dir.join("contacts.vcf"),
+                dir.join("inbox").join("message1.bmsg"),
+                dir.join("inbox").join("message2.bmsg"),
+            ]
+            .map(|path| path.to_string_lossy().to_string())
+        );
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
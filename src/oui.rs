@@ -0,0 +1,141 @@
+use std::{collections::HashMap, sync::LazyLock};
+
+use bluer::{Address, AddressType};
+
+static VENDORS: LazyLock<HashMap<[u8; 3], &'static str>> = LazyLock::new(|| {
+    include_str!("oui.tsv")
+        .lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let (prefix, vendor) = line.split_once('\t')?;
+            let prefix = u32::from_str_radix(prefix, 16).ok()?;
+            let [_, a, b, c] = prefix.to_be_bytes();
+            Some(([a, b, c], vendor))
+        })
+        .collect()
+});
+
+pub fn vendor(address: Address) -> Option<&'static str> {
+    VENDORS
+        .get(&[address.0[0], address.0[1], address.0[2]])
+        .copied()
+}
+
+// What the address of a device tells about it. The LE random addresses are told apart by their 2
+// most significant bits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AddressKind {
+    Public { vendor: Option<&'static str> },
+    RandomStatic,
+    ResolvablePrivate,
+    NonResolvablePrivate,
+}
+
+impl AddressKind {
+    pub fn new(address: Address, address_type: AddressType) -> Self {
+        match address_type {
+            AddressType::BrEdr | AddressType::LePublic => AddressKind::Public {
+                vendor: vendor(address),
+            },
+            AddressType::LeRandom => match address.0[0] >> 6 {
+                0b11 => AddressKind::RandomStatic,
+                0b01 => AddressKind::ResolvablePrivate,
+                _ => AddressKind::NonResolvablePrivate,
+            },
+        }
+    }
+
+    pub fn vendor(self) -> Option<&'static str> {
+        match self {
+            AddressKind::Public { vendor } => vendor,
+            _ => None,
+        }
+    }
+
+    pub fn description(self) -> String {
+        match self {
+            AddressKind::Public {
+                vendor: Some(vendor),
+            } => format!("Public ({vendor})"),
+            AddressKind::Public { vendor: None } => "Public".to_string(),
+            AddressKind::RandomStatic => "Random static".to_string(),
+            AddressKind::ResolvablePrivate => "Resolvable private".to_string(),
+            AddressKind::NonResolvablePrivate => "Non-resolvable private".to_string(),
+        }
+    }
+
+    // Shown instead of the address of the devices without a name, e.g. "Bose 4C:87:…"
+    pub fn placeholder(self, address: Address) -> Option<String> {
+        match self {
+            AddressKind::Public {
+                vendor: Some(vendor),
+            } => Some(format!(
+                "{vendor} {:02X}:{:02X}:…",
+                address.0[0], address.0[1]
+            )),
+            AddressKind::Public { vendor: None } => None,
+            _ => Some(self.description()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn public_addresses() {
+        let raspberry_pi: Address = "B8:27:EB:12:34:56".parse().unwrap();
+        let kind = AddressKind::new(raspberry_pi, AddressType::BrEdr);
+        assert_eq!(kind.vendor(), Some("Raspberry Pi Foundation"));
+        assert_eq!(
+            kind.placeholder(raspberry_pi).as_deref(),
+            Some("Raspberry Pi Foundation B8:27:…")
+        );
+
+        let unknown: Address = "02:00:00:12:34:56".parse().unwrap();
+        let kind = AddressKind::new(unknown, AddressType::LePublic);
+        assert_eq!(kind, AddressKind::Public { vendor: None });
+        assert_eq!(kind.placeholder(unknown), None);
+    }
+
+    #[test]
+    fn vendors() {
+        let vendor = |address: &str| vendor(address.parse().unwrap());
+        assert_eq!(vendor("00:1B:63:12:34:56"), Some("Apple"));
+        assert_eq!(vendor("24:0A:C4:12:34:56"), Some("Espressif"));
+        assert_eq!(vendor("04:52:C7:12:34:56"), Some("Bose"));
+        assert_eq!(vendor("00:12:4B:12:34:56"), Some("Texas Instruments"));
+        assert_eq!(vendor("28:CD:C1:12:34:56"), Some("Raspberry Pi Trading"));
+    }
+
+    #[test]
+    fn table_is_well_formed() {
+        let prefixes: Vec<&str> = include_str!("oui.tsv")
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| {
+                let (prefix, vendor) = line.split_once('\t').unwrap();
+                assert!(
+                    prefix.len() == 6 && prefix.chars().all(|c| c.is_ascii_hexdigit()),
+                    "{line}"
+                );
+                assert!(!vendor.trim().is_empty(), "{line}");
+                prefix
+            })
+            .collect();
+        assert!(prefixes.is_sorted(), "the prefixes are not sorted");
+        assert_eq!(VENDORS.len(), prefixes.len(), "a prefix is repeated");
+    }
+
+    #[test]
+    fn random_addresses() {
+        let kind =
+            |address: &str| AddressKind::new(address.parse().unwrap(), AddressType::LeRandom);
+        assert_eq!(kind("C4:12:34:56:78:9A"), AddressKind::RandomStatic);
+        assert_eq!(kind("5E:12:34:56:78:9A"), AddressKind::ResolvablePrivate);
+        assert_eq!(kind("1E:12:34:56:78:9A"), AddressKind::NonResolvablePrivate);
+        // The vendor of a random address is meaningless
+        assert_eq!(kind("B8:27:EB:12:34:56").vendor(), None);
+    }
+}
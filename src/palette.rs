@@ -0,0 +1,558 @@
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, BorderType, Clear, List, ListState, Padding, Paragraph},
+};
+use tui_input::Input;
+
+use bluer::Address;
+
+use crate::{
+    app::{App, FocusedBlock},
+    bluetooth::Controller,
+};
+
+// Row the command applies to before its key is handled
+#[derive(Debug, Clone, PartialEq)]
+pub enum Target {
+    Global,
+    Adapter(String),
+    PairedDevice { adapter: String, address: Address },
+    NewDevice { adapter: String, address: Address },
+}
+
+// Rows of the target in the current lists
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Location {
+    pub adapter: Option<usize>,
+    pub device: Option<usize>,
+}
+
+impl Target {
+    pub fn focused_block(&self) -> Option<FocusedBlock> {
+        match self {
+            Target::Global => None,
+            Target::Adapter(_) => Some(FocusedBlock::Adapter),
+            Target::PairedDevice { .. } => Some(FocusedBlock::PairedDevices),
+            Target::NewDevice { .. } => Some(FocusedBlock::NewDevices),
+        }
+    }
+
+    // The lists are sorted again on every refresh, so the rows are found by name and address
+    pub fn locate(&self, controllers: &[Controller]) -> Option<Location> {
+        let adapters: Vec<_> = controllers
+            .iter()
+            .map(|controller| {
+                (
+                    controller.name.as_str(),
+                    controller.paired_devices.iter().map(|d| d.addr).collect(),
+                    controller.new_devices.iter().map(|d| d.addr).collect(),
+                )
+            })
+            .collect();
+        self.locate_in(&adapters)
+    }
+
+    // None when the adapter or the device is gone
+    fn locate_in(&self, adapters: &[(&str, Vec<Address>, Vec<Address>)]) -> Option<Location> {
+        let adapter = |name: &str| adapters.iter().position(|(n, _, _)| *n == name);
+        match self {
+            Target::Global => Some(Location {
+                adapter: None,
+                device: None,
+            }),
+            Target::Adapter(name) => Some(Location {
+                adapter: Some(adapter(name)?),
+                device: None,
+            }),
+            Target::PairedDevice {
+                adapter: name,
+                address,
+            }
+            | Target::NewDevice {
+                adapter: name,
+                address,
+            } => {
+                let i = adapter(name)?;
+                let (_, paired, new) = &adapters[i];
+                let devices = if let Target::PairedDevice { .. } = self {
+                    paired
+                } else {
+                    new
+                };
+                Some(Location {
+                    adapter: Some(i),
+                    device: Some(devices.iter().position(|a| a == address)?),
+                })
+            }
+        }
+    }
+}
+
+// What a command does, to its target when it has one
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    ToggleConnect,
+    ToggleTrust,
+    ToggleFavorite,
+    Rename,
+    AddToGroup,
+    Unpair,
+    ShowDetails,
+    SendFile,
+    Serial,
+    Sensors,
+    Record,
+    Media,
+    ToggleNetwork,
+    PhoneData,
+    Pair,
+    TogglePower,
+    TogglePairing,
+    ToggleDiscovery,
+    ToggleSharing,
+    Advertise,
+    GattServer,
+    AddDevice,
+    CancelTransfers,
+    ToggleScanning,
+    Search,
+    Groups,
+    Beacons,
+    KeyBindings,
+    Quit,
+}
+
+// An action of the app, run with its target selected
+#[derive(Debug, Clone)]
+pub struct Command {
+    pub label: String,
+    pub target: Target,
+    pub action: Action,
+}
+
+impl Command {
+    fn new(label: String, target: Target, action: Action) -> Self {
+        Self {
+            label,
+            target,
+            action,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct Palette {
+    pub input: Input,
+    pub state: ListState,
+    pub commands: Vec<Command>,
+    // Indices of the commands matching the input, best match first
+    pub matches: Vec<usize>,
+}
+
+impl Palette {
+    pub fn new(app: &App) -> Self {
+        let mut palette = Self {
+            commands: commands(app),
+            ..Self::default()
+        };
+        palette.update_matches();
+        palette
+    }
+
+    pub fn update_matches(&mut self) {
+        let pattern = self.input.value();
+        let mut scored: Vec<(i64, usize)> = self
+            .commands
+            .iter()
+            .enumerate()
+            .filter_map(|(i, command)| fuzzy_score(pattern, &command.label).map(|s| (s, i)))
+            .collect();
+        scored.sort_by_key(|(score, i)| (-score, *i));
+        self.matches = scored.into_iter().map(|(_, i)| i).collect();
+        self.state.select(if self.matches.is_empty() {
+            None
+        } else {
+            Some(0)
+        });
+    }
+
+    pub fn selected(&self) -> Option<&Command> {
+        self.state
+            .selected()
+            .and_then(|i| self.matches.get(i))
+            .map(|i| &self.commands[*i])
+    }
+
+    pub fn next(&mut self) {
+        if !self.matches.is_empty() {
+            let i = self
+                .state
+                .selected()
+                .map_or(0, |i| (i + 1) % self.matches.len());
+            self.state.select(Some(i));
+        }
+    }
+
+    pub fn previous(&mut self) {
+        if !self.matches.is_empty() {
+            let len = self.matches.len();
+            let i = self.state.selected().map_or(0, |i| (i + len - 1) % len);
+            self.state.select(Some(i));
+        }
+    }
+
+    pub fn render(&mut self, frame: &mut Frame, area: Rect) {
+        let area = area.centered(Constraint::Max(70), Constraint::Percentage(60));
+
+        let block = Block::bordered()
+            .title(" Commands ")
+            .title_style(Style::default().bold())
+            .padding(Padding::horizontal(1))
+            .border_type(BorderType::Thick)
+            .border_style(Style::default().green());
+        let inner = block.inner(area);
+
+        let [input_area, _, list_area] = Layout::vertical([
+            Constraint::Length(1),
+            Constraint::Length(1),
+            Constraint::Fill(1),
+        ])
+        .areas(inner);
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+        frame.render_widget(
+            Paragraph::new(Line::from(vec![
+                "> ".bold(),
+                self.input.value().into(),
+                "█".into(),
+            ])),
+            input_area,
+        );
+
+        let items: Vec<&str> = self
+            .matches
+            .iter()
+            .map(|i| self.commands[*i].label.as_str())
+            .collect();
+        if items.is_empty() {
+            frame.render_widget(Paragraph::new("No matching command").dark_gray(), list_area);
+        } else {
+            frame.render_stateful_widget(
+                List::new(items).highlight_style(Style::default().white().on_dark_gray()),
+                list_area,
+                &mut self.state,
+            );
+        }
+    }
+}
+
+// Every command available in the current state of the app
+fn commands(app: &App) -> Vec<Command> {
+    let mut commands = Vec::new();
+
+    if let Some(selected_controller) = app.controller_state.selected() {
+        let controller = &app.controllers[selected_controller];
+
+        for device in &controller.paired_devices {
+            let name = device.display_name();
+            let target = Target::PairedDevice {
+                adapter: controller.name.clone(),
+                address: device.addr,
+            };
+            commands.extend([
+                Command::new(
+                    if device.is_connected {
+                        format!("Disconnect {name}")
+                    } else {
+                        format!("Connect {name}")
+                    },
+                    target.clone(),
+                    Action::ToggleConnect,
+                ),
+                Command::new(
+                    if device.is_trusted {
+                        format!("Untrust {name}")
+                    } else {
+                        format!("Trust {name}")
+                    },
+                    target.clone(),
+                    Action::ToggleTrust,
+                ),
+                Command::new(
+                    if device.is_favorite {
+                        format!("Unfavorite {name}")
+                    } else {
+                        format!("Favorite {name}")
+                    },
+                    target.clone(),
+                    Action::ToggleFavorite,
+                ),
+                Command::new(format!("Rename {name}"), target.clone(), Action::Rename),
+                Command::new(
+                    format!("Add {name} to a group"),
+                    target.clone(),
+                    Action::AddToGroup,
+                ),
+                Command::new(format!("Unpair {name}"), target.clone(), Action::Unpair),
+                Command::new(
+                    format!("Show details of {name}"),
+                    target.clone(),
+                    Action::ShowDetails,
+                ),
+                Command::new(
+                    format!("Send a file to {name}"),
+                    target.clone(),
+                    Action::SendFile,
+                ),
+                Command::new(
+                    format!("Open a serial terminal to {name}"),
+                    target.clone(),
+                    Action::Serial,
+                ),
+            ]);
+            if device.is_connected {
+                commands.extend([
+                    Command::new(
+                        format!("Sensor readings of {name}"),
+                        target.clone(),
+                        Action::Sensors,
+                    ),
+                    Command::new(
+                        format!("Record the notifications of {name}"),
+                        target.clone(),
+                        Action::Record,
+                    ),
+                ]);
+            }
+            if app.player(device.addr).is_some() {
+                commands.push(Command::new(
+                    format!("Media controls of {name}"),
+                    target.clone(),
+                    Action::Media,
+                ));
+            }
+            if let Some(network) = app.network(device.addr) {
+                commands.push(Command::new(
+                    if network.connected {
+                        format!("Disconnect from the network of {name}")
+                    } else {
+                        format!("Connect to the network of {name}")
+                    },
+                    target.clone(),
+                    Action::ToggleNetwork,
+                ));
+            }
+            if device.is_connected {
+                commands.push(Command::new(
+                    format!("Export the phonebook and messages of {name}"),
+                    target.clone(),
+                    Action::PhoneData,
+                ));
+            }
+        }
+
+        for device in &controller.new_devices {
+            let name = device.display_name();
+            let target = Target::NewDevice {
+                adapter: controller.name.clone(),
+                address: device.addr,
+            };
+            commands.extend([
+                Command::new(format!("Pair {name}"), target.clone(), Action::Pair),
+                Command::new(
+                    format!("Show details of {name}"),
+                    target.clone(),
+                    Action::ShowDetails,
+                ),
+            ]);
+        }
+    }
+
+    for controller in &app.controllers {
+        let name = &controller.name;
+        let target = Target::Adapter(name.clone());
+        commands.extend([
+            Command::new(
+                if controller.is_powered {
+                    format!("Power off {name}")
+                } else {
+                    format!("Power on {name}")
+                },
+                target.clone(),
+                Action::TogglePower,
+            ),
+            Command::new(
+                if controller.is_pairable {
+                    format!("Disable pairing on {name}")
+                } else {
+                    format!("Enable pairing on {name}")
+                },
+                target.clone(),
+                Action::TogglePairing,
+            ),
+            Command::new(
+                if controller.is_discoverable {
+                    format!("Disable discovery on {name}")
+                } else {
+                    format!("Enable discovery on {name}")
+                },
+                target.clone(),
+                Action::ToggleDiscovery,
+            ),
+            Command::new(
+                if app.sharing.contains(&controller.name) {
+                    format!("Stop sharing the connection on {name}")
+                } else {
+                    format!("Share the connection on {name}")
+                },
+                target.clone(),
+                Action::ToggleSharing,
+            ),
+            Command::new(
+                format!("Advertise on {name}"),
+                target.clone(),
+                Action::Advertise,
+            ),
+            Command::new(
+                format!("Serve the GATT application on {name}"),
+                target.clone(),
+                Action::GattServer,
+            ),
+            Command::new(
+                format!("Add a device to {name} by its address"),
+                target.clone(),
+                Action::AddDevice,
+            ),
+        ]);
+    }
+
+    if !app.transfers.is_empty() {
+        commands.push(Command::new(
+            "Cancel the file transfers".to_string(),
+            Target::Global,
+            Action::CancelTransfers,
+        ));
+    }
+
+    commands.extend([
+        Command::new(
+            "Start/Stop scanning".to_string(),
+            Target::Global,
+            Action::ToggleScanning,
+        ),
+        Command::new("Search devices".to_string(), Target::Global, Action::Search),
+        Command::new("Device groups".to_string(), Target::Global, Action::Groups),
+        Command::new(
+            "Beacons nearby".to_string(),
+            Target::Global,
+            Action::Beacons,
+        ),
+        Command::new(
+            "Show all key bindings".to_string(),
+            Target::Global,
+            Action::KeyBindings,
+        ),
+        Command::new("Quit".to_string(), Target::Global, Action::Quit),
+    ]);
+
+    commands
+}
+
+// Score of `pattern` as a case insensitive subsequence of `text`, `None` if it is not one.
+// Consecutive characters and characters at the start of a word score higher.
+pub fn fuzzy_score(pattern: &str, text: &str) -> Option<i64> {
+    let text: Vec<char> = text.chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous_match: Option<usize> = None;
+
+    for p in pattern.chars().filter(|c| !c.is_whitespace()) {
+        let index =
+            (position..text.len()).find(|&i| text[i].to_lowercase().eq(p.to_lowercase()))?;
+
+        score += 1;
+        if previous_match.is_some_and(|previous| previous + 1 == index) {
+            score += 5;
+        }
+        if index == 0 || !text[index - 1].is_alphanumeric() {
+            score += 3;
+        }
+        // Gaps cost a little so that tighter matches come first
+        score -= i64::try_from(index - position).unwrap_or(i64::MAX).min(5);
+
+        previous_match = Some(index);
+        position = index + 1;
+    }
+
+    Some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn fuzzy_matching() {
+        assert!(fuzzy_score("", "Quit").is_some());
+        assert!(fuzzy_score("cnhead", "Connect Headset").is_some());
+        assert!(fuzzy_score("xyz", "Connect Headset").is_none());
+        assert!(fuzzy_score("headset connect", "Connect Headset").is_none());
+
+        let connect = fuzzy_score("con", "Connect Headset").unwrap();
+        let discovery = fuzzy_score("con", "Enable discovery on hci0").unwrap();
+        assert!(connect > discovery);
+    }
+
+    #[test]
+    fn targets_follow_the_reordered_lists() {
+        let headset = Address::new([0x00, 0x1A, 0x7D, 0xDA, 0x71, 0x13]);
+        let mouse = Address::new([0xC8, 0x3F, 0x26, 0x01, 0x02, 0x03]);
+        let target = Target::PairedDevice {
+            adapter: "hci1".to_string(),
+            address: mouse,
+        };
+
+        let opened = [
+            ("hci0", vec![], vec![]),
+            ("hci1", vec![mouse, headset], vec![]),
+        ];
+        assert_eq!(
+            target.locate_in(&opened),
+            Some(Location {
+                adapter: Some(1),
+                device: Some(0),
+            })
+        );
+
+        // Refreshed while the palette was open
+        let refreshed = [
+            ("hci1", vec![headset, mouse], vec![]),
+            ("hci0", vec![], vec![]),
+        ];
+        assert_eq!(
+            target.locate_in(&refreshed),
+            Some(Location {
+                adapter: Some(0),
+                device: Some(1),
+            })
+        );
+        assert_eq!(
+            Target::Adapter("hci0".to_string()).locate_in(&refreshed),
+            Some(Location {
+                adapter: Some(1),
+                device: None,
+            })
+        );
+
+        let unpaired = [("hci1", vec![headset], vec![mouse])];
+        assert_eq!(target.locate_in(&unpaired), None);
+        assert_eq!(
+            Target::Adapter("hci0".to_string()).locate_in(&unpaired),
+            None
+        );
+    }
+}
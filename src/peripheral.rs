@@ -0,0 +1,663 @@
+use std::{
+    collections::{BTreeMap, VecDeque},
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::Duration,
+};
+
+use anyhow::{Context, bail};
+use bluer::{
+    Adapter, Address,
+    gatt::local::{
+        Application, ApplicationHandle, Characteristic, CharacteristicNotify,
+        CharacteristicNotifyMethod, CharacteristicRead, CharacteristicWrite,
+        CharacteristicWriteMethod, ReqError, Service,
+    },
+};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Clear, Padding, Paragraph},
+};
+use serde::Deserialize;
+use tokio::sync::mpsc::UnboundedSender;
+
+use crate::{
+    advertising::{format_hex, parse_hex, parse_uuid},
+    app::AppResult,
+    event::Event,
+    history::format_time,
+    storage,
+};
+
+// Activity kept in the panel
+const ACTIVITY: usize = 200;
+
+// A GATT application declared in TOML, served by an adapter to test the apps of the centrals
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ApplicationFile {
+    #[serde(default, rename = "service")]
+    pub services: Vec<ServiceFile>,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct ServiceFile {
+    pub uuid: String,
+    #[serde(default = "default_primary")]
+    pub primary: bool,
+    #[serde(default, rename = "characteristic")]
+    pub characteristics: Vec<CharacteristicFile>,
+}
+
+fn default_primary() -> bool {
+    true
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "kebab-case")]
+pub enum Flag {
+    Read,
+    Write,
+    WriteWithoutResponse,
+    Notify,
+    Indicate,
+}
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct CharacteristicFile {
+    pub uuid: String,
+    // Shown in the activity, the UUID by default
+    pub name: Option<String>,
+    pub flags: Vec<Flag>,
+    // The value is one of: hex bytes, UTF-8 text, the content of a file or the output of a
+    // command, read again for each read and notification
+    pub value: Option<String>,
+    pub text: Option<String>,
+    pub file: Option<PathBuf>,
+    pub command: Option<String>,
+    // Milliseconds between the notifications
+    #[serde(default = "default_notify_interval")]
+    pub notify_interval: u64,
+}
+
+fn default_notify_interval() -> u64 {
+    1000
+}
+
+impl CharacteristicFile {
+    fn label(&self) -> String {
+        self.name.clone().unwrap_or_else(|| self.uuid.clone())
+    }
+}
+
+impl ApplicationFile {
+    pub fn load(file_path: &Path) -> AppResult<Self> {
+        let content = std::fs::read_to_string(file_path)
+            .with_context(|| format!("unable to read {}", file_path.display()))?;
+        let application: Self = toml::from_str(&content)
+            .with_context(|| format!("invalid GATT application {}", file_path.display()))?;
+        if application.services.is_empty() {
+            bail!("{} does not declare any service", file_path.display());
+        }
+        Ok(application)
+    }
+}
+
+#[derive(Debug)]
+enum Source {
+    // Changed by the writes
+    Value(Mutex<Vec<u8>>),
+    File(PathBuf),
+    Command(String),
+}
+
+impl Source {
+    fn new(characteristic: &CharacteristicFile) -> Result<Self, String> {
+        let label = characteristic.label();
+        match (
+            &characteristic.value,
+            &characteristic.text,
+            &characteristic.file,
+            &characteristic.command,
+        ) {
+            (None, None, None, None) => Ok(Source::Value(Mutex::default())),
+            (Some(value), None, None, None) => Ok(Source::Value(Mutex::new(parse_hex(value)?))),
+            (None, Some(text), None, None) => Ok(Source::Value(Mutex::new(text.clone().into()))),
+            (None, None, Some(file), None) => Ok(Source::File(file.clone())),
+            (None, None, None, Some(command)) => Ok(Source::Command(command.clone())),
+            _ => Err(format!(
+                "{label}: only one of value, text, file and command can be set"
+            )),
+        }
+    }
+
+    async fn read(&self) -> AppResult<Vec<u8>> {
+        match self {
+            Source::Value(value) => Ok(value.lock().unwrap().clone()),
+            Source::File(file) => {
+                std::fs::read(file).with_context(|| format!("unable to read {}", file.display()))
+            }
+            Source::Command(command) => {
+                let command = command.clone();
+                let output = tokio::task::spawn_blocking(move || {
+                    std::process::Command::new("sh")
+                        .arg("-c")
+                        .arg(&command)
+                        .output()
+                })
+                .await??;
+                if !output.status.success() {
+                    bail!("the command failed with {}", output.status);
+                }
+                Ok(output.stdout)
+            }
+        }
+    }
+
+    // The values of the commands can not be written
+    fn write(&self, data: &[u8], offset: usize) -> AppResult<()> {
+        let write = |value: &mut Vec<u8>| {
+            value.resize(value.len().max(offset), 0);
+            value.truncate(offset);
+            value.extend_from_slice(data);
+        };
+        match self {
+            Source::Value(value) => write(&mut value.lock().unwrap()),
+            Source::File(file) => {
+                let mut value = std::fs::read(file).unwrap_or_default();
+                write(&mut value);
+                std::fs::write(file, value)
+                    .with_context(|| format!("unable to write {}", file.display()))?;
+            }
+            Source::Command(_) => bail!("the value comes from a command"),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ActivityKind {
+    Read(Vec<u8>),
+    Write(Vec<u8>),
+    Subscribed,
+    Unsubscribed,
+    Failed(String),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Activity {
+    pub time: u64,
+    // BlueZ does not tell which central subscribes
+    pub central: Option<Address>,
+    pub characteristic: String,
+    pub kind: ActivityKind,
+}
+
+// A served characteristic, the requests of the centrals are reported as activity
+#[derive(Debug)]
+struct Served {
+    label: String,
+    source: Source,
+    sender: UnboundedSender<Event>,
+}
+
+impl Served {
+    fn report(&self, central: Option<Address>, kind: ActivityKind) {
+        let _ = self.sender.send(Event::GattActivity(Activity {
+            time: storage::now(),
+            central,
+            characteristic: self.label.clone(),
+            kind,
+        }));
+    }
+
+    // Long values are read in several requests, only the first one is reported
+    async fn read(&self, central: Address, offset: usize) -> Result<Vec<u8>, ReqError> {
+        match self.source.read().await {
+            Ok(value) => {
+                if offset == 0 {
+                    self.report(Some(central), ActivityKind::Read(value.clone()));
+                }
+                value
+                    .get(offset..)
+                    .map(<[u8]>::to_vec)
+                    .ok_or(ReqError::InvalidOffset)
+            }
+            Err(e) => {
+                self.report(Some(central), ActivityKind::Failed(e.to_string()));
+                Err(ReqError::Failed)
+            }
+        }
+    }
+
+    fn write(&self, central: Address, data: Vec<u8>, offset: usize) -> Result<(), ReqError> {
+        match self.source.write(&data, offset) {
+            Ok(()) => {
+                self.report(Some(central), ActivityKind::Write(data));
+                Ok(())
+            }
+            Err(e) => {
+                self.report(Some(central), ActivityKind::Failed(e.to_string()));
+                Err(ReqError::NotSupported)
+            }
+        }
+    }
+}
+
+fn characteristic(
+    file: &CharacteristicFile,
+    sender: &UnboundedSender<Event>,
+) -> Result<Characteristic, String> {
+    let served = Arc::new(Served {
+        label: file.label(),
+        source: Source::new(file)?,
+        sender: sender.clone(),
+    });
+    let has = |flag: Flag| file.flags.contains(&flag);
+
+    let read = has(Flag::Read).then(|| {
+        let served = served.clone();
+        CharacteristicRead {
+            read: true,
+            fun: Box::new(move |request| {
+                let served = served.clone();
+                Box::pin(async move {
+                    served
+                        .read(request.device_address, request.offset.into())
+                        .await
+                })
+            }),
+            ..Default::default()
+        }
+    });
+
+    let write = (has(Flag::Write) || has(Flag::WriteWithoutResponse)).then(|| {
+        let served = served.clone();
+        CharacteristicWrite {
+            write: has(Flag::Write),
+            write_without_response: has(Flag::WriteWithoutResponse),
+            method: CharacteristicWriteMethod::Fun(Box::new(move |data, request| {
+                let served = served.clone();
+                Box::pin(async move {
+                    served.write(request.device_address, data, request.offset.into())
+                })
+            })),
+            ..Default::default()
+        }
+    });
+
+    let interval = Duration::from_millis(file.notify_interval);
+    let notify = (has(Flag::Notify) || has(Flag::Indicate)).then(|| CharacteristicNotify {
+        notify: has(Flag::Notify),
+        indicate: has(Flag::Indicate),
+        method: CharacteristicNotifyMethod::Fun(Box::new(move |mut notifier| {
+            let served = served.clone();
+            Box::pin(async move {
+                served.report(None, ActivityKind::Subscribed);
+                loop {
+                    match served.source.read().await {
+                        Ok(value) => {
+                            if notifier.notify(value).await.is_err() {
+                                break;
+                            }
+                        }
+                        Err(e) => {
+                            served.report(None, ActivityKind::Failed(e.to_string()));
+                            break;
+                        }
+                    }
+                    tokio::select! {
+                        () = tokio::time::sleep(interval) => {}
+                        () = notifier.stopped() => break,
+                    }
+                }
+                served.report(None, ActivityKind::Unsubscribed);
+            })
+        })),
+        ..Default::default()
+    });
+
+    Ok(Characteristic {
+        uuid: parse_uuid(&file.uuid)?,
+        read,
+        write,
+        notify,
+        ..Default::default()
+    })
+}
+
+pub fn application(
+    file: &ApplicationFile,
+    sender: &UnboundedSender<Event>,
+) -> Result<Application, String> {
+    let services = file
+        .services
+        .iter()
+        .map(|service| {
+            Ok(Service {
+                uuid: parse_uuid(&service.uuid)?,
+                primary: service.primary,
+                characteristics: service
+                    .characteristics
+                    .iter()
+                    .map(|c| characteristic(c, sender))
+                    .collect::<Result<_, String>>()?,
+                ..Default::default()
+            })
+        })
+        .collect::<Result<_, String>>()?;
+    Ok(Application {
+        services,
+        ..Default::default()
+    })
+}
+
+// The panel of the GATT application, it is served until it is stopped or bluetui exits
+#[derive(Debug)]
+pub struct GattServer {
+    pub adapter: String,
+    pub file_path: PathBuf,
+    handle: Option<ApplicationHandle>,
+    activity: VecDeque<Activity>,
+    // Reads and writes of each central
+    centrals: BTreeMap<Address, (usize, usize)>,
+    subscriptions: usize,
+    pub error: Option<String>,
+}
+
+impl GattServer {
+    pub fn new(adapter: String, file_path: PathBuf) -> Self {
+        Self {
+            adapter,
+            file_path,
+            handle: None,
+            activity: VecDeque::new(),
+            centrals: BTreeMap::new(),
+            subscriptions: 0,
+            error: None,
+        }
+    }
+
+    pub fn is_serving(&self) -> bool {
+        self.handle.is_some()
+    }
+
+    pub async fn start(&mut self, adapter: &Adapter, sender: &UnboundedSender<Event>) {
+        let application = ApplicationFile::load(&self.file_path)
+            .map_err(|e| format!("{e:#}"))
+            .and_then(|file| application(&file, sender));
+        let result = match application {
+            Ok(application) => adapter
+                .serve_gatt_application(application)
+                .await
+                .map_err(|e| e.to_string()),
+            Err(e) => Err(e),
+        };
+        match result {
+            Ok(handle) => {
+                self.handle = Some(handle);
+                self.error = None;
+                self.activity.clear();
+                self.centrals.clear();
+                self.subscriptions = 0;
+            }
+            Err(e) => self.error = Some(e),
+        }
+    }
+
+    pub fn stop(&mut self) {
+        self.handle = None;
+        self.subscriptions = 0;
+    }
+
+    pub fn record(&mut self, activity: Activity) {
+        if let Some(central) = activity.central {
+            let (reads, writes) = self.centrals.entry(central).or_default();
+            match activity.kind {
+                ActivityKind::Read(_) => *reads += 1,
+                ActivityKind::Write(_) => *writes += 1,
+                _ => {}
+            }
+        }
+        match activity.kind {
+            ActivityKind::Subscribed => self.subscriptions += 1,
+            ActivityKind::Unsubscribed => self.subscriptions = self.subscriptions.saturating_sub(1),
+            _ => {}
+        }
+        if self.activity.len() == ACTIVITY {
+            self.activity.pop_front();
+        }
+        self.activity.push_back(activity);
+    }
+
+    // `connected` tells whether a central is still connected to the adapter
+    pub fn render(&self, connected: impl Fn(Address) -> bool, frame: &mut Frame, area: Rect) {
+        let area = area.centered(Constraint::Percentage(80), Constraint::Percentage(80));
+
+        let block = Block::bordered()
+            .title(format!(" GATT server on {} ", self.adapter))
+            .title_style(Style::default().bold())
+            .padding(Padding::horizontal(1))
+            .border_type(BorderType::Thick)
+            .border_style(Style::default().green());
+        let inner = block.inner(area);
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+
+        let [status_area, _, centrals_area, _, activity_area] = Layout::vertical([
+            Constraint::Length(2),
+            Constraint::Length(1),
+            Constraint::Length(self.centrals.len().clamp(1, 5) as u16 + 1),
+            Constraint::Length(1),
+            Constraint::Fill(1),
+        ])
+        .areas(inner);
+
+        let state = if self.is_serving() {
+            Span::from(format!(
+                "serving · {} notification session(s)",
+                self.subscriptions
+            ))
+            .green()
+        } else {
+            Span::from("stopped").dark_gray()
+        };
+        let mut status = vec![
+            Line::from(vec![
+                Span::from("Application  "),
+                Span::from(self.file_path.display().to_string()).bold(),
+            ]),
+            Line::from(vec![Span::from("State        "), state]),
+        ];
+        if let Some(error) = &self.error {
+            status[1] = Line::from(vec![
+                Span::from("State        "),
+                Span::from(error.as_str()).red(),
+            ]);
+        }
+        frame.render_widget(Paragraph::new(status), status_area);
+
+        let mut centrals = vec![Line::from("Centrals").bold()];
+        if self.centrals.is_empty() {
+            centrals.push(Line::from(" none yet").dark_gray());
+        }
+        centrals.extend(
+            self.centrals
+                .iter()
+                .take(5)
+                .map(|(address, (reads, writes))| {
+                    Line::from(vec![
+                        Span::from(format!(" {address}  {reads} read(s)  {writes} write(s)  ")),
+                        if connected(*address) {
+                            Span::from("connected").green()
+                        } else {
+                            Span::from("disconnected").dark_gray()
+                        },
+                    ])
+                }),
+        );
+        frame.render_widget(Paragraph::new(centrals), centrals_area);
+
+        let height = activity_area.height.saturating_sub(1) as usize;
+        let mut activity = vec![Line::from("Activity").bold()];
+        activity.extend(
+            self.activity
+                .iter()
+                .rev()
+                .take(height)
+                .rev()
+                .map(|activity| {
+                    let time = format_time(activity.time);
+                    let central = activity
+                        .central
+                        .map(|address| format!("{address} "))
+                        .unwrap_or_default();
+                    let kind = match &activity.kind {
+                        ActivityKind::Read(value) => format!("read {}", format_hex(value)),
+                        ActivityKind::Write(value) => format!("write {}", format_hex(value)),
+                        ActivityKind::Subscribed => "subscribed".to_string(),
+                        ActivityKind::Unsubscribed => "unsubscribed".to_string(),
+                        ActivityKind::Failed(e) => format!("failed: {e}"),
+                    };
+                    Line::from(vec![
+                        Span::from(format!(" {} ", &time[11..19])).dark_gray(),
+                        Span::from(central),
+                        Span::from(format!("{} ", activity.characteristic)).bold(),
+                        Span::from(kind),
+                    ])
+                }),
+        );
+        frame.render_widget(Paragraph::new(activity), activity_area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_snapshot;
+    use ratatui::{Terminal, backend::TestBackend};
+    use tokio::sync::mpsc::unbounded_channel;
+
+    const HEART_RATE: &str = r#"
+[[service]]
+uuid = "180d"
+
+[[service.characteristic]]
+uuid = "2a37"
+name = "Heart rate"
+flags = ["read", "notify"]
+command = "printf '\\000\\110'"
+
+[[service.characteristic]]
+uuid = "2a39"
+name = "Control point"
+flags = ["write"]
+value = "00"
+"#;
+
+    #[test]
+    fn parse_an_application() {
+        let file: ApplicationFile = toml::from_str(HEART_RATE).unwrap();
+        assert!(file.services[0].primary);
+        assert_eq!(
+            file.services[0].characteristics[0].flags,
+            [Flag::Read, Flag::Notify]
+        );
+        assert_eq!(file.services[0].characteristics[0].notify_interval, 1000);
+
+        let (sender, _) = unbounded_channel();
+        let application = application(&file, &sender).unwrap();
+        assert_eq!(application.services[0].uuid, parse_uuid("180d").unwrap());
+        let characteristics = &application.services[0].characteristics;
+        assert!(characteristics[0].read.is_some() && characteristics[0].notify.is_some());
+        assert!(characteristics[1].write.is_some() && characteristics[1].read.is_none());
+    }
+
+    #[test]
+    fn only_one_source() {
+        let file: ApplicationFile = toml::from_str(
+            "[[service]]\nuuid = \"180f\"\n[[service.characteristic]]\nuuid = \"2a19\"\nflags = [\"read\"]\nvalue = \"64\"\ntext = \"d\"\n",
+        )
+        .unwrap();
+        let (sender, _) = unbounded_channel();
+        assert_eq!(
+            application(&file, &sender).unwrap_err(),
+            "2a19: only one of value, text, file and command can be set"
+        );
+    }
+
+    #[tokio::test]
+    async fn reads_and_writes_are_reported() {
+        let central = Address::new(*b"DEADBE");
+        let (sender, mut events) = unbounded_channel();
+        let file: ApplicationFile = toml::from_str(HEART_RATE).unwrap();
+        let characteristic = |i: usize| Served {
+            label: file.services[0].characteristics[i].label(),
+            source: Source::new(&file.services[0].characteristics[i]).unwrap(),
+            sender: sender.clone(),
+        };
+
+        let heart_rate = characteristic(0);
+        assert_eq!(heart_rate.read(central, 0).await.unwrap(), [0, 72]);
+        assert_eq!(heart_rate.read(central, 1).await.unwrap(), [72]);
+        assert!(heart_rate.write(central, vec![1], 0).is_err());
+
+        let control_point = characteristic(1);
+        control_point.write(central, vec![1, 2], 0).unwrap();
+        control_point.write(central, vec![3], 1).unwrap();
+        assert_eq!(control_point.source.read().await.unwrap(), [1, 3]);
+
+        let mut server = GattServer::new("hci0".to_string(), PathBuf::from("gatt.toml"));
+        while let Ok(Event::GattActivity(activity)) = events.try_recv() {
+            server.record(activity);
+        }
+        let kinds: Vec<_> = server.activity.iter().map(|a| a.kind.clone()).collect();
+        assert_eq!(
+            kinds,
+            [
+                ActivityKind::Read(vec![0, 72]),
+                ActivityKind::Failed("the value comes from a command".to_string()),
+                ActivityKind::Write(vec![1, 2]),
+                ActivityKind::Write(vec![3]),
+            ]
+        );
+        assert_eq!(server.centrals[&central], (1, 2));
+    }
+
+    #[test]
+    fn render() {
+        let mut terminal = Terminal::new(TestBackend::new(90, 20)).unwrap();
+        let mut server = GattServer::new(
+            "hci0".to_string(),
+            PathBuf::from("/home/user/.config/bluetui/gatt.toml"),
+        );
+        let central = Address::new(*b"DEADBE");
+        for (time, kind) in [
+            (45_296, ActivityKind::Read(vec![0, 72])),
+            (45_297, ActivityKind::Write(vec![1])),
+        ] {
+            server.record(Activity {
+                time,
+                central: Some(central),
+                characteristic: "Heart rate".to_string(),
+                kind,
+            });
+        }
+        server.record(Activity {
+            time: 45_298,
+            central: None,
+            characteristic: "Heart rate".to_string(),
+            kind: ActivityKind::Subscribed,
+        });
+        terminal
+            .draw(|frame| server.render(|_| true, frame, frame.area()))
+            .unwrap();
+        assert_snapshot!(terminal.backend());
+    }
+}
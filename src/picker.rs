@@ -0,0 +1,185 @@
+use std::{
+    io,
+    path::{Path, PathBuf},
+};
+
+use ratatui::{
+    Frame,
+    layout::{Constraint, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, BorderType, Clear, List, ListState, Padding, Paragraph},
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Entry {
+    pub name: String,
+    pub is_dir: bool,
+}
+
+// Browser of the file system to choose a file
+#[derive(Debug, Default)]
+pub struct FilePicker {
+    pub dir: PathBuf,
+    pub entries: Vec<Entry>,
+    pub state: ListState,
+}
+
+impl FilePicker {
+    pub fn open(dir: &Path) -> io::Result<Self> {
+        let mut picker = Self::default();
+        picker.change_dir(dir)?;
+        Ok(picker)
+    }
+
+    // Directories first, hidden entries are skipped
+    fn change_dir(&mut self, dir: &Path) -> io::Result<()> {
+        let mut entries: Vec<Entry> = std::fs::read_dir(dir)?
+            .filter_map(Result::ok)
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().to_string();
+                if name.starts_with('.') {
+                    return None;
+                }
+                let is_dir = entry.path().is_dir();
+                Some(Entry { name, is_dir })
+            })
+            .collect();
+        entries.sort_by(|a, b| {
+            b.is_dir
+                .cmp(&a.is_dir)
+                .then_with(|| a.name.to_lowercase().cmp(&b.name.to_lowercase()))
+        });
+
+        self.dir = dir.to_path_buf();
+        self.entries = entries;
+        self.state.select(if self.entries.is_empty() {
+            None
+        } else {
+            Some(0)
+        });
+        Ok(())
+    }
+
+    pub fn next(&mut self) {
+        if !self.entries.is_empty() {
+            let i = self
+                .state
+                .selected()
+                .map_or(0, |i| (i + 1).min(self.entries.len() - 1));
+            self.state.select(Some(i));
+        }
+    }
+
+    pub fn previous(&mut self) {
+        if !self.entries.is_empty() {
+            let i = self.state.selected().map_or(0, |i| i.saturating_sub(1));
+            self.state.select(Some(i));
+        }
+    }
+
+    // Enter the selected directory, or return the selected file
+    pub fn enter(&mut self) -> io::Result<Option<PathBuf>> {
+        let Some(entry) = self.state.selected().and_then(|i| self.entries.get(i)) else {
+            return Ok(None);
+        };
+        let path = self.dir.join(&entry.name);
+        if entry.is_dir {
+            self.change_dir(&path)?;
+            Ok(None)
+        } else {
+            Ok(Some(path))
+        }
+    }
+
+    // Go to the parent directory with the directory we come from selected
+    pub fn parent(&mut self) -> io::Result<()> {
+        let Some(parent) = self.dir.parent().map(Path::to_path_buf) else {
+            return Ok(());
+        };
+        let previous = self
+            .dir
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string());
+        self.change_dir(&parent)?;
+        if let Some(i) = self
+            .entries
+            .iter()
+            .position(|entry| Some(&entry.name) == previous.as_ref())
+        {
+            self.state.select(Some(i));
+        }
+        Ok(())
+    }
+
+    pub fn render(&mut self, title: &str, frame: &mut Frame, area: Rect) {
+        let area = area.centered(Constraint::Max(70), Constraint::Percentage(60));
+
+        let block = Block::bordered()
+            .title(format!(" {title} "))
+            .title_bottom(Line::from(format!(" {} ", self.dir.display())).dark_gray())
+            .title_style(Style::default().bold())
+            .padding(Padding::horizontal(1))
+            .border_type(BorderType::Thick)
+            .border_style(Style::default().green());
+        let inner = block.inner(area);
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+
+        if self.entries.is_empty() {
+            frame.render_widget(Paragraph::new("Empty directory").dark_gray(), inner);
+            return;
+        }
+
+        let items: Vec<Line> = self
+            .entries
+            .iter()
+            .map(|entry| {
+                if entry.is_dir {
+                    Line::from(format!(" {}/", entry.name)).blue()
+                } else {
+                    Line::from(format!(" {}", entry.name))
+                }
+            })
+            .collect();
+        frame.render_stateful_widget(
+            List::new(items).highlight_style(Style::default().white().on_dark_gray()),
+            inner,
+            &mut self.state,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn browse_directories() {
+        let root = std::env::temp_dir().join(format!("bluetui-picker-{}", std::process::id()));
+        let _ = std::fs::remove_dir_all(&root);
+        std::fs::create_dir_all(root.join("Photos")).unwrap();
+        std::fs::write(root.join("b.txt"), "").unwrap();
+        std::fs::write(root.join("A.txt"), "").unwrap();
+        std::fs::write(root.join(".hidden"), "").unwrap();
+        std::fs::write(root.join("Photos").join("cat.jpg"), "").unwrap();
+
+        let mut picker = FilePicker::open(&root).unwrap();
+        let names: Vec<&str> = picker.entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["Photos", "A.txt", "b.txt"]);
+
+        assert_eq!(picker.enter().unwrap(), None);
+        assert_eq!(picker.dir, root.join("Photos"));
+        assert_eq!(
+            picker.enter().unwrap(),
+            Some(root.join("Photos").join("cat.jpg"))
+        );
+
+        picker.parent().unwrap();
+        assert_eq!(picker.dir, root);
+        assert_eq!(picker.state.selected(), Some(0));
+
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,705 @@
+use std::{
+    collections::HashSet,
+    fmt,
+    fs::OpenOptions,
+    io::{self, LineWriter, Write},
+    path::{Path, PathBuf},
+    sync::{
+        Arc,
+        atomic::{AtomicUsize, Ordering},
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::{Context, bail};
+use bluer::{Address, Device, Session, Uuid, gatt::remote::Characteristic};
+use clap::ValueEnum;
+use futures::{StreamExt, stream::BoxStream};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Rect},
+    style::{Style, Stylize},
+    text::Line,
+    widgets::{Block, BorderType, Clear, List, ListState, Padding, Paragraph},
+};
+use serde::{Deserialize, Serialize};
+use tokio::{sync::mpsc::UnboundedSender, task::JoinHandle};
+
+use crate::{
+    advertising::format_hex,
+    app::AppResult,
+    assigned_numbers::{characteristic_name, short_uuid},
+    event::Event,
+    history::format_duration,
+    sensors::decode,
+    storage,
+};
+
+// Time between two attempts to connect a device that went out of range
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize, ValueEnum)]
+#[serde(rename_all = "lowercase")]
+pub enum RecordFormat {
+    #[default]
+    Csv,
+    Jsonl,
+}
+
+impl RecordFormat {
+    pub fn extension(self) -> &'static str {
+        match self {
+            RecordFormat::Csv => "csv",
+            RecordFormat::Jsonl => "jsonl",
+        }
+    }
+
+    // JSON Lines for the `.jsonl` and `.json` files, CSV otherwise
+    pub fn from_path(path: &Path) -> Self {
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("jsonl" | "json") => RecordFormat::Jsonl,
+            _ => RecordFormat::Csv,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct Record<'a> {
+    time_ms: u64,
+    characteristic: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<&'static str>,
+    value: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    decoded: Option<String>,
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+pub fn now_ms() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or_default()
+}
+
+// Writes the values of characteristics, one line per value. The lines are flushed as they are
+// written so an interrupted capture keeps all its values
+pub struct Recorder {
+    pub path: Option<PathBuf>,
+    writer: Box<dyn Write + Send>,
+    format: RecordFormat,
+    decode: bool,
+    // Shared with the popup of the recordings running in the background
+    count: Arc<AtomicUsize>,
+}
+
+impl fmt::Debug for Recorder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Recorder")
+            .field("path", &self.path)
+            .field("format", &self.format)
+            .field("count", &self.count())
+            .finish_non_exhaustive()
+    }
+}
+
+impl Recorder {
+    pub fn new(
+        writer: Box<dyn Write + Send>,
+        format: RecordFormat,
+        decode: bool,
+    ) -> AppResult<Self> {
+        let mut recorder = Self {
+            path: None,
+            writer,
+            format,
+            decode,
+            count: Arc::default(),
+        };
+        if format == RecordFormat::Csv {
+            let header = if decode {
+                "time_ms,characteristic,name,value,decoded"
+            } else {
+                "time_ms,characteristic,name,value"
+            };
+            writeln!(recorder.writer, "{header}")?;
+        }
+        Ok(recorder)
+    }
+
+    pub fn create(path: &Path, format: RecordFormat, decode: bool) -> AppResult<Self> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(true)
+            .write(true)
+            .open(path)
+            .with_context(|| format!("unable to open {}", path.display()))?;
+        let mut recorder = Self::new(Box::new(LineWriter::new(file)), format, decode)?;
+        recorder.path = Some(path.to_path_buf());
+        Ok(recorder)
+    }
+
+    pub fn write(&mut self, time_ms: u64, characteristic: &Uuid, value: &[u8]) -> AppResult<()> {
+        let hex = format_hex(value);
+        let record = Record {
+            time_ms,
+            characteristic: characteristic.to_string(),
+            name: characteristic_name(characteristic),
+            value: &hex,
+            decoded: if self.decode {
+                decode(characteristic, value)
+            } else {
+                None
+            },
+        };
+        match self.format {
+            RecordFormat::Csv => {
+                let mut fields = vec![
+                    record.time_ms.to_string(),
+                    record.characteristic,
+                    csv_field(record.name.unwrap_or_default()),
+                    hex.clone(),
+                ];
+                if self.decode {
+                    fields.push(csv_field(&record.decoded.unwrap_or_default()));
+                }
+                writeln!(self.writer, "{}", fields.join(","))?;
+            }
+            RecordFormat::Jsonl => {
+                serde_json::to_writer(&mut self.writer, &record)?;
+                writeln!(self.writer)?;
+            }
+        }
+        self.writer.flush()?;
+        self.count.fetch_add(1, Ordering::Relaxed);
+        Ok(())
+    }
+
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+}
+
+// Subscribes to the characteristics, or to all the ones that notify when none is given
+async fn subscribe(
+    characteristics: Vec<Characteristic>,
+    wanted: &[Uuid],
+) -> AppResult<Vec<BoxStream<'static, (Uuid, Vec<u8>)>>> {
+    let mut notifications = Vec::new();
+    for characteristic in characteristics {
+        let uuid = characteristic.uuid().await?;
+        let flags = characteristic.flags().await?;
+        let notifies = flags.notify || flags.indicate;
+        if wanted.is_empty() && !notifies || !wanted.is_empty() && !wanted.contains(&uuid) {
+            continue;
+        }
+        if !notifies {
+            bail!("{uuid} does not notify its value");
+        }
+        let values = characteristic.notify().await?;
+        notifications.push(values.map(move |value| (uuid, value)).boxed());
+    }
+    Ok(notifications)
+}
+
+// What happens to a recording, reported as it changes
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RecordingState {
+    // Subscribed to this many characteristics
+    Recording(usize),
+    Disconnected,
+}
+
+async fn characteristics(device: &Device) -> AppResult<Vec<Characteristic>> {
+    let mut characteristics = Vec::new();
+    for service in device.services().await? {
+        characteristics.extend(service.characteristics().await?);
+    }
+    Ok(characteristics)
+}
+
+// Connects the device until it is back in range, then waits for its services
+async fn reconnect(device: &Device) {
+    loop {
+        let connected =
+            device.is_connected().await.unwrap_or(false) || device.connect().await.is_ok();
+        if connected && device.is_services_resolved().await.unwrap_or(false) {
+            return;
+        }
+        tokio::time::sleep(RECONNECT_DELAY).await;
+    }
+}
+
+// Writes the notifications of the characteristics until the future is dropped. A disconnection
+// does not end the recording, the device is connected again and the characteristics subscribed
+// again
+pub async fn record(
+    device: &Device,
+    wanted: &[Uuid],
+    recorder: &mut Recorder,
+    mut on_state: impl FnMut(RecordingState),
+) -> AppResult<()> {
+    let mut subscribed = false;
+    loop {
+        reconnect(device).await;
+        let notifications = match subscribe(characteristics(device).await?, wanted).await {
+            Ok(notifications) => notifications,
+            // The device went away again while it was subscribed
+            Err(_) if subscribed && !device.is_connected().await.unwrap_or(false) => continue,
+            Err(e) => return Err(e),
+        };
+        if notifications.is_empty() {
+            bail!("{} has no characteristic to record", device.address());
+        }
+        subscribed = true;
+        on_state(RecordingState::Recording(notifications.len()));
+
+        // The notifications end with the connection
+        let mut notifications = futures::stream::select_all(notifications);
+        while let Some((uuid, value)) = notifications.next().await {
+            recorder.write(now_ms(), &uuid, &value)?;
+        }
+        on_state(RecordingState::Disconnected);
+    }
+}
+
+// Records the notifications of a device until ctrl+c is pressed or the duration is over
+pub async fn run(
+    address: Address,
+    wanted: &[Uuid],
+    mut recorder: Recorder,
+    duration: Option<Duration>,
+) -> AppResult<()> {
+    let session = Session::new().await?;
+    let adapter = session.default_adapter().await?;
+    let device = adapter.device(address)?;
+    if !device.is_connected().await? {
+        eprintln!("Connecting to {address}");
+        device.connect().await?;
+    }
+
+    let destination = recorder
+        .path
+        .as_ref()
+        .map(|path| format!(" to {}", path.display()))
+        .unwrap_or_default();
+    let mut disconnected: Option<Instant> = None;
+    let recording = record(&device, wanted, &mut recorder, |state| match state {
+        RecordingState::Recording(count) => match disconnected.take() {
+            Some(since) => eprintln!(
+                "Reconnected to {address}, no value was recorded for {}",
+                format_duration(since.elapsed().as_secs())
+            ),
+            None => eprintln!(
+                "Recording {count} characteristic(s) of {address}{destination}, ctrl+c to stop"
+            ),
+        },
+        RecordingState::Disconnected => {
+            disconnected = Some(Instant::now());
+            eprintln!("{address} disconnected, reconnecting");
+        }
+    });
+
+    let deadline = async {
+        match duration {
+            Some(duration) => tokio::time::sleep(duration).await,
+            None => std::future::pending().await,
+        }
+    };
+    tokio::select! {
+        result = recording => result?,
+        _ = tokio::signal::ctrl_c() => {}
+        () = deadline => {}
+    }
+    eprintln!("{} value(s) recorded", recorder.count());
+    Ok(())
+}
+
+// The characteristics of the device that notify their value, vendor ones included
+async fn notifying(device: &Device) -> AppResult<Vec<Uuid>> {
+    while !device.is_services_resolved().await? {
+        tokio::time::sleep(Duration::from_millis(200)).await;
+    }
+    let mut uuids = Vec::new();
+    for characteristic in characteristics(device).await? {
+        let flags = characteristic.flags().await?;
+        let uuid = characteristic.uuid().await?;
+        if (flags.notify || flags.indicate) && !uuids.contains(&uuid) {
+            uuids.push(uuid);
+        }
+    }
+    Ok(uuids)
+}
+
+// e.g. "Heart Rate Measurement (0x2a37)", the UUID of the vendor characteristics
+fn characteristic_label(uuid: &Uuid) -> String {
+    match (characteristic_name(uuid), short_uuid(uuid)) {
+        (Some(name), Some(short)) => format!("{name} ({short:#06x})"),
+        (None, Some(short)) => format!("{short:#06x}"),
+        _ => uuid.to_string(),
+    }
+}
+
+// Where the recordings started from the TUI are written
+fn recording_path(device: Address, format: RecordFormat) -> AppResult<PathBuf> {
+    let dir = storage::data_dir()?.join("recordings");
+    std::fs::create_dir_all(&dir).context("unable to create the recordings dir")?;
+    Ok(dir.join(format!(
+        "{}-{}.{}",
+        device.to_string().replace(':', "_"),
+        storage::now(),
+        format.extension()
+    )))
+}
+
+// A recording running in the background until it is stopped, whether its popup is open or not
+#[derive(Debug)]
+pub struct Recording {
+    pub device: Address,
+    pub path: PathBuf,
+    // `None` until the characteristics are subscribed
+    pub state: Option<RecordingState>,
+    disconnected: Option<Instant>,
+    count: Arc<AtomicUsize>,
+    task: JoinHandle<()>,
+}
+
+// The recording stops with the task
+impl Drop for Recording {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl Recording {
+    pub fn start(
+        device: Device,
+        characteristics: Vec<Uuid>,
+        format: RecordFormat,
+        sender: UnboundedSender<Event>,
+    ) -> AppResult<Self> {
+        let address = device.address();
+        let path = recording_path(address, format)?;
+        let mut recorder = Recorder::create(&path, format, true)?;
+        let count = recorder.count.clone();
+        let task = tokio::spawn(async move {
+            let result = record(&device, &characteristics, &mut recorder, |state| {
+                let _ = sender.send(Event::RecordingState(address, state));
+            })
+            .await;
+            if let Err(e) = result {
+                let _ = sender.send(Event::RecordingFailed(address, e.to_string()));
+            }
+        });
+        Ok(Self {
+            device: address,
+            path,
+            state: None,
+            disconnected: None,
+            count,
+            task,
+        })
+    }
+
+    pub fn count(&self) -> usize {
+        self.count.load(Ordering::Relaxed)
+    }
+
+    // Returns what to tell about the gaps in the recording
+    pub fn update(&mut self, state: RecordingState, device_name: &str) -> Option<String> {
+        self.state = Some(state);
+        match state {
+            RecordingState::Disconnected => {
+                self.disconnected = Some(Instant::now());
+                Some(format!(
+                    "{device_name} disconnected, the recording resumes when it reconnects"
+                ))
+            }
+            RecordingState::Recording(_) => self.disconnected.take().map(|since| {
+                format!(
+                    "Recording of {device_name} resumed, no value was recorded for {}",
+                    format_duration(since.elapsed().as_secs())
+                )
+            }),
+        }
+    }
+
+    fn lines(&self) -> Vec<Line<'static>> {
+        let state = match self.state {
+            None => Line::from("Subscribing…").dark_gray(),
+            Some(RecordingState::Recording(characteristics)) => Line::from(format!(
+                "{} values from {characteristics} characteristic(s)",
+                self.count()
+            )),
+            Some(RecordingState::Disconnected) => Line::from(format!(
+                "{} values, disconnected for {}, reconnecting…",
+                self.count(),
+                format_duration(
+                    self.disconnected
+                        .map_or(0, |since| since.elapsed().as_secs())
+                )
+            ))
+            .yellow(),
+        };
+        vec![
+            Line::from(format!("Recording to {}", self.path.display())),
+            Line::default(),
+            state,
+        ]
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum PickerStatus {
+    Discovering,
+    Ready,
+    Failed(String),
+}
+
+// Popup picking the characteristics of a device to record
+#[derive(Debug)]
+pub struct RecorderPicker {
+    pub device: Address,
+    pub status: PickerStatus,
+    characteristics: Vec<Uuid>,
+    chosen: HashSet<Uuid>,
+    state: ListState,
+    task: JoinHandle<()>,
+}
+
+impl Drop for RecorderPicker {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl RecorderPicker {
+    pub fn new(device: Device, sender: UnboundedSender<Event>) -> Self {
+        let address = device.address();
+        let task = tokio::spawn(async move {
+            let event = match notifying(&device).await {
+                Ok(characteristics) => Event::RecordableCharacteristics(address, characteristics),
+                Err(e) => Event::RecorderFailed(address, e.to_string()),
+            };
+            let _ = sender.send(event);
+        });
+        Self {
+            device: address,
+            status: PickerStatus::Discovering,
+            characteristics: Vec::new(),
+            chosen: HashSet::new(),
+            state: ListState::default(),
+            task,
+        }
+    }
+
+    pub fn found(&mut self, characteristics: Vec<Uuid>) {
+        self.state
+            .select((!characteristics.is_empty()).then_some(0));
+        self.characteristics = characteristics;
+        self.status = PickerStatus::Ready;
+    }
+
+    pub fn failed(&mut self, reason: String) {
+        self.status = PickerStatus::Failed(reason);
+    }
+
+    pub fn next(&mut self) {
+        if !self.characteristics.is_empty() {
+            let i = self
+                .state
+                .selected()
+                .map_or(0, |i| (i + 1) % self.characteristics.len());
+            self.state.select(Some(i));
+        }
+    }
+
+    pub fn previous(&mut self) {
+        if !self.characteristics.is_empty() {
+            let len = self.characteristics.len();
+            let i = self.state.selected().map_or(0, |i| (i + len - 1) % len);
+            self.state.select(Some(i));
+        }
+    }
+
+    pub fn toggle(&mut self) {
+        if let Some(uuid) = self
+            .state
+            .selected()
+            .and_then(|i| self.characteristics.get(i))
+            && !self.chosen.remove(uuid)
+        {
+            self.chosen.insert(*uuid);
+        }
+    }
+
+    // The chosen characteristics in the order they are listed, the highlighted one when none is
+    // chosen
+    pub fn chosen(&self) -> Vec<Uuid> {
+        if self.chosen.is_empty() {
+            return self
+                .state
+                .selected()
+                .and_then(|i| self.characteristics.get(i))
+                .copied()
+                .into_iter()
+                .collect();
+        }
+        self.characteristics
+            .iter()
+            .filter(|uuid| self.chosen.contains(uuid))
+            .copied()
+            .collect()
+    }
+
+    pub fn render(
+        &mut self,
+        device_name: &str,
+        recording: Option<&Recording>,
+        frame: &mut Frame,
+        area: Rect,
+    ) {
+        let message = match (&self.status, recording) {
+            (_, Some(recording)) => Some(recording.lines()),
+            (PickerStatus::Discovering, None) => Some(vec![
+                Line::from("Discovering the characteristics…").dark_gray(),
+            ]),
+            (PickerStatus::Failed(reason), None) => Some(vec![Line::from(reason.clone()).red()]),
+            (PickerStatus::Ready, None) if self.characteristics.is_empty() => Some(vec![
+                Line::from("No characteristic notifies its value").dark_gray(),
+            ]),
+            (PickerStatus::Ready, None) => None,
+        };
+
+        let height = message
+            .as_ref()
+            .map_or(self.characteristics.len(), Vec::len) as u16
+            + 4;
+        let area = area.centered(
+            Constraint::Max(80),
+            Constraint::Length(height.min(area.height)),
+        );
+        let block = Block::bordered()
+            .title(format!(" Recorder · {device_name} "))
+            .title_style(Style::default().bold())
+            .padding(Padding::uniform(1))
+            .border_type(BorderType::Thick)
+            .border_style(Style::default().green());
+
+        frame.render_widget(Clear, area);
+        if let Some(lines) = message {
+            frame.render_widget(Paragraph::new(lines).block(block), area);
+            return;
+        }
+        let items: Vec<String> = self
+            .characteristics
+            .iter()
+            .map(|uuid| {
+                let mark = if self.chosen.contains(uuid) {
+                    "[x]"
+                } else {
+                    "[ ]"
+                };
+                format!("{mark} {}", characteristic_label(uuid))
+            })
+            .collect();
+        frame.render_stateful_widget(
+            List::new(items)
+                .block(block)
+                .highlight_style(Style::default().white().on_dark_gray()),
+            area,
+            &mut self.state,
+        );
+    }
+}
+
+// The standard output, for the captures piped to another program
+pub fn stdout() -> Box<dyn Write + Send> {
+    Box::new(LineWriter::new(io::stdout()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::advertising::parse_uuid;
+    use std::sync::{Arc, Mutex};
+
+    // Keeps what the recorder writes
+    #[derive(Clone, Default)]
+    struct Buffer(Arc<Mutex<Vec<u8>>>);
+
+    impl Write for Buffer {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().write(buf)
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl Buffer {
+        fn text(&self) -> String {
+            String::from_utf8(self.0.lock().unwrap().clone()).unwrap()
+        }
+    }
+
+    #[test]
+    fn csv() {
+        let buffer = Buffer::default();
+        let mut recorder =
+            Recorder::new(Box::new(buffer.clone()), RecordFormat::Csv, true).unwrap();
+        let heart_rate = parse_uuid("2a37").unwrap();
+        recorder.write(1000, &heart_rate, &[0x00, 0x48]).unwrap();
+        recorder
+            .write(2000, &heart_rate, &[0x1d, 0x48, 0x00, 0x10, 0x00])
+            .unwrap();
+        recorder
+            .write(3000, &parse_uuid("ffe1").unwrap(), &[0xbe, 0xef])
+            .unwrap();
+
+        assert_eq!(recorder.count(), 3);
+        assert_eq!(
+            buffer.text(),
+            "time_ms,characteristic,name,value,decoded\n\
+             1000,00002a37-0000-1000-8000-00805f9b34fb,Heart Rate Measurement,0048,72 bpm\n\
+             2000,00002a37-0000-1000-8000-00805f9b34fb,Heart Rate Measurement,1d48001000,\"72 bpm, no contact, 16 kJ\"\n\
+             3000,0000ffe1-0000-1000-8000-00805f9b34fb,,beef,\n"
+        );
+    }
+
+    #[test]
+    fn json_lines() {
+        let buffer = Buffer::default();
+        let mut recorder =
+            Recorder::new(Box::new(buffer.clone()), RecordFormat::Jsonl, false).unwrap();
+        recorder
+            .write(1000, &parse_uuid("2a19").unwrap(), &[0x55])
+            .unwrap();
+        assert_eq!(
+            buffer.text(),
+            "{\"time_ms\":1000,\"characteristic\":\"00002a19-0000-1000-8000-00805f9b34fb\",\
+             \"name\":\"Battery Level\",\"value\":\"55\"}\n"
+        );
+    }
+
+    #[test]
+    fn format_from_the_extension() {
+        assert_eq!(
+            RecordFormat::from_path(Path::new("hr.jsonl")),
+            RecordFormat::Jsonl
+        );
+        assert_eq!(
+            RecordFormat::from_path(Path::new("hr.csv")),
+            RecordFormat::Csv
+        );
+        assert_eq!(RecordFormat::from_path(Path::new("hr")), RecordFormat::Csv);
+    }
+}
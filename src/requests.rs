@@ -2,7 +2,7 @@ use std::{borrow::Cow, fmt::Write};
 
 use crate::requests::{
     confirmation::Confirmation, display_passkey::DisplayPasskey, display_pin_code::DisplayPinCode,
-    enter_passkey::EnterPasskey, enter_pin_code::EnterPinCode,
+    enter_passkey::EnterPasskey, enter_pin_code::EnterPinCode, receive_file::ReceiveFile,
 };
 
 pub mod confirmation;
@@ -10,6 +10,7 @@ pub mod display_passkey;
 pub mod display_pin_code;
 pub mod enter_passkey;
 pub mod enter_pin_code;
+pub mod receive_file;
 
 #[derive(Debug, Default)]
 pub struct Requests {
@@ -18,6 +19,7 @@ pub struct Requests {
     pub enter_passkey: Option<EnterPasskey>,
     pub display_pin_code: Option<DisplayPinCode>,
     pub display_passkey: Option<DisplayPasskey>,
+    pub receive_file: Option<ReceiveFile>,
 }
 
 impl Requests {
@@ -36,6 +38,9 @@ impl Requests {
     pub fn init_display_passkey(&mut self, req: DisplayPasskey) {
         self.display_passkey = Some(req);
     }
+    pub fn init_receive_file(&mut self, req: ReceiveFile) {
+        self.receive_file = Some(req);
+    }
 }
 
 fn pad_str(input: &str, length: usize) -> Cow<'_, str> {
@@ -49,7 +54,7 @@ fn pad_str(input: &str, length: usize) -> Cow<'_, str> {
     }
 }
 
-fn pad_string(input: String, length: usize) -> String {
+pub fn pad_string(input: String, length: usize) -> String {
     let current_length = input.chars().count();
     if current_length >= length {
         input
@@ -48,7 +48,12 @@ impl Confirmation {
         self.confirmed = !self.confirmed;
     }
 
-    pub fn render(&self, frame: &mut Frame, area: Rect) {
+    pub fn select(&mut self, confirmed: bool) {
+        self.confirmed = confirmed;
+    }
+
+    // Popup, message and choices areas
+    fn layout(area: Rect) -> (Rect, Rect, Rect) {
         let block = Layout::vertical([
             Constraint::Fill(1),
             Constraint::Length(8),
@@ -63,21 +68,34 @@ impl Confirmation {
         ])
         .split(block[1])[1];
 
-        let (message_block, choices_block) = {
-            let chunks = Layout::vertical(
-                [
-                    Constraint::Length(1),
-                    Constraint::Length(3),
-                    Constraint::Length(1),
-                    Constraint::Length(2),
-                    Constraint::Length(1),
-                ]
-                .as_ref(),
-            )
-            .split(block);
-
-            (chunks[1], chunks[3])
-        };
+        let chunks = Layout::vertical(
+            [
+                Constraint::Length(1),
+                Constraint::Length(3),
+                Constraint::Length(1),
+                Constraint::Length(2),
+                Constraint::Length(1),
+            ]
+            .as_ref(),
+        )
+        .split(block);
+
+        (block, chunks[1], chunks[3])
+    }
+
+    // Areas of the "No" and "Yes" choices
+    pub fn choices(area: Rect) -> (Rect, Rect) {
+        let (_, _, choices_block) = Self::layout(area);
+        // "No", 8 spaces, then "Yes", centered on the first line
+        let x = choices_block.x + choices_block.width.saturating_sub(13) / 2;
+        (
+            Rect::new(x, choices_block.y, 2, 1),
+            Rect::new(x + 10, choices_block.y, 3, 1),
+        )
+    }
+
+    pub fn render(&self, frame: &mut Frame, area: Rect) {
+        let (block, message_block, choices_block) = Self::layout(area);
 
         let message = Text::from(vec![
             Line::from(vec![
@@ -158,4 +176,24 @@ mod tests {
 
         assert_snapshot!(format!("{passkey}-{confirmed}"), terminal.backend());
     }
+
+    #[test]
+    fn choices_match_the_rendered_buttons() {
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        let confirmation = Confirmation::new("adapter".to_string(), Address::new(*b"DEADBE"), 1);
+        terminal
+            .draw(|frame| confirmation.render(frame, frame.area()))
+            .unwrap();
+
+        let (no, yes) = Confirmation::choices(Rect::new(0, 0, 80, 24));
+        let buffer = terminal.backend().buffer();
+        let text = |area: Rect| -> String {
+            area.positions()
+                .map(|position| buffer[position].symbol().to_string())
+                .collect()
+        };
+
+        assert_eq!(text(no), "No");
+        assert_eq!(text(yes), "Yes");
+    }
 }
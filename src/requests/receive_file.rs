@@ -0,0 +1,246 @@
+use std::path::{Path, PathBuf};
+
+use crossterm::event::{KeyCode, KeyEvent};
+
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span, Text},
+    widgets::{Block, BorderType, Borders, Clear, List},
+};
+
+use bluer::Address;
+use tui_input::{Input, backend::crossterm::EventHandler};
+
+use crate::{
+    app::AppResult,
+    event::Event,
+    obex::{agent::ReceiveAgent, format_size},
+    requests::{pad_str, pad_string},
+};
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum FocusedSection {
+    Destination,
+    Reject,
+    #[default]
+    Accept,
+}
+
+// A file pushed by a device, waiting to be accepted
+#[derive(Debug, Clone)]
+pub struct ReceiveFile {
+    // Path of the transfer in obexd, the request is answered for it
+    pub transfer: String,
+    pub device: Address,
+    pub file_name: String,
+    pub size: Option<u64>,
+    focused_section: FocusedSection,
+    destination: Input,
+    error: Option<String>,
+}
+
+impl ReceiveFile {
+    pub fn new(
+        transfer: String,
+        device: Address,
+        file_name: String,
+        size: Option<u64>,
+        destination: &Path,
+    ) -> Self {
+        Self {
+            transfer,
+            device,
+            file_name,
+            size,
+            focused_section: FocusedSection::default(),
+            destination: Input::new(destination.to_string_lossy().to_string()),
+            error: None,
+        }
+    }
+
+    pub fn submit(&mut self, agent: &ReceiveAgent) -> AppResult<()> {
+        if self.focused_section == FocusedSection::Reject {
+            return self.reject(agent);
+        }
+
+        let dir = PathBuf::from(self.destination.value().trim());
+        self.error = None;
+        if dir.as_os_str().is_empty() {
+            self.error = Some("Required field.".to_string());
+            return Ok(());
+        }
+        if let Err(e) = std::fs::create_dir_all(&dir) {
+            self.error = Some(e.to_string());
+            return Ok(());
+        }
+
+        agent.answer(&self.transfer, Some(dir));
+        agent.event_sender.send(Event::ReceiveFileSubmitted)?;
+        Ok(())
+    }
+
+    pub fn reject(&mut self, agent: &ReceiveAgent) -> AppResult<()> {
+        agent.answer(&self.transfer, None);
+        agent.event_sender.send(Event::ReceiveFileSubmitted)?;
+        Ok(())
+    }
+
+    pub fn handle_key_events(
+        &mut self,
+        key_event: KeyEvent,
+        agent: &ReceiveAgent,
+    ) -> AppResult<()> {
+        match key_event.code {
+            KeyCode::Tab => {
+                self.focused_section = match self.focused_section {
+                    FocusedSection::Destination => FocusedSection::Reject,
+                    FocusedSection::Reject => FocusedSection::Accept,
+                    FocusedSection::Accept => FocusedSection::Destination,
+                };
+            }
+            KeyCode::BackTab => {
+                self.focused_section = match self.focused_section {
+                    FocusedSection::Destination => FocusedSection::Accept,
+                    FocusedSection::Reject => FocusedSection::Destination,
+                    FocusedSection::Accept => FocusedSection::Reject,
+                };
+            }
+            KeyCode::Enter => self.submit(agent)?,
+            _ => {
+                if self.focused_section == FocusedSection::Destination {
+                    self.destination
+                        .handle_event(&crossterm::event::Event::Key(key_event));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn render(&self, device_name: &str, frame: &mut Frame, area: Rect) {
+        let layout = Layout::vertical([
+            Constraint::Fill(1),
+            Constraint::Length(9),
+            Constraint::Fill(1),
+        ])
+        .split(area);
+
+        let block = Layout::horizontal([
+            Constraint::Fill(1),
+            Constraint::Max(70),
+            Constraint::Fill(1),
+        ])
+        .split(layout[1])[1];
+
+        let (message_block, fields_block, choices_block) = {
+            let chunks = Layout::vertical(
+                [
+                    Constraint::Length(1),
+                    Constraint::Length(1), // message
+                    Constraint::Length(1),
+                    Constraint::Length(3), // file and destination
+                    Constraint::Length(1),
+                    Constraint::Length(1), // choices
+                    Constraint::Length(1),
+                ]
+                .as_ref(),
+            )
+            .split(block);
+
+            (chunks[1], chunks[3], chunks[5])
+        };
+
+        let fields_block = Layout::horizontal(
+            [Constraint::Max(3), Constraint::Fill(1), Constraint::Max(3)].as_ref(),
+        )
+        .split(fields_block)[1];
+
+        let message = Text::from(format!("{device_name} wants to send you a file")).centered();
+
+        let size = self
+            .size
+            .map(|size| format!(" ({})", format_size(size)))
+            .unwrap_or_default();
+        let items = vec![
+            Line::from(vec![
+                Span::from("File     "),
+                Span::from(format!("{}{size}", self.file_name)).bold(),
+            ]),
+            Line::from(vec![
+                {
+                    if self.focused_section == FocusedSection::Destination {
+                        Span::from("Save to").green().bold()
+                    } else {
+                        Span::from("Save to")
+                    }
+                },
+                Span::from("  "),
+                Span::from(pad_string(format!(" {}", self.destination.value()), 50))
+                    .bg(Color::DarkGray),
+            ]),
+            Line::from(vec![Span::from(pad_str(" ", 9)), {
+                if let Some(error) = &self.error {
+                    Span::from(pad_str(error, 50))
+                } else {
+                    Span::from("")
+                }
+            }])
+            .red(),
+        ];
+
+        let choice = |label: &'static str, section: FocusedSection| {
+            if self.focused_section == section {
+                Span::from(label)
+                    .style(Style::default().bg(Color::Blue))
+                    .bold()
+            } else {
+                Span::from(label)
+            }
+        };
+        let choices = Line::from(vec![
+            choice("Reject", FocusedSection::Reject),
+            Span::from("        "),
+            choice("Accept", FocusedSection::Accept),
+        ]);
+
+        frame.render_widget(Clear, block);
+
+        frame.render_widget(
+            Block::new()
+                .borders(Borders::ALL)
+                .border_type(BorderType::Thick)
+                .border_style(Style::default().fg(Color::Green)),
+            block,
+        );
+
+        frame.render_widget(message, message_block);
+        frame.render_widget(List::new(items), fields_block);
+        frame.render_widget(choices.centered(), choices_block);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_snapshot;
+    use ratatui::{Terminal, backend::TestBackend};
+
+    #[test]
+    fn render() {
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        let request = ReceiveFile::new(
+            "/org/bluez/obex/server/session0/transfer0".to_string(),
+            Address::new(*b"DEADBE"),
+            "photo.jpg".to_string(),
+            Some(2_621_440),
+            Path::new("/home/user/Downloads"),
+        );
+        terminal
+            .draw(|frame| request.render("Phone", frame, frame.area()))
+            .unwrap();
+
+        assert_snapshot!(terminal.backend());
+    }
+}
@@ -0,0 +1,541 @@
+use std::collections::HashMap;
+
+use bluer::{Address, Uuid};
+use futures::{StreamExt, stream::BoxStream};
+use ratatui::{
+    Frame,
+    layout::{Constraint, Rect},
+    style::{Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Clear, Padding, Paragraph},
+};
+use tokio::{sync::mpsc::UnboundedSender, task::JoinHandle};
+
+use crate::{
+    app::AppResult,
+    assigned_numbers::{service_label, short_uuid, uuid16},
+    bluetooth::Device,
+    event::Event,
+    history::format_duration,
+};
+
+const HEART_RATE_MEASUREMENT: u16 = 0x2a37;
+const BODY_SENSOR_LOCATION: u16 = 0x2a38;
+const BATTERY_LEVEL: u16 = 0x2a19;
+const TEMPERATURE_MEASUREMENT: u16 = 0x2a1c;
+const TEMPERATURE_TYPE: u16 = 0x2a1d;
+const CSC_MEASUREMENT: u16 = 0x2a5b;
+const TEMPERATURE: u16 = 0x2a6e;
+const HUMIDITY: u16 = 0x2a6f;
+const PRESSURE: u16 = 0x2a6d;
+
+// Characteristics shown on the dashboard, by service and in the order they are shown
+const SENSORS: &[(u16, &[(u16, &str)])] = &[
+    (
+        0x180d,
+        &[
+            (HEART_RATE_MEASUREMENT, "Heart rate"),
+            (BODY_SENSOR_LOCATION, "Location"),
+        ],
+    ),
+    (
+        0x1809,
+        &[
+            (TEMPERATURE_MEASUREMENT, "Temperature"),
+            (TEMPERATURE_TYPE, "Location"),
+        ],
+    ),
+    (
+        0x181a,
+        &[
+            (TEMPERATURE, "Temperature"),
+            (HUMIDITY, "Humidity"),
+            (PRESSURE, "Pressure"),
+        ],
+    ),
+    (0x1816, &[(CSC_MEASUREMENT, "Revolutions")]),
+    (0x180f, &[(BATTERY_LEVEL, "Battery")]),
+    (
+        0x180a,
+        &[
+            (0x2a29, "Manufacturer"),
+            (0x2a24, "Model"),
+            (0x2a25, "Serial number"),
+            (0x2a27, "Hardware"),
+            (0x2a26, "Firmware"),
+            (0x2a28, "Software"),
+        ],
+    ),
+];
+
+const BODY_LOCATIONS: [&str; 7] = [
+    "Other", "Chest", "Wrist", "Finger", "Hand", "Ear lobe", "Foot",
+];
+const TEMPERATURE_TYPES: [&str; 10] = [
+    "",
+    "Armpit",
+    "Body",
+    "Ear",
+    "Finger",
+    "Gastrointestinal tract",
+    "Mouth",
+    "Rectum",
+    "Toe",
+    "Eardrum",
+];
+
+// The RR intervals and the wheel and crank event times are in 1/1024 s
+const TIME_UNIT: f64 = 1024.0;
+
+// Seconds without a new value after which the age of a reading is shown
+const STALE: u64 = 5;
+
+fn is_sensor(characteristic: u16) -> bool {
+    SENSORS
+        .iter()
+        .any(|(_, characteristics)| characteristics.iter().any(|(c, _)| *c == characteristic))
+}
+
+fn le_u16(data: &[u8], at: usize) -> Option<u16> {
+    Some(u16::from_le_bytes(data.get(at..at + 2)?.try_into().ok()?))
+}
+
+fn le_u32(data: &[u8], at: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(data.get(at..at + 4)?.try_into().ok()?))
+}
+
+// IEEE 11073 32 bit FLOAT, a 24 bit mantissa and an 8 bit exponent
+fn ieee11073_float(data: &[u8], at: usize) -> Option<f64> {
+    let raw = le_u32(data, at)?;
+    let mantissa = raw & 0x00ff_ffff;
+    // NaN, NRes, +INFINITY, -INFINITY and a reserved value
+    if (0x007f_fffe..=0x0080_0002).contains(&mantissa) {
+        return None;
+    }
+    let mantissa = (mantissa << 8).cast_signed() >> 8;
+    let exponent = (raw >> 24) as u8;
+    Some(f64::from(mantissa) * 10f64.powi(i32::from(exponent.cast_signed())))
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct HeartRate {
+    pub bpm: u16,
+    // `None` when the sensor can not tell whether it touches the skin
+    pub contact: Option<bool>,
+    // Kilo joules since the last reset
+    pub energy: Option<u16>,
+    // Seconds between the beats
+    pub rr_intervals: Vec<f64>,
+}
+
+pub fn heart_rate(data: &[u8]) -> Option<HeartRate> {
+    let flags = *data.first()?;
+    let (bpm, mut at) = if flags & 0x01 == 0 {
+        (u16::from(*data.get(1)?), 2)
+    } else {
+        (le_u16(data, 1)?, 3)
+    };
+    let contact = (flags & 0x04 != 0).then_some(flags & 0x02 != 0);
+    let energy = if flags & 0x08 == 0 {
+        None
+    } else {
+        let energy = le_u16(data, at)?;
+        at += 2;
+        Some(energy)
+    };
+    let rr_intervals = if flags & 0x10 == 0 {
+        Vec::new()
+    } else {
+        data[at.min(data.len())..]
+            .chunks_exact(2)
+            .map(|rr| f64::from(u16::from_le_bytes([rr[0], rr[1]])) / TIME_UNIT)
+            .collect()
+    };
+    Some(HeartRate {
+        bpm,
+        contact,
+        energy,
+        rr_intervals,
+    })
+}
+
+// Cumulative revolutions and the time of the last revolution
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CscMeasurement {
+    pub wheel: Option<(u32, u16)>,
+    pub crank: Option<(u16, u16)>,
+}
+
+pub fn csc_measurement(data: &[u8]) -> Option<CscMeasurement> {
+    let flags = *data.first()?;
+    let mut measurement = CscMeasurement::default();
+    let mut at = 1;
+    if flags & 0x01 != 0 {
+        measurement.wheel = Some((le_u32(data, at)?, le_u16(data, at + 4)?));
+        at += 6;
+    }
+    if flags & 0x02 != 0 {
+        measurement.crank = Some((le_u16(data, at)?, le_u16(data, at + 2)?));
+    }
+    Some(measurement)
+}
+
+// Revolutions per minute between two measurements, the counters and the times wrap around
+fn rpm(previous: (u32, u16), current: (u32, u16), counter_max: u32) -> Option<f64> {
+    let revolutions = if current.0 >= previous.0 {
+        current.0 - previous.0
+    } else {
+        counter_max - previous.0 + current.0 + 1
+    };
+    let elapsed = current.1.wrapping_sub(previous.1);
+    (elapsed != 0).then(|| f64::from(revolutions) * 60.0 * TIME_UNIT / f64::from(elapsed))
+}
+
+impl CscMeasurement {
+    // Wheel and crank revolutions per minute since the previous measurement
+    pub fn rates(&self, previous: &CscMeasurement) -> (Option<f64>, Option<f64>) {
+        let wheel = previous
+            .wheel
+            .zip(self.wheel)
+            .and_then(|(previous, current)| rpm(previous, current, u32::MAX));
+        let crank = previous
+            .crank
+            .zip(self.crank)
+            .and_then(|(previous, current)| {
+                rpm(
+                    (u32::from(previous.0), previous.1),
+                    (u32::from(current.0), current.1),
+                    u32::from(u16::MAX),
+                )
+            });
+        (wheel, crank)
+    }
+}
+
+// The value of a characteristic in a readable form, `None` for the unknown characteristics and
+// the malformed values
+pub fn decode(uuid: &Uuid, data: &[u8]) -> Option<String> {
+    match short_uuid(uuid)? {
+        HEART_RATE_MEASUREMENT => {
+            let heart_rate = heart_rate(data)?;
+            let mut parts = vec![format!("{} bpm", heart_rate.bpm)];
+            if heart_rate.contact == Some(false) {
+                parts.push("no contact".to_string());
+            }
+            if let Some(energy) = heart_rate.energy {
+                parts.push(format!("{energy} kJ"));
+            }
+            if let Some(rr) = heart_rate.rr_intervals.last() {
+                parts.push(format!("RR {rr:.3} s"));
+            }
+            Some(parts.join(", "))
+        }
+        BODY_SENSOR_LOCATION => BODY_LOCATIONS
+            .get(usize::from(*data.first()?))
+            .map(|location| (*location).to_string()),
+        BATTERY_LEVEL => Some(format!("{}%", data.first()?)),
+        TEMPERATURE_MEASUREMENT => {
+            let unit = if data.first()? & 0x01 == 0 {
+                "°C"
+            } else {
+                "°F"
+            };
+            Some(format!("{:.2} {unit}", ieee11073_float(data, 1)?))
+        }
+        TEMPERATURE_TYPE => TEMPERATURE_TYPES
+            .get(usize::from(*data.first()?))
+            .filter(|location| !location.is_empty())
+            .map(|location| (*location).to_string()),
+        TEMPERATURE => {
+            let value = le_u16(data, 0)?.cast_signed();
+            (value != i16::MIN).then(|| format!("{:.2} °C", f64::from(value) / 100.0))
+        }
+        HUMIDITY => {
+            let value = le_u16(data, 0)?;
+            (value != u16::MAX).then(|| format!("{:.2}%", f64::from(value) / 100.0))
+        }
+        PRESSURE => Some(format!("{:.1} hPa", f64::from(le_u32(data, 0)?) / 1000.0)),
+        CSC_MEASUREMENT => {
+            let measurement = csc_measurement(data)?;
+            let mut parts = Vec::new();
+            if let Some((revolutions, _)) = measurement.wheel {
+                parts.push(format!("wheel {revolutions}"));
+            }
+            if let Some((revolutions, _)) = measurement.crank {
+                parts.push(format!("crank {revolutions}"));
+            }
+            Some(parts.join(", "))
+        }
+        0x2a24..=0x2a29 => Some(
+            String::from_utf8_lossy(data)
+                .trim_end_matches('\0')
+                .to_string(),
+        ),
+        _ => None,
+    }
+}
+
+// Reads the sensors of the device once, then forwards their notifications until the task is
+// aborted
+pub fn subscribe(device: Device, sender: UnboundedSender<Event>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let address = device.addr;
+        if let Err(e) = watch(&device, &sender).await {
+            let _ = sender.send(Event::SensorsFailed(address, e.to_string()));
+        }
+    })
+}
+
+async fn watch(device: &Device, sender: &UnboundedSender<Event>) -> AppResult<()> {
+    let address = device.addr;
+    let mut found = Vec::new();
+    let mut notifications: Vec<BoxStream<'static, (Uuid, Vec<u8>)>> = Vec::new();
+    for service in device.gatt_services().await? {
+        for characteristic in service.characteristics().await? {
+            let uuid = characteristic.uuid().await?;
+            if !short_uuid(&uuid).is_some_and(is_sensor) {
+                continue;
+            }
+            found.push(uuid);
+
+            let flags = characteristic.flags().await?;
+            if flags.read
+                && let Ok(value) = characteristic.read().await
+            {
+                let _ = sender.send(Event::SensorValue(address, uuid, value));
+            }
+            if flags.notify || flags.indicate {
+                let values = characteristic.notify().await?;
+                notifications.push(values.map(move |value| (uuid, value)).boxed());
+            }
+        }
+    }
+    let _ = sender.send(Event::SensorsFound(address, found));
+
+    let mut notifications = futures::stream::select_all(notifications);
+    while let Some((uuid, value)) = notifications.next().await {
+        if sender
+            .send(Event::SensorValue(address, uuid, value))
+            .is_err()
+        {
+            break;
+        }
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum DashboardStatus {
+    Discovering,
+    Ready,
+    Failed(String),
+}
+
+#[derive(Debug)]
+pub struct SensorDashboard {
+    pub device: Address,
+    pub status: DashboardStatus,
+    // Characteristics found on the device
+    found: Vec<u16>,
+    // Last value of each characteristic and when it was received
+    values: HashMap<u16, (u64, Vec<u8>)>,
+    // The two last CSC measurements, the rates are computed from them
+    csc: Option<(CscMeasurement, CscMeasurement)>,
+    task: JoinHandle<()>,
+}
+
+// The notifications stop with the task
+impl Drop for SensorDashboard {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+impl SensorDashboard {
+    pub fn new(device: Device, sender: UnboundedSender<Event>) -> Self {
+        Self {
+            device: device.addr,
+            status: DashboardStatus::Discovering,
+            found: Vec::new(),
+            values: HashMap::new(),
+            csc: None,
+            task: subscribe(device, sender),
+        }
+    }
+
+    pub fn found(&mut self, characteristics: &[Uuid]) {
+        self.found = characteristics.iter().filter_map(short_uuid).collect();
+        self.status = DashboardStatus::Ready;
+    }
+
+    pub fn failed(&mut self, reason: String) {
+        self.status = DashboardStatus::Failed(reason);
+    }
+
+    pub fn update(&mut self, uuid: &Uuid, value: Vec<u8>, time: u64) {
+        let Some(characteristic) = short_uuid(uuid) else {
+            return;
+        };
+        if characteristic == CSC_MEASUREMENT
+            && let Some(measurement) = csc_measurement(&value)
+        {
+            let previous = self.csc.map_or(measurement, |(_, last)| last);
+            self.csc = Some((previous, measurement));
+        }
+        self.values.insert(characteristic, (time, value));
+    }
+
+    fn value(&self, characteristic: u16, now: u64) -> Option<Line<'static>> {
+        let (time, data) = self.values.get(&characteristic)?;
+        let value =
+            decode(&uuid16(characteristic), data).unwrap_or_else(|| "unreadable".to_string());
+        let mut spans = vec![Span::from(value)];
+        // The static values are read once, the measurements are expected to keep coming
+        let elapsed = now.saturating_sub(*time);
+        if elapsed >= STALE
+            && matches!(
+                characteristic,
+                HEART_RATE_MEASUREMENT | TEMPERATURE_MEASUREMENT | CSC_MEASUREMENT
+            )
+        {
+            spans.push(Span::from(format!("  {} ago", format_duration(elapsed))).dark_gray());
+        }
+        Some(Line::from(spans))
+    }
+
+    fn lines(&self, now: u64) -> Vec<Line<'static>> {
+        let field = |label: &str, value: Line<'static>| {
+            let mut spans = vec![Span::from(format!("  {label:<16}")).bold()];
+            spans.extend(value.spans);
+            Line::from(spans)
+        };
+
+        let mut lines = Vec::new();
+        for (service, characteristics) in SENSORS {
+            let found: Vec<&(u16, &str)> = characteristics
+                .iter()
+                .filter(|(characteristic, _)| self.found.contains(characteristic))
+                .collect();
+            if found.is_empty() {
+                continue;
+            }
+            if !lines.is_empty() {
+                lines.push(Line::default());
+            }
+            lines.push(Line::from(service_label(&uuid16(*service))).yellow().bold());
+            for (characteristic, label) in found {
+                let value = self
+                    .value(*characteristic, now)
+                    .unwrap_or_else(|| Line::from("waiting…").dark_gray());
+                lines.push(field(label, value));
+
+                if *characteristic == CSC_MEASUREMENT
+                    && let Some((previous, last)) = &self.csc
+                {
+                    let (wheel, crank) = last.rates(previous);
+                    if let Some(wheel) = wheel {
+                        lines.push(field("Wheel", Line::from(format!("{wheel:.0} rpm"))));
+                    }
+                    if let Some(crank) = crank {
+                        lines.push(field("Cadence", Line::from(format!("{crank:.0} rpm"))));
+                    }
+                }
+            }
+        }
+        lines
+    }
+
+    pub fn render(&self, device_name: &str, now: u64, frame: &mut Frame, area: Rect) {
+        let lines = match &self.status {
+            DashboardStatus::Discovering => {
+                vec![Line::from("Discovering the services…").dark_gray()]
+            }
+            DashboardStatus::Failed(reason) => vec![Line::from(reason.clone()).red()],
+            DashboardStatus::Ready if self.found.is_empty() => {
+                vec![Line::from("No standard sensor on this device").dark_gray()]
+            }
+            DashboardStatus::Ready => self.lines(now),
+        };
+
+        let height = (lines.len() as u16 + 4).min(area.height);
+        let area = area.centered(Constraint::Max(70), Constraint::Length(height));
+        let block = Block::bordered()
+            .title(format!(" Sensors · {device_name} "))
+            .title_style(Style::default().bold())
+            .padding(Padding::uniform(1))
+            .border_type(BorderType::Thick)
+            .border_style(Style::default().green());
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(Paragraph::new(lines).block(block), area);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::advertising::{parse_hex, parse_uuid};
+
+    fn decoded(characteristic: &str, data: &str) -> Option<String> {
+        decode(
+            &parse_uuid(characteristic).unwrap(),
+            &parse_hex(data).unwrap(),
+        )
+    }
+
+    #[test]
+    fn heart_rate_measurements() {
+        assert_eq!(decoded("2a37", "00 48").as_deref(), Some("72 bpm"));
+        // 16 bit value, no contact, energy and RR intervals
+        assert_eq!(
+            heart_rate(&parse_hex("1d 4800 1000 0004 0002").unwrap()),
+            Some(HeartRate {
+                bpm: 72,
+                contact: Some(false),
+                energy: Some(16),
+                rr_intervals: vec![1.0, 0.5],
+            })
+        );
+        assert_eq!(
+            decoded("2a37", "1d 4800 1000 0004 0002").as_deref(),
+            Some("72 bpm, no contact, 16 kJ, RR 0.500 s")
+        );
+        assert_eq!(decoded("2a37", "01 48"), None);
+    }
+
+    #[test]
+    fn thermometers() {
+        // 36.5 °C as 365 × 10^-1
+        assert_eq!(decoded("2a1c", "00 6d0100ff").as_deref(), Some("36.50 °C"));
+        assert_eq!(decoded("2a1c", "01 ffff7f00"), None);
+        assert_eq!(decoded("2a1d", "03").as_deref(), Some("Ear"));
+        assert_eq!(decoded("2a6e", "f6 08").as_deref(), Some("22.94 °C"));
+        assert_eq!(decoded("2a6e", "0c fe").as_deref(), Some("-5.00 °C"));
+        assert_eq!(decoded("2a6f", "a6 0f").as_deref(), Some("40.06%"));
+        assert_eq!(
+            decoded("2a6d", "f0 74 0f 00").as_deref(),
+            Some("1013.0 hPa")
+        );
+    }
+
+    #[test]
+    fn device_information() {
+        assert_eq!(decoded("2a29", "506f6c6172 00").as_deref(), Some("Polar"));
+        assert_eq!(decoded("2a19", "55").as_deref(), Some("85%"));
+        assert_eq!(decoded("2a38", "01").as_deref(), Some("Chest"));
+        assert_eq!(decoded("2a00", "00"), None);
+    }
+
+    #[test]
+    fn cycling_rates() {
+        let previous = csc_measurement(&parse_hex("03 0a000000 0004 0500 0004").unwrap()).unwrap();
+        // 2 wheel revolutions and 1 crank revolution in half a second
+        let current = csc_measurement(&parse_hex("03 0c000000 0006 0600 0006").unwrap()).unwrap();
+        assert_eq!(current.rates(&previous), (Some(240.0), Some(120.0)));
+
+        // The crank counter wraps around
+        let previous = csc_measurement(&parse_hex("02 ffff f0ff").unwrap()).unwrap();
+        let current = csc_measurement(&parse_hex("02 0000 f003").unwrap()).unwrap();
+        assert_eq!(current.rates(&previous), (None, Some(60.0)));
+    }
+}
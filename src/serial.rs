@@ -0,0 +1,607 @@
+use std::{
+    fs::{File, OpenOptions},
+    io::Write,
+    path::PathBuf,
+    sync::Arc,
+};
+
+use anyhow::Context;
+use bluer::{
+    Address, Session, Uuid,
+    rfcomm::{Profile, ProfileHandle, Role, SocketAddr, Stream},
+};
+use futures::StreamExt;
+use ratatui::{
+    Frame,
+    layout::{Constraint, Layout, Rect},
+    style::{Color, Style, Stylize},
+    text::{Line, Span},
+    widgets::{Block, BorderType, Clear, List, ListState, Padding, Paragraph},
+};
+use serde::{Deserialize, Serialize};
+use tokio::{
+    io::{AsyncReadExt, AsyncWriteExt},
+    sync::mpsc::{UnboundedSender, unbounded_channel},
+};
+use tui_input::Input;
+
+use crate::{
+    app::AppResult, assigned_numbers::short_uuid, bluetooth::Device, event::Event, storage,
+};
+
+// Serial services worth a profile connection, the other 16 bit ones are not serial ports
+const SERIAL_SERVICES: [(u16, &str); 2] = [(0x1101, "Serial Port"), (0x1103, "Dial-up Networking")];
+
+// Received bytes kept on screen
+const SCROLLBACK: usize = 64 * 1024;
+
+const HEX_WIDTH: usize = 16;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SerialTarget {
+    // Connected through a profile, BlueZ finds the channel with SDP
+    Service { uuid: Uuid, name: String },
+    Channel(u8),
+}
+
+impl SerialTarget {
+    pub fn label(&self) -> String {
+        match self {
+            SerialTarget::Service { name, .. } => name.clone(),
+            SerialTarget::Channel(channel) => format!("RFCOMM channel {channel}"),
+        }
+    }
+}
+
+// The serial services of the device. The vendor serial ports with a 128 bit UUID are only
+// offered when they are listed in `vendor_services`, nothing tells them apart from the other
+// vendor services
+pub fn serial_targets(uuids: &[Uuid], vendor_services: &[Uuid]) -> Vec<SerialTarget> {
+    let mut targets: Vec<SerialTarget> = uuids
+        .iter()
+        .filter_map(|uuid| match short_uuid(uuid) {
+            Some(short) => SERIAL_SERVICES
+                .iter()
+                .find(|(id, _)| *id == short)
+                .map(|(_, name)| SerialTarget::Service {
+                    uuid: *uuid,
+                    name: (*name).to_string(),
+                }),
+            None if vendor_services.contains(uuid) => Some(SerialTarget::Service {
+                uuid: *uuid,
+                name: format!("Service {uuid}"),
+            }),
+            None => None,
+        })
+        .collect();
+    targets.push(SerialTarget::Channel(1));
+    targets
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+    Cr,
+    None,
+}
+
+impl LineEnding {
+    pub fn bytes(self) -> &'static [u8] {
+        match self {
+            LineEnding::Lf => b"\n",
+            LineEnding::CrLf => b"\r\n",
+            LineEnding::Cr => b"\r",
+            LineEnding::None => b"",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            LineEnding::Lf => "LF",
+            LineEnding::CrLf => "CRLF",
+            LineEnding::Cr => "CR",
+            LineEnding::None => "no line ending",
+        }
+    }
+
+    #[must_use]
+    pub fn next(self) -> Self {
+        match self {
+            LineEnding::Lf => LineEnding::CrLf,
+            LineEnding::CrLf => LineEnding::Cr,
+            LineEnding::Cr => LineEnding::None,
+            LineEnding::None => LineEnding::Lf,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum DisplayMode {
+    Text,
+    Hex,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum SerialStatus {
+    Choosing,
+    Connecting,
+    Connected,
+    Closed(Option<String>),
+}
+
+// Opens the stream and forwards it until either side closes it. The terminal writes to the
+// sender of `Event::SerialConnected`, dropping it closes the stream
+pub fn open(
+    session: Arc<Session>,
+    device: Device,
+    target: SerialTarget,
+    sender: UnboundedSender<Event>,
+) {
+    tokio::spawn(async move {
+        let address = device.addr;
+        let (stream, profile) = match open_stream(&session, &device, &target).await {
+            Ok(opened) => opened,
+            Err(e) => {
+                let _ = sender.send(Event::SerialClosed(address, Some(e.to_string())));
+                return;
+            }
+        };
+
+        let (mut reader, mut writer) = stream.into_split();
+        let (tx, mut rx) = unbounded_channel::<Vec<u8>>();
+        let _ = sender.send(Event::SerialConnected(address, tx));
+
+        let mut buffer = [0u8; 1024];
+        let reason = loop {
+            tokio::select! {
+                read = reader.read(&mut buffer) => match read {
+                    Ok(0) => break None,
+                    Ok(n) => {
+                        let _ = sender.send(Event::SerialReceived(address, buffer[..n].to_vec()));
+                    }
+                    Err(e) => break Some(e.to_string()),
+                },
+                data = rx.recv() => match data {
+                    Some(data) => {
+                        if let Err(e) = writer.write_all(&data).await {
+                            break Some(e.to_string());
+                        }
+                    }
+                    None => break None,
+                },
+            }
+        };
+        drop(profile);
+        let _ = sender.send(Event::SerialClosed(address, reason));
+    });
+}
+
+// The profile stays registered as long as the stream is open
+async fn open_stream(
+    session: &Session,
+    device: &Device,
+    target: &SerialTarget,
+) -> AppResult<(Stream, Option<ProfileHandle>)> {
+    match target {
+        SerialTarget::Channel(channel) => {
+            let stream = Stream::connect(SocketAddr::new(device.addr, *channel)).await?;
+            Ok((stream, None))
+        }
+        SerialTarget::Service { uuid, .. } => {
+            let mut profile = session
+                .register_profile(Profile {
+                    uuid: *uuid,
+                    role: Some(Role::Client),
+                    require_authentication: Some(false),
+                    require_authorization: Some(false),
+                    auto_connect: Some(false),
+                    ..Default::default()
+                })
+                .await?;
+
+            // BlueZ hands the socket to the profile before the connection call returns
+            let connect = device.connect_profile(uuid);
+            tokio::pin!(connect);
+            let request = tokio::select! {
+                request = profile.next() => request,
+                connected = &mut connect => {
+                    connected?;
+                    profile.next().await
+                }
+            }
+            .context("The profile was unregistered")?;
+            Ok((request.accept()?, Some(profile)))
+        }
+    }
+}
+
+// Lines of a hex dump, with the offset of the first byte
+pub fn hex_lines(bytes: &[u8], offset: usize) -> Vec<String> {
+    bytes
+        .chunks(HEX_WIDTH)
+        .enumerate()
+        .map(|(i, chunk)| {
+            let hex: Vec<String> = chunk.iter().map(|b| format!("{b:02x}")).collect();
+            let ascii: String = chunk
+                .iter()
+                .map(|b| {
+                    if b.is_ascii_graphic() || *b == b' ' {
+                        *b as char
+                    } else {
+                        '.'
+                    }
+                })
+                .collect();
+            format!(
+                "{:08x}  {:<width$}  {ascii}",
+                offset + i * HEX_WIDTH,
+                hex.join(" "),
+                width = HEX_WIDTH * 3 - 1
+            )
+        })
+        .collect()
+}
+
+pub fn text_lines(bytes: &[u8]) -> Vec<String> {
+    String::from_utf8_lossy(bytes)
+        .split('\n')
+        .map(|line| {
+            line.replace('\t', "    ")
+                .chars()
+                .filter(|c| !c.is_control())
+                .collect()
+        })
+        .collect()
+}
+
+#[derive(Debug)]
+pub struct SerialTerminal {
+    pub device: Address,
+    pub targets: Vec<SerialTarget>,
+    pub state: ListState,
+    pub status: SerialStatus,
+    pub input: Input,
+    pub mode: DisplayMode,
+    pub line_ending: LineEnding,
+    writer: Option<UnboundedSender<Vec<u8>>>,
+    received: Vec<u8>,
+    // Bytes dropped from the scrollback, for the offsets of the hex dump
+    dropped: usize,
+    log: Option<(PathBuf, File)>,
+}
+
+impl SerialTerminal {
+    pub fn new(
+        device: Address,
+        uuids: &[Uuid],
+        vendor_services: &[Uuid],
+        line_ending: LineEnding,
+    ) -> Self {
+        Self {
+            device,
+            targets: serial_targets(uuids, vendor_services),
+            state: ListState::default().with_selected(Some(0)),
+            status: SerialStatus::Choosing,
+            input: Input::default(),
+            mode: DisplayMode::Text,
+            line_ending,
+            writer: None,
+            received: Vec::new(),
+            dropped: 0,
+            log: None,
+        }
+    }
+
+    pub fn next(&mut self) {
+        let i = self
+            .state
+            .selected()
+            .map_or(0, |i| (i + 1).min(self.targets.len() - 1));
+        self.state.select(Some(i));
+    }
+
+    pub fn previous(&mut self) {
+        let i = self.state.selected().map_or(0, |i| i.saturating_sub(1));
+        self.state.select(Some(i));
+    }
+
+    // RFCOMM channels go from 1 to 30
+    pub fn change_channel(&mut self, delta: i8) {
+        if let Some(SerialTarget::Channel(channel)) =
+            self.state.selected().and_then(|i| self.targets.get_mut(i))
+        {
+            *channel = channel.saturating_add_signed(delta).clamp(1, 30);
+        }
+    }
+
+    pub fn selected(&self) -> Option<&SerialTarget> {
+        self.targets.get(self.state.selected()?)
+    }
+
+    pub fn connected(&mut self, writer: UnboundedSender<Vec<u8>>) {
+        self.writer = Some(writer);
+        self.status = SerialStatus::Connected;
+    }
+
+    pub fn closed(&mut self, reason: Option<String>) {
+        self.writer = None;
+        self.status = SerialStatus::Closed(reason);
+    }
+
+    pub fn receive(&mut self, bytes: &[u8]) -> AppResult<()> {
+        self.received.extend_from_slice(bytes);
+        if self.received.len() > SCROLLBACK {
+            // Whole hex lines are dropped so the dump stays aligned
+            let excess = self.received.len() - SCROLLBACK;
+            let excess = excess.div_ceil(HEX_WIDTH) * HEX_WIDTH;
+            self.received.drain(..excess);
+            self.dropped += excess;
+        }
+        if let Some((_, file)) = &mut self.log {
+            file.write_all(bytes)?;
+        }
+        Ok(())
+    }
+
+    // Sends the typed line followed by the line ending
+    pub fn send(&mut self) -> AppResult<()> {
+        let Some(writer) = &self.writer else {
+            return Ok(());
+        };
+        let mut data = self.input.value().as_bytes().to_vec();
+        data.extend_from_slice(self.line_ending.bytes());
+        if let Some((_, file)) = &mut self.log {
+            file.write_all(&data)?;
+        }
+        writer
+            .send(data)
+            .map_err(|_| anyhow::anyhow!("The connection is closed"))?;
+        self.input.reset();
+        Ok(())
+    }
+
+    pub fn toggle_mode(&mut self) {
+        self.mode = match self.mode {
+            DisplayMode::Text => DisplayMode::Hex,
+            DisplayMode::Hex => DisplayMode::Text,
+        };
+    }
+
+    // Returns the file the session is logged to, `None` when the logging stops
+    pub fn toggle_log(&mut self) -> AppResult<Option<PathBuf>> {
+        if self.log.take().is_some() {
+            return Ok(None);
+        }
+        let dir = storage::data_dir()?.join("serial");
+        std::fs::create_dir_all(&dir).context("unable to create the serial logs dir")?;
+        let path = dir.join(format!(
+            "{}-{}.log",
+            self.device.to_string().replace(':', "_"),
+            storage::now()
+        ));
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .with_context(|| format!("unable to open {}", path.display()))?;
+        self.log = Some((path.clone(), file));
+        Ok(Some(path))
+    }
+
+    pub fn render(&mut self, device_name: &str, frame: &mut Frame, area: Rect) {
+        let area = area.centered(Constraint::Percentage(80), Constraint::Percentage(80));
+
+        let status = match &self.status {
+            SerialStatus::Choosing => String::new(),
+            SerialStatus::Connecting => " connecting… ".to_string(),
+            SerialStatus::Connected => format!(
+                " {} · {} · {} ",
+                match self.mode {
+                    DisplayMode::Text => "text",
+                    DisplayMode::Hex => "hex",
+                },
+                self.line_ending.label(),
+                if self.log.is_some() {
+                    "logging"
+                } else {
+                    "not logging"
+                }
+            ),
+            SerialStatus::Closed(None) => " closed ".to_string(),
+            SerialStatus::Closed(Some(reason)) => format!(" closed: {reason} "),
+        };
+        let block = Block::bordered()
+            .title(format!(" Serial terminal · {device_name} "))
+            .title_style(Style::default().bold())
+            .title_bottom(Line::from(status).right_aligned())
+            .padding(Padding::horizontal(1))
+            .border_type(BorderType::Thick)
+            .border_style(Style::default().green());
+        let inner = block.inner(area);
+
+        frame.render_widget(Clear, area);
+        frame.render_widget(block, area);
+
+        if self.status == SerialStatus::Choosing {
+            let items: Vec<Line> = self
+                .targets
+                .iter()
+                .map(|target| match target {
+                    SerialTarget::Channel(_) => Line::from(vec![
+                        Span::from(format!(" {}", target.label())),
+                        Span::from("  ←/→").dark_gray(),
+                    ]),
+                    SerialTarget::Service { .. } => Line::from(format!(" {}", target.label())),
+                })
+                .collect();
+            let [title_area, _, list_area] = Layout::vertical([
+                Constraint::Length(1),
+                Constraint::Length(1),
+                Constraint::Fill(1),
+            ])
+            .areas(inner);
+            frame.render_widget(Paragraph::new("Connect to").bold(), title_area);
+            frame.render_stateful_widget(
+                List::new(items).highlight_style(Style::default().white().on_dark_gray()),
+                list_area,
+                &mut self.state,
+            );
+            return;
+        }
+
+        let [data_area, input_area] =
+            Layout::vertical([Constraint::Fill(1), Constraint::Length(1)]).areas(inner);
+
+        let lines = match self.mode {
+            DisplayMode::Text => text_lines(&self.received),
+            DisplayMode::Hex => hex_lines(&self.received, self.dropped),
+        };
+        let height = data_area.height as usize;
+        let lines: Vec<Line> = lines
+            .into_iter()
+            .rev()
+            .take(height)
+            .rev()
+            .map(Line::from)
+            .collect();
+        frame.render_widget(Paragraph::new(lines), data_area);
+
+        let prompt = Span::from("> ").fg(Color::Green);
+        frame.render_widget(
+            Line::from(vec![prompt, Span::from(self.input.value())]),
+            input_area,
+        );
+        if self.status == SerialStatus::Connected {
+            let x = input_area.x + 2 + u16::try_from(self.input.visual_cursor()).unwrap_or(0);
+            frame.set_cursor_position((x.min(input_area.right()), input_area.y));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use insta::assert_snapshot;
+    use ratatui::{Terminal, backend::TestBackend};
+
+    fn uuid(s: &str) -> Uuid {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn serial_targets_from_the_device_uuids() {
+        let uuids = [
+            uuid("00001101-0000-1000-8000-00805f9b34fb"),
+            uuid("0000110b-0000-1000-8000-00805f9b34fb"),
+            uuid("00001103-0000-1000-8000-00805f9b34fb"),
+            uuid("0000fe59-0000-1000-8000-00805f9b34fb"),
+            uuid("6e400001-b5a3-f393-e0a9-e50e24dcca9e"),
+        ];
+        assert_eq!(
+            serial_targets(&uuids, &[]),
+            [
+                SerialTarget::Service {
+                    uuid: uuid("00001101-0000-1000-8000-00805f9b34fb"),
+                    name: "Serial Port".to_string(),
+                },
+                SerialTarget::Service {
+                    uuid: uuid("00001103-0000-1000-8000-00805f9b34fb"),
+                    name: "Dial-up Networking".to_string(),
+                },
+                SerialTarget::Channel(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn configured_vendor_serial_services() {
+        let spp = uuid("00000000-deca-fade-deca-deafdecacaff");
+        let targets = serial_targets(&[uuid("0000180f-0000-1000-8000-00805f9b34fb"), spp], &[spp]);
+        assert_eq!(
+            targets,
+            [
+                SerialTarget::Service {
+                    uuid: spp,
+                    name: format!("Service {spp}"),
+                },
+                SerialTarget::Channel(1),
+            ]
+        );
+    }
+
+    #[test]
+    fn hex_dump() {
+        assert_eq!(
+            hex_lines(b"AT+VER?\r\nOK\r\n0123456789", 32),
+            [
+                "00000020  41 54 2b 56 45 52 3f 0d 0a 4f 4b 0d 0a 30 31 32  AT+VER?..OK..012",
+                "00000030  33 34 35 36 37 38 39                             3456789",
+            ]
+        );
+    }
+
+    #[test]
+    fn text_with_line_endings() {
+        assert_eq!(
+            text_lines(b"OK\r\n\tv1.2\x07\nready"),
+            ["OK", "    v1.2", "ready"]
+        );
+    }
+
+    #[test]
+    fn scrollback_drops_whole_hex_lines() {
+        let mut terminal = SerialTerminal::new(Address::any(), &[], &[], LineEnding::Lf);
+        terminal.receive(&vec![b'a'; SCROLLBACK]).unwrap();
+        terminal.receive(b"bc").unwrap();
+        assert_eq!(terminal.dropped, HEX_WIDTH);
+        assert_eq!(terminal.received.len(), SCROLLBACK + 2 - HEX_WIDTH);
+        assert!(terminal.received.ends_with(b"abc"));
+    }
+
+    #[test]
+    fn send_lines_with_the_line_ending() {
+        let mut terminal = SerialTerminal::new(Address::any(), &[], &[], LineEnding::CrLf);
+        let (tx, mut rx) = unbounded_channel();
+        terminal.connected(tx);
+        terminal.input = Input::new("AT".to_string());
+        terminal.send().unwrap();
+        terminal.line_ending = terminal.line_ending.next().next();
+        terminal.send().unwrap();
+
+        assert_eq!(rx.try_recv().unwrap(), b"AT\r\n");
+        assert_eq!(rx.try_recv().unwrap(), b"");
+        assert!(terminal.input.value().is_empty());
+    }
+
+    #[test]
+    fn channels_stay_in_range() {
+        let mut terminal = SerialTerminal::new(Address::any(), &[], &[], LineEnding::Lf);
+        terminal.change_channel(-1);
+        assert_eq!(terminal.selected(), Some(&SerialTarget::Channel(1)));
+        terminal.change_channel(5);
+        assert_eq!(terminal.selected(), Some(&SerialTarget::Channel(6)));
+    }
+
+    #[test]
+    fn render() {
+        let mut terminal = Terminal::new(TestBackend::new(110, 16)).unwrap();
+        let mut serial = SerialTerminal::new(Address::new(*b"DEADBE"), &[], &[], LineEnding::CrLf);
+        let (tx, _rx) = unbounded_channel();
+        serial.connected(tx);
+        serial.receive(b"HC-05 ready\r\nOK\r\n").unwrap();
+        serial.input = Input::new("AT+NAME?".to_string());
+        terminal
+            .draw(|frame| serial.render("HC-05", frame, frame.area()))
+            .unwrap();
+        assert_snapshot!(terminal.backend());
+
+        serial.toggle_mode();
+        terminal
+            .draw(|frame| serial.render("HC-05", frame, frame.area()))
+            .unwrap();
+        assert_snapshot!(terminal.backend());
+    }
+}
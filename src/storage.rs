@@ -0,0 +1,41 @@
+use std::{
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+
+use crate::app::AppResult;
+
+pub fn data_dir() -> AppResult<PathBuf> {
+    Ok(dirs::data_dir()
+        .context("unable to find data_dir")?
+        .join("bluetui"))
+}
+
+// Write to a temporary file first so a crash never leaves a truncated file behind
+pub fn write(file_path: &Path, content: &str) -> AppResult<()> {
+    if let Some(parent) = file_path.parent()
+        && !parent.exists()
+    {
+        std::fs::create_dir_all(parent).context("unable to create parent dir(s)")?;
+    }
+
+    let mut tmp_file_path = file_path.as_os_str().to_owned();
+    tmp_file_path.push(".tmp");
+
+    std::fs::write(&tmp_file_path, content)
+        .with_context(|| format!("error writing {}", file_path.display()))?;
+    std::fs::rename(&tmp_file_path, file_path)
+        .with_context(|| format!("error writing {}", file_path.display()))?;
+
+    Ok(())
+}
+
+// Seconds since the unix epoch
+pub fn now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}